@@ -0,0 +1,122 @@
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::awards::Award;
+use crate::events::GameEvent;
+use crate::player::PlayerColour;
+
+/// Fewest cards a monopoly has to take to earn `Achievement::BigMonopoly`
+pub const BIG_MONOPOLY_CARDS: usize = 6;
+
+/// Badges handed out for notable moments over the course of a game
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Achievement {
+    /// Built the first city of the game
+    FirstCity,
+    /// Won the game without holding the longest road
+    WonWithoutLongestRoad,
+    /// Took at least `BIG_MONOPOLY_CARDS` cards with a single monopoly
+    BigMonopoly,
+}
+
+/// Watches a game's events, working out when a player has earned an achievement
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AchievementTracker {
+    first_city_built: bool,
+    longest_road: Option<PlayerColour>,
+}
+
+impl AchievementTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take note of `event`, returning the achievement it earns and who earned it, if any
+    pub fn observe(&mut self, event: &GameEvent) -> Option<(PlayerColour, Achievement)> {
+        match *event {
+            GameEvent::CityBuilt { player } if !self.first_city_built => {
+                self.first_city_built = true;
+                Some((player, Achievement::FirstCity))
+            }
+            GameEvent::MonopolyPlayed { player, taken, .. } if taken >= BIG_MONOPOLY_CARDS => {
+                Some((player, Achievement::BigMonopoly))
+            }
+            GameEvent::AwardChanged {
+                award: Award::LongestRoad,
+                holder,
+            } => {
+                self.longest_road = holder;
+                None
+            }
+            GameEvent::GameWon { player } if self.longest_road != Some(player) => {
+                Some((player, Achievement::WonWithoutLongestRoad))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resources::ResourceKind;
+    use alloc::vec;
+
+    #[test]
+    fn test_first_city() {
+        let mut tracker = AchievementTracker::new();
+        let red = GameEvent::CityBuilt {
+            player: PlayerColour::Red,
+        };
+        assert_eq!(
+            tracker.observe(&red),
+            Some((PlayerColour::Red, Achievement::FirstCity))
+        );
+        assert_eq!(
+            tracker.observe(&GameEvent::CityBuilt {
+                player: PlayerColour::Blue
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_big_monopoly() {
+        let mut tracker = AchievementTracker::new();
+        let monopoly = |taken| GameEvent::MonopolyPlayed {
+            player: PlayerColour::Red,
+            resource: ResourceKind::Ore,
+            taken,
+            from: vec![(PlayerColour::Blue, taken)],
+        };
+        assert_eq!(tracker.observe(&monopoly(BIG_MONOPOLY_CARDS - 1)), None);
+        assert_eq!(
+            tracker.observe(&monopoly(BIG_MONOPOLY_CARDS)),
+            Some((PlayerColour::Red, Achievement::BigMonopoly))
+        );
+    }
+
+    #[test]
+    fn test_won_without_longest_road() {
+        let mut tracker = AchievementTracker::new();
+        tracker.observe(&GameEvent::AwardChanged {
+            award: Award::LongestRoad,
+            holder: Some(PlayerColour::Red),
+        });
+        let won = |player| GameEvent::GameWon { player };
+        assert_eq!(tracker.observe(&won(PlayerColour::Red)), None);
+        assert_eq!(
+            tracker.observe(&won(PlayerColour::Blue)),
+            Some((PlayerColour::Blue, Achievement::WonWithoutLongestRoad))
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::board::{EdgeId, VertexId};
+use crate::extensions::ExtensionAction;
+use crate::fish::FishPurchase;
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+use crate::scenarios::ScenarioAction;
+
+/// Something a player can do, passed to `Game::apply`
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Action {
+    PlaceInitialSettlement {
+        vertex: VertexId,
+    },
+    RollDice,
+    Discard {
+        cards: Resources,
+    },
+    MoveRobber {
+        tile: usize,
+        victim: Option<PlayerColour>,
+        /// Resource to steal, when the game lets the robber choose
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        #[cfg_attr(feature = "ts-rs", ts(optional))]
+        resource: Option<ResourceKind>,
+    },
+    BuildSettlement {
+        vertex: VertexId,
+    },
+    BuildRoad {
+        edge: EdgeId,
+    },
+    UpgradeToCity {
+        vertex: VertexId,
+    },
+    BuyDevelopmentCard,
+    PlayKnight {
+        tile: usize,
+        victim: Option<PlayerColour>,
+        /// Resource to steal, when the game lets the robber choose
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        #[cfg_attr(feature = "ts-rs", ts(optional))]
+        resource: Option<ResourceKind>,
+    },
+    PlayMonopoly {
+        resource: ResourceKind,
+    },
+    PlayRoadBuilding,
+    PlaceFreeRoad {
+        edge: EdgeId,
+    },
+    SpendFish {
+        purchase: FishPurchase,
+    },
+    Scenario {
+        action: ScenarioAction,
+    },
+    Extension {
+        action: ExtensionAction,
+    },
+    /// Offer the other players some of the player's cards in return for others
+    ProposeTrade {
+        offering: Resources,
+        wants: Resources,
+    },
+    AcceptTrade {
+        trade: Uuid,
+    },
+    /// Make a trade the player offered with `partner`, one of the players who accepted it
+    FinalizeTrade {
+        trade: Uuid,
+        partner: PlayerColour,
+    },
+    WithdrawTrade {
+        trade: Uuid,
+    },
+    EndTurn,
+}
+
+/// Why `Game::apply` refused an action
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ActionError {
+    /// Actions of this kind can't be taken right now, because it's not the player's turn or the
+    /// game hasn't reached the right phase
+    NotAllowedNow(String),
+    /// The action could be taken now, but not like this, since it breaks the rules or the player
+    /// can't afford it
+    Illegal(String),
+}
+
+impl ActionError {
+    pub fn reason(&self) -> &str {
+        match self {
+            ActionError::NotAllowedNow(reason) | ActionError::Illegal(reason) => reason,
+        }
+    }
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason())
+    }
+}
+
+impl std::error::Error for ActionError {}
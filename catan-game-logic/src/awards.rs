@@ -0,0 +1,132 @@
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::player::PlayerColour;
+
+/// Victory points a player earns from holding an award
+pub const AWARD_POINTS: usize = 2;
+
+/// Special cards held by whichever player leads a category, worth extra victory points
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Award {
+    /// The longest continuous road, of at least 5 segments
+    LongestRoad,
+    /// The most knights played, at least 3 of them
+    LargestArmy,
+    /// The most harbor points, at least 3 of them, when playing the Harbormaster variant
+    Harbormaster,
+}
+
+impl Award {
+    /// Lowest score a player needs before they can hold this award
+    pub fn minimum(&self) -> usize {
+        match self {
+            Award::LongestRoad => 5,
+            Award::LargestArmy => 3,
+            Award::Harbormaster => 3,
+        }
+    }
+
+    /// Work out who should hold this award, given who holds it now and each player's score
+    ///
+    /// the current holder keeps it unless someone strictly beats them. If the holder drops below
+    /// the minimum, or loses the lead, it goes to whoever now has the highest score, or back to
+    /// the supply if that's tied
+    pub fn holder(
+        &self,
+        current: Option<PlayerColour>,
+        scores: &[(PlayerColour, usize)],
+    ) -> Option<PlayerColour> {
+        let best = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+        if best < self.minimum() {
+            return None;
+        }
+
+        let score_of = |player: PlayerColour| {
+            scores
+                .iter()
+                .find(|(p, _)| *p == player)
+                .map(|(_, score)| *score)
+        };
+        if let Some(current) = current {
+            if score_of(current) == Some(best) {
+                return Some(current);
+            }
+        }
+
+        let mut leaders = scores.iter().filter(|(_, score)| *score == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((leader, _)), None) => Some(*leader),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_first_holder() {
+        let army = Award::LargestArmy;
+        assert_eq!(
+            army.holder(None, &[(PlayerColour::Red, 2), (PlayerColour::Blue, 1)]),
+            None
+        );
+        assert_eq!(
+            army.holder(None, &[(PlayerColour::Red, 3), (PlayerColour::Blue, 1)]),
+            Some(PlayerColour::Red)
+        );
+        assert_eq!(
+            army.holder(None, &[(PlayerColour::Red, 3), (PlayerColour::Blue, 3)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_holder_keeps_ties() {
+        let army = Award::LargestArmy;
+        let scores = [(PlayerColour::Red, 4), (PlayerColour::Blue, 4)];
+        assert_eq!(
+            army.holder(Some(PlayerColour::Red), &scores),
+            Some(PlayerColour::Red)
+        );
+
+        let scores = [(PlayerColour::Red, 4), (PlayerColour::Blue, 5)];
+        assert_eq!(
+            army.holder(Some(PlayerColour::Red), &scores),
+            Some(PlayerColour::Blue)
+        );
+    }
+
+    #[test]
+    fn test_holder_drops_below_minimum() {
+        let road = Award::LongestRoad;
+
+        // nobody else qualifies, so the card goes back to the supply
+        let scores = [(PlayerColour::Red, 4), (PlayerColour::Blue, 3)];
+        assert_eq!(road.holder(Some(PlayerColour::Red), &scores), None);
+
+        // the holder's road was broken, leaving two others tied
+        let scores = [
+            (PlayerColour::Red, 4),
+            (PlayerColour::Blue, 6),
+            (PlayerColour::Green, 6),
+        ];
+        assert_eq!(road.holder(Some(PlayerColour::Red), &scores), None);
+
+        let scores = [(PlayerColour::Red, 5), (PlayerColour::Blue, 6)];
+        assert_eq!(
+            road.holder(Some(PlayerColour::Red), &scores),
+            Some(PlayerColour::Blue)
+        );
+    }
+}
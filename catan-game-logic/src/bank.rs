@@ -1,178 +1,196 @@
-use std::collections::HashMap;
-use std::mem::variant_count;
-
-pub(self) use anyhow::{anyhow, Result};
+use std::fmt::Display;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "rand")]
+use rand::{seq::SliceRandom, Rng};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 
 use crate::development_cards::*;
-use crate::player::PlayerColour;
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
 use crate::resources::*;
-use crate::trade::Trade;
-
-use DevelopmentCard::*;
 
 pub const TOTAL_RESOURCES: usize = 19;
 
-/// Bank handles distributing resources and development cards, and trades
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-pub struct Bank {
-    development_cards: HashMap<DevelopmentCard, usize>,
-    resources: Resources,
-    #[serde(with = "uuid_map")]
-    trades: HashMap<Uuid, Trade>,
+/// Failures from the bank which callers may want to handle specifically, recovered from an
+/// `anyhow::Error` with `downcast_ref`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BankError {
+    /// Every development card has already been bought
+    DeckEmpty,
 }
 
-mod uuid_map {
-    use crate::trade::Trade;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    use std::collections::HashMap;
-    use uuid::Uuid;
-
-    pub fn serialize<S>(map: &HashMap<Uuid, Trade>, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let new_hm: HashMap<String, &Trade> = map.iter().map(|(k, v)| (k.to_string(), v)).collect();
-        new_hm.serialize(serializer)
+impl Display for BankError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BankError::DeckEmpty => write!(f, "No development cards available"),
+        }
     }
+}
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<Uuid, Trade>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let vec: HashMap<String, Trade> = HashMap::deserialize(deserializer).unwrap();
-        let map: HashMap<Uuid, Trade> = vec
-            .into_iter()
-            .map(|(k, v)| (Uuid::parse_str(&k).unwrap(), v))
-            .collect();
-        Ok(map)
-    }
+impl std::error::Error for BankError {}
+
+/// What anyone can see of the bank, from `Bank::summary`
+///
+/// the deck is face down, so only its size is shown
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BankSummary {
+    pub resources: Resources,
+    pub development_cards: usize,
+}
+
+/// Bank handles distributing resources and development cards
+///
+/// the development cards are shuffled once when the bank is created and dealt from the top
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bank {
+    /// Development cards in the order they'll be dealt, the top of the deck last
+    deck: Vec<DevelopmentCard>,
+    resources: Resources,
 }
 
 impl Bank {
     /// Create a new instance of bank with the correct number of total resources and development cards
     pub fn new() -> Self {
+        #[cfg(feature = "rand")]
+        return Self::with_deck(&DevelopmentCardCounts::base_game(), &mut entropy_rng());
+        #[cfg(not(feature = "rand"))]
         Bank {
-            development_cards: HashMap::from([
-                (YearOfPlenty, 2),
-                (RoadBuilding, 2),
-                (Monopoly, 2),
-                (HiddenVictoryPoint, 5),
-                (Knight, 14),
-            ]),
+            deck: deck_of(&DevelopmentCardCounts::base_game()),
             resources: Resources::new_with_amount(TOTAL_RESOURCES),
-            trades: HashMap::new(),
         }
     }
 
-    /// Select a random development card, and distribute it to the player
-    /// fails if there are no more development cards to distribute
-    pub fn distribute_random_development_card(&mut self) -> Result<DevelopmentCard> {
-        let mut i = 0;
-        loop {
-            let dev_card_kind = DevelopmentCard::random();
-            let dev_card = self.development_cards.get_mut(&dev_card_kind);
-            match dev_card {
-                Some(n) if *n > 0 => {
-                    *n -= 1;
-                    break Ok(dev_card_kind);
-                }
-                Some(_) | None => (),
-            };
-            i += 1;
-
-            if i == variant_count::<DevelopmentCard>() {
-                break Err(anyhow!("No development cards available"));
-            }
+    /// Create a bank whose deck holds `development_cards`, shuffled with `rng`
+    #[cfg(feature = "rand")]
+    pub fn with_deck(development_cards: &DevelopmentCardCounts, rng: &mut impl Rng) -> Self {
+        let mut deck = deck_of(development_cards);
+        deck.shuffle(rng);
+        Bank {
+            deck,
+            resources: Resources::new_with_amount(TOTAL_RESOURCES),
         }
     }
 
-    /// Distribute an amount of a specific resource
-    pub fn distribute_resource(&mut self, kind: ResourceKind, amount: usize) -> Result<Resources> {
-        if (self.resources[kind] as i32) - (amount as i32) < 0 {
-            return Err(anyhow!("Cannot distribute that amount of resources"));
-        };
-
-        let mut distributed_resources = Resources::new();
-        distributed_resources[kind] = amount;
-        self.resources[kind] -= amount;
-
-        Ok(distributed_resources)
+    /// Deal the development card on top of the deck
+    /// fails with `BankError::DeckEmpty` if there are no more development cards to distribute
+    #[cfg(feature = "rand")]
+    pub fn distribute_random_development_card(&mut self) -> Result<DevelopmentCard> {
+        self.deck.pop().ok_or(BankError::DeckEmpty.into())
     }
 
-    pub fn propose_trade_with_bank(&mut self, player: PlayerColour, wants: Resources) {
-        let requirements = wants * 4;
+    /// Whether every development card has been distributed
+    pub fn is_deck_exhausted(&self) -> bool {
+        self.deck.is_empty()
+    }
 
-        let _trade_id = self.propose_trade(player, requirements, wants);
-        todo!()
+    /// Every resource card the bank has left
+    pub fn resources(&self) -> &Resources {
+        &self.resources
     }
 
-    pub fn return_resources(&mut self, resources: Resources) {
-        self.resources += resources;
+    /// Number of development cards of one kind left in the deck
+    pub fn development_cards_remaining(&self, kind: DevelopmentCard) -> usize {
+        self.deck.iter().filter(|card| **card == kind).count()
     }
 
-    pub fn return_dev_card(&mut self, kind: DevelopmentCard) {
-        *self.development_cards.get_mut(&kind).unwrap() += 1;
+    /// Development cards of every kind left in the deck, but not the order they'll come out in
+    pub fn remaining_dev_cards(&self) -> DevelopmentCardCounts {
+        self.deck.iter().map(|card| (*card, 1)).collect()
     }
 
-    pub fn get_trade(&self, trade_id: Uuid) -> Option<&Trade> {
-        self.trades.get(&trade_id)
+    /// Whether the bank holds enough to hand out all of `resources`
+    pub fn can_afford_distribution(&self, resources: &Resources) -> bool {
+        self.resources.contains(resources)
     }
 
-    pub fn get_trade_mut(&mut self, trade_id: Uuid) -> Option<&mut Trade> {
-        self.trades.get_mut(&trade_id)
+    /// The bank as players can see it, for sending to clients
+    pub fn summary(&self) -> BankSummary {
+        BankSummary {
+            resources: self.resources,
+            development_cards: self.deck.len(),
+        }
     }
 
-    /// Propose a new trade to the other players
+    /// Replace what's left in the bank, e.g. when restoring a saved position
     ///
-    /// creates a new instance of a `Trade` object, and insert it into the `trades` hashmap
-    pub fn propose_trade(
+    /// saved positions only keep how many of each card are left, so the deck is shuffled again
+    pub(crate) fn restock(
         &mut self,
-        from: PlayerColour,
-        offering: Resources,
-        wants: Resources,
-    ) -> Uuid {
-        let t = Trade::new(from, offering, wants);
-        let uuid = Uuid::new_v4();
-        self.trades.insert(uuid, t);
-        uuid
+        resources: Resources,
+        development_cards: impl IntoIterator<Item = (DevelopmentCard, usize)>,
+    ) {
+        self.resources = resources;
+        self.deck = deck_of(&development_cards.into_iter().collect());
+        #[cfg(feature = "rand")]
+        self.deck.shuffle(&mut entropy_rng());
     }
 
-    /// Indicate a player is willing to make a trade
-    pub fn accept_trade(&mut self, trade_id: Uuid, accepted_by: PlayerColour) -> Result<()> {
-        let trade = self.trades.get_mut(&trade_id);
+    /// Amount of a resource the bank has left to distribute
+    pub fn remaining(&self, kind: ResourceKind) -> usize {
+        self.resources[kind]
+    }
 
-        if trade.is_none() {
-            return Err(anyhow!("Trade not found"));
-        };
+    /// Distribute an amount of a specific resource
+    pub fn distribute_resource(&mut self, kind: ResourceKind, amount: usize) -> Result<Resources> {
+        self.resources
+            .try_remove(kind, amount)
+            .map_err(|_| anyhow!("Cannot distribute that amount of resources"))?;
 
-        trade.unwrap().accept(accepted_by)?;
+        let mut distributed_resources = Resources::new();
+        distributed_resources[kind] = amount;
 
-        Ok(())
+        Ok(distributed_resources)
     }
 
-    /// Indicate that the player offering the trade is willing to finalize the player
-    pub fn finalize_trade(&mut self, trade_id: Uuid, player: PlayerColour) -> Result<()> {
-        let trade = self.trades.get_mut(&trade_id);
+    /// Take `resources` out of the bank, taking nothing unless it has all of them
+    pub fn take_resources(&mut self, resources: Resources) -> Result<()> {
+        self.resources
+            .try_sub(resources)
+            .map_err(|_| anyhow!("Cannot distribute that amount of resources"))
+    }
 
-        if trade.is_none() {
-            return Err(anyhow!("Trade not found"));
-        }
+    pub fn return_resources(&mut self, resources: Resources) {
+        self.resources += resources;
+    }
 
-        trade.unwrap().confirm_recipient(player)?;
+    /// Put a development card back on the bottom of the deck
+    pub fn return_dev_card(&mut self, kind: DevelopmentCard) {
+        self.deck.insert(0, kind);
+    }
 
-        Ok(())
+    /// Pull a card of `kind` out of the deck from wherever it is, for tests to hand to a player
+    #[cfg(test)]
+    pub(crate) fn take_dev_card(&mut self, kind: DevelopmentCard) {
+        let idx = self.deck.iter().position(|card| *card == kind).unwrap();
+        self.deck.remove(idx);
     }
 }
 
+/// Every card in `counts`, in the order of `DevelopmentCard::ALL`
+fn deck_of(counts: &DevelopmentCardCounts) -> Vec<DevelopmentCard> {
+    counts
+        .iter()
+        .flat_map(|(card, n)| std::iter::repeat_n(card, n))
+        .collect()
+}
+
 impl Default for Bank {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rand"))]
 mod test {
     use super::*;
     use crate::{resources::Resources, *};
@@ -187,11 +205,42 @@ mod test {
         assert_eq!(b.resources[Lumber], 19);
         assert_eq!(b.resources[Brick], 19);
 
-        assert_eq!(b.development_cards.get(&YearOfPlenty), Some(&2));
-        assert_eq!(b.development_cards.get(&Monopoly), Some(&2));
-        assert_eq!(b.development_cards.get(&Knight), Some(&14));
-        assert_eq!(b.development_cards.get(&RoadBuilding), Some(&2));
-        assert_eq!(b.development_cards.get(&HiddenVictoryPoint), Some(&5));
+        let cards = b.remaining_dev_cards();
+        assert_eq!(cards[YearOfPlenty], 2);
+        assert_eq!(cards[Monopoly], 2);
+        assert_eq!(cards[Knight], 14);
+        assert_eq!(cards[RoadBuilding], 2);
+        assert_eq!(cards[HiddenVictoryPoint], 5);
+    }
+
+    #[test]
+    fn test_custom_deck() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let cards: DevelopmentCardCounts = [(Knight, 3), (Monopoly, 1)].into_iter().collect();
+        let mut b = Bank::with_deck(&cards, &mut StdRng::seed_from_u64(5));
+        let mut same = Bank::with_deck(&cards, &mut StdRng::seed_from_u64(5));
+        assert_eq!(b.remaining_dev_cards(), cards);
+        assert_eq!(b.summary().development_cards, 4);
+
+        for _ in 0..4 {
+            let card = b.distribute_random_development_card().unwrap();
+            assert_eq!(same.distribute_random_development_card().unwrap(), card);
+        }
+        assert!(b.is_deck_exhausted());
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut b = Bank::new();
+        assert_eq!(b.remaining_dev_cards()[Knight], 14);
+        assert!(b.can_afford_distribution(&Resources::new_explicit(19, 0, 0, 0, 1)));
+        assert!(!b.can_afford_distribution(&Resources::new_explicit(20, 0, 0, 0, 0)));
+
+        b.distribute_resource(Wool, 4).unwrap();
+        let summary = b.summary();
+        assert_eq!(summary.resources[Wool], 15);
+        assert_eq!(summary.development_cards, 25);
     }
 
     #[test]
@@ -225,52 +274,20 @@ mod test {
     }
 
     #[test]
-    fn test_propose_trade() {
+    fn test_deck_exhaustion() {
         let mut b = Bank::new();
-        let p1 = player::PlayerColour::Red;
-        let trade_id = b.propose_trade(
-            p1,
-            Resources::new_explicit(0, 0, 1, 0, 1),
-            Resources::new_explicit(2, 0, 0, 0, 0),
-        );
-        assert_eq!(b.trades.len(), 1);
-        assert!(b.get_trade(trade_id).is_some());
-    }
+        for _ in 0..25 {
+            assert!(!b.is_deck_exhausted());
+            assert!(b.distribute_random_development_card().is_ok());
+        }
+        assert!(b.is_deck_exhausted());
 
-    #[test]
-    fn test_accept_trade() {
-        let mut b = Bank::new();
-        let p1 = player::PlayerColour::Red;
-        let p2 = player::PlayerColour::Blue;
-        let trade_id = b.propose_trade(
-            p1,
-            Resources::new_explicit(0, 0, 1, 0, 1),
-            Resources::new_explicit(2, 0, 0, 0, 0),
-        );
-        assert!(b.accept_trade(trade_id, p2).is_ok());
-        assert_eq!(
-            *b.get_trade(trade_id).unwrap().state(),
-            trade::TradeState::Proposed
-        );
-    }
+        let err = b.distribute_random_development_card().unwrap_err();
+        assert_eq!(err.downcast_ref::<BankError>(), Some(&BankError::DeckEmpty));
 
-    #[test]
-    fn test_finalize_trade() {
-        let mut b = Bank::new();
-        let p1 = player::PlayerColour::Red;
-        let p2 = player::PlayerColour::Blue;
-        let trade_id = b.propose_trade(
-            p1,
-            Resources::new_explicit(0, 0, 1, 0, 1),
-            Resources::new_explicit(2, 0, 0, 0, 0),
-        );
-        let _ = b.accept_trade(trade_id, p2);
-        let _ = b.finalize_trade(trade_id, p2);
-
-        assert_eq!(
-            *b.get_trade(trade_id).unwrap().state(),
-            trade::TradeState::LockedIn
-        )
+        b.return_dev_card(Knight);
+        assert!(!b.is_deck_exhausted());
+        assert_eq!(b.distribute_random_development_card().unwrap(), Knight);
     }
 
     #[test]
@@ -281,4 +298,17 @@ mod test {
         assert!(dc.is_ok());
         b.return_dev_card(dc.unwrap());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialization_is_stable() {
+        let mut b = Bank::new();
+        for _ in 0..5 {
+            b.distribute_random_development_card().unwrap();
+        }
+        let json = serde_json::to_string(&b).unwrap();
+        assert_eq!(serde_json::to_string(&b.clone()).unwrap(), json);
+        let back: Bank = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
 }
@@ -1,66 +1,144 @@
-use std::mem::variant_count;
+use std::hash::{Hash, Hasher};
 use std::ops::Index;
 
-use rand::{thread_rng, Rng};
+use anyhow::{anyhow, Result};
+
+#[cfg(feature = "rand")]
+use rand::distributions::{Distribution, Standard};
+#[cfg(feature = "rand")]
+use rand::seq::SliceRandom;
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 use uuid::Uuid;
 
-use petgraph::graph::Node;
-use petgraph::prelude::*;
-
 use crate::building::Building;
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+use crate::player::PlayerColour;
 use crate::resources::ResourceKind;
-use crate::Game;
+use crate::zobrist::{colour_code, key, Feature};
 
 pub const DEFAULT_TILE_COUNT: usize = 19;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+/// The base game's number tokens, one for every tile but the desert
+#[cfg(feature = "rand")]
+const BASE_GAME_TOKENS: [usize; DEFAULT_TILE_COUNT - 1] =
+    [2, 3, 3, 4, 4, 5, 5, 6, 6, 8, 8, 9, 9, 10, 10, 11, 11, 12];
+
+/// Number of tiles between the centre tile and the edge of the default board
+pub const BOARD_RADIUS: i32 = 2;
+
+/// Neighbouring tile offsets in axial coordinates, clockwise from the top left neighbour
+const DIRECTIONS: [(i32, i32); 6] = [(0, -1), (1, -1), (1, 0), (0, 1), (-1, 1), (-1, 0)];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum HarborKind {
     Generic,
     Special(ResourceKind),
 }
 
 impl HarborKind {
+    /// Number of distinct harbor kinds
+    pub const COUNT: usize = 2;
+
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        match rng.gen_range(0..=variant_count::<HarborKind>() - 1) {
-            0 => HarborKind::Generic,
-            1 => HarborKind::Special(ResourceKind::random()),
-            n => panic!("Invalid index, i: {}", n),
+        entropy_rng().gen()
+    }
+}
+
+/// Picks harbors as often as they turn up in the base game, where 4 of the 9 are generic and
+/// there's one special harbor for each resource
+#[cfg(feature = "rand")]
+impl Distribution<HarborKind> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> HarborKind {
+        match rng.gen_range(0..9) {
+            0..=3 => HarborKind::Generic,
+            n => HarborKind::Special(ResourceKind::ALL[n - 4]),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TileKind {
     Resource(ResourceKind),
     Desert,
     ResourceWithHarbor(HarborKind, ResourceKind),
 }
 
+#[cfg(feature = "rand")]
 use TileKind::*;
 
 impl TileKind {
+    /// Number of distinct tile kinds
+    pub const COUNT: usize = 3;
+
+    /// The resource produced by tiles of this kind, if any
+    pub fn resource(&self) -> Option<ResourceKind> {
+        match *self {
+            TileKind::Resource(kind) | TileKind::ResourceWithHarbor(_, kind) => Some(kind),
+            TileKind::Desert => None,
+        }
+    }
+
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        match rng.gen_range(0..=variant_count::<TileKind>() - 1) {
-            0 => Resource(ResourceKind::random()),
-            1 => Desert,
-            2 => ResourceWithHarbor(HarborKind::random(), ResourceKind::random()),
-            n => panic!("Invalid index, i: {}", n),
+        entropy_rng().gen()
+    }
+}
+
+/// Picks resource tiles as if from the base game's, with each resource as common as it is there
+/// and half of them on one of the 9 harbors
+///
+/// never the desert, as a board has exactly one, which `Board::random_with` places itself
+#[cfg(feature = "rand")]
+impl Distribution<TileKind> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> TileKind {
+        let resource = rng.gen();
+        match rng.gen_bool(0.5) {
+            true => ResourceWithHarbor(rng.gen(), resource),
+            false => Resource(resource),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+/// Picks a tile kind from `Standard`, with one of the base game's number tokens, so never a 7
+#[cfg(feature = "rand")]
+impl Distribution<Tile> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tile {
+        Tile {
+            kind: rng.gen(),
+            id: uuid::Builder::from_random_bytes(rng.gen()).into_uuid(),
+            token: *BASE_GAME_TOKENS.choose(rng).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Tile {
     kind: TileKind,
-    #[serde(with = "uuid::serde::compact")]
+    #[cfg_attr(feature = "serde", serde(with = "uuid::serde::compact"))]
+    #[cfg_attr(feature = "ts-rs", ts(type = "number[]"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "[u8; 16]"))]
     id: Uuid,
     token: usize,
-    intersections: [Option<Building>; 6],
 }
 
 impl Tile {
@@ -69,19 +147,12 @@ impl Tile {
             kind,
             id: Uuid::new_v4(),
             token,
-            intersections: [None; 6],
         }
     }
 
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        let (d1, d2) = Game::roll_dice();
-        let token = (d1 + d2) as usize;
-        Self {
-            kind: TileKind::random(),
-            id: Uuid::new_v4(),
-            token,
-            intersections: [None; 6],
-        }
+        entropy_rng().gen()
     }
 
     pub fn id(&self) -> &Uuid {
@@ -95,109 +166,812 @@ impl Tile {
     pub fn token(&self) -> &usize {
         &self.token
     }
-
-    pub fn intersections(&self) -> &[Option<Building>] {
-        &self.intersections
-    }
 }
 
+#[cfg(feature = "rand")]
 impl Default for Tile {
     fn default() -> Self {
-        let roll = Game::roll_dice();
-        let roll = roll.0 + roll.1;
-        Self {
-            kind: TileKind::random(),
-            id: Uuid::new_v4(),
-            token: roll as usize,
-            intersections: [None; 6],
+        Self::random()
+    }
+}
+
+/// Identifies an intersection between tiles, where settlements and cities are built
+///
+/// this is the vertex's index on the board, which only means the same thing on boards of the
+/// same layout. `Vertex::position` names it independently of how vertices are numbered
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct VertexId(usize);
+
+impl VertexId {
+    pub fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// Somewhere on the board a piece can be built, a vertex for settlements and cities or an edge
+/// for roads
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Location {
+    Vertex(VertexId),
+    Edge(EdgeId),
+}
+
+/// An intersection between up to three tiles
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Vertex {
+    id: VertexId,
+    position: VertexKey,
+    /// The first `tile_count` of these are the tiles meeting here
+    tiles: [usize; 3],
+    tile_count: usize,
+    building: Option<(PlayerColour, Building)>,
+}
+
+impl Vertex {
+    pub fn id(&self) -> &VertexId {
+        &self.id
+    }
+
+    /// Axial coordinates of the three tiles meeting at this vertex, some of which may be off the
+    /// board, in sorted order
+    ///
+    /// unlike the id, this doesn't depend on the order vertices were numbered in, so clients can
+    /// hold on to it to refer to the same vertex across requests
+    pub fn position(&self) -> &[(i32, i32); 3] {
+        &self.position
+    }
+
+    /// Indices of the tiles which meet at this vertex
+    pub fn tiles(&self) -> &[usize] {
+        &self.tiles[..self.tile_count]
+    }
+
+    /// The building at this vertex, and the player who owns it
+    pub fn building(&self) -> Option<&(PlayerColour, Building)> {
+        self.building.as_ref()
+    }
+}
+
+/// Identifies the path between two neighbouring vertices, where roads are built
+///
+/// like `VertexId`, this is an index, and `Edge::position` is the layout-independent name
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct EdgeId(usize);
+
+impl EdgeId {
+    pub fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// The path between two neighbouring vertices
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Edge {
+    id: EdgeId,
+    position: EdgeKey,
+    vertices: [VertexId; 2],
+    road: Option<PlayerColour>,
+}
+
+impl Edge {
+    pub fn id(&self) -> &EdgeId {
+        &self.id
+    }
+
+    /// Axial coordinates of the two tiles either side of this edge, one of which may be off the
+    /// board, in sorted order
+    pub fn position(&self) -> &[(i32, i32); 2] {
+        &self.position
+    }
+
+    /// The vertices at either end of this edge
+    pub fn vertices(&self) -> &[VertexId; 2] {
+        &self.vertices
+    }
+
+    /// Owner of the road along this edge, if one has been built
+    pub fn road(&self) -> Option<&PlayerColour> {
+        self.road.as_ref()
+    }
+}
+
+/// Axial coordinates of each tile on the default board, numbered row by row from the top left
+pub(crate) fn tile_coordinates() -> Vec<(i32, i32)> {
+    let mut coords = Vec::new();
+    for r in -BOARD_RADIUS..=BOARD_RADIUS {
+        for q in (-BOARD_RADIUS).max(-BOARD_RADIUS - r)..=BOARD_RADIUS.min(BOARD_RADIUS - r) {
+            coords.push((q, r));
+        }
+    }
+    coords
+}
+
+/// The three tiles meeting at a vertex, some of which may be off the board, in sorted order
+type VertexKey = [(i32, i32); 3];
+
+/// The two tiles either side of an edge, some of which may be off the board, in sorted order
+type EdgeKey = [(i32, i32); 2];
+
+/// Work out the intersections between the given tiles, and the paths connecting them
+///
+/// each corner of a tile is identified by the three (possibly off-board) tiles which meet there,
+/// so corners shared between neighbouring tiles collapse into a single vertex, and likewise each
+/// side by the two tiles either side of it. Vertices and edges are numbered in the order they're
+/// first found, walking each tile's corners clockwise from the top
+fn layout_for(coords: &[(i32, i32)]) -> (Vec<Vertex>, Vec<Edge>) {
+    let mut keys: Vec<VertexKey> = Vec::new();
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+
+    for (tile, &(q, r)) in coords.iter().enumerate() {
+        let mut corners = Vec::with_capacity(DIRECTIONS.len());
+        for corner in 0..DIRECTIONS.len() {
+            let (d1, d2) = (
+                DIRECTIONS[corner],
+                DIRECTIONS[(corner + 1) % DIRECTIONS.len()],
+            );
+            let mut key = [(q, r), (q + d1.0, r + d1.1), (q + d2.0, r + d2.1)];
+            key.sort();
+
+            match keys.iter().position(|k| *k == key) {
+                Some(idx) => {
+                    let vertex = &mut vertices[idx];
+                    vertex.tiles[vertex.tile_count] = tile;
+                    vertex.tile_count += 1;
+                    corners.push(VertexId(idx));
+                }
+                None => {
+                    keys.push(key);
+                    corners.push(VertexId(vertices.len()));
+                    vertices.push(Vertex {
+                        id: VertexId(vertices.len()),
+                        position: key,
+                        tiles: [tile, 0, 0],
+                        tile_count: 1,
+                        building: None,
+                    });
+                }
+            }
+        }
+
+        for side in 0..corners.len() {
+            let mut ends = [corners[side], corners[(side + 1) % corners.len()]];
+            ends.sort();
+            if !edges.iter().any(|edge| edge.vertices == ends) {
+                let (a, b) = (&vertices[ends[0].0].position, &vertices[ends[1].0].position);
+                let mut shared = a.iter().filter(|coord| b.contains(coord));
+                let position = [
+                    *shared.next().expect("neighbouring corners share two tiles"),
+                    *shared.next().expect("neighbouring corners share two tiles"),
+                ];
+                edges.push(Edge {
+                    id: EdgeId(edges.len()),
+                    position,
+                    vertices: ends,
+                    road: None,
+                });
+            }
         }
     }
+
+    (vertices, edges)
 }
 
-/// Helper macro to make generating graphs with connections between nodes easier
-macro_rules! graph {
-    ($graph:ident, $node_refs:ident, [$([$from:expr => [$($to:expr),*]]),*]) => {{
-        $graph.extend_with_edges([$($(($node_refs[$from - 1], $node_refs[$to - 1]),)*)*]);
-    }}
+/// Rotate a tile's axial coordinates a sixth of a turn clockwise around the centre tile
+fn rotate_coordinate((q, r): (i32, i32)) -> (i32, i32) {
+    (-r, q + r)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Board(UnGraph<Tile, Option<Building>>);
+/// Reflect a tile's axial coordinates left to right, keeping each tile in the same row
+fn mirror_coordinate((q, r): (i32, i32)) -> (i32, i32) {
+    (-q - r, r)
+}
+
+/// The tiles of a board and everything built on them
+///
+/// tiles, vertices and edges are kept in flat arrays indexed by their ids, so cloning a board is
+/// just a copy of each array
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "BoardData"))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Board {
+    tiles: Vec<Tile>,
+    vertices: Vec<Vertex>,
+    edges: Vec<Edge>,
+    robber: Option<usize>,
+    /// Zobrist hash of the tiles and everything on them, kept up to date as they change
+    ///
+    /// written out along with the board, but worked out again when it's read back
+    #[cfg_attr(feature = "ts-rs", ts(type = "number"))]
+    hash: u64,
+}
+
+/// A board as it's read back, before its hash is worked out
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+struct BoardData {
+    tiles: Vec<Tile>,
+    vertices: Vec<Vertex>,
+    edges: Vec<Edge>,
+    robber: Option<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<BoardData> for Board {
+    fn from(data: BoardData) -> Self {
+        let mut board = Board {
+            tiles: data.tiles,
+            vertices: data.vertices,
+            edges: data.edges,
+            robber: data.robber,
+            hash: 0,
+        };
+        board.hash = board.full_hash();
+        board
+    }
+}
 
 impl Board {
+    #[cfg(feature = "rand")]
     pub fn new() -> Self {
-        let mut graph: UnGraph<Tile, Option<Building>> = UnGraph::new_undirected();
-        let mut ids: Vec<_> = Vec::new();
-        for _ in 0..DEFAULT_TILE_COUNT {
-            ids.push(graph.add_node(Tile::random()));
-        }
-
-        // FIXME: There's probably a good way to extend this to game boards
-        // with >= 7 tiles in diameter, but this works fine for now
-        graph!(graph, ids, [
-               [1 => [2, 4, 5]],
-               [2 => [1, 5, 6, 3]],
-               [3 => [2, 6, 7]],
-               [4 => [1, 5, 9, 8]],
-               [5 => [1, 2, 6, 10, 9, 4]],
-               [6 => [2, 3, 7, 11, 10, 5]],
-               [7 => [3, 6, 11, 12]],
-               [8 => [4, 9, 13]],
-               [9 => [4, 5, 10, 14, 13, 8]],
-               [10 => [5, 6, 11, 15, 14, 9]],
-               [11 => [6, 7, 12, 16, 15, 10]],
-               [12 => [7, 11, 16]],
-               [13 => [8, 9, 14, 17]],
-               [14 => [9, 10, 11, 16, 19, 18, 14]],
-               [15 => [10, 11, 16, 19, 18, 14]],
-               [16 => [12, 11, 15, 19]],
-               [17 => [13, 14, 18]],
-               [18 => [17, 14, 15, 19]],
-               [19 => [18, 15, 16]]
-        ]);
-
-        Board(graph)
-    }
-}
-
-impl Default for Board {
-    fn default() -> Self {
-        Self(UnGraph::new_undirected())
+        Self::random_with(&mut entropy_rng())
+    }
+
+    /// A random board like `new`, with every tile drawn from `rng`
+    ///
+    /// there's exactly one desert, with the robber's 7 rather than a number token, the rest of
+    /// the tiles share out the base game's tokens between them, and harbors only go on the coast
+    #[cfg(feature = "rand")]
+    pub fn random_with(rng: &mut impl Rng) -> Self {
+        let desert = rng.gen_range(0..DEFAULT_TILE_COUNT);
+        let mut tokens = BASE_GAME_TOKENS;
+        tokens.shuffle(rng);
+        let mut tokens = tokens.into_iter();
+        let coordinates = tile_coordinates();
+        Self::from_tiles(std::array::from_fn(|idx| {
+            let mut tile: Tile = rng.gen();
+            if idx == desert {
+                tile.kind = Desert;
+                tile.token = 7;
+                return tile;
+            }
+            tile.token = tokens
+                .next()
+                .expect("a token for every tile but the desert");
+            let (q, r) = coordinates[idx];
+            let coastal = q.abs().max(r.abs()).max((q + r).abs()) == BOARD_RADIUS;
+            if let (ResourceWithHarbor(_, resource), false) = (tile.kind, coastal) {
+                tile.kind = Resource(resource);
+            }
+            tile
+        }))
+    }
+
+    /// Lay out the given tiles on the default board, row by row from the top left
+    pub fn from_tiles(tiles: [Tile; DEFAULT_TILE_COUNT]) -> Self {
+        let robber = tiles.iter().position(|tile| tile.kind == TileKind::Desert);
+
+        let (vertices, edges) = layout_for(&tile_coordinates());
+
+        let mut board = Board {
+            tiles: tiles.to_vec(),
+            vertices,
+            edges,
+            robber,
+            hash: 0,
+        };
+        board.hash = board.full_hash();
+        board
+    }
+
+    /// The same tiles with nothing built on them, and the robber back on the desert
+    pub fn cleared(&self) -> Self {
+        let mut board = self.clone();
+        for vertex in &mut board.vertices {
+            vertex.building = None;
+        }
+        for edge in &mut board.edges {
+            edge.road = None;
+        }
+        board.robber = board
+            .tiles
+            .iter()
+            .position(|tile| tile.kind == TileKind::Desert);
+        board.hash = board.full_hash();
+        board
+    }
+
+    /// The same board turned `k` sixths of a turn clockwise, with everything built on it moved
+    /// along with its tiles
+    pub fn rotate(&self, k: usize) -> Self {
+        self.transformed(|mut coord| {
+            for _ in 0..k % 6 {
+                coord = rotate_coordinate(coord);
+            }
+            coord
+        })
+    }
+
+    /// The same board reflected left to right, with everything built on it moved along with its
+    /// tiles
+    pub fn mirror(&self) -> Self {
+        self.transformed(mirror_coordinate)
+    }
+
+    /// Every rotation of this board and of its mirror image, starting with the board itself
+    pub fn symmetries(&self) -> Vec<Self> {
+        let mirrored = self.mirror();
+        (0..6)
+            .map(|k| self.rotate(k))
+            .chain((0..6).map(|k| mirrored.rotate(k)))
+            .collect()
+    }
+
+    /// The symmetry of this board which sorts first, so that boards which are rotations or
+    /// reflections of each other share a canonical form
+    ///
+    /// boards are compared tile by tile on their kind and token, then on the robber, buildings
+    /// and roads
+    pub fn canonical(&self) -> Self {
+        self.symmetries()
+            .into_iter()
+            .min_by_key(|board| board.symmetry_key())
+            .expect("a board has at least one symmetry")
+    }
+
+    /// Everything about the board which should survive a rotation or reflection, in an order
+    /// which can be compared
+    #[allow(clippy::type_complexity)]
+    fn symmetry_key(
+        &self,
+    ) -> (
+        Vec<(TileKind, usize)>,
+        Option<usize>,
+        Vec<Option<(PlayerColour, Building)>>,
+        Vec<Option<PlayerColour>>,
+    ) {
+        (
+            self.tiles
+                .iter()
+                .map(|tile| (tile.kind, tile.token))
+                .collect(),
+            self.robber,
+            self.vertices.iter().map(|vertex| vertex.building).collect(),
+            self.edges.iter().map(|edge| edge.road).collect(),
+        )
+    }
+
+    /// Move every tile from its coordinates to `f` of them, bringing the robber, buildings and
+    /// roads along too
+    fn transformed(&self, f: impl Fn((i32, i32)) -> (i32, i32)) -> Self {
+        let coords = tile_coordinates();
+        if self.tile_count() != coords.len() {
+            return self.clone();
+        }
+        let moved: Vec<usize> = coords
+            .iter()
+            .map(|coord| {
+                let to = f(*coord);
+                coords
+                    .iter()
+                    .position(|c| *c == to)
+                    .expect("board is symmetric")
+            })
+            .collect();
+
+        let tiles = std::array::from_fn(|idx| {
+            let from = moved
+                .iter()
+                .position(|to| *to == idx)
+                .expect("tiles are permuted");
+            self.tiles[from]
+        });
+        let mut board = Board::from_tiles(tiles);
+        board.robber = self.robber.map(|tile| moved[tile]);
+
+        let moved_vertex = |vertex: VertexId| {
+            let mut key = self.vertices[vertex.0].position.map(&f);
+            key.sort();
+            self.vertex_at(key).expect("vertices are permuted")
+        };
+        for vertex in &self.vertices {
+            board.vertices[moved_vertex(vertex.id).0].building = vertex.building;
+        }
+        for edge in self.edges.iter().filter(|edge| edge.road.is_some()) {
+            let [a, b] = edge.vertices.map(moved_vertex);
+            if let Some(id) = board.edge_between(a, b) {
+                board.edges[id.0].road = edge.road;
+            }
+        }
+        board.hash = board.full_hash();
+        board
+    }
+
+    /// Zobrist hash of the board, covering its tiles, the robber, and every building and road
+    ///
+    /// the hash is updated as pieces are placed and the robber moves, so it's free to read, and
+    /// equal boards always have equal hashes
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    /// Work the board's hash out from scratch
+    pub(crate) fn full_hash(&self) -> u64 {
+        let tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(idx, tile)| tile_key(idx, tile));
+        let buildings = self
+            .vertices
+            .iter()
+            .filter_map(|vertex| vertex.building.map(|b| building_key(vertex.id, b)));
+        let roads = self
+            .edges
+            .iter()
+            .filter_map(|edge| edge.road.map(|owner| road_key(edge.id, owner)));
+        tiles
+            .chain(buildings)
+            .chain(roads)
+            .chain(self.robber.map(robber_key))
+            .fold(0, |hash, key| hash ^ key)
+    }
+
+    /// Index of the tile the robber is currently on, which starts out on the desert
+    pub fn robber(&self) -> Option<usize> {
+        self.robber
+    }
+
+    pub(crate) fn set_robber(&mut self, tile: usize) -> Result<()> {
+        if tile >= self.tile_count() {
+            return Err(anyhow!("No tile with that index"));
+        }
+        self.remove_robber();
+        self.robber = Some(tile);
+        self.hash ^= robber_key(tile);
+        Ok(())
+    }
+
+    /// Take the robber off the board, so it blocks nothing until it's next moved
+    pub(crate) fn remove_robber(&mut self) {
+        if let Some(tile) = self.robber.take() {
+            self.hash ^= robber_key(tile);
+        }
+    }
+
+    pub fn tile(&self, index: usize) -> Option<&Tile> {
+        self.tiles.get(index)
+    }
+
+    /// Index of the tile with the given id
+    pub fn tile_index(&self, id: &Uuid) -> Option<usize> {
+        self.tiles.iter().position(|tile| tile.id == *id)
+    }
+
+    /// The tile with the given id
+    ///
+    /// there's no mutable version, since tiles are only swapped out through the board so its
+    /// hash stays up to date
+    pub fn tile_by_id(&self, id: &Uuid) -> Option<&Tile> {
+        self.tile_index(id).map(|idx| &self.tiles[idx])
+    }
+
+    /// Swap the tile at `index` for another
+    pub(crate) fn set_tile(&mut self, index: usize, tile: Tile) -> Result<()> {
+        let slot = self
+            .tiles
+            .get_mut(index)
+            .ok_or(anyhow!("No tile with that index"))?;
+        let old = std::mem::replace(slot, tile);
+        self.hash ^= tile_key(index, &old) ^ tile_key(index, &tile);
+        Ok(())
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn vertex(&self, id: VertexId) -> Option<&Vertex> {
+        self.vertices.get(id.0)
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn edge(&self, id: EdgeId) -> Option<&Edge> {
+        self.edges.get(id.0)
+    }
+
+    /// The vertex where the three tiles at these coordinates meet, given in any order, see
+    /// `Vertex::position`
+    pub fn vertex_at(&self, mut position: [(i32, i32); 3]) -> Option<VertexId> {
+        position.sort();
+        self.vertices
+            .iter()
+            .find(|vertex| vertex.position == position)
+            .map(|vertex| vertex.id)
+    }
+
+    /// The edge between the two tiles at these coordinates, given in any order, see
+    /// `Edge::position`
+    pub fn edge_at(&self, mut position: [(i32, i32); 2]) -> Option<EdgeId> {
+        position.sort();
+        self.edges
+            .iter()
+            .find(|edge| edge.position == position)
+            .map(|edge| edge.id)
+    }
+
+    /// The edge joining two neighbouring vertices, if they are neighbours
+    pub fn edge_between(&self, a: VertexId, b: VertexId) -> Option<EdgeId> {
+        let mut ends = [a, b];
+        ends.sort();
+        self.edges
+            .iter()
+            .find(|edge| edge.vertices == ends)
+            .map(|edge| edge.id)
+    }
+
+    /// Vertices one edge away from `vertex`
+    pub fn adjacent_vertices(&self, vertex: VertexId) -> impl Iterator<Item = VertexId> + '_ {
+        self.edges
+            .iter()
+            .filter_map(move |edge| match edge.vertices {
+                [a, b] if a == vertex => Some(b),
+                [a, b] if b == vertex => Some(a),
+                _ => None,
+            })
+    }
+
+    /// Whether a settlement could go at `vertex` without breaking the distance rule, i.e. the
+    /// vertex and all of its neighbours are empty
+    pub fn satisfies_distance_rule(&self, vertex: VertexId) -> bool {
+        let occupied = |id: VertexId| matches!(self.vertex(id), Some(v) if v.building.is_some());
+        self.vertex(vertex).is_some()
+            && !occupied(vertex)
+            && !self.adjacent_vertices(vertex).any(occupied)
+    }
+
+    /// The harbor `vertex` can trade through, i.e. a harbor tile it sits on the coast of
+    pub fn harbor(&self, vertex: VertexId) -> Option<HarborKind> {
+        let vertex = self.vertex(vertex)?;
+        if vertex.tiles().len() == 3 {
+            return None;
+        }
+        vertex
+            .tiles()
+            .iter()
+            .find_map(|tile| match self.tile(*tile)?.kind() {
+                TileKind::ResourceWithHarbor(harbor, _) => Some(*harbor),
+                _ => None,
+            })
+    }
+
+    /// Harbor points for the Harbormaster card, one for each of `player`'s settlements on a
+    /// harbor and two for each city
+    pub fn harbor_points(&self, player: &PlayerColour) -> usize {
+        self.vertices
+            .iter()
+            .filter(|vertex| self.harbor(vertex.id).is_some())
+            .map(|vertex| match vertex.building() {
+                Some((owner, Building::Settlement)) if owner == player => 1,
+                Some((owner, Building::City)) if owner == player => 2,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Vertices at the corners of the tile at `tile`
+    pub fn vertices_of_tile(&self, tile: usize) -> impl Iterator<Item = &Vertex> {
+        self.vertices
+            .iter()
+            .filter(move |vertex| vertex.tiles().contains(&tile))
+    }
+
+    /// Every building on a corner of the tile at `tile`, with its owner and where it stands
+    pub fn buildings_of_tile(
+        &self,
+        tile: usize,
+    ) -> impl Iterator<Item = (VertexId, PlayerColour, Building)> + '_ {
+        self.vertices_of_tile(tile).filter_map(|vertex| {
+            let (owner, building) = vertex.building?;
+            Some((vertex.id, owner, building))
+        })
+    }
+
+    /// Indices of the tiles sharing a side with the tile at `tile`, in order
+    pub fn adjacent_tiles(&self, tile: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut neighbours: Vec<usize> = self
+            .vertices_of_tile(tile)
+            .flat_map(|vertex| vertex.tiles().iter().copied())
+            .filter(|other| *other != tile)
+            .collect();
+        neighbours.sort();
+        neighbours.dedup();
+        neighbours.into_iter()
+    }
+
+    /// Edges around the sides of the tile at `tile`
+    pub fn edges_of_tile(&self, tile: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| {
+            edge.vertices
+                .iter()
+                .all(|v| matches!(self.vertex(*v), Some(vertex) if vertex.tiles().contains(&tile)))
+        })
+    }
+
+    /// Place, replace or remove the building at a vertex
+    pub(crate) fn set_building(
+        &mut self,
+        id: VertexId,
+        building: Option<(PlayerColour, Building)>,
+    ) -> Result<()> {
+        let vertex = self
+            .vertices
+            .get_mut(id.0)
+            .ok_or(anyhow!("No vertex with that ID"))?;
+        let old = std::mem::replace(&mut vertex.building, building);
+        for building in old.into_iter().chain(building) {
+            self.hash ^= building_key(id, building);
+        }
+        Ok(())
+    }
+
+    /// Place, replace or remove the road along an edge
+    pub(crate) fn set_road(&mut self, id: EdgeId, road: Option<PlayerColour>) -> Result<()> {
+        let edge = self
+            .edges
+            .get_mut(id.0)
+            .ok_or(anyhow!("No edge with that ID"))?;
+        let old = std::mem::replace(&mut edge.road, road);
+        for owner in old.into_iter().chain(road) {
+            self.hash ^= road_key(id, owner);
+        }
+        Ok(())
+    }
+
+    /// Number of edges in `player`'s longest continuous road, where no edge is counted twice
+    ///
+    /// a road can end at a vertex built on by another player, but can't continue through it
+    pub fn longest_road(&self, player: &PlayerColour) -> usize {
+        let mut used = vec![false; self.edges.len()];
+        self.edges
+            .iter()
+            .filter(|edge| edge.road.as_ref() == Some(player))
+            .flat_map(|edge| edge.vertices)
+            .map(|start| self.extend_road(player, start, &mut used))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Length of the longest road `player` could follow on from `from`, without using any of the
+    /// edges in `used`
+    fn extend_road(&self, player: &PlayerColour, from: VertexId, used: &mut Vec<bool>) -> usize {
+        let mut longest = 0;
+        for edge in &self.edges {
+            if edge.road.as_ref() != Some(player)
+                || used[edge.id.0]
+                || !edge.vertices.contains(&from)
+            {
+                continue;
+            }
+            let next = match edge.vertices {
+                [a, b] if a == from => b,
+                [a, _] => a,
+            };
+
+            let building = self.vertex(next).and_then(|v| v.building());
+            if matches!(building, Some((owner, _)) if owner != player) {
+                longest = longest.max(1);
+                continue;
+            }
+
+            used[edge.id.0] = true;
+            longest = longest.max(1 + self.extend_road(player, next, used));
+            used[edge.id.0] = false;
+        }
+        longest
     }
 }
 
-impl PartialEq for Board {
-    fn eq(&self, other: &Self) -> bool {
-        let nodes_match = self
-            .0
-            .node_indices()
-            .zip(other.0.node_indices())
-            .all(|(lhs_i, rhs_i)| self.0[lhs_i] == other.0[rhs_i]);
+fn tile_key(idx: usize, tile: &Tile) -> u64 {
+    let resource = |kind: ResourceKind| kind as u64 + 1;
+    let kind = match tile.kind {
+        TileKind::Resource(kind) => resource(kind),
+        TileKind::Desert => 0,
+        TileKind::ResourceWithHarbor(HarborKind::Generic, kind) => 0x10 | resource(kind),
+        TileKind::ResourceWithHarbor(HarborKind::Special(special), kind) => {
+            0x20 | (resource(special) << 8) | resource(kind)
+        }
+    };
+    key(Feature::Tile, &[idx as u64, kind, tile.token as u64])
+}
 
-        let edges_match = self.0.edge_indices().all(|idx| self.0[idx] == other.0[idx]);
+fn robber_key(tile: usize) -> u64 {
+    key(Feature::Robber, &[tile as u64])
+}
 
-        nodes_match && edges_match
+fn building_key(vertex: VertexId, (owner, building): (PlayerColour, Building)) -> u64 {
+    key(
+        Feature::Building,
+        &[vertex.0 as u64, colour_code(&owner), building as u64],
+    )
+}
+
+fn road_key(edge: EdgeId, owner: PlayerColour) -> u64 {
+    key(Feature::Road, &[edge.0 as u64, colour_code(&owner)])
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.tiles == other.tiles
+            && self.vertices == other.vertices
+            && self.edges == other.edges
+            && self.robber == other.robber
     }
 }
 
 impl Index<usize> for Board {
-    type Output = Node<Tile>;
+    type Output = Tile;
     fn index(&self, target: usize) -> &Self::Output {
         if target > DEFAULT_TILE_COUNT {
             panic!("Index out of bounds");
         }
-        &self.0.raw_nodes()[target]
+        &self.tiles[target]
     }
 }
 
 impl Eq for Board {}
-#[cfg(test)]
+
+/// Hashes the board's Zobrist hash, which equal boards always share
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
 mod test {
     use std::panic::catch_unwind;
 
     use uuid::Uuid;
 
-    use super::{Board, Tile};
+    use super::{Board, EdgeId, HarborKind, Tile, TileKind, VertexId};
+    use crate::building::Building;
+    use crate::player::PlayerColour;
+    use crate::resources::ResourceKind;
 
     #[test]
     fn test_random() {
@@ -209,21 +983,389 @@ mod test {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn test_tile_distribution() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let kinds: Vec<TileKind> = (0..18_000).map(|_| rng.gen()).collect();
+        assert!(!kinds.contains(&TileKind::Desert));
+        let generic = kinds
+            .iter()
+            .filter(|k| matches!(k, TileKind::ResourceWithHarbor(HarborKind::Generic, _)))
+            .count();
+        // half of the 18000 resource tiles have harbors, and 4 in 9 of those are generic
+        assert!(generic.abs_diff(4000) < 300);
+    }
+
+    #[test]
+    fn test_random_boards_validate() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut tokens = super::BASE_GAME_TOKENS.to_vec();
+        tokens.sort();
+        for seed in 0..50 {
+            let b = Board::random_with(&mut StdRng::seed_from_u64(seed));
+            assert_eq!(b.validate(), Ok(()), "seed {}", seed);
+
+            let deserts: Vec<_> = (0..b.tile_count())
+                .filter(|&idx| *b[idx].kind() == TileKind::Desert)
+                .collect();
+            assert_eq!(deserts.len(), 1);
+            assert_eq!(b.robber(), Some(deserts[0]));
+            let mut dealt: Vec<_> = (0..b.tile_count())
+                .filter(|&idx| idx != deserts[0])
+                .map(|idx| *b[idx].token())
+                .collect();
+            dealt.sort();
+            assert_eq!(dealt, tokens);
+        }
+    }
+
     #[test]
     fn test_init() {
         let b = Board::new();
 
-        for node_idx in b.0.node_indices() {
-            let node = b.0[node_idx];
-            assert!(Uuid::parse_str(&node.id().to_string()).is_ok());
-            assert!(2 <= *node.token() && *node.token() <= 12)
+        for tile in &b.tiles {
+            assert!(Uuid::parse_str(&tile.id().to_string()).is_ok());
+            assert!(2 <= *tile.token() && *tile.token() <= 12)
+        }
+
+        assert_eq!(b.tile_count(), 19);
+        assert_eq!(b[4], b.tiles[4]);
+    }
+
+    #[test]
+    fn test_vertices() {
+        let b = Board::new();
+        assert_eq!(b.vertices().len(), 54);
+
+        // every tile has six corners, and the centre tile shares each of them with two others
+        for tile in 0..19 {
+            assert_eq!(b.vertices_of_tile(tile).count(), 6);
+        }
+        assert!(b.vertices_of_tile(9).all(|v| v.tiles().len() == 3));
+
+        let coastal = b.vertices().iter().filter(|v| v.tiles().len() < 3).count();
+        assert_eq!(coastal, 30);
+
+        assert_eq!(b.vertex(VertexId::new(0)).unwrap().tiles(), &[0]);
+        assert!(b.vertex(VertexId::new(54)).is_none());
+    }
+
+    #[test]
+    fn test_tile_lookup() {
+        let b = Board::new();
+        let id = *b.tiles[4].id();
+        assert_eq!(b.tile_index(&id), Some(4));
+        assert_eq!(b.tile_by_id(&id), Some(&b.tiles[4]));
+        assert!(b.tile_by_id(&Uuid::new_v4()).is_none());
+
+        // the centre tile is surrounded, corner tiles only touch three others
+        assert_eq!(
+            b.adjacent_tiles(9).collect::<Vec<_>>(),
+            vec![4, 5, 8, 10, 13, 14]
+        );
+        assert_eq!(b.adjacent_tiles(0).collect::<Vec<_>>(), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_buildings_of_tile() {
+        let mut b = Board::new();
+        b.set_building(VertexId::new(0), Some((PlayerColour::Red, Building::City)))
+            .unwrap();
+        b.set_building(
+            VertexId::new(2),
+            Some((PlayerColour::Blue, Building::Settlement)),
+        )
+        .unwrap();
+        assert_eq!(
+            b.buildings_of_tile(0).collect::<Vec<_>>(),
+            vec![
+                (VertexId::new(0), PlayerColour::Red, Building::City),
+                (VertexId::new(2), PlayerColour::Blue, Building::Settlement),
+            ]
+        );
+        assert_eq!(b.buildings_of_tile(18).count(), 0);
+    }
+
+    #[test]
+    fn test_positions() {
+        let b = Board::new();
+        for vertex in b.vertices() {
+            let [x, y, z] = *vertex.position();
+            assert_eq!(b.vertex_at([z, x, y]), Some(*vertex.id()));
+        }
+        for edge in b.edges() {
+            let [x, y] = *edge.position();
+            assert_eq!(b.edge_at([y, x]), Some(*edge.id()));
+            for end in edge.vertices() {
+                let corner = b.vertex(*end).unwrap().position();
+                assert!(corner.contains(&x) && corner.contains(&y));
+            }
+        }
+        assert!(b.vertex_at([(5, 5), (5, 6), (6, 5)]).is_none());
+
+        // the same vertex has the same position on any board
+        let other = Board::new();
+        assert_eq!(other.vertices()[7].position(), b.vertices()[7].position());
+    }
+
+    #[test]
+    fn test_edges() {
+        let mut b = Board::new();
+        assert_eq!(b.edges().len(), 72);
+
+        // corner vertices on the coast have two neighbours, everything else has three
+        let vertex = VertexId::new(0);
+        assert_eq!(b.adjacent_vertices(vertex).count(), 2);
+        assert_eq!(b.adjacent_vertices(VertexId::new(2)).count(), 3);
+        assert!(b
+            .vertices()
+            .iter()
+            .all(|v| (2..=3).contains(&b.adjacent_vertices(*v.id()).count())));
+
+        assert!(b.satisfies_distance_rule(vertex));
+        b.set_building(vertex, Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        assert!(!b.satisfies_distance_rule(vertex));
+        assert!(!b.satisfies_distance_rule(VertexId::new(1)));
+        assert!(b.satisfies_distance_rule(VertexId::new(2)));
+        assert!(!b.satisfies_distance_rule(VertexId::new(54)));
+    }
+
+    #[test]
+    fn test_longest_road() {
+        let mut b = Board::new();
+        assert_eq!(b.longest_road(&PlayerColour::Red), 0);
+        assert_eq!(
+            b.edge_between(VertexId::new(1), VertexId::new(0)),
+            b.edge_between(VertexId::new(0), VertexId::new(1))
+        );
+        assert_eq!(b.edge_between(VertexId::new(0), VertexId::new(3)), None);
+
+        let ring: Vec<_> = b.edges_of_tile(9).map(|edge| *edge.id()).collect();
+        assert_eq!(ring.len(), 6);
+        for edge in &ring[..5] {
+            b.set_road(*edge, Some(PlayerColour::Red)).unwrap();
+        }
+        assert_eq!(b.longest_road(&PlayerColour::Red), 5);
+        assert_eq!(b.longest_road(&PlayerColour::Blue), 0);
+
+        // closing the loop still only counts each edge once
+        b.set_road(ring[5], Some(PlayerColour::Red)).unwrap();
+        assert_eq!(b.longest_road(&PlayerColour::Red), 6);
+
+        // a branch off the loop adds to the longest road, but a second branch can't
+        let corner = *b.edge(ring[0]).unwrap().vertices();
+        let spurs: Vec<_> = corner
+            .iter()
+            .flat_map(|v| b.adjacent_vertices(*v).map(move |n| (*v, n)))
+            .filter_map(|(v, n)| b.edge_between(v, n))
+            .filter(|edge| !ring.contains(edge))
+            .collect();
+        assert_eq!(spurs.len(), 2);
+        b.set_road(spurs[0], Some(PlayerColour::Red)).unwrap();
+        assert_eq!(b.longest_road(&PlayerColour::Red), 7);
+        b.set_road(spurs[1], Some(PlayerColour::Red)).unwrap();
+        assert_eq!(b.longest_road(&PlayerColour::Red), 7);
+    }
+
+    #[test]
+    fn test_longest_road_interrupted() {
+        let mut b = Board::new();
+        let ring: Vec<_> = b.edges_of_tile(9).map(|edge| *edge.id()).collect();
+        for edge in &ring[..5] {
+            b.set_road(*edge, Some(PlayerColour::Red)).unwrap();
+        }
+
+        // a vertex between two of red's road segments
+        let gap = *b.edge(ring[5]).unwrap().vertices();
+        let middle = *b
+            .edge(ring[0])
+            .unwrap()
+            .vertices()
+            .iter()
+            .find(|v| !gap.contains(v))
+            .unwrap();
+
+        // red's own buildings don't break the road
+        b.set_building(middle, Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        assert_eq!(b.longest_road(&PlayerColour::Red), 5);
+
+        b.set_building(middle, Some((PlayerColour::Blue, Building::Settlement)))
+            .unwrap();
+        assert!(b.longest_road(&PlayerColour::Red) < 5);
+        assert_eq!(b.longest_road(&PlayerColour::Blue), 0);
+    }
+
+    #[test]
+    fn test_harbor_points() {
+        let harbor = TileKind::ResourceWithHarbor(HarborKind::Generic, ResourceKind::Brick);
+        let mut b = Board::from_tiles(std::array::from_fn(|idx| match idx {
+            0 => Tile::new(harbor, 5),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Ore), 6),
+        }));
+
+        // vertex 0 is on the coast of tile 0, vertex 2 is inland
+        assert_eq!(b.harbor(VertexId::new(0)), Some(HarborKind::Generic));
+        assert_eq!(b.harbor(VertexId::new(2)), None);
+
+        b.set_building(VertexId::new(2), Some((PlayerColour::Red, Building::City)))
+            .unwrap();
+        assert_eq!(b.harbor_points(&PlayerColour::Red), 0);
+        b.set_building(
+            VertexId::new(0),
+            Some((PlayerColour::Red, Building::Settlement)),
+        )
+        .unwrap();
+        assert_eq!(b.harbor_points(&PlayerColour::Red), 1);
+        b.set_building(VertexId::new(0), Some((PlayerColour::Red, Building::City)))
+            .unwrap();
+        assert_eq!(b.harbor_points(&PlayerColour::Red), 2);
+        assert_eq!(b.harbor_points(&PlayerColour::Blue), 0);
+    }
+
+    /// A board with every tile different, so that no two symmetries look the same
+    fn asymmetric_board() -> Board {
+        let kinds = ResourceKind::ALL;
+        Board::from_tiles(std::array::from_fn(|idx| {
+            Tile::new(TileKind::Resource(kinds[idx % kinds.len()]), idx + 2)
+        }))
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut b = asymmetric_board();
+        b.set_building(
+            VertexId::new(2),
+            Some((PlayerColour::Red, Building::Settlement)),
+        )
+        .unwrap();
+        let road = b.edge_between(VertexId::new(1), VertexId::new(2)).unwrap();
+        b.set_road(road, Some(PlayerColour::Red)).unwrap();
+        b.set_robber(0).unwrap();
+
+        // the top row is (0, -2), (1, -2), (2, -2), and the next starts at (-1, -1)
+        let rotated = b.rotate(1);
+        assert_eq!(rotated.tile(2).unwrap().kind(), b.tile(0).unwrap().kind());
+        assert_eq!(rotated.tile(9).unwrap().kind(), b.tile(9).unwrap().kind());
+        assert_eq!(rotated.robber(), Some(2));
+
+        // vertex 2 sits between tiles 0, 1 and 4, which turn into tiles 2, 6 and 5
+        let settled: Vec<_> = rotated
+            .vertices()
+            .iter()
+            .filter(|vertex| vertex.building().is_some())
+            .collect();
+        assert_eq!(settled.len(), 1);
+        let mut tiles = settled[0].tiles().to_vec();
+        tiles.sort();
+        assert_eq!(tiles, vec![2, 5, 6]);
+        assert_eq!(rotated.longest_road(&PlayerColour::Red), 1);
+
+        assert_eq!(b.rotate(6).symmetry_key(), b.symmetry_key());
+        assert_eq!(b.rotate(2).rotate(4).symmetry_key(), b.symmetry_key());
+    }
+
+    #[test]
+    fn test_mirror() {
+        let b = asymmetric_board();
+        let mirrored = b.mirror();
+        assert_eq!(mirrored.tile(0).unwrap().kind(), b.tile(2).unwrap().kind());
+        assert_eq!(mirrored.tile(3).unwrap().kind(), b.tile(6).unwrap().kind());
+        assert_eq!(mirrored.mirror().symmetry_key(), b.symmetry_key());
+    }
+
+    #[test]
+    fn test_canonical() {
+        let b = asymmetric_board();
+        let canonical = b.canonical().symmetry_key();
+        for symmetry in b.symmetries() {
+            assert_eq!(symmetry.canonical().symmetry_key(), canonical);
         }
+        assert!(b
+            .symmetries()
+            .iter()
+            .all(|board| canonical <= board.symmetry_key()));
+        assert_eq!(b.symmetries().len(), 12);
+    }
+
+    #[test]
+    fn test_zobrist() {
+        let mut b = asymmetric_board();
+        let empty = b.zobrist();
+        assert_eq!(empty, b.full_hash());
+
+        b.set_building(VertexId(4), Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        b.set_road(EdgeId(2), Some(PlayerColour::Blue)).unwrap();
+        b.set_robber(5).unwrap();
+        assert_ne!(b.zobrist(), empty);
+        assert_eq!(b.zobrist(), b.full_hash());
+
+        // upgrading swaps one building's key for another's
+        b.set_building(VertexId(4), Some((PlayerColour::Red, Building::City)))
+            .unwrap();
+        assert_eq!(b.zobrist(), b.full_hash());
+        assert_eq!(b.rotate(1).zobrist(), b.rotate(1).full_hash());
+
+        b.set_building(VertexId(4), None).unwrap();
+        b.set_road(EdgeId(2), None).unwrap();
+        b.remove_robber();
+        assert_eq!(b.zobrist(), empty);
+    }
+
+    #[test]
+    fn test_cleared() {
+        let mut b = asymmetric_board();
+        let empty = b.clone();
+        b.set_building(VertexId(4), Some((PlayerColour::Red, Building::City)))
+            .unwrap();
+        b.set_road(EdgeId(2), Some(PlayerColour::Blue)).unwrap();
+        b.set_robber(5).unwrap();
+
+        let cleared = b.cleared();
+        assert_eq!(cleared, empty);
+        assert_eq!(cleared.zobrist(), empty.zobrist());
+    }
+
+    #[test]
+    fn test_eq_and_hash() {
+        use std::collections::HashSet;
+
+        let b = Board::new();
+        assert_ne!(Board::default(), b);
+        assert_ne!(b, Board::default());
+
+        let mut moved = b.clone();
+        let tile = (0..moved.tile_count())
+            .find(|&idx| Some(idx) != b.robber())
+            .unwrap();
+        moved.set_robber(tile).unwrap();
+        assert_ne!(moved, b);
+
+        let boards: HashSet<_> = [Board::default(), b.clone(), b.clone(), moved].into();
+        assert_eq!(boards.len(), 3);
+    }
+
+    #[test]
+    fn test_robber_starts_on_desert() {
+        let tiles = std::array::from_fn(|idx| match idx {
+            7 => Tile::new(TileKind::Desert, 7),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Ore), 6),
+        });
+        let mut b = Board::from_tiles(tiles);
+        assert_eq!(b.robber(), Some(7));
 
-        assert_eq!(b.0.node_count(), 19);
-        assert_eq!(b.0.edge_count(), 85);
+        assert!(b.set_robber(19).is_err());
+        b.set_robber(3).unwrap();
+        assert_eq!(b.robber(), Some(3));
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn test_serde() {
         let b = Board::new();
 
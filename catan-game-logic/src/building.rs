@@ -1,11 +1,20 @@
 use Building::*;
 
+use crate::options::CostTable;
 use crate::resources::Resources;
 
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 
-#[derive(Debug, Copy, Serialize, Deserialize, Clone, Eq, PartialEq)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Building {
     Settlement,
     City,
@@ -13,11 +22,17 @@ pub enum Building {
 }
 
 impl Building {
+    /// Price in the official rules, a game's own prices are in `GameOptions::costs`
     pub fn get_resource_cost(&self) -> Resources {
+        CostTable::default().building(*self)
+    }
+
+    /// Number of resources collected from each adjacent tile when its number is rolled
+    pub fn production(&self) -> usize {
         match *self {
-            Settlement => Resources::new_explicit(0, 1, 1, 1, 1),
-            City => Resources::new_explicit(3, 2, 0, 0, 0),
-            Road => Resources::new_explicit(0, 0, 0, 1, 1),
+            Settlement => 1,
+            City => 2,
+            Road => 0,
         }
     }
 }
@@ -0,0 +1,228 @@
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::awards::Award;
+use crate::events::{Decision, GameEvent};
+use crate::platform::now;
+use crate::player::PlayerColour;
+
+/// Longest message a player can send, in characters
+pub const MAX_CHAT_MESSAGE_LEN: usize = 500;
+
+/// Who a chat message came from
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChatAuthor {
+    Player(PlayerColour),
+    /// The game itself, announcing something which happened at the table
+    System,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ChatMessage {
+    /// Numbered from 0 in the order messages were sent, like events
+    pub seq: usize,
+    pub author: ChatAuthor,
+    pub text: String,
+    #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::SystemTimeJson"))]
+    pub sent_at: SystemTime,
+}
+
+/// The table talk of a game, from players haggling over trades to the game announcing what
+/// happened
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Chat {
+    messages: Vec<ChatMessage>,
+}
+
+impl Chat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every message so far, oldest first
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Every message from sequence number `since` onwards
+    pub fn since(&self, since: usize) -> impl Iterator<Item = &ChatMessage> {
+        self.messages.iter().skip(since)
+    }
+
+    /// Sequence number the next message will get
+    pub fn next_message(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Send `text` from `player`, trimmed of any whitespace around it
+    pub fn say(&mut self, player: PlayerColour, text: &str) -> Result<&ChatMessage> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(anyhow!("There's nothing to say"));
+        }
+        if text.chars().count() > MAX_CHAT_MESSAGE_LEN {
+            return Err(anyhow!(
+                "Messages can be at most {} characters",
+                MAX_CHAT_MESSAGE_LEN
+            ));
+        }
+        Ok(self.push(ChatAuthor::Player(player), text.into()))
+    }
+
+    /// Announce `text` to the table
+    pub fn announce(&mut self, text: impl Into<String>) -> &ChatMessage {
+        self.push(ChatAuthor::System, text.into())
+    }
+
+    /// Announce whichever of `events` are worth a mention in the chat, like robberies and
+    /// trades, returning how many were
+    pub fn announce_events(&mut self, events: &[GameEvent]) -> usize {
+        let before = self.messages.len();
+        for text in events.iter().filter_map(announcement) {
+            self.announce(text);
+        }
+        self.messages.len() - before
+    }
+
+    fn push(&mut self, author: ChatAuthor, text: String) -> &ChatMessage {
+        self.messages.push(ChatMessage {
+            seq: self.messages.len(),
+            author,
+            text,
+            sent_at: now(),
+        });
+        self.messages.last().expect("a message was just pushed")
+    }
+}
+
+/// What the chat says about `event`, if anything
+fn announcement(event: &GameEvent) -> Option<String> {
+    let text = match event {
+        GameEvent::RobberMoved {
+            player,
+            victim: Some(victim),
+            stole_card: true,
+            ..
+        } => format!("{} stole from {}", name(player), name(victim)),
+        GameEvent::MonopolyPlayed {
+            player,
+            resource,
+            taken,
+            ..
+        } => format!(
+            "{} played a monopoly on {}, taking {}",
+            name(player),
+            resource,
+            taken
+        ),
+        GameEvent::TradeCompleted { from, to, .. } => {
+            format!("{} traded with {}", name(from), name(to))
+        }
+        GameEvent::AwardChanged {
+            award,
+            holder: Some(holder),
+        } => format!("{} took {}", name(holder), award_name(award)),
+        GameEvent::TimedOut { player, decision } => {
+            let decision = match decision {
+                Decision::Discard => "discard",
+                Decision::MoveRobber => "move the robber",
+                Decision::TradeResponse => "answer a trade",
+                Decision::Move => "move",
+            };
+            format!("{} ran out of time to {}", name(player), decision)
+        }
+        GameEvent::GameWon { player } => format!("{} won the game", name(player)),
+        GameEvent::Resigned { player } => format!("{} resigned", name(player)),
+        GameEvent::GameEnded { winner: None } => "The game was ended".into(),
+        GameEvent::GameEnded {
+            winner: Some(winner),
+        } => format!("The game was ended, with {} as the winner", name(winner)),
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// `player`'s colour, capitalised to start a sentence
+fn name(player: &PlayerColour) -> String {
+    let colour = player.to_string();
+    let mut chars = colour.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => colour,
+    }
+}
+
+fn award_name(award: &Award) -> &'static str {
+    match award {
+        Award::LongestRoad => "the longest road",
+        Award::LargestArmy => "the largest army",
+        Award::Harbormaster => "the harbormaster",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_say() {
+        let mut chat = Chat::new();
+        let message = chat.say(PlayerColour::Red, "  wood for sheep?  ").unwrap();
+        assert_eq!(message.text, "wood for sheep?");
+        assert_eq!(message.author, ChatAuthor::Player(PlayerColour::Red));
+
+        assert!(chat.say(PlayerColour::Blue, "   ").is_err());
+        let long = "a".repeat(MAX_CHAT_MESSAGE_LEN + 1);
+        assert!(chat.say(PlayerColour::Blue, &long).is_err());
+        chat.say(PlayerColour::Blue, "no").unwrap();
+
+        let seqs: Vec<_> = chat.since(1).map(|message| message.seq).collect();
+        assert_eq!(seqs, vec![1]);
+        assert_eq!(chat.next_message(), 2);
+    }
+
+    #[test]
+    fn test_announce_events() {
+        let mut chat = Chat::new();
+        let events = [
+            GameEvent::SettlementBuilt {
+                player: PlayerColour::Red,
+            },
+            GameEvent::RobberMoved {
+                player: PlayerColour::Red,
+                tile: 3,
+                victim: Some(PlayerColour::Blue),
+                stole_card: true,
+            },
+            GameEvent::AwardChanged {
+                award: Award::LongestRoad,
+                holder: Some(PlayerColour::Blue),
+            },
+        ];
+        assert_eq!(chat.announce_events(&events), 2);
+        let texts: Vec<_> = chat.messages().iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Red stole from Blue", "Blue took the longest road"]
+        );
+        assert!(chat
+            .messages()
+            .iter()
+            .all(|m| m.author == ChatAuthor::System));
+    }
+}
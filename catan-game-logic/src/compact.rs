@@ -0,0 +1,297 @@
+use anyhow::{anyhow, Result};
+
+use crate::board::{Board, EdgeId, HarborKind, Tile, TileKind, VertexId, DEFAULT_TILE_COUNT};
+use crate::building::Building;
+use crate::development_cards::DevelopmentCard;
+use crate::player::{Player, PlayerColour};
+use crate::resources::{ResourceKind, Resources};
+
+/// Version byte at the start of every compact encoding, bumped whenever the layout changes
+pub const COMPACT_VERSION: u8 = 2;
+
+/// Stands for "nobody" wherever a player index is expected
+pub(crate) const NOBODY: u8 = u8::MAX;
+
+/// Counts are stored in a single byte, which is plenty for anything in a game of Catan
+pub(crate) fn small(n: usize) -> u8 {
+    n.min(u8::MAX as usize) as u8
+}
+
+/// Reads back the bytes written by `Game::encode_compact`, failing cleanly if they run out
+pub(crate) struct CompactReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CompactReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(crate) fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or(anyhow!("Compact encoding ends too soon"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0; N];
+        for byte in &mut out {
+            *byte = self.byte()?;
+        }
+        Ok(out)
+    }
+
+    pub(crate) fn count(&mut self) -> Result<usize> {
+        Ok(self.byte()? as usize)
+    }
+
+    /// Fail if anything is left over, which means the bytes weren't a compact encoding
+    pub(crate) fn finish(self) -> Result<()> {
+        match self.pos == self.bytes.len() {
+            true => Ok(()),
+            false => Err(anyhow!("Unexpected bytes after the compact encoding")),
+        }
+    }
+}
+
+pub(crate) fn encode_colour(colour: &PlayerColour) -> [u8; 4] {
+    match *colour {
+        PlayerColour::Red => [0, 0, 0, 0],
+        PlayerColour::Green => [1, 0, 0, 0],
+        PlayerColour::Blue => [2, 0, 0, 0],
+        PlayerColour::Purple => [3, 0, 0, 0],
+        PlayerColour::Custom { r, g, b } => [4, r, g, b],
+    }
+}
+
+pub(crate) fn decode_colour([tag, r, g, b]: [u8; 4]) -> Result<PlayerColour> {
+    Ok(match tag {
+        0 => PlayerColour::Red,
+        1 => PlayerColour::Green,
+        2 => PlayerColour::Blue,
+        3 => PlayerColour::Purple,
+        4 => PlayerColour::Custom { r, g, b },
+        _ => return Err(anyhow!("Unknown colour tag {}", tag)),
+    })
+}
+
+/// Index of `player` in turn order, or `NOBODY`
+pub(crate) fn encode_player_index(players: &[PlayerColour], player: Option<PlayerColour>) -> u8 {
+    player
+        .and_then(|player| players.iter().position(|p| *p == player))
+        .map_or(NOBODY, small)
+}
+
+pub(crate) fn decode_player_index(
+    players: &[PlayerColour],
+    byte: u8,
+) -> Result<Option<PlayerColour>> {
+    match byte {
+        NOBODY => Ok(None),
+        idx => players
+            .get(idx as usize)
+            .copied()
+            .map(Some)
+            .ok_or(anyhow!("No player at index {}", idx)),
+    }
+}
+
+pub(crate) fn encode_resources(resources: &Resources, out: &mut Vec<u8>) {
+    out.extend(resources.into_iter().map(|(_, n)| small(n)));
+}
+
+pub(crate) fn decode_resources(reader: &mut CompactReader) -> Result<Resources> {
+    let mut resources = Resources::new();
+    for kind in ResourceKind::ALL {
+        resources[kind] = reader.count()?;
+    }
+    Ok(resources)
+}
+
+pub(crate) fn decode_development_card(byte: u8) -> Result<DevelopmentCard> {
+    DevelopmentCard::ALL
+        .get(byte as usize)
+        .copied()
+        .ok_or(anyhow!("Unknown development card {}", byte))
+}
+
+/// A player's colour, hand, cards, points and remaining pieces, in 21 bytes
+pub(crate) fn encode_player(player: &Player, out: &mut Vec<u8>) {
+    out.extend(encode_colour(player.colour()));
+    encode_resources(player.resources(), out);
+    for card in DevelopmentCard::ALL {
+        let held = player.development_cards().iter().filter(|c| **c == card);
+        out.push(small(held.count()));
+    }
+    out.push(small(player.victory_points()));
+    out.push(small(player.knights_played()));
+    for kind in [Building::Settlement, Building::City, Building::Road] {
+        out.push(small(player.stock().remaining(kind)));
+    }
+    out.push(small(player.chits()));
+    out.push(small(player.fish()));
+}
+
+pub(crate) fn decode_player(reader: &mut CompactReader) -> Result<Player> {
+    let mut player = Player::new(decode_colour(reader.array()?)?);
+    *player.resources_mut() = decode_resources(reader)?;
+    for card in DevelopmentCard::ALL {
+        let held = reader.count()?;
+        player
+            .development_cards_mut()
+            .extend(std::iter::repeat_n(card, held));
+    }
+    *player.victory_points_mut() = reader.count()?;
+    *player.knights_played_mut() = reader.count()?;
+    for kind in [Building::Settlement, Building::City, Building::Road] {
+        let stock = player.stock_mut();
+        while stock.remaining(kind) > 0 {
+            stock.take(kind)?;
+        }
+        for _ in 0..reader.count()? {
+            stock.put_back(kind);
+        }
+    }
+    *player.chits_mut() = reader.count()?;
+    *player.fish_mut() = reader.count()?;
+    Ok(player)
+}
+
+fn encode_tile(tile: &Tile) -> [u8; 2] {
+    let kind = match *tile.kind() {
+        TileKind::Desert => 0,
+        TileKind::Resource(kind) => kind as u8 + 1,
+        TileKind::ResourceWithHarbor(HarborKind::Generic, kind) => 0x10 | (kind as u8 + 1),
+        TileKind::ResourceWithHarbor(HarborKind::Special(special), kind) => {
+            0x40 | ((special as u8) << 3) | (kind as u8 + 1)
+        }
+    };
+    [kind, small(*tile.token())]
+}
+
+fn decode_tile([kind, token]: [u8; 2]) -> Result<Tile> {
+    let resource = |code: u8| {
+        ResourceKind::ALL
+            .get((code as usize).wrapping_sub(1))
+            .copied()
+            .ok_or(anyhow!("Unknown tile kind {}", kind))
+    };
+    let kind = match kind {
+        0 => TileKind::Desert,
+        k if k & 0x40 != 0 => TileKind::ResourceWithHarbor(
+            HarborKind::Special(resource(((k >> 3) & 0x7) + 1)?),
+            resource(k & 0x7)?,
+        ),
+        k if k & 0x10 != 0 => TileKind::ResourceWithHarbor(HarborKind::Generic, resource(k & 0xf)?),
+        k => TileKind::Resource(resource(k)?),
+    };
+    Ok(Tile::new(kind, token as usize))
+}
+
+/// Every tile, the robber, and a byte for each vertex and edge saying what's built there
+///
+/// only boards laid out by `Board::from_tiles` can be encoded
+pub(crate) fn encode_board(board: &Board, players: &[PlayerColour], out: &mut Vec<u8>) {
+    for idx in 0..DEFAULT_TILE_COUNT {
+        out.extend(board.tile(idx).map_or([0, 0], encode_tile));
+    }
+    out.push(board.robber().map_or(NOBODY, small));
+    for vertex in board.vertices() {
+        out.push(match vertex.building() {
+            None => 0,
+            Some((owner, building)) => {
+                let owner = encode_player_index(players, Some(*owner));
+                (owner << 2) | (*building as u8 + 1)
+            }
+        });
+    }
+    for edge in board.edges() {
+        out.push(encode_player_index(players, edge.road().copied()));
+    }
+}
+
+pub(crate) fn decode_board(reader: &mut CompactReader, players: &[PlayerColour]) -> Result<Board> {
+    let mut tiles = Vec::with_capacity(DEFAULT_TILE_COUNT);
+    for _ in 0..DEFAULT_TILE_COUNT {
+        tiles.push(decode_tile(reader.array()?)?);
+    }
+    let mut board = Board::from_tiles(tiles.try_into().expect("one tile per index"));
+    match reader.byte()? {
+        NOBODY => board.remove_robber(),
+        tile => board.set_robber(tile as usize)?,
+    }
+
+    for idx in 0..board.vertices().len() {
+        let building = match reader.byte()? {
+            0 => None,
+            byte => {
+                let owner = decode_player_index(players, byte >> 2)?
+                    .ok_or(anyhow!("Building without an owner"))?;
+                let building = match byte & 0x3 {
+                    1 => Building::Settlement,
+                    2 => Building::City,
+                    _ => return Err(anyhow!("Unknown building {}", byte)),
+                };
+                Some((owner, building))
+            }
+        };
+        board.set_building(VertexId::new(idx), building)?;
+    }
+    for idx in 0..board.edges().len() {
+        let road = decode_player_index(players, reader.byte()?)?;
+        board.set_road(EdgeId::new(idx), road)?;
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tiles() {
+        for kind in [
+            TileKind::Desert,
+            TileKind::Resource(ResourceKind::Lumber),
+            TileKind::ResourceWithHarbor(HarborKind::Generic, ResourceKind::Ore),
+            TileKind::ResourceWithHarbor(
+                HarborKind::Special(ResourceKind::Lumber),
+                ResourceKind::Wool,
+            ),
+        ] {
+            let tile = Tile::new(kind, 9);
+            let decoded = decode_tile(encode_tile(&tile)).unwrap();
+            assert_eq!((*decoded.kind(), *decoded.token()), (kind, 9));
+        }
+        assert!(decode_tile([9, 2]).is_err());
+    }
+
+    #[test]
+    fn test_player() {
+        let mut player = Player::new(PlayerColour::Custom { r: 1, g: 2, b: 3 });
+        *player.resources_mut() = Resources::new_explicit(1, 2, 3, 4, 5);
+        player
+            .development_cards_mut()
+            .extend([DevelopmentCard::Knight, DevelopmentCard::Knight]);
+        player.stock_mut().take(Building::Road).unwrap();
+        *player.victory_points_mut() = 3;
+
+        let mut out = Vec::new();
+        encode_player(&player, &mut out);
+        assert_eq!(out.len(), 21);
+        let mut reader = CompactReader::new(&out);
+        assert_eq!(decode_player(&mut reader).unwrap(), player);
+        reader.finish().unwrap();
+    }
+
+    #[test]
+    fn test_reader_runs_out() {
+        let mut reader = CompactReader::new(&[1, 2]);
+        assert!(reader.array::<3>().is_err());
+        assert!(CompactReader::new(&[1]).finish().is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, EdgeId, Tile, VertexId};
+use crate::building::Building;
+use crate::development_cards::DevelopmentCard;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState, TurnPhase};
+use crate::player::{Player, PlayerColour};
+use crate::resources::Resources;
+
+/// What changed between two positions of a game, from `Game::diff`, for keeping a copy of the
+/// game in sync without sending the whole thing after every action
+///
+/// covers the turn, the board, players' hands and cards, what's left in the bank, and new
+/// events. Anything left out, like timers, trades and options, isn't synced. Fields with nothing
+/// to say are left out when serialised
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GameDelta {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub state: Option<GameState>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub phase: Option<TurnPhase>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub turn_no: Option<usize>,
+    /// Free roads left to place from a Road Building card
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub free_roads: Option<u8>,
+    /// The whole board, when it can't be described piece by piece, e.g. for a new layout
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub board: Option<Board>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub tiles: Vec<(usize, Tile)>,
+    /// Tile the robber moved to
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub robber: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub buildings: Vec<(VertexId, Option<(PlayerColour, Building)>)>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub roads: Vec<(EdgeId, Option<PlayerColour>)>,
+    /// Every player in their new turn order, when players joined, left or were reseated
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub turn_order: Option<Vec<PlayerColour>>,
+    /// Players who changed in any way, or who joined
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub players: Vec<Player>,
+    /// Resource cards left in the bank
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bank: Option<Resources>,
+    /// Development cards left in the bank, for each kind whose count changed
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub development_cards: Vec<(DevelopmentCard, usize)>,
+    /// Events since the earlier position
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub events: Vec<GameEvent>,
+}
+
+impl GameDelta {
+    /// Bring `game` up to date, given it's in the position this delta was made from
+    ///
+    /// fails without changing anything if the delta refers to pieces or players `game` doesn't
+    /// have
+    pub fn apply(&self, game: &mut Game) -> Result<()> {
+        game.apply_delta(self)
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::dice::FixedDice;
+    use crate::game::running_game;
+
+    #[test]
+    fn test_diff_and_apply() {
+        let before = running_game();
+        let mut after = before.clone();
+        after.set_dice_roller(FixedDice::new([(3, 4)]));
+        after.roll_dice().unwrap();
+        let tile = match before.get_board().robber() {
+            Some(0) => 1,
+            _ => 0,
+        };
+        after.move_robber(PlayerColour::Red, tile, None).unwrap();
+        *after
+            .get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .resources_mut() = Resources::new_explicit(3, 2, 0, 0, 0);
+        after
+            .upgrade_to_city(PlayerColour::Red, VertexId::new(2))
+            .unwrap();
+
+        let delta = after.diff(&before);
+        assert!(!delta.is_empty());
+        assert_eq!(delta.phase, Some(after.phase()));
+        assert_eq!(delta.robber, Some(tile));
+        assert_eq!(
+            delta.buildings,
+            vec![(VertexId::new(2), Some((PlayerColour::Red, Building::City)))]
+        );
+        assert!(delta.board.is_none());
+
+        let mut synced = before.clone();
+        delta.apply(&mut synced).unwrap();
+        assert_eq!(synced.phase(), after.phase());
+        assert_eq!(synced.players(), after.players());
+        assert_eq!(synced.events(), after.events());
+        assert_eq!(synced.get_board(), after.get_board());
+        assert_eq!(synced.zobrist(), after.zobrist());
+
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_new_board_and_players() {
+        let before = running_game();
+        let mut after = Game::new();
+        after.add_player(PlayerColour::Blue);
+        after.add_player(PlayerColour::Green);
+
+        let delta = after.diff(&before);
+        // a board without a desert has no robber, so gets sent whole
+        assert!(delta.board.is_some() || !delta.tiles.is_empty());
+        assert_eq!(
+            delta.turn_order,
+            Some(vec![PlayerColour::Blue, PlayerColour::Green])
+        );
+
+        let mut synced = before.clone();
+        delta.apply(&mut synced).unwrap();
+        assert_eq!(synced.players(), after.players());
+        assert_eq!(synced.get_board(), after.get_board());
+    }
+
+    #[test]
+    fn test_apply_mismatch() {
+        let g = running_game();
+        let delta = GameDelta {
+            players: vec![Player::new(PlayerColour::Purple)],
+            ..GameDelta::default()
+        };
+        let mut target = g.clone();
+        assert!(delta.apply(&mut target).is_err());
+        assert_eq!(target, g);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let delta = GameDelta {
+            robber: Some(3),
+            ..GameDelta::default()
+        };
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(json, r#"{"robber":3}"#);
+        assert_eq!(serde_json::from_str::<GameDelta>(&json).unwrap(), delta);
+    }
+}
@@ -1,9 +1,29 @@
-use rand::{thread_rng, Rng};
+use core::fmt::Display;
+use core::ops::{Index, IndexMut};
+use core::str::FromStr;
 
+use anyhow::{anyhow, Result};
+
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::options::CostTable;
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+use crate::resources::Resources;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Hash)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum DevelopmentCard {
     YearOfPlenty,
     Monopoly,
@@ -13,16 +33,153 @@ pub enum DevelopmentCard {
 }
 
 impl DevelopmentCard {
+    /// Number of distinct development card kinds
+    pub const COUNT: usize = 5;
+
+    /// Every kind of development card
+    pub const ALL: [DevelopmentCard; DevelopmentCard::COUNT] = [
+        DevelopmentCard::YearOfPlenty,
+        DevelopmentCard::Monopoly,
+        DevelopmentCard::Knight,
+        DevelopmentCard::RoadBuilding,
+        DevelopmentCard::HiddenVictoryPoint,
+    ];
+
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        let variants = [
-            DevelopmentCard::YearOfPlenty,
-            DevelopmentCard::Monopoly,
-            DevelopmentCard::Knight,
-            DevelopmentCard::RoadBuilding,
-            DevelopmentCard::HiddenVictoryPoint,
-        ];
-        let idx = rng.gen_range(0..variants.len());
-        variants[idx]
+        let mut rng = entropy_rng();
+        let idx = rng.gen_range(0..Self::ALL.len());
+        Self::ALL[idx]
+    }
+
+    /// Resources needed to buy a development card from the bank in the official rules, a game's
+    /// own price is in `GameOptions::costs`
+    pub fn cost() -> Resources {
+        CostTable::default().development_card
+    }
+
+    /// Snake case name of the card, as it's serialised
+    fn name(&self) -> &'static str {
+        match self {
+            DevelopmentCard::YearOfPlenty => "year_of_plenty",
+            DevelopmentCard::Monopoly => "monopoly",
+            DevelopmentCard::Knight => "knight",
+            DevelopmentCard::RoadBuilding => "road_building",
+            DevelopmentCard::HiddenVictoryPoint => "hidden_victory_point",
+        }
+    }
+}
+
+/// A number of development cards of each kind, like what's left of the deck
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DevelopmentCardCounts([usize; DevelopmentCard::COUNT]);
+
+impl DevelopmentCardCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The 25 card deck from the base game
+    pub fn base_game() -> Self {
+        [
+            (DevelopmentCard::YearOfPlenty, 2),
+            (DevelopmentCard::RoadBuilding, 2),
+            (DevelopmentCard::Monopoly, 2),
+            (DevelopmentCard::HiddenVictoryPoint, 5),
+            (DevelopmentCard::Knight, 14),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// How many of each kind there are, in the order of `DevelopmentCard::ALL`
+    pub fn iter(&self) -> impl Iterator<Item = (DevelopmentCard, usize)> + '_ {
+        DevelopmentCard::ALL.into_iter().zip(self.0.iter().copied())
+    }
+}
+
+impl Index<DevelopmentCard> for DevelopmentCardCounts {
+    type Output = usize;
+    fn index(&self, index: DevelopmentCard) -> &Self::Output {
+        &self.0[index as usize]
+    }
+}
+
+impl IndexMut<DevelopmentCard> for DevelopmentCardCounts {
+    fn index_mut(&mut self, index: DevelopmentCard) -> &mut Self::Output {
+        &mut self.0[index as usize]
+    }
+}
+
+impl FromIterator<(DevelopmentCard, usize)> for DevelopmentCardCounts {
+    fn from_iter<T: IntoIterator<Item = (DevelopmentCard, usize)>>(iter: T) -> Self {
+        let mut counts = Self::new();
+        for (card, n) in iter {
+            counts[card] += n;
+        }
+        counts
+    }
+}
+
+impl Display for DevelopmentCard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Reads back what `Display` writes, ignoring case
+impl FromStr for DevelopmentCard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|card| s.eq_ignore_ascii_case(card.name()))
+            .ok_or(anyhow!("Unknown development card {}", s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_card_text() {
+        for card in DevelopmentCard::ALL {
+            assert_eq!(card.to_string().parse::<DevelopmentCard>().unwrap(), card);
+        }
+        assert_eq!(DevelopmentCard::YearOfPlenty.to_string(), "year_of_plenty");
+        assert_eq!(
+            "Knight".parse::<DevelopmentCard>().unwrap(),
+            DevelopmentCard::Knight
+        );
+        assert!("soldier".parse::<DevelopmentCard>().is_err());
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut counts: DevelopmentCardCounts =
+            [(DevelopmentCard::Knight, 2), (DevelopmentCard::Monopoly, 1)]
+                .into_iter()
+                .collect();
+        counts[DevelopmentCard::Knight] += 1;
+        assert_eq!(counts[DevelopmentCard::Knight], 3);
+        assert_eq!(counts[DevelopmentCard::YearOfPlenty], 0);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(DevelopmentCardCounts::base_game().total(), 25);
+        assert_eq!(
+            counts.iter().filter(|(_, n)| *n > 0).collect::<Vec<_>>(),
+            vec![(DevelopmentCard::Monopoly, 1), (DevelopmentCard::Knight, 3)]
+        );
     }
 }
@@ -0,0 +1,525 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+use crate::player::PlayerColour;
+
+/// Number of faces on each die
+pub const DIE_FACES: u8 = 6;
+
+/// Smallest and largest totals two dice can roll
+pub const MIN_ROLL: u8 = 2;
+pub const MAX_ROLL: u8 = 2 * DIE_FACES;
+
+/// A source of dice rolls, owned by a `Game`
+pub trait DiceRoller: Debug + Send + Sync {
+    /// Roll both dice, returning the value shown on each
+    fn roll(&mut self) -> (u8, u8);
+
+    /// Clone this roller into a new box, so games holding one can still be cloned
+    fn clone_box(&self) -> Box<dyn DiceRoller>;
+}
+
+/// Every possible outcome of rolling two dice, in order
+pub fn all_combinations() -> Vec<(u8, u8)> {
+    (1..=DIE_FACES)
+        .flat_map(|d1| (1..=DIE_FACES).map(move |d2| (d1, d2)))
+        .collect()
+}
+
+/// Number of the 36 possible combinations of two dice which add up to `total`
+pub fn combinations_for(total: u8) -> usize {
+    if !(MIN_ROLL..=MAX_ROLL).contains(&total) {
+        return 0;
+    }
+    (DIE_FACES as usize) - (7_i32 - total as i32).unsigned_abs() as usize
+}
+
+/// Two fair six-sided dice
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct FairDice {
+    rng: StdRng,
+}
+
+#[cfg(feature = "rand")]
+impl FairDice {
+    pub fn new() -> Self {
+        Self { rng: entropy_rng() }
+    }
+
+    /// Create a pair of dice whose rolls are reproducible from `seed`
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl Default for FairDice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "rand")]
+impl DiceRoller for FairDice {
+    fn roll(&mut self) -> (u8, u8) {
+        (
+            self.rng.gen_range(1..=DIE_FACES),
+            self.rng.gen_range(1..=DIE_FACES),
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn DiceRoller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Replays a fixed sequence of rolls, starting again from the beginning once exhausted
+///
+/// mostly useful in tests, where the outcome of each roll needs to be known ahead of time
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FixedDice {
+    rolls: Vec<(u8, u8)>,
+    next: usize,
+}
+
+impl FixedDice {
+    /// Panics if `rolls` is empty, or contains a value which can't be shown on a die
+    pub fn new(rolls: impl IntoIterator<Item = (u8, u8)>) -> Self {
+        let rolls: Vec<_> = rolls.into_iter().collect();
+        if rolls.is_empty() {
+            panic!("FixedDice needs at least one roll");
+        }
+        if rolls
+            .iter()
+            .any(|&(d1, d2)| !(1..=DIE_FACES).contains(&d1) || !(1..=DIE_FACES).contains(&d2))
+        {
+            panic!("FixedDice rolls must be between 1 and {}", DIE_FACES);
+        }
+        Self { rolls, next: 0 }
+    }
+}
+
+impl DiceRoller for FixedDice {
+    fn roll(&mut self) -> (u8, u8) {
+        let roll = self.rolls[self.next];
+        self.next = (self.next + 1) % self.rolls.len();
+        roll
+    }
+
+    fn clone_box(&self) -> Box<dyn DiceRoller> {
+        Box::new(self.clone())
+    }
+}
+
+/// The card deck variant of the dice, one card for each of the 36 possible rolls
+///
+/// the deck is reshuffled once only `reshuffle_at` cards remain, which keeps the rolls close to
+/// the expected distribution while leaving the last few rolls unpredictable
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct DiceDeck {
+    cards: Vec<(u8, u8)>,
+    reshuffle_at: usize,
+    rng: StdRng,
+}
+
+#[cfg(feature = "rand")]
+impl DiceDeck {
+    /// Number of cards left when the deck is reshuffled in the official rules
+    pub const DEFAULT_RESHUFFLE_AT: usize = 5;
+
+    pub fn new() -> Self {
+        Self::with_rng(entropy_rng(), Self::DEFAULT_RESHUFFLE_AT)
+    }
+
+    pub fn seeded(seed: u64, reshuffle_at: usize) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed), reshuffle_at)
+    }
+
+    fn with_rng(rng: StdRng, reshuffle_at: usize) -> Self {
+        let mut deck = Self {
+            cards: Vec::new(),
+            reshuffle_at: reshuffle_at.min(all_combinations().len() - 1),
+            rng,
+        };
+        deck.shuffle();
+        deck
+    }
+
+    /// Number of cards left to draw before the deck is reshuffled
+    pub fn remaining(&self) -> usize {
+        self.cards.len() - self.reshuffle_at
+    }
+
+    fn shuffle(&mut self) {
+        self.cards = all_combinations();
+        self.cards.shuffle(&mut self.rng);
+    }
+}
+
+#[cfg(feature = "rand")]
+impl Default for DiceDeck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "rand")]
+impl DiceRoller for DiceDeck {
+    fn roll(&mut self) -> (u8, u8) {
+        if self.cards.len() <= self.reshuffle_at {
+            self.shuffle();
+        }
+        self.cards.pop().unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn DiceRoller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Owned handle to the `DiceRoller` used by a game
+///
+/// the roller isn't part of the game state, so two handles always compare equal
+#[derive(Debug)]
+pub struct Dice(Box<dyn DiceRoller>);
+
+impl Dice {
+    pub fn new(roller: impl DiceRoller + 'static) -> Self {
+        Self(Box::new(roller))
+    }
+
+    pub fn roll(&mut self) -> (u8, u8) {
+        self.0.roll()
+    }
+}
+
+impl Clone for Dice {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for Dice {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Dice {}
+
+impl Default for Dice {
+    /// Fair dice when randomness is available, otherwise every combination in turn
+    fn default() -> Self {
+        #[cfg(feature = "rand")]
+        let roller = FairDice::new();
+        #[cfg(not(feature = "rand"))]
+        let roller = FixedDice::new(all_combinations());
+        Self::new(roller)
+    }
+}
+
+/// A single roll of the dice, and who rolled it
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RollRecord {
+    player: PlayerColour,
+    turn: usize,
+    dice: (u8, u8),
+}
+
+impl RollRecord {
+    pub fn new(player: PlayerColour, turn: usize, dice: (u8, u8)) -> Self {
+        Self { player, turn, dice }
+    }
+
+    pub fn player(&self) -> &PlayerColour {
+        &self.player
+    }
+
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn dice(&self) -> (u8, u8) {
+        self.dice
+    }
+
+    pub fn total(&self) -> u8 {
+        self.dice.0 + self.dice.1
+    }
+}
+
+/// Every roll made over the course of a game, oldest first
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RollHistory(Vec<RollRecord>);
+
+impl RollHistory {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn record(&mut self, record: RollRecord) {
+        self.0.push(record);
+    }
+
+    pub fn records(&self) -> &[RollRecord] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of times `total` has been rolled
+    pub fn count(&self, total: u8) -> usize {
+        self.0
+            .iter()
+            .filter(|record| record.total() == total)
+            .count()
+    }
+
+    /// Number of times each total has been rolled, where index 0 holds the count for `MIN_ROLL`
+    pub fn counts(&self) -> [usize; (MAX_ROLL - MIN_ROLL + 1) as usize] {
+        let mut counts = [0; (MAX_ROLL - MIN_ROLL + 1) as usize];
+        for record in &self.0 {
+            counts[(record.total() - MIN_ROLL) as usize] += 1;
+        }
+        counts
+    }
+
+    /// Rolls made by a single player
+    pub fn by_player<'a>(
+        &'a self,
+        player: &'a PlayerColour,
+    ) -> impl Iterator<Item = &'a RollRecord> {
+        self.0
+            .iter()
+            .filter(move |record| record.player() == player)
+    }
+
+    /// Pearson's chi-squared statistic for the totals rolled against fair dice, with 10 degrees
+    /// of freedom
+    ///
+    /// a sanity check rather than a verdict, since it's only meaningful after a good few dozen
+    /// rolls, but fair dice only go above 23.2 about one game in a hundred
+    pub fn chi_squared(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let rolls = self.len() as f64;
+        self.counts()
+            .iter()
+            .zip(MIN_ROLL..=MAX_ROLL)
+            .map(|(&count, total)| {
+                let expected = rolls * combinations_for(total) as f64 / 36.0;
+                (count as f64 - expected) * (count as f64 - expected) / expected
+            })
+            .sum()
+    }
+}
+
+/// Cards a player collected from rolls, against what they'd expect on average from the buildings
+/// they had each time the dice were rolled
+///
+/// expected production doesn't know about the robber or the bank running short, so even a player
+/// with average rolls tends to come out a little behind
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Luck {
+    player: PlayerColour,
+    rolls: usize,
+    produced: usize,
+    /// Expected production in 36ths of a card, one for each combination of the dice
+    expected: usize,
+}
+
+impl Luck {
+    pub fn new(player: PlayerColour) -> Self {
+        Self {
+            player,
+            rolls: 0,
+            produced: 0,
+            expected: 0,
+        }
+    }
+
+    /// Count a roll, where `production` is how many cards the player would collect for each
+    /// total from `MIN_ROLL` up
+    pub fn expect(&mut self, production: impl IntoIterator<Item = usize>) {
+        self.rolls += 1;
+        self.expected += production
+            .into_iter()
+            .zip(MIN_ROLL..=MAX_ROLL)
+            .map(|(cards, total)| cards * combinations_for(total))
+            .sum::<usize>();
+    }
+
+    /// Count cards the player actually collected from a roll
+    pub fn produce(&mut self, cards: usize) {
+        self.produced += cards;
+    }
+
+    pub fn player(&self) -> &PlayerColour {
+        &self.player
+    }
+
+    pub fn rolls(&self) -> usize {
+        self.rolls
+    }
+
+    pub fn produced(&self) -> usize {
+        self.produced
+    }
+
+    pub fn expected(&self) -> f64 {
+        self.expected as f64 / 36.0
+    }
+
+    /// How many more cards the player got than expected, negative if they got fewer
+    pub fn difference(&self) -> f64 {
+        self.produced as f64 - self.expected()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_fair_dice() {
+        let mut dice = FairDice::seeded(7);
+        let rolls: Vec<_> = (0..1000).map(|_| dice.roll()).collect();
+
+        assert!(rolls
+            .iter()
+            .all(|&(d1, d2)| (1..=6).contains(&d1) && (1..=6).contains(&d2)));
+        assert!(rolls.iter().any(|&(d1, _)| d1 == 6));
+        assert!(rolls.iter().any(|&(d1, d2)| d1 + d2 == 12));
+
+        let mut other = FairDice::seeded(7);
+        assert_eq!(rolls[0], other.roll());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_fixed_dice() {
+        let mut dice = FixedDice::new([(1, 2), (6, 6)]);
+        assert_eq!(dice.roll(), (1, 2));
+        assert_eq!(dice.roll(), (6, 6));
+        assert_eq!(dice.roll(), (1, 2));
+
+        assert!(std::panic::catch_unwind(|| FixedDice::new([])).is_err());
+        assert!(std::panic::catch_unwind(|| FixedDice::new([(0, 7)])).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_dice_deck() {
+        let mut deck = DiceDeck::seeded(3, 0);
+        let mut drawn: Vec<_> = (0..36).map(|_| deck.roll()).collect();
+        drawn.sort();
+        assert_eq!(drawn, all_combinations());
+
+        let mut deck = DiceDeck::seeded(3, DiceDeck::DEFAULT_RESHUFFLE_AT);
+        assert_eq!(deck.remaining(), 31);
+        (0..31).for_each(|_| {
+            deck.roll();
+        });
+        assert_eq!(deck.remaining(), 0);
+        deck.roll();
+        assert_eq!(deck.remaining(), 30);
+    }
+
+    #[test]
+    fn test_combinations_for() {
+        assert_eq!(combinations_for(7), 6);
+        assert_eq!(combinations_for(2), 1);
+        assert_eq!(combinations_for(12), 1);
+        assert_eq!(combinations_for(8), 5);
+        assert_eq!(combinations_for(1), 0);
+        assert_eq!(combinations_for(13), 0);
+
+        let total: usize = (MIN_ROLL..=MAX_ROLL).map(combinations_for).sum();
+        assert_eq!(total, all_combinations().len());
+    }
+
+    #[test]
+    fn test_roll_history() {
+        let mut history = RollHistory::new();
+        history.record(RollRecord::new(PlayerColour::Red, 0, (3, 4)));
+        history.record(RollRecord::new(PlayerColour::Blue, 1, (6, 1)));
+        history.record(RollRecord::new(PlayerColour::Red, 2, (1, 1)));
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.count(7), 2);
+        assert_eq!(history.count(12), 0);
+
+        let counts = history.counts();
+        assert_eq!(counts[0], 1);
+        assert_eq!(counts[5], 2);
+        assert_eq!(counts.iter().sum::<usize>(), 3);
+
+        assert_eq!(history.by_player(&PlayerColour::Red).count(), 2);
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        assert_eq!(RollHistory::new().chi_squared(), 0.0);
+
+        // every combination once is exactly what fair dice should roll
+        let mut fair = RollHistory::new();
+        for dice in all_combinations() {
+            fair.record(RollRecord::new(PlayerColour::Red, 0, dice));
+        }
+        assert!(fair.chi_squared() < 1e-9);
+
+        let mut loaded = RollHistory::new();
+        for turn in 0..36 {
+            loaded.record(RollRecord::new(PlayerColour::Red, turn, (4, 4)));
+        }
+        assert!(loaded.chi_squared() > 23.2);
+    }
+
+    #[test]
+    fn test_luck() {
+        let mut luck = Luck::new(PlayerColour::Red);
+        // a settlement on a 6 and another on an 8
+        let production = |total| usize::from(total == 6 || total == 8);
+        for _ in 0..3 {
+            luck.expect((MIN_ROLL..=MAX_ROLL).map(production));
+        }
+        luck.produce(2);
+
+        assert_eq!(luck.rolls(), 3);
+        assert_eq!(luck.produced(), 2);
+        assert!((luck.expected() - 30.0 / 36.0).abs() < 1e-9);
+        assert!((luck.difference() - (2.0 - 30.0 / 36.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dice_handle() {
+        let mut dice = Dice::new(FixedDice::new([(2, 3)]));
+        let mut cloned = dice.clone();
+        assert_eq!(dice.roll(), (2, 3));
+        assert_eq!(cloned.roll(), (2, 3));
+        assert_eq!(dice, Dice::default());
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::options::{DEFAULT_DECISION_TIMEOUT, DISCARD_THRESHOLD};
+use crate::player::PlayerColour;
+
+/// How long players get to choose their discards before cards are discarded for them
+pub const DEFAULT_DISCARD_TIMEOUT: Duration = DEFAULT_DECISION_TIMEOUT;
+
+/// Keeps track of which players still owe a discard after a 7 has been rolled
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DiscardManager {
+    pending: Vec<(PlayerColour, usize)>,
+    deadline: Option<SystemTime>,
+    timeout: Duration,
+    #[cfg_attr(feature = "serde", serde(default = "default_threshold"))]
+    threshold: usize,
+}
+
+#[cfg(feature = "serde")]
+fn default_threshold() -> usize {
+    DISCARD_THRESHOLD
+}
+
+impl DiscardManager {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            deadline: None,
+            timeout: DEFAULT_DISCARD_TIMEOUT,
+            threshold: DISCARD_THRESHOLD,
+        }
+    }
+
+    /// Work out who has to discard, given the size of each player's hand when the 7 was rolled
+    pub fn request(
+        &mut self,
+        hand_sizes: impl IntoIterator<Item = (PlayerColour, usize)>,
+        now: SystemTime,
+    ) {
+        self.pending = hand_sizes
+            .into_iter()
+            .filter(|(_, size)| *size > self.threshold)
+            .map(|(player, size)| (player, size / 2))
+            .collect();
+        self.deadline = match self.pending.is_empty() {
+            true => None,
+            false => Some(now + self.timeout),
+        };
+    }
+
+    /// Pick up discards which were already owed, e.g. in a restored position, giving players the
+    /// full timeout from `now`
+    pub(crate) fn restore(&mut self, pending: Vec<(PlayerColour, usize)>, now: SystemTime) {
+        self.deadline = (!pending.is_empty()).then_some(now + self.timeout);
+        self.pending = pending;
+    }
+
+    /// Players who still have to discard, along with how many cards they owe
+    pub fn pending(&self) -> &[(PlayerColour, usize)] {
+        &self.pending
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Number of cards `player` still has to discard, if any
+    pub fn required(&self, player: &PlayerColour) -> Option<usize> {
+        self.pending
+            .iter()
+            .find(|(p, _)| p == player)
+            .map(|(_, count)| *count)
+    }
+
+    /// Mark `player`'s discard as submitted
+    pub fn resolve(&mut self, player: &PlayerColour) {
+        self.pending.retain(|(p, _)| p != player);
+        if self.pending.is_empty() {
+            self.deadline = None;
+        }
+    }
+
+    /// Whether players have run out of time to choose their own discards
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        matches!(self.deadline, Some(deadline) if now >= deadline)
+    }
+
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Most cards a player can hold when a 7 is rolled without having to discard
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn set_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold;
+    }
+
+    /// Push back the deadline, if there is one, e.g. to make up for time the game spent paused
+    pub fn extend_deadline(&mut self, by: Duration) {
+        if let Some(deadline) = self.deadline.as_mut() {
+            *deadline += by;
+        }
+    }
+}
+
+impl Default for DiscardManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let mut d = DiscardManager::new();
+        let now = SystemTime::UNIX_EPOCH;
+        d.request(
+            [
+                (PlayerColour::Red, 7),
+                (PlayerColour::Blue, 8),
+                (PlayerColour::Green, 11),
+            ],
+            now,
+        );
+
+        assert_eq!(
+            d.pending(),
+            &[(PlayerColour::Blue, 4), (PlayerColour::Green, 5)]
+        );
+        assert_eq!(d.required(&PlayerColour::Red), None);
+        assert_eq!(d.required(&PlayerColour::Green), Some(5));
+        assert_eq!(d.deadline(), Some(now + DEFAULT_DISCARD_TIMEOUT));
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut d = DiscardManager::new();
+        d.set_threshold(9);
+        d.request(
+            [(PlayerColour::Red, 9), (PlayerColour::Blue, 10)],
+            SystemTime::UNIX_EPOCH,
+        );
+        assert_eq!(d.pending(), &[(PlayerColour::Blue, 5)]);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut d = DiscardManager::new();
+        let now = SystemTime::UNIX_EPOCH;
+        d.request([(PlayerColour::Red, 9), (PlayerColour::Blue, 8)], now);
+
+        d.resolve(&PlayerColour::Red);
+        assert!(d.is_pending());
+        d.resolve(&PlayerColour::Blue);
+        assert!(!d.is_pending());
+        assert_eq!(d.deadline(), None);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut d = DiscardManager::new();
+        d.set_timeout(Duration::from_secs(5));
+        let now = SystemTime::UNIX_EPOCH;
+
+        d.request([(PlayerColour::Red, 3)], now);
+        assert!(!d.is_expired(now + Duration::from_secs(10)));
+
+        d.request([(PlayerColour::Red, 9)], now);
+        assert!(!d.is_expired(now + Duration::from_secs(4)));
+        assert!(d.is_expired(now + Duration::from_secs(5)));
+
+        d.extend_deadline(Duration::from_secs(5));
+        assert!(!d.is_expired(now + Duration::from_secs(5)));
+        assert!(d.is_expired(now + Duration::from_secs(10)));
+    }
+}
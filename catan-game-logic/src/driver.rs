@@ -0,0 +1,282 @@
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::actions::Action;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState};
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+use crate::score::GameSummary;
+use crate::trade::{Trade, TradeState};
+use crate::view::PlayerView;
+
+/// Default number of steps a driven game can take before it's abandoned as a draw
+pub const DEFAULT_MAX_STEPS: usize = 5000;
+
+/// Makes the decisions for one seat of a game run by a `GameDriver`, whether that's a bot, a
+/// scripted tutorial or a test
+///
+/// controllers only get to see their own `PlayerView`, the same as a player at the table
+pub trait PlayerController: Debug {
+    /// Choose one of `legal`, the actions the player can take right now
+    fn choose_action(&mut self, view: &PlayerView, legal: &[Action]) -> Action;
+
+    /// Choose `count` cards from `view.hand` to discard after a 7
+    fn choose_discard(&mut self, view: &PlayerView, count: usize) -> Resources;
+
+    /// Whether to accept `trade`, which another player just offered, declining unless overridden
+    fn respond_to_trade(&mut self, _view: &PlayerView, _trade_id: Uuid, _trade: &Trade) -> bool {
+        false
+    }
+}
+
+/// Runs a game from start to finish by asking each seat's `PlayerController` what to do
+///
+/// every step asks whoever the game is waiting on for one decision, and any trade offers made
+/// since the last step are put to the other players first
+#[derive(Debug)]
+pub struct GameDriver {
+    game: Game,
+    seats: Vec<(PlayerColour, Box<dyn PlayerController>)>,
+    max_steps: usize,
+    /// Events before this one have already been looked at for trade offers
+    seen: usize,
+}
+
+impl GameDriver {
+    /// Drive `game`, which needs a controller for each of its players before it can be stepped
+    pub fn new(game: Game) -> Self {
+        let seen = game.events().len();
+        Self {
+            game,
+            seats: Vec::new(),
+            max_steps: DEFAULT_MAX_STEPS,
+            seen,
+        }
+    }
+
+    /// Let `controller` make `player`'s decisions, replacing any controller they already had
+    pub fn with_controller(
+        mut self,
+        player: PlayerColour,
+        controller: impl PlayerController + 'static,
+    ) -> Self {
+        self.seats.retain(|(colour, _)| *colour != player);
+        self.seats.push((player, Box::new(controller)));
+        self
+    }
+
+    /// Abandon the game if it hasn't finished after `max_steps` steps of `run`
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    /// Have the player the game is waiting on make one decision, returning whether the game is
+    /// over
+    ///
+    /// fails if a controller chooses something the game doesn't allow
+    pub fn step(&mut self) -> Result<bool> {
+        self.put_trades_to_players()?;
+        if self.game.state() == GameState::Complete {
+            return Ok(true);
+        }
+        let player = self
+            .game
+            .awaiting_player()
+            .ok_or(anyhow!("The game isn't waiting on anyone"))?;
+        let view = self.game.view_for(&player)?;
+        let controller = self.controller(player)?;
+        let action = match view.discard {
+            Some(count) => Action::Discard {
+                cards: controller.choose_discard(&view, count),
+            },
+            None => controller.choose_action(&view, &view.legal_actions),
+        };
+        self.game
+            .apply(player, action.clone())
+            .map_err(|err| anyhow!("{:?} chose {:?}, which failed: {}", player, action, err))?;
+        Ok(self.game.state() == GameState::Complete)
+    }
+
+    /// Step until the game is won or it runs out of steps, returning how it turned out
+    pub fn run(&mut self) -> Result<GameSummary> {
+        for _ in 0..self.max_steps {
+            if self.step()? {
+                break;
+            }
+        }
+        Ok(self.game.summary())
+    }
+
+    fn controller(&mut self, player: PlayerColour) -> Result<&mut Box<dyn PlayerController>> {
+        self.seats
+            .iter_mut()
+            .find(|(colour, _)| *colour == player)
+            .map(|(_, controller)| controller)
+            .ok_or(anyhow!("No controller for {:?}", player))
+    }
+
+    /// Ask every other player about each trade offered since the last step
+    fn put_trades_to_players(&mut self) -> Result<()> {
+        let offers: Vec<_> = self.game.events()[self.seen..]
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::TradeProposed { trade, player, .. } => Some((*trade, *player)),
+                _ => None,
+            })
+            .collect();
+        self.seen = self.game.events().len();
+
+        for (trade_id, from) in offers {
+            let others: Vec<_> = self
+                .game
+                .players()
+                .iter()
+                .map(|p| *p.colour())
+                .filter(|colour| *colour != from)
+                .collect();
+            for player in others {
+                let Some(trade) = self.game.get_trades().get_trade(trade_id).cloned() else {
+                    break;
+                };
+                if *trade.state() != TradeState::Proposed {
+                    break;
+                }
+                let view = self.game.view_for(&player)?;
+                if self
+                    .controller(player)?
+                    .respond_to_trade(&view, trade_id, &trade)
+                {
+                    self.game.accept_trade(trade_id, player)?;
+                }
+            }
+        }
+        self.seen = self.game.events().len();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::dice::FixedDice;
+    use crate::resources::ResourceKind;
+
+    /// Always takes the first legal action, discards from the start of its hand and takes
+    /// every trade
+    #[derive(Debug)]
+    struct FirstAction;
+
+    impl PlayerController for FirstAction {
+        fn choose_action(&mut self, _view: &PlayerView, legal: &[Action]) -> Action {
+            legal[0].clone()
+        }
+
+        fn choose_discard(&mut self, view: &PlayerView, count: usize) -> Resources {
+            let mut cards = Resources::new();
+            for (kind, n) in view.hand.iter() {
+                cards[kind] = n.min(count - cards.total());
+            }
+            cards
+        }
+
+        fn respond_to_trade(&mut self, _view: &PlayerView, _id: Uuid, _trade: &Trade) -> bool {
+            true
+        }
+    }
+
+    fn driver() -> GameDriver {
+        let mut game = Game::new();
+        game.add_player(PlayerColour::Red);
+        game.add_player(PlayerColour::Blue);
+        game.set_dice_roller(FixedDice::new([(3, 4)]));
+        GameDriver::new(game)
+            .with_controller(PlayerColour::Red, FirstAction)
+            .with_controller(PlayerColour::Blue, FirstAction)
+    }
+
+    #[test]
+    fn test_setup_and_discard() {
+        let mut d = driver();
+        while d.game().state() == GameState::Setup {
+            assert!(!d.step().unwrap());
+        }
+
+        let first = d.game().awaiting_player().unwrap();
+        d.game_mut()
+            .set_hand(first, Resources::new_explicit(8, 0, 0, 0, 0));
+        d.step().unwrap();
+        assert_eq!(d.game().players_awaiting_discard(), [(first, 4)]);
+        d.step().unwrap();
+        assert!(d.game().players_awaiting_discard().is_empty());
+        assert_eq!(
+            d.game().get_player(&first).unwrap().resources()[ResourceKind::Ore],
+            4
+        );
+    }
+
+    #[test]
+    fn test_trade_responses() {
+        let mut d = driver();
+        while d.game().state() == GameState::Setup {
+            d.step().unwrap();
+        }
+        let first = d.game().awaiting_player().unwrap();
+        // a 7 with nothing to discard leaves the first player to move the robber, then trade
+        d.step().unwrap();
+        d.step().unwrap();
+        d.game_mut()
+            .set_hand(first, Resources::new_explicit(1, 0, 0, 0, 0));
+        let trade_id = d
+            .game_mut()
+            .propose_trade(
+                first,
+                Resources::new_explicit(1, 0, 0, 0, 0),
+                Resources::new_explicit(0, 1, 0, 0, 0),
+            )
+            .unwrap();
+
+        // the other player takes the offer up, then whoever offered it makes or withdraws it
+        d.step().unwrap();
+        let accepted: Vec<_> = d
+            .game()
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::TradeAccepted { trade, player } if *trade == trade_id => Some(*player),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(accepted.len(), 1);
+        assert_ne!(accepted[0], first);
+        assert!(d
+            .game()
+            .get_trades()
+            .get_trade(trade_id)
+            .is_none_or(|trade| *trade.state() == TradeState::Accepted));
+    }
+
+    #[test]
+    fn test_missing_controller() {
+        let mut game = Game::new();
+        game.add_player(PlayerColour::Red);
+        let mut d = GameDriver::new(game).with_max_steps(10);
+        let err = d.run().unwrap_err();
+        assert_eq!(err.to_string(), "No controller for Red");
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{anyhow, Result};
+
+use crate::actions::Action;
+use crate::board::{EdgeId, HarborKind, TileKind, VertexId, DEFAULT_TILE_COUNT};
+use crate::building::Building;
+use crate::development_cards::DevelopmentCard;
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+use crate::view::PlayerView;
+
+/// Number of vertices on the default board
+pub const VERTEX_COUNT: usize = 54;
+/// Number of edges on the default board
+pub const EDGE_COUNT: usize = 72;
+/// Seats in an encoded observation, the viewer first and then their opponents in turn order
+pub const SEATS: usize = 4;
+
+/// Resource one-hot, desert, generic harbor, special harbor one-hot, number token and robber
+const TILE_FEATURES: usize = 2 * ResourceKind::COUNT + 4;
+/// A settlement and a city for each seat
+const VERTEX_FEATURES: usize = 2 * SEATS;
+/// A road for each seat
+const EDGE_FEATURES: usize = SEATS;
+/// Game state, turn phase, whose turn it is and the viewer's discard
+const TURN_FEATURES: usize = 4 + 5 + SEATS + 1;
+/// The viewer's hand, their development cards and their victory points
+const VIEWER_FEATURES: usize = ResourceKind::COUNT + DevelopmentCard::ALL.len() + 1;
+/// Whether the seat is taken, cards in hand, development cards, victory points and knights
+const OPPONENT_FEATURES: usize = 5;
+/// The bank's resources and development cards
+const BANK_FEATURES: usize = ResourceKind::COUNT + 1;
+
+/// Length of every vector from `PlayerView::encode`
+pub const OBSERVATION_SIZE: usize = DEFAULT_TILE_COUNT * TILE_FEATURES
+    + VERTEX_COUNT * VERTEX_FEATURES
+    + EDGE_COUNT * EDGE_FEATURES
+    + TURN_FEATURES
+    + VIEWER_FEATURES
+    + (SEATS - 1) * OPPONENT_FEATURES
+    + BANK_FEATURES;
+
+/// Tile to move the robber to, along with nobody or one of the opponents to steal from
+const ROBBER_TARGETS: usize = DEFAULT_TILE_COUNT * SEATS;
+
+const PLACE_INITIAL_SETTLEMENT: usize = 0;
+const ROLL_DICE: usize = PLACE_INITIAL_SETTLEMENT + VERTEX_COUNT;
+const MOVE_ROBBER: usize = ROLL_DICE + 1;
+const BUILD_SETTLEMENT: usize = MOVE_ROBBER + ROBBER_TARGETS;
+const BUILD_ROAD: usize = BUILD_SETTLEMENT + VERTEX_COUNT;
+const UPGRADE_TO_CITY: usize = BUILD_ROAD + EDGE_COUNT;
+const BUY_DEVELOPMENT_CARD: usize = UPGRADE_TO_CITY + VERTEX_COUNT;
+const PLAY_KNIGHT: usize = BUY_DEVELOPMENT_CARD + 1;
+const PLAY_MONOPOLY: usize = PLAY_KNIGHT + ROBBER_TARGETS;
+const PLAY_ROAD_BUILDING: usize = PLAY_MONOPOLY + ResourceKind::COUNT;
+const PLACE_FREE_ROAD: usize = PLAY_ROAD_BUILDING + 1;
+const END_TURN: usize = PLACE_FREE_ROAD + EDGE_COUNT;
+
+/// Number of actions `PlayerView::action_index` tells apart
+pub const ACTION_SPACE: usize = END_TURN + 1;
+
+/// Writes an observation a block of features at a time
+struct Features(Vec<f32>);
+
+impl Features {
+    fn push(&mut self, value: usize) {
+        self.0.push(value as f32);
+    }
+
+    fn flag(&mut self, on: bool) {
+        self.0.push(if on { 1.0 } else { 0.0 });
+    }
+
+    /// `len` features, with only the one at `hot`, if any, switched on
+    fn one_hot(&mut self, hot: Option<usize>, len: usize) {
+        for i in 0..len {
+            self.flag(hot == Some(i));
+        }
+    }
+
+    fn resources(&mut self, resources: &Resources) {
+        for kind in ResourceKind::ALL {
+            self.push(resources[kind]);
+        }
+    }
+}
+
+fn resource_index(kind: ResourceKind) -> usize {
+    ResourceKind::ALL.iter().position(|k| *k == kind).unwrap()
+}
+
+impl PlayerView {
+    /// The view as a fixed size vector of `OBSERVATION_SIZE` numbers, for training agents
+    ///
+    /// players are placed in seats relative to the viewer, who is always seat 0, so the same
+    /// network can play any colour. Counts are left as they are rather than scaled. Only the
+    /// default board, with at most `SEATS` players, fits the layout
+    pub fn encode(&self) -> Result<Vec<f32>> {
+        let board = &self.board;
+        if board.tile_count() != DEFAULT_TILE_COUNT
+            || board.vertices().len() != VERTEX_COUNT
+            || board.edges().len() != EDGE_COUNT
+        {
+            return Err(anyhow!("Only the default board can be encoded"));
+        }
+        if self.opponents.len() >= SEATS {
+            return Err(anyhow!(
+                "Only games of up to {} players can be encoded",
+                SEATS
+            ));
+        }
+
+        let mut out = Features(Vec::with_capacity(OBSERVATION_SIZE));
+        for idx in 0..DEFAULT_TILE_COUNT {
+            let tile = board.tile(idx).ok_or(anyhow!("Missing tile {}", idx))?;
+            out.one_hot(
+                tile.kind().resource().map(resource_index),
+                ResourceKind::COUNT,
+            );
+            out.flag(*tile.kind() == TileKind::Desert);
+            let harbor = match tile.kind() {
+                TileKind::ResourceWithHarbor(harbor, _) => Some(*harbor),
+                _ => None,
+            };
+            out.flag(harbor == Some(HarborKind::Generic));
+            let special = match harbor {
+                Some(HarborKind::Special(kind)) => Some(resource_index(kind)),
+                _ => None,
+            };
+            out.one_hot(special, ResourceKind::COUNT);
+            out.push(*tile.token());
+            out.flag(board.robber() == Some(idx));
+        }
+        for vertex in board.vertices() {
+            let building = vertex.building().and_then(|(owner, building)| {
+                let offset = match building {
+                    Building::City => SEATS,
+                    _ => 0,
+                };
+                Some(offset + self.seat(owner)?)
+            });
+            out.one_hot(building, VERTEX_FEATURES);
+        }
+        for edge in board.edges() {
+            out.one_hot(
+                edge.road().and_then(|owner| self.seat(owner)),
+                EDGE_FEATURES,
+            );
+        }
+
+        out.one_hot(Some(self.state as usize), 4);
+        out.one_hot(Some(self.phase as usize), 5);
+        out.one_hot(self.current_player.and_then(|p| self.seat(&p)), SEATS);
+        out.push(self.discard.unwrap_or(0));
+
+        out.resources(&self.hand);
+        for card in DevelopmentCard::ALL {
+            out.push(
+                self.development_cards
+                    .iter()
+                    .filter(|c| **c == card)
+                    .count(),
+            );
+        }
+        out.push(self.victory_points.total());
+
+        for seat in 0..SEATS - 1 {
+            match self.opponents.get(seat) {
+                Some(opponent) => {
+                    out.flag(true);
+                    out.push(opponent.cards);
+                    out.push(opponent.development_cards);
+                    out.push(opponent.victory_points);
+                    out.push(opponent.knights_played);
+                }
+                None => out.one_hot(None, OPPONENT_FEATURES),
+            }
+        }
+
+        out.resources(&self.bank.resources);
+        out.push(self.bank.development_cards);
+        debug_assert_eq!(out.0.len(), OBSERVATION_SIZE);
+        Ok(out.0)
+    }
+
+    /// Where `player` sits relative to the viewer, who is seat 0
+    pub fn seat(&self, player: &PlayerColour) -> Option<usize> {
+        if *player == self.viewer {
+            return Some(0);
+        }
+        let seat = self.opponents.iter().position(|p| p.colour == *player)? + 1;
+        (seat < SEATS).then_some(seat)
+    }
+
+    /// Index of `action` in `0..ACTION_SPACE`, or `None` for actions outside the fixed action
+    /// space, i.e. discards, choosing the resource to steal, and scenario, extension and fish
+    /// actions
+    pub fn action_index(&self, action: &Action) -> Option<usize> {
+        let robber = |tile: usize, victim: &Option<PlayerColour>| {
+            let slot = match victim {
+                Some(victim) => self.seat(victim).filter(|seat| *seat > 0)?,
+                None => 0,
+            };
+            (tile < DEFAULT_TILE_COUNT).then_some(tile * SEATS + slot)
+        };
+        let vertex = |id: &VertexId| (id.index() < VERTEX_COUNT).then_some(id.index());
+        let edge = |id: &EdgeId| (id.index() < EDGE_COUNT).then_some(id.index());
+
+        Some(match action {
+            Action::PlaceInitialSettlement { vertex: v } => PLACE_INITIAL_SETTLEMENT + vertex(v)?,
+            Action::RollDice => ROLL_DICE,
+            Action::MoveRobber {
+                tile,
+                victim,
+                resource: None,
+            } => MOVE_ROBBER + robber(*tile, victim)?,
+            Action::BuildSettlement { vertex: v } => BUILD_SETTLEMENT + vertex(v)?,
+            Action::BuildRoad { edge: e } => BUILD_ROAD + edge(e)?,
+            Action::UpgradeToCity { vertex: v } => UPGRADE_TO_CITY + vertex(v)?,
+            Action::BuyDevelopmentCard => BUY_DEVELOPMENT_CARD,
+            Action::PlayKnight {
+                tile,
+                victim,
+                resource: None,
+            } => PLAY_KNIGHT + robber(*tile, victim)?,
+            Action::PlayMonopoly { resource } => PLAY_MONOPOLY + resource_index(*resource),
+            Action::PlayRoadBuilding => PLAY_ROAD_BUILDING,
+            Action::PlaceFreeRoad { edge: e } => PLACE_FREE_ROAD + edge(e)?,
+            Action::EndTurn => END_TURN,
+            _ => return None,
+        })
+    }
+
+    /// The action at `index` of the action space, the reverse of `action_index`
+    ///
+    /// the action isn't necessarily legal; check it against `action_mask` first
+    pub fn action_at(&self, index: usize) -> Option<Action> {
+        let robber = |i: usize| -> Option<(usize, Option<PlayerColour>)> {
+            let victim = match i % SEATS {
+                0 => None,
+                seat => Some(self.opponents.get(seat - 1)?.colour),
+            };
+            Some((i / SEATS, victim))
+        };
+
+        Some(match index {
+            i if i < ROLL_DICE => Action::PlaceInitialSettlement {
+                vertex: VertexId::new(i - PLACE_INITIAL_SETTLEMENT),
+            },
+            ROLL_DICE => Action::RollDice,
+            i if i < BUILD_SETTLEMENT => {
+                let (tile, victim) = robber(i - MOVE_ROBBER)?;
+                Action::MoveRobber {
+                    tile,
+                    victim,
+                    resource: None,
+                }
+            }
+            i if i < BUILD_ROAD => Action::BuildSettlement {
+                vertex: VertexId::new(i - BUILD_SETTLEMENT),
+            },
+            i if i < UPGRADE_TO_CITY => Action::BuildRoad {
+                edge: EdgeId::new(i - BUILD_ROAD),
+            },
+            i if i < BUY_DEVELOPMENT_CARD => Action::UpgradeToCity {
+                vertex: VertexId::new(i - UPGRADE_TO_CITY),
+            },
+            BUY_DEVELOPMENT_CARD => Action::BuyDevelopmentCard,
+            i if i < PLAY_MONOPOLY => {
+                let (tile, victim) = robber(i - PLAY_KNIGHT)?;
+                Action::PlayKnight {
+                    tile,
+                    victim,
+                    resource: None,
+                }
+            }
+            i if i < PLAY_ROAD_BUILDING => Action::PlayMonopoly {
+                resource: ResourceKind::ALL[i - PLAY_MONOPOLY],
+            },
+            PLAY_ROAD_BUILDING => Action::PlayRoadBuilding,
+            i if i < END_TURN => Action::PlaceFreeRoad {
+                edge: EdgeId::new(i - PLACE_FREE_ROAD),
+            },
+            END_TURN => Action::EndTurn,
+            _ => return None,
+        })
+    }
+
+    /// Which of the `ACTION_SPACE` actions are among the view's legal actions
+    pub fn action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; ACTION_SPACE];
+        for action in &self.legal_actions {
+            if let Some(idx) = self.action_index(action) {
+                mask[idx] = true;
+            }
+        }
+        mask
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::board::Board;
+    use crate::game::Game;
+
+    fn game() -> Game {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.add_player(PlayerColour::Green);
+        g
+    }
+
+    #[test]
+    fn test_default_board_size() {
+        let board = Board::new();
+        assert_eq!(board.vertices().len(), VERTEX_COUNT);
+        assert_eq!(board.edges().len(), EDGE_COUNT);
+    }
+
+    #[test]
+    fn test_encode() {
+        let g = game();
+        let red = g.encode_observation(&PlayerColour::Red).unwrap();
+        assert_eq!(red.len(), OBSERVATION_SIZE);
+        assert_eq!(red, g.encode_observation(&PlayerColour::Red).unwrap());
+        assert_eq!(
+            g.encode_observation(&PlayerColour::Blue).unwrap().len(),
+            OBSERVATION_SIZE
+        );
+        assert!(g.encode_observation(&PlayerColour::Purple).is_err());
+    }
+
+    #[test]
+    fn test_action_space() {
+        let g = game();
+        let view = g.view_for(&PlayerColour::Red).unwrap();
+        for idx in 0..ACTION_SPACE {
+            if let Some(action) = view.action_at(idx) {
+                assert_eq!(view.action_index(&action), Some(idx), "{:?}", action);
+            }
+        }
+        assert_eq!(view.action_at(ACTION_SPACE), None);
+        assert_eq!(
+            view.action_index(&Action::Discard {
+                cards: Resources::new()
+            }),
+            None
+        );
+
+        // a victim is a seat relative to the viewer, and nobody in the last seat
+        let robber = Action::MoveRobber {
+            tile: 3,
+            victim: Some(PlayerColour::Green),
+            resource: None,
+        };
+        let idx = view.action_index(&robber).unwrap();
+        assert_eq!(view.action_at(idx), Some(robber));
+        assert_eq!(view.action_at(MOVE_ROBBER + 3), None);
+
+        let mask = view.action_mask();
+        assert_eq!(mask.len(), ACTION_SPACE);
+        assert_eq!(
+            mask.iter().filter(|legal| **legal).count(),
+            view.legal_actions.len()
+        );
+    }
+}
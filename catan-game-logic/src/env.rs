@@ -0,0 +1,266 @@
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use anyhow::{anyhow, Result};
+
+use crate::actions::{Action, ActionError};
+use crate::dice::FairDice;
+use crate::events::GameEvent;
+use crate::game::{Game, GameState};
+use crate::options::GameOptions;
+use crate::player::PlayerColour;
+use crate::view::PlayerView;
+
+/// What the agent about to act gets to see, i.e. their `PlayerView`
+///
+/// discards aren't listed in `legal_actions`, so an agent owing a `discard` picks the cards for
+/// `Action::Discard` itself
+pub type Observation = PlayerView;
+
+/// The built in ways `Env::step` scores a step for the player who took it
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Reward {
+    /// 1 for the step which wins the game, and nothing otherwise
+    #[default]
+    Win,
+    /// How many victory points the step gained, or lost
+    VictoryPoints,
+}
+
+type ShapingFn = dyn Fn(PlayerColour, &[GameEvent]) -> f64 + Send + Sync;
+
+/// Extra reward for a step, worked out from the player who took it and the events it caused
+#[derive(Clone)]
+pub struct RewardShaping(Arc<ShapingFn>);
+
+impl RewardShaping {
+    pub fn new(f: impl Fn(PlayerColour, &[GameEvent]) -> f64 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub fn reward(&self, player: PlayerColour, events: &[GameEvent]) -> f64 {
+        (self.0)(player, events)
+    }
+}
+
+impl Debug for RewardShaping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RewardShaping")
+    }
+}
+
+/// A reinforcement learning style environment around `Game`, in the spirit of gym's
+/// `reset`/`step`
+///
+/// every player is driven through the one environment, so each observation is for whoever the
+/// game is waiting on next, and each reward is for the player who took the step
+#[derive(Debug, Clone)]
+pub struct Env {
+    players: Vec<PlayerColour>,
+    options: GameOptions,
+    reward: Reward,
+    shaping: Vec<RewardShaping>,
+    game: Game,
+}
+
+impl Env {
+    pub fn new(players: Vec<PlayerColour>, options: GameOptions, reward: Reward) -> Self {
+        let game = Game::with_options(options);
+        Self {
+            players,
+            options,
+            reward,
+            shaping: Vec::new(),
+            game,
+        }
+    }
+
+    /// Add `shaping` on top of the `Reward` for every step from now on
+    ///
+    /// e.g. taking a point off for every discard
+    pub fn add_reward_shaping(&mut self, shaping: RewardShaping) {
+        self.shaping.push(shaping);
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    /// Start a fresh game, returning the first player's observation
+    ///
+    /// `seed` decides the turn order, every roll of the dice, the board, the order of the
+    /// development cards and everything else left to chance, unless the options already seed
+    /// the board or the deck
+    pub fn reset(&mut self, seed: u64) -> Result<Observation> {
+        let mut game = Game::with_options(GameOptions {
+            deck_seed: self.options.deck_seed.or(Some(seed)),
+            board_seed: self.options.board_seed.or(Some(seed)),
+            ..self.options
+        });
+        for player in &self.players {
+            game.add_player(*player);
+        }
+        game.randomize_turn_order(&mut StdRng::seed_from_u64(seed))?;
+        game.set_dice_roller(FairDice::seeded(seed));
+        game.seed_rng(StdRng::seed_from_u64(seed).gen());
+        self.game = game;
+        self.observe()
+    }
+
+    /// Take `action` for the player the game is waiting on, returning the next observation, the
+    /// reward for the step and whether the game is over
+    ///
+    /// once the game is over, the observation is for the player who took the final step
+    pub fn step(&mut self, action: Action) -> Result<(Observation, f64, bool), ActionError> {
+        let player = self
+            .game
+            .awaiting_player()
+            .ok_or(ActionError::NotAllowedNow("The game is over".into()))?;
+        let points_before = self.victory_points(&player);
+        let events_before = self.game.events().len();
+        self.game.apply(player, action)?;
+
+        let events = &self.game.events()[events_before..];
+        let shaped: f64 = self.shaping.iter().map(|s| s.reward(player, events)).sum();
+        let reward = shaped
+            + match self.reward {
+                Reward::Win => match self.game.winner() {
+                    Some(winner) if winner == player => 1.0,
+                    _ => 0.0,
+                },
+                Reward::VictoryPoints => self.victory_points(&player) as f64 - points_before as f64,
+            };
+        let done = self.game.state() == GameState::Complete;
+        let next = match self.game.awaiting_player() {
+            Some(next) if !done => next,
+            _ => player,
+        };
+        let observation = self
+            .game
+            .view_for(&next)
+            .map_err(|err| ActionError::Illegal(err.to_string()))?;
+        Ok((observation, reward, done))
+    }
+
+    /// Observation for the player the game is waiting on
+    pub fn observe(&self) -> Result<Observation> {
+        let player = self
+            .game
+            .awaiting_player()
+            .ok_or(anyhow!("The game isn't waiting on anyone"))?;
+        self.game.view_for(&player)
+    }
+
+    fn victory_points(&self, player: &PlayerColour) -> usize {
+        self.game.vp_breakdown(player).map_or(0, |vp| vp.total())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::resources::Resources;
+
+    fn env(reward: Reward) -> Env {
+        Env::new(
+            vec![PlayerColour::Red, PlayerColour::Blue],
+            GameOptions::default(),
+            reward,
+        )
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut e = env(Reward::Win);
+        let first = e.reset(7).unwrap();
+        assert!(first
+            .legal_actions
+            .iter()
+            .all(|a| matches!(a, Action::PlaceInitialSettlement { .. })));
+        assert_eq!(e.reset(7).unwrap().viewer, first.viewer);
+        assert_eq!(e.game().players().len(), 2);
+    }
+
+    #[test]
+    fn test_victory_point_reward() {
+        let mut e = env(Reward::VictoryPoints);
+        let obs = e.reset(1).unwrap();
+        let settle = obs.legal_actions[0].clone();
+        let (_, reward, done) = e.step(settle.clone()).unwrap();
+        assert_eq!(reward, 1.0);
+        assert!(!done);
+
+        // the same spot can't be taken twice
+        assert!(e.step(settle).is_err());
+    }
+
+    #[test]
+    fn test_reward_shaping() {
+        let mut e = env(Reward::Win);
+        e.add_reward_shaping(RewardShaping::new(|player, events| {
+            let cards: usize = events
+                .iter()
+                .filter_map(|event| match event {
+                    GameEvent::Production {
+                        player: p,
+                        resources,
+                    } if *p == player => Some(resources.total()),
+                    _ => None,
+                })
+                .sum();
+            cards as f64
+        }));
+
+        let mut obs = e.reset(5).unwrap();
+        let mut rewards = BTreeMap::new();
+        for _ in 0..4 {
+            let player = obs.viewer;
+            let (next, reward, _) = e.step(obs.legal_actions[0].clone()).unwrap();
+            *rewards.entry(player).or_insert(0.0) += reward;
+            obs = next;
+        }
+
+        // a point for each card handed out by the second settlements
+        for p in e.game().players() {
+            let cards = p.hand_size();
+            assert_eq!(rewards[p.colour()], cards as f64);
+        }
+    }
+
+    #[test]
+    fn test_random_play() {
+        let mut e = env(Reward::Win);
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut obs = e.reset(3).unwrap();
+        for _ in 0..200 {
+            let action = match obs.legal_actions.len() {
+                0 => {
+                    let owed = obs.discard.unwrap();
+                    let mut cards = Resources::new();
+                    for (kind, n) in obs.hand {
+                        cards[kind] += n.min(owed - cards.total());
+                    }
+                    Action::Discard { cards }
+                }
+                n => obs.legal_actions[rng.gen_range(0..n)].clone(),
+            };
+            let (next, reward, done) = e.step(action).unwrap();
+            assert_eq!(reward == 1.0, done);
+            if done {
+                return;
+            }
+            obs = next;
+        }
+    }
+}
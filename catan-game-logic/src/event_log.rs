@@ -0,0 +1,133 @@
+use std::io::{BufRead, Write};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::player::PlayerColour;
+
+/// One line of a game's exported log, see `Game::export_log`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventLogEntry {
+    /// Where the event comes in the game, counting up from 0
+    pub seq: usize,
+    /// When the event happened, in milliseconds since the Unix epoch, if the game knows
+    pub timestamp: Option<u64>,
+    /// The player whose action set the event off, if it came from one
+    pub player: Option<PlayerColour>,
+    pub event: GameEvent,
+}
+
+impl Game {
+    /// Every event so far, along with when it happened and who set it off
+    pub fn log_entries(&self) -> Vec<EventLogEntry> {
+        self.events()
+            .iter()
+            .enumerate()
+            .map(|(seq, event)| {
+                let origin = self.event_origin(seq);
+                EventLogEntry {
+                    seq,
+                    timestamp: origin
+                        .and_then(|origin| origin.at.duration_since(UNIX_EPOCH).ok())
+                        .map(|since| since.as_millis() as u64),
+                    player: origin.and_then(|origin| origin.player),
+                    event: event.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Write every event to `writer` as a line of JSON, see `EventLogEntry`, returning how many
+    /// were written
+    ///
+    /// read the log back with `import_log`, or line by line with anything that reads JSON
+    pub fn export_log(&self, mut writer: impl Write) -> Result<usize> {
+        let entries = self.log_entries();
+        for entry in &entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(entries.len())
+    }
+}
+
+/// Read back a log written by `Game::export_log`, skipping blank lines
+///
+/// fails if any line isn't an entry, or the entries aren't numbered in order from 0, which
+/// means the log was cut short or pieced together wrongly
+pub fn import_log(reader: impl BufRead) -> Result<Vec<EventLogEntry>> {
+    let mut entries: Vec<EventLogEntry> = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: EventLogEntry = serde_json::from_str(&line)
+            .map_err(|err| anyhow!("Line {} of the log isn't an entry: {}", idx + 1, err))?;
+        if entry.seq != entries.len() {
+            return Err(anyhow!(
+                "Line {} of the log is event {}, but event {} was expected",
+                idx + 1,
+                entry.seq,
+                entries.len()
+            ));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+
+    /// A game where red has placed their first settlement
+    fn settled_game() -> Game {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        let settle = g.legal_actions(&PlayerColour::Red)[0].clone();
+        g.apply(PlayerColour::Red, settle).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_export_log() {
+        let g = settled_game();
+        let mut log = Vec::new();
+        assert_eq!(g.export_log(&mut log).unwrap(), g.events().len());
+        let text = String::from_utf8(log.clone()).unwrap();
+        assert_eq!(text.lines().count(), g.events().len());
+
+        let entries = import_log(log.as_slice()).unwrap();
+        assert_eq!(entries, g.log_entries());
+        let built = entries
+            .iter()
+            .find(|entry| matches!(entry.event, GameEvent::SettlementBuilt { .. }))
+            .unwrap();
+        assert_eq!(built.player, Some(PlayerColour::Red));
+        assert!(built.timestamp.is_some());
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let g = settled_game();
+        let mut log = Vec::new();
+        g.export_log(&mut log).unwrap();
+        let text = String::from_utf8(log).unwrap();
+
+        // blank lines are fine, but gaps and garbage aren't
+        let spaced = text.replace('\n', "\n\n");
+        assert_eq!(
+            import_log(spaced.as_bytes()).unwrap().len(),
+            g.events().len()
+        );
+        let gap: String = text.lines().skip(1).collect::<Vec<_>>().join("\n");
+        assert!(import_log(gap.as_bytes()).is_err());
+        assert!(import_log("{\"seq\": 0}".as_bytes()).is_err());
+    }
+}
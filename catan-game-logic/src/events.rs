@@ -0,0 +1,140 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+#[cfg(feature = "std")]
+use uuid::Uuid;
+
+use crate::achievements::Achievement;
+use crate::awards::Award;
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+
+/// Decisions players have to make in time, or have made for them
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Decision {
+    Discard,
+    MoveRobber,
+    TradeResponse,
+    /// Moving at all before the player's clock ran out, see `GameOptions::clock`
+    Move,
+}
+
+/// Something observable which happened over the course of a game
+///
+/// events only say what every player could see at the table, e.g. a card being stolen, but not
+/// which card, or a development card being bought, but not what it was. See `Game::subscribe`
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum GameEvent {
+    /// Resources handed to a player, either from a roll or from their second settlement
+    Production {
+        player: PlayerColour,
+        resources: Resources,
+    },
+    /// Fish hauled in from the lake by a player with a settlement or city on its shore
+    FishHaul { player: PlayerColour, fish: usize },
+    /// The current player rolled the dice at the start of their turn
+    DiceRolled {
+        player: PlayerColour,
+        dice: (u8, u8),
+    },
+    /// A player's roll in the opening dice-off, deciding who goes first
+    DiceOffRoll {
+        player: PlayerColour,
+        dice: (u8, u8),
+    },
+    /// The order players will take their turns in, starting with the first player
+    TurnOrder { players: Vec<PlayerColour> },
+    /// Cards a player gave back to the bank after a 7, for holding too many
+    Discarded {
+        player: PlayerColour,
+        cards: Resources,
+    },
+    /// How many cards a player has left after discarding, without saying which
+    HandSize { player: PlayerColour, cards: usize },
+    /// A settlement was placed on the board
+    SettlementBuilt { player: PlayerColour },
+    /// A road was built
+    RoadBuilt { player: PlayerColour },
+    /// A settlement was upgraded to a city
+    CityBuilt { player: PlayerColour },
+    /// The robber was moved to `tile`, stealing a card from `victim` if they had any
+    RobberMoved {
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+        stole_card: bool,
+    },
+    /// A development card was bought, without saying which
+    DevelopmentCardBought { player: PlayerColour },
+    /// A knight was played, before the robber moved
+    KnightPlayed { player: PlayerColour },
+    /// A Road Building card was played, before its free roads were placed
+    RoadBuildingPlayed { player: PlayerColour },
+    /// A monopoly was played, taking every card of one resource from the other players
+    MonopolyPlayed {
+        player: PlayerColour,
+        resource: ResourceKind,
+        taken: usize,
+        /// How many cards were taken from each of the other players
+        #[cfg_attr(feature = "serde", serde(default))]
+        from: Vec<(PlayerColour, usize)>,
+    },
+    /// A player offered a trade to the other players
+    #[cfg(feature = "std")]
+    TradeProposed {
+        trade: Uuid,
+        player: PlayerColour,
+        offering: Resources,
+        wants: Resources,
+    },
+    /// A player said they'd take a trade
+    #[cfg(feature = "std")]
+    TradeAccepted { trade: Uuid, player: PlayerColour },
+    /// A trade went through, swapping cards between the two players
+    #[cfg(feature = "std")]
+    TradeCompleted {
+        trade: Uuid,
+        from: PlayerColour,
+        to: PlayerColour,
+    },
+    /// A trade nobody accepted in time was taken off the table
+    #[cfg(feature = "std")]
+    TradeWithdrawn { trade: Uuid },
+    /// A player's visible victory points changed, to `points`
+    VictoryPointsChanged { player: PlayerColour, points: usize },
+    /// An award changed hands, or went back to the supply
+    AwardChanged {
+        award: Award,
+        holder: Option<PlayerColour>,
+    },
+    /// A player won, by reaching enough victory points or by everyone else resigning, ending
+    /// the game
+    GameWon { player: PlayerColour },
+    /// A player gave up, leaving their pieces on the board
+    Resigned { player: PlayerColour },
+    /// The game was ended before anyone won it, with `winner` declared the winner if there is
+    /// one, see `Game::end`
+    GameEnded { winner: Option<PlayerColour> },
+    AchievementEarned {
+        player: PlayerColour,
+        achievement: Achievement,
+    },
+    /// A player ran out of time to make a decision, so the game made a default choice for them
+    TimedOut {
+        player: PlayerColour,
+        decision: Decision,
+    },
+}
@@ -0,0 +1,311 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+
+/// A card or building added by an extension, and what it costs to buy
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExtensionItem {
+    pub name: String,
+    pub cost: Resources,
+}
+
+impl ExtensionItem {
+    pub fn new(name: impl Into<String>, cost: Resources) -> Self {
+        Self {
+            name: name.into(),
+            cost,
+        }
+    }
+}
+
+/// Names a card or building belonging to one of a game's extensions
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ExtensionItemId {
+    /// Name of the extension the item comes from
+    pub extension: String,
+    pub item: String,
+}
+
+impl ExtensionItemId {
+    pub fn new(extension: impl Into<String>, item: impl Into<String>) -> Self {
+        Self {
+            extension: extension.into(),
+            item: item.into(),
+        }
+    }
+}
+
+/// Actions on the cards and buildings added by extensions
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ExtensionAction {
+    Build { id: ExtensionItemId },
+    BuyCard { id: ExtensionItemId },
+    PlayCard { id: ExtensionItemId },
+}
+
+/// House rules and add-ons which other crates can plug into the engine, registered with
+/// `Game::register_extension`
+///
+/// extensions declare the cards and buildings they add, and `Game` takes care of paying for
+/// them and keeping track of who holds which cards. What they actually do is up to the
+/// extension's hooks, which mostly default to doing nothing
+pub trait RulesExtension: Debug + Send + Sync {
+    /// Name of the extension, which must be unique within a game
+    fn name(&self) -> &str;
+
+    /// Cards players can buy, hold and later play
+    fn cards(&self) -> Vec<ExtensionItem> {
+        Vec::new()
+    }
+
+    /// Buildings players can pay for during their turn
+    fn buildings(&self) -> Vec<ExtensionItem> {
+        Vec::new()
+    }
+
+    /// Called for every event the game records, after it's been recorded
+    ///
+    /// events emitted by extension hooks aren't passed back to the extensions
+    fn on_event(&mut self, _game: &mut Game, _event: &GameEvent) {}
+
+    /// Called as `player` ends their turn, before play passes on
+    fn on_turn_end(&mut self, _game: &mut Game, _player: PlayerColour) {}
+
+    /// Called before `player` pays for one of this extension's buildings, and can stop them
+    /// building it by returning an error
+    fn on_build(&mut self, _game: &mut Game, _player: PlayerColour, _building: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when `player` plays one of this extension's cards, before it leaves their hand,
+    /// and can stop them playing it by returning an error
+    fn on_play_card(&mut self, _game: &mut Game, _player: PlayerColour, card: &str) -> Result<()> {
+        Err(anyhow!("{} can't be played", card))
+    }
+
+    fn clone_box(&self) -> Box<dyn RulesExtension>;
+
+    /// Lets callers get back to the concrete extension, e.g. to read its state
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// The extensions registered with a game, in the order they were registered
+///
+/// like `ScenarioHandle`, two sets of extensions are equal when they have the same names
+#[derive(Debug, Default)]
+pub struct Extensions(pub(crate) Vec<Box<dyn RulesExtension>>);
+
+impl Extensions {
+    pub fn get(&self, name: &str) -> Option<&dyn RulesExtension> {
+        self.0
+            .iter()
+            .find(|ext| ext.name() == name)
+            .map(|ext| ext.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn RulesExtension> {
+        self.0.iter().map(|ext| ext.as_ref())
+    }
+
+    /// The card or building `id` refers to, from `items` of the extension it names
+    pub(crate) fn item(
+        &self,
+        id: &ExtensionItemId,
+        items: impl Fn(&dyn RulesExtension) -> Vec<ExtensionItem>,
+    ) -> Result<ExtensionItem> {
+        let ext = self
+            .get(&id.extension)
+            .ok_or(anyhow!("No extension called {}", id.extension))?;
+        items(ext)
+            .into_iter()
+            .find(|item| item.name == id.item)
+            .ok_or(anyhow!("{} doesn't add {}", id.extension, id.item))
+    }
+}
+
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        Self(self.0.iter().map(|ext| ext.clone_box()).collect())
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter()
+            .map(|ext| ext.name())
+            .eq(other.iter().map(|ext| ext.name()))
+    }
+}
+
+impl Eq for Extensions {}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::actions::Action;
+    use crate::board::VertexId;
+    use crate::dice::FixedDice;
+    use crate::resources::ResourceKind;
+
+    /// Walls which are worth a point, and a bounty card which pays out a chit
+    #[derive(Debug, Clone, Default)]
+    struct Fortifications {
+        events_seen: usize,
+        turns_ended: usize,
+    }
+
+    impl RulesExtension for Fortifications {
+        fn name(&self) -> &str {
+            "Fortifications"
+        }
+
+        fn cards(&self) -> Vec<ExtensionItem> {
+            vec![ExtensionItem::new(
+                "Bounty",
+                Resources::new_explicit(1, 0, 0, 0, 0),
+            )]
+        }
+
+        fn buildings(&self) -> Vec<ExtensionItem> {
+            vec![ExtensionItem::new(
+                "Wall",
+                Resources::new_explicit(0, 0, 0, 2, 0),
+            )]
+        }
+
+        fn on_event(&mut self, _game: &mut Game, _event: &GameEvent) {
+            self.events_seen += 1;
+        }
+
+        fn on_turn_end(&mut self, _game: &mut Game, _player: PlayerColour) {
+            self.turns_ended += 1;
+        }
+
+        fn on_build(
+            &mut self,
+            game: &mut Game,
+            player: PlayerColour,
+            _building: &str,
+        ) -> Result<()> {
+            *game.get_player_mut(player)?.chits_mut() += 1;
+            Ok(())
+        }
+
+        fn on_play_card(
+            &mut self,
+            game: &mut Game,
+            player: PlayerColour,
+            _card: &str,
+        ) -> Result<()> {
+            *game.get_player_mut(player)?.chits_mut() += 1;
+            Ok(())
+        }
+
+        fn clone_box(&self) -> Box<dyn RulesExtension> {
+            Box::new(self.clone())
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn fortified_game() -> Game {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.register_extension(Fortifications::default()).unwrap();
+        g
+    }
+
+    fn fortifications(g: &Game) -> &Fortifications {
+        g.extensions()
+            .get("Fortifications")
+            .unwrap()
+            .as_any()
+            .downcast_ref()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_register() {
+        let mut g = fortified_game();
+        g.randomize_turn_order(&mut crate::platform::entropy_rng())
+            .unwrap();
+        assert!(g.register_extension(Fortifications::default()).is_err());
+        assert_eq!(g.extensions().iter().count(), 1);
+
+        // the turn order event was passed on to the extension
+        assert_eq!(fortifications(&g).events_seen, g.events().len());
+        assert_eq!(g.clone().extensions(), g.extensions());
+    }
+
+    #[test]
+    fn test_build_and_play() {
+        let mut g = fortified_game();
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        for (player, vertex) in [
+            (PlayerColour::Red, 2),
+            (PlayerColour::Blue, 30),
+            (PlayerColour::Blue, 40),
+            (PlayerColour::Red, 10),
+        ] {
+            g.place_initial_settlement(player, VertexId::new(vertex))
+                .unwrap();
+        }
+        g.roll_dice().unwrap();
+        let player = PlayerColour::Red;
+        g.set_hand(player, Resources::new_explicit(1, 0, 0, 2, 0));
+
+        let wall = ExtensionItemId::new("Fortifications", "Wall");
+        let bounty = ExtensionItemId::new("Fortifications", "Bounty");
+        let actions = g.legal_actions(&player);
+        for action in [
+            ExtensionAction::Build { id: wall.clone() },
+            ExtensionAction::BuyCard { id: bounty.clone() },
+        ] {
+            assert!(actions.contains(&Action::Extension { action }));
+        }
+
+        g.build_extension_building(player, &wall).unwrap();
+        assert!(g.build_extension_building(player, &wall).is_err());
+        g.buy_extension_card(player, &bounty).unwrap();
+        assert_eq!(g.extension_cards(&player), vec![&bounty]);
+        assert_eq!(
+            g.get_player(&player).unwrap().resources()[ResourceKind::Brick],
+            0
+        );
+
+        g.apply(
+            player,
+            Action::Extension {
+                action: ExtensionAction::PlayCard { id: bounty },
+            },
+        )
+        .unwrap();
+        assert!(g.extension_cards(&player).is_empty());
+        assert_eq!(g.get_player(&player).unwrap().chits(), 2);
+
+        g.end_turn(player).unwrap();
+        assert_eq!(fortifications(&g).turns_ended, 1);
+    }
+}
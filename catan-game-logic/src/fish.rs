@@ -0,0 +1,137 @@
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::board::EdgeId;
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+use crate::player::PlayerColour;
+use crate::resources::ResourceKind;
+
+/// Rolls which haul in fish for everyone settled on the shore of the lake
+pub const LAKE_ROLLS: [u8; 4] = [2, 3, 11, 12];
+
+/// How many fish tokens of each size make up a full bag, as `(fish, tokens)` pairs
+pub const FISH_TOKENS: [(usize, usize); 3] = [(1, 11), (2, 10), (3, 8)];
+
+/// Things fish can be traded in for, during the current player's turn
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum FishPurchase {
+    /// Take the robber off the board until the next 7 is rolled
+    RemoveRobber,
+    /// Steal a random resource from another player
+    StealResource { victim: PlayerColour },
+    /// Take a resource of your choice from the bank
+    TakeResource { resource: ResourceKind },
+    /// Build a road without paying for it
+    FreeRoad { edge: EdgeId },
+    /// Draw a development card without paying for it
+    DevelopmentCard,
+}
+
+impl FishPurchase {
+    /// Number of fish this purchase costs
+    pub fn cost(&self) -> usize {
+        match self {
+            FishPurchase::RemoveRobber => 2,
+            FishPurchase::StealResource { .. } => 3,
+            FishPurchase::TakeResource { .. } => 4,
+            FishPurchase::FreeRoad { .. } => 5,
+            FishPurchase::DevelopmentCard => 7,
+        }
+    }
+}
+
+/// The bag fish tokens are drawn from, each worth between one and three fish
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FishBag {
+    tokens: Vec<usize>,
+}
+
+impl FishBag {
+    pub fn new() -> Self {
+        Self {
+            tokens: FISH_TOKENS
+                .iter()
+                .flat_map(|&(fish, count)| std::iter::repeat_n(fish, count))
+                .collect(),
+        }
+    }
+
+    /// Draw a token from the bag, returning how many fish it's worth
+    ///
+    /// spent fish aren't tracked as tokens, so once the bag runs dry it's refilled with a full set
+    pub fn draw(&mut self) -> usize {
+        #[cfg(feature = "rand")]
+        return self.draw_with(&mut entropy_rng());
+        #[cfg(not(feature = "rand"))]
+        {
+            if self.tokens.is_empty() {
+                *self = Self::new();
+            }
+            self.tokens.pop().unwrap_or_default()
+        }
+    }
+
+    /// Draw a token like `draw`, picking it with `rng`
+    #[cfg(feature = "rand")]
+    pub fn draw_with(&mut self, rng: &mut impl Rng) -> usize {
+        if self.tokens.is_empty() {
+            *self = Self::new();
+        }
+        let idx = rng.gen_range(0..self.tokens.len());
+        self.tokens.swap_remove(idx)
+    }
+
+    /// Number of tokens left in the bag
+    pub fn remaining(&self) -> usize {
+        self.tokens.len()
+    }
+}
+
+impl Default for FishBag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bag() {
+        let mut bag = FishBag::new();
+        assert_eq!(bag.remaining(), 29);
+
+        let fish: usize = (0..29).map(|_| bag.draw()).sum();
+        assert_eq!(fish, 11 + 2 * 10 + 3 * 8);
+        assert_eq!(bag.remaining(), 0);
+
+        assert!((1..=3).contains(&bag.draw()));
+        assert_eq!(bag.remaining(), 28);
+    }
+
+    #[test]
+    fn test_costs() {
+        assert_eq!(FishPurchase::RemoveRobber.cost(), 2);
+        assert_eq!(
+            FishPurchase::TakeResource {
+                resource: ResourceKind::Ore
+            }
+            .cost(),
+            4
+        );
+        assert_eq!(FishPurchase::DevelopmentCard.cost(), 7);
+    }
+}
@@ -1,206 +1,5670 @@
-use crate::board::Board;
-use crate::resources::Resources;
+use crate::achievements::AchievementTracker;
+use crate::actions::{Action, ActionError};
+use crate::awards::{Award, AWARD_POINTS};
+#[cfg(feature = "rand")]
+use crate::bank::BankError;
+use crate::board::{Board, EdgeId, Location, Tile, TileKind, VertexId};
+use crate::building::Building;
+use crate::compact::{
+    decode_board, decode_development_card, decode_player, decode_player_index, decode_resources,
+    encode_board, encode_player, encode_player_index, encode_resources, small, CompactReader,
+    COMPACT_VERSION,
+};
+use crate::delta::GameDelta;
+use crate::development_cards::DevelopmentCard;
+use crate::dice::{
+    combinations_for, Dice, DiceRoller, Luck, RollHistory, RollRecord, MAX_ROLL, MIN_ROLL,
+};
+use crate::discard::DiscardManager;
+use crate::events::{Decision, GameEvent};
+use crate::extensions::{
+    ExtensionAction, ExtensionItem, ExtensionItemId, Extensions, RulesExtension,
+};
+use crate::fish::{FishBag, FishPurchase, LAKE_ROLLS};
+use crate::options::{ClockExpiry, GameOptions, Resignation, TurnClock};
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+use crate::platform::now;
+use crate::player_map::{PlayerMap, PlayerMut};
+use crate::resources::{ResourceKind, Resources};
+use crate::scenarios::{Scenario, ScenarioHandle};
+use crate::score::{GameSummary, PlayerSummary, VpBreakdown};
+#[cfg(all(feature = "rand", feature = "serde"))]
+use crate::selfplay::{Agent, RandomAgent};
 use crate::trade::TradeState::*;
+use crate::trade_manager::TradeManager;
+use crate::transfer::Transfer;
+use crate::view::{OpponentView, PlayerView};
+use crate::zobrist::{colour_code, key, Feature};
 use crate::Player;
 use crate::{bank::Bank, player::PlayerColour};
 
 use anyhow::{anyhow, Result};
-use rand::{thread_rng, Rng};
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 use uuid::Uuid;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
-#[serde(rename_all = "lowercase")]
+/// Rolling this total produces nothing, and moves the robber instead
+pub const ROBBER_ROLL: u8 = 7;
+
+/// Victory points a player needs, on their own turn, to win the game
+pub const VICTORY_POINTS_TO_WIN: usize = 10;
+
+/// Give up on breaking a tie in the dice-off after this many re-rolls, leaving the tied players
+/// in the order they joined
+pub const MAX_DICE_OFF_ROUNDS: usize = 10;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum GameState {
     Setup,
     Running,
+    /// Frozen by the host or an admin, nobody can act until the game is resumed
+    Paused,
     Complete,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+/// Where the current player has got to in their turn
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TurnPhase {
+    /// Waiting for the dice to be rolled
+    Roll,
+    /// A 7 was rolled, and players holding too many cards are choosing what to discard
+    Discard,
+    /// A 7 was rolled, and the current player has to move the robber
+    MoveRobber,
+    /// The dice have been rolled, so the current player can trade and build until they end their
+    /// turn
+    Main,
+    /// A Road Building card was played, and the current player is placing its free roads before
+    /// carrying on with their turn
+    PlacingFreeRoads,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+// the derived impls are kept as `Game::serialize` and `Game::deserialize`, so the `Deserialize`
+// impl below can work the hash out again afterwards
+#[cfg_attr(feature = "serde", serde(remote = "Self"))]
 pub struct Game {
-    players: Vec<Player>,
+    players: PlayerMap,
     board: Board,
     bank: Bank,
     state: GameState,
     turn_no: usize,
+    phase: TurnPhase,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    dice: Dice,
+    roll_history: RollHistory,
+    /// Each player's production from rolls, against what they'd have expected
+    #[cfg_attr(feature = "serde", serde(default))]
+    luck: Vec<Luck>,
+    discards: DiscardManager,
+    /// Trades offered between players; trades with the bank or a harbor happen straight away
+    #[cfg_attr(feature = "serde", serde(default))]
+    trades: TradeManager,
+    setup_placements: usize,
+    events: Vec<GameEvent>,
+    /// When each event happened and whose action set it off, in the same order as `events`
+    #[cfg_attr(feature = "serde", serde(default))]
+    event_origins: Vec<EventOrigin>,
+    /// Development cards bought during the current turn, which can't be played until a later one
+    bought_this_turn: Vec<DevelopmentCard>,
+    /// Only one development card can be played each turn
+    development_card_played: bool,
+    /// Roads left to place for free from a Road Building card
+    free_roads: u8,
+    longest_road: Option<PlayerColour>,
+    largest_army: Option<PlayerColour>,
+    harbormaster: Option<PlayerColour>,
+    /// State to go back to once a paused game resumes, and when it was paused
+    paused: Option<(GameState, SystemTime)>,
+    /// Player in charge of the game, the first to join unless handed on
+    host: Option<PlayerColour>,
+    /// Votes to kick a player, as `(voter, target)` pairs
+    kick_votes: Vec<(PlayerColour, PlayerColour)>,
+    options: GameOptions,
+    fish: FishBag,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    scenario: ScenarioHandle,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    extensions: Extensions,
+    /// Cards added by extensions, and the players holding them
+    extension_cards: Vec<(PlayerColour, ExtensionItemId)>,
+    achievements: AchievementTracker,
+    winner: Option<PlayerColour>,
+    /// When the current player runs out of time to move the robber
+    robber_deadline: Option<SystemTime>,
+    /// Open trade offers, and when players run out of time to respond to them
+    trade_deadlines: Vec<(Uuid, SystemTime)>,
+    /// The player on the clock, and since when
+    #[cfg_attr(feature = "serde", serde(default))]
+    clock: Option<(PlayerColour, SystemTime)>,
+    /// Time each player has left with a chess clock, as of when they last stopped theirs
+    #[cfg_attr(feature = "serde", serde(default))]
+    clocks: Vec<(PlayerColour, Duration)>,
+    /// Players who have given up, in the order they did
+    #[cfg_attr(feature = "serde", serde(default))]
+    resigned: Vec<PlayerColour>,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    agents: BotAgents,
+    #[cfg_attr(feature = "serde", serde(default = "Uuid::new_v4"))]
+    id: Uuid,
+    /// The game this is a rematch of, see `rematch`
+    #[cfg_attr(feature = "serde", serde(default))]
+    rematch_of: Option<Uuid>,
+    /// Zobrist hash of the turn and anything owed or already done in it, kept up to date as they
+    /// change
+    #[cfg_attr(feature = "serde", serde(default))]
+    hash: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rng: GameRng,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Game {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Game::serialize(self, serializer)
+    }
+}
+
+/// Reads a game back, working out its hash from the turn rather than trusting a saved one which
+/// could be missing or edited, as the board and players do for theirs
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut game = Game::deserialize(deserializer)?;
+        game.hash = game.turn_hash();
+        Ok(game)
+    }
 }
 
 impl Game {
+    #[cfg(feature = "rand")]
     pub fn new() -> Self {
-        Game {
-            players: Vec::new(),
+        let mut game = Game {
+            players: PlayerMap::new(),
             board: Board::new(),
             bank: Bank::new(),
             state: GameState::Setup,
             turn_no: 0,
+            phase: TurnPhase::Roll,
+            dice: Dice::default(),
+            roll_history: RollHistory::new(),
+            luck: Vec::new(),
+            discards: DiscardManager::new(),
+            trades: TradeManager::new(),
+            setup_placements: 0,
+            events: Vec::new(),
+            event_origins: Vec::new(),
+            bought_this_turn: Vec::new(),
+            development_card_played: false,
+            free_roads: 0,
+            longest_road: None,
+            largest_army: None,
+            harbormaster: None,
+            paused: None,
+            host: None,
+            kick_votes: Vec::new(),
+            options: GameOptions::default(),
+            fish: FishBag::new(),
+            scenario: ScenarioHandle::default(),
+            extensions: Extensions::default(),
+            extension_cards: Vec::new(),
+            achievements: AchievementTracker::new(),
+            winner: None,
+            robber_deadline: None,
+            trade_deadlines: Vec::new(),
+            clock: None,
+            clocks: Vec::new(),
+            resigned: Vec::new(),
+            agents: BotAgents::default(),
+            id: Uuid::new_v4(),
+            rematch_of: None,
+            hash: 0,
+            rng: GameRng::default(),
+        };
+        game.hash = game.turn_hash();
+        game
+    }
+
+    /// Create a game on a random board, playing with the given rule variants
+    #[cfg(feature = "rand")]
+    pub fn with_options(options: GameOptions) -> Self {
+        let bank = match options.deck_seed {
+            Some(seed) => {
+                Bank::with_deck(&options.development_cards, &mut StdRng::seed_from_u64(seed))
+            }
+            None => Bank::with_deck(&options.development_cards, &mut entropy_rng()),
+        };
+        let mut game = Game {
+            options,
+            bank,
+            ..Self::new()
+        };
+        if let Some(seed) = options.board_seed {
+            game.board = Board::random_with(&mut StdRng::seed_from_u64(seed));
+        }
+        game.discards.set_timeout(options.timeouts.discard);
+        game.discards.set_threshold(options.discard_threshold);
+        game
+    }
+
+    pub fn options(&self) -> &GameOptions {
+        &self.options
+    }
+
+    /// Play `scenario` on top of the base rules, replacing any scenario already in play
+    pub fn set_scenario(&mut self, scenario: impl Scenario + 'static) {
+        self.scenario = ScenarioHandle(Some(Box::new(scenario)));
+        self.with_scenario(|scenario, game| scenario.on_setup(game));
+    }
+
+    pub fn scenario(&self) -> Option<&dyn Scenario> {
+        self.scenario.0.as_deref()
+    }
+
+    /// Run `f` against the scenario in play, if there is one
+    ///
+    /// the scenario is taken out of the game while `f` runs, so that it can change the game too
+    fn with_scenario<R>(&mut self, f: impl FnOnce(&mut dyn Scenario, &mut Game) -> R) -> Option<R> {
+        let mut scenario = self.scenario.0.take()?;
+        let result = f(scenario.as_mut(), self);
+        self.scenario.0 = Some(scenario);
+        Some(result)
+    }
+
+    /// Add `extension` to the game, alongside any which are already registered
+    pub fn register_extension(&mut self, extension: impl RulesExtension + 'static) -> Result<()> {
+        if self.extensions.get(extension.name()).is_some() {
+            return Err(anyhow!("{} is already registered", extension.name()));
+        }
+        self.extensions.0.push(Box::new(extension));
+        Ok(())
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Cards from extensions which `player` is holding
+    pub fn extension_cards(&self, player: &PlayerColour) -> Vec<&ExtensionItemId> {
+        self.extension_cards
+            .iter()
+            .filter(|(p, _)| p == player)
+            .map(|(_, id)| id)
+            .collect()
+    }
+
+    /// Run `f` against every registered extension in turn, taking them out of the game while it
+    /// runs just like `with_scenario`
+    fn with_extensions(&mut self, mut f: impl FnMut(&mut dyn RulesExtension, &mut Game)) {
+        let mut extensions = std::mem::take(&mut self.extensions.0);
+        for extension in extensions.iter_mut() {
+            f(extension.as_mut(), self);
+        }
+        extensions.append(&mut self.extensions.0);
+        self.extensions.0 = extensions;
+    }
+
+    /// Run `f` against the extension called `name`
+    fn with_extension<R>(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut dyn RulesExtension, &mut Game) -> Result<R>,
+    ) -> Result<R> {
+        let idx = self
+            .extensions
+            .0
+            .iter()
+            .position(|ext| ext.name() == name)
+            .ok_or(anyhow!("No extension called {}", name))?;
+        let mut extension = self.extensions.0.remove(idx);
+        let result = f(extension.as_mut(), self);
+        self.extensions.0.insert(idx, extension);
+        result
+    }
+
+    /// Pay for one of the buildings an extension adds, on `player`'s turn
+    pub fn build_extension_building(
+        &mut self,
+        player: PlayerColour,
+        id: &ExtensionItemId,
+    ) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        let building = self.extensions.item(id, |ext| ext.buildings())?;
+        if !self
+            .get_player(&player)?
+            .resources()
+            .contains(&building.cost)
+        {
+            return Err(anyhow!("Not enough resources to build {}", building.name));
+        }
+
+        self.with_extension(&id.extension, |ext, game| {
+            ext.on_build(game, player, &id.item)
+        })?;
+        self.pay_to_bank(player, building.cost)
+    }
+
+    /// Buy one of the cards an extension adds, which goes into `player`'s hand to play later
+    pub fn buy_extension_card(&mut self, player: PlayerColour, id: &ExtensionItemId) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        let card = self.extensions.item(id, |ext| ext.cards())?;
+        self.pay_to_bank(player, card.cost)?;
+        self.extension_cards.push((player, id.clone()));
+        Ok(())
+    }
+
+    /// Play an extension card from `player`'s hand
+    pub fn play_extension_card(
+        &mut self,
+        player: PlayerColour,
+        id: &ExtensionItemId,
+    ) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        let idx = self
+            .extension_cards
+            .iter()
+            .position(|(p, card)| *p == player && card == id)
+            .ok_or(anyhow!("That player isn't holding {}", id.item))?;
+
+        self.with_extension(&id.extension, |ext, game| {
+            ext.on_play_card(game, player, &id.item)
+        })?;
+        self.extension_cards.remove(idx);
+        Ok(())
+    }
+
+    /// Every extension card and building `player` could buy or play right now
+    fn extension_actions(&self, player: &PlayerColour) -> Vec<ExtensionAction> {
+        let Ok(p) = self.get_player(player) else {
+            return Vec::new();
+        };
+
+        let mut actions = Vec::new();
+        for ext in self.extensions.iter() {
+            let affordable = |items: Vec<_>| {
+                items
+                    .into_iter()
+                    .filter(|item: &ExtensionItem| p.resources().contains(&item.cost))
+                    .map(|item| ExtensionItemId::new(ext.name(), item.name))
+            };
+            actions.extend(affordable(ext.buildings()).map(|id| ExtensionAction::Build { id }));
+            actions.extend(affordable(ext.cards()).map(|id| ExtensionAction::BuyCard { id }));
+        }
+        actions.extend(
+            self.extension_cards(player)
+                .into_iter()
+                .map(|id| ExtensionAction::PlayCard { id: id.clone() }),
+        );
+        actions
+    }
+
+    /// Identifies this game for as long as it lasts, and links rematches back to it
+    pub fn get_game_id(&self) -> Result<Uuid> {
+        Ok(self.id)
+    }
+
+    /// The id of the game this is a rematch of, if it is one
+    pub fn rematch_of(&self) -> Option<Uuid> {
+        self.rematch_of
+    }
+
+    /// Seat a new player at the end of the turn order, doing nothing if their colour is taken
+    pub fn add_player(&mut self, colour: PlayerColour) {
+        if self.update_players(|players| players.push(Player::new(colour))) {
+            self.host.get_or_insert(colour);
+        }
+    }
+
+    pub fn host(&self) -> Option<PlayerColour> {
+        self.host
+    }
+
+    /// Hand the host role from `host` to another player
+    pub fn transfer_host(&mut self, host: PlayerColour, to: PlayerColour) -> Result<()> {
+        if self.host != Some(host) {
+            return Err(anyhow!("Only the host can do that"));
+        }
+        self.get_player(&to)?;
+        self.host = Some(to);
+        Ok(())
+    }
+
+    /// Kick `target` out of the game on the host's say-so
+    pub fn kick(&mut self, host: PlayerColour, target: PlayerColour) -> Result<()> {
+        if self.host != Some(host) {
+            return Err(anyhow!("Only the host can do that"));
+        }
+        if host == target {
+            return Err(anyhow!("The host can't kick themselves"));
+        }
+        self.remove_or_replace(target)
+    }
+
+    /// Take `target` out of the game without a host or a vote, such as for the people running
+    /// the server
+    pub fn remove_player(&mut self, target: PlayerColour) -> Result<()> {
+        self.remove_or_replace(target)
+    }
+
+    /// Vote to kick `target` out of the game, returning whether enough votes have now been cast
+    ///
+    /// a kick needs votes from more than half of the other players
+    pub fn vote_kick(&mut self, voter: PlayerColour, target: PlayerColour) -> Result<bool> {
+        if self.state == GameState::Complete {
+            return Err(anyhow!("The game has finished"));
+        }
+        if voter == target {
+            return Err(anyhow!("Cannot vote to kick yourself"));
+        }
+        self.get_player(&voter)?;
+        self.get_player(&target)?;
+        if !self.kick_votes.contains(&(voter, target)) {
+            self.kick_votes.push((voter, target));
+        }
+
+        let votes = self.kick_votes.iter().filter(|(_, t)| *t == target).count();
+        let needed = (self.players.len() - 1) / 2 + 1;
+        if votes < needed {
+            return Ok(false);
+        }
+        self.remove_or_replace(target)?;
+        Ok(true)
+    }
+
+    /// Take `target` out of the game
+    ///
+    /// a player kicked partway through setup is replaced by a bot, since the snake order depends
+    /// on who has already placed. Otherwise they're removed entirely, with their cards going back
+    /// to the bank and their pieces coming off the board, and nobody is left waiting on them for
+    /// a discard, a trade or their clock
+    fn remove_or_replace(&mut self, target: PlayerColour) -> Result<()> {
+        let idx = self
+            .players
+            .position(&target)
+            .ok_or(anyhow!("Could not find that player"))?;
+        self.kick_votes
+            .retain(|(voter, t)| *voter != target && *t != target);
+
+        if self.state == GameState::Setup && self.setup_placements > 0 {
+            self.get_player_mut(target)?.set_bot(true);
+            return Ok(());
+        }
+
+        let current = self.turn_no % self.players.len();
+        let player = self
+            .update_players(|players| players.remove(&target))
+            .ok_or(anyhow!("Could not find that player"))?;
+        self.bank.return_resources(*player.resources());
+        for card in player.development_cards() {
+            self.bank.return_dev_card(*card);
+        }
+        for id in self
+            .board
+            .vertices()
+            .iter()
+            .filter(|v| matches!(v.building(), Some((owner, _)) if *owner == target))
+            .map(|v| *v.id())
+            .collect::<Vec<_>>()
+        {
+            self.board.set_building(id, None)?;
+        }
+        for id in self
+            .board
+            .edges()
+            .iter()
+            .filter(|e| e.road() == Some(&target))
+            .map(|e| *e.id())
+            .collect::<Vec<_>>()
+        {
+            self.board.set_road(id, None)?;
+        }
+        self.update_awards();
+
+        let trades: Vec<_> = self
+            .trades
+            .iter()
+            .filter(|(_, trade)| {
+                *trade.state() != Accepted
+                    && (trade.get_offering_player() == target
+                        || trade.get_trade_partner().ok() == Some(target))
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for trade_id in trades {
+            self.trades.cancel_trade(trade_id);
+        }
+        let trades = &self.trades;
+        self.trade_deadlines
+            .retain(|(id, _)| trades.get_trade(*id).is_some());
+        self.clocks.retain(|(colour, _)| *colour != target);
+        if matches!(self.clock, Some((on_clock, _)) if on_clock == target) {
+            self.clock = None;
+        }
+        if self.discards.required(&target).is_some() {
+            self.update_discards(|discards| discards.resolve(&target));
+        }
+
+        if self.host == Some(target) {
+            self.host = self.players.first().map(|p| *p.colour());
+        }
+        if self.players.is_empty() {
+            return Ok(());
+        }
+
+        // keep the turn with whoever had it, or pass it on if it was the kicked player's
+        let next = match idx < current {
+            true => current - 1,
+            false => current % self.players.len(),
+        };
+        if idx == current && self.state != GameState::Setup {
+            // the 7 went with them, so nobody owes a discard or a robber move for it any more
+            self.update_discards(|discards| discards.restore(Vec::new(), now()));
+            self.robber_deadline = None;
+            self.set_phase(TurnPhase::Roll);
+            self.clear_turn_cards();
+        } else if self.phase == TurnPhase::Discard && !self.discards.is_pending() {
+            self.await_robber();
+        }
+        let len = self.players.len();
+        self.set_turn_no(self.turn_no + (next + len - self.turn_no % len) % len);
+        Ok(())
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn phase(&self) -> TurnPhase {
+        self.phase
+    }
+
+    /// The player whose turn it currently is
+    pub fn current_player(&self) -> Result<&Player> {
+        if self.players.is_empty() {
+            return Err(anyhow!("There are no players in this game"));
+        }
+        Ok(&self.players[self.turn_no % self.players.len()])
+    }
+
+    /// Roll the dice using this game's `DiceRoller` on behalf of the current player, recording
+    /// the roll in the game's history and handing out whatever it produces
+    pub fn roll_dice(&mut self) -> Result<(u8, u8)> {
+        let player = *self.current_player()?.colour();
+        self.ensure_turn(&player, TurnPhase::Roll)?;
+        trace_span!(DEBUG, "roll", %player, turn = self.turn_no);
+        let roll = self.dice.roll();
+        self.roll_history
+            .record(RollRecord::new(player, self.turn_no, roll));
+        self.emit(GameEvent::DiceRolled { player, dice: roll });
+        self.expect_production();
+
+        match roll.0 + roll.1 {
+            ROBBER_ROLL => {
+                let hand_sizes: Vec<_> = self
+                    .players
+                    .iter()
+                    .map(|p| (*p.colour(), p.hand_size()))
+                    .collect();
+                self.update_discards(|discards| discards.request(hand_sizes, now()));
+                match self.discards.is_pending() {
+                    true => self.set_phase(TurnPhase::Discard),
+                    false => self.await_robber(),
+                }
+            }
+            total => {
+                self.distribute_production(total);
+                if self.options.fishermen && LAKE_ROLLS.contains(&total) {
+                    self.haul_fish();
+                }
+                self.set_phase(TurnPhase::Main);
+            }
+        }
+
+        let total = roll.0 + roll.1;
+        self.with_scenario(|scenario, game| scenario.on_roll(game, total));
+        Ok(roll)
+    }
+
+    /// Freeze the game, rejecting every action and stopping the discard timer until it's resumed
+    pub fn pause(&mut self) -> Result<()> {
+        match self.state {
+            GameState::Paused => Err(anyhow!("The game is already paused")),
+            GameState::Complete => Err(anyhow!("Cannot pause a finished game")),
+            state => {
+                self.paused = Some((state, now()));
+                self.set_state(GameState::Paused);
+                Ok(())
+            }
+        }
+    }
+
+    /// End the game before anyone has won it, such as when it's abandoned, declaring `winner` the
+    /// winner if there is one
+    pub fn end(&mut self, winner: Option<PlayerColour>) -> Result<()> {
+        if self.state == GameState::Complete {
+            return Err(not_allowed("The game has already finished"));
+        }
+        if let Some(winner) = winner {
+            self.get_player(&winner)?;
+        }
+        self.set_state(GameState::Complete);
+        self.paused = None;
+        self.clock = None;
+        self.set_winner(winner);
+        self.emit(GameEvent::GameEnded { winner });
+        Ok(())
+    }
+
+    /// Pick a paused game back up where it left off, giving players back the time they had left
+    /// to discard
+    pub fn resume(&mut self) -> Result<()> {
+        let (state, paused_at) = self
+            .paused
+            .take()
+            .ok_or(anyhow!("The game is not paused"))?;
+        let elapsed = now().duration_since(paused_at).unwrap_or_default();
+        self.discards.extend_deadline(elapsed);
+        for deadline in self
+            .robber_deadline
+            .iter_mut()
+            .chain(
+                self.trade_deadlines
+                    .iter_mut()
+                    .map(|(_, deadline)| deadline),
+            )
+            .chain(self.clock.iter_mut().map(|(_, since)| since))
+        {
+            *deadline += elapsed;
+        }
+        self.set_state(state);
+        Ok(())
+    }
+
+    fn ensure_not_paused(&self) -> Result<()> {
+        match self.state {
+            GameState::Paused => Err(not_allowed("The game is paused")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the game is waiting on something before anyone can take another action
+    fn ensure_can_act(&self) -> Result<()> {
+        self.ensure_not_paused()?;
+        match self.phase {
+            TurnPhase::Discard => Err(not_allowed("Waiting for players to discard")),
+            TurnPhase::MoveRobber => Err(not_allowed("Waiting for the robber to be moved")),
+            TurnPhase::PlacingFreeRoads => Err(not_allowed("Waiting for free roads to be placed")),
+            TurnPhase::Roll | TurnPhase::Main => Ok(()),
+        }
+    }
+
+    /// Fails unless the game is running, it's `player`'s turn, and their turn has reached `phase`
+    pub(crate) fn ensure_turn(&self, player: &PlayerColour, phase: TurnPhase) -> Result<()> {
+        self.ensure_not_paused()?;
+        if self.state != GameState::Running {
+            return Err(not_allowed("The game is not running"));
+        }
+        if self.current_player()?.colour() != player {
+            return Err(not_allowed("It's not that player's turn"));
+        }
+        if self.phase != phase {
+            self.ensure_can_act()?;
+            return Err(not_allowed("Cannot do that at this point in the turn"));
+        }
+        Ok(())
+    }
+
+    /// End `player`'s turn, passing the dice to the next player
+    pub fn end_turn(&mut self, player: PlayerColour) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        self.with_scenario(|scenario, game| scenario.on_turn_end(game, player));
+        self.with_extensions(|ext, game| ext.on_turn_end(game, player));
+        self.next_turn();
+        Ok(())
+    }
+
+    /// Pass the dice to the next player
+    fn next_turn(&mut self) {
+        self.set_turn_no(self.turn_no + 1);
+        self.set_phase(TurnPhase::Roll);
+        self.clear_turn_cards();
+    }
+
+    /// `player` gives up the game, leaving their pieces on the board
+    ///
+    /// from then on their turns are skipped or played by a bot, as `GameOptions::resignation`
+    /// says. Once everyone but one player has resigned, that player wins
+    pub fn resign(&mut self, player: PlayerColour) -> Result<()> {
+        self.ensure_not_paused()?;
+        if !matches!(self.state, GameState::Setup | GameState::Running) {
+            return Err(not_allowed("The game isn't being played"));
+        }
+        self.get_player(&player)?;
+        if self.is_resigned(&player) {
+            return Err(anyhow!("{} has already resigned", player));
+        }
+        self.resigned.push(player);
+        self.emit(GameEvent::Resigned { player });
+
+        let remaining: Vec<_> = self
+            .players
+            .iter()
+            .map(|p| *p.colour())
+            .filter(|p| !self.is_resigned(p))
+            .collect();
+        if let [winner] = remaining[..] {
+            self.set_state(GameState::Complete);
+            self.set_winner(Some(winner));
+            self.emit(GameEvent::GameWon { player: winner });
+            return Ok(());
+        }
+
+        let now = now();
+        match self.options.resignation {
+            Resignation::SkipTurns => self.skip_resigned(now),
+            Resignation::Bot => self.get_player_mut(player)?.set_bot(true),
+        }
+        self.pass_clock(None, now);
+        Ok(())
+    }
+
+    /// Whether `player` has given up the game, see `resign`
+    pub fn is_resigned(&self, player: &PlayerColour) -> bool {
+        self.resigned.contains(player)
+    }
+
+    /// Move the game on past any resigned players it's waiting on, if their turns are skipped
+    ///
+    /// their own turns are passed over without rolling, and anything else they owe, like a
+    /// discard, gets the default choice
+    fn skip_resigned(&mut self, now: SystemTime) {
+        if self.options.resignation != Resignation::SkipTurns {
+            return;
+        }
+        while let Some(player) = self.awaiting_player().filter(|p| self.is_resigned(p)) {
+            let own_turn = self.state == GameState::Running
+                && matches!(self.phase, TurnPhase::Roll | TurnPhase::Main)
+                && self.discards.pending().is_empty();
+            if own_turn {
+                self.next_turn();
+                continue;
+            }
+            let Ok(action) = self.default_move(player) else {
+                return;
+            };
+            if self.apply_at(player, action, now).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Hand `player`'s seat to `agent`, such as when they drop out of the game, which makes
+    /// their moves whenever `play_bots` is called
+    #[cfg(all(feature = "rand", feature = "serde"))]
+    pub fn replace_with_bot(
+        &mut self,
+        player: PlayerColour,
+        agent: impl Agent + Clone + Send + 'static,
+    ) -> Result<()> {
+        self.get_player_mut(player)?.set_bot(true);
+        self.agents.0.retain(|(colour, _)| *colour != player);
+        self.agents.0.push((player, Box::new(agent)));
+        Ok(())
+    }
+
+    /// Make the bots' moves until the game waits on somebody else, or `max_moves` have been
+    /// made, returning them in the order they were made
+    ///
+    /// players handed to an agent by `replace_with_bot` are played by it, and any other bots
+    /// play at random
+    #[cfg(all(feature = "rand", feature = "serde"))]
+    pub fn play_bots(&mut self, rng: &mut StdRng, max_moves: usize) -> Vec<(PlayerColour, Action)> {
+        let mut moves = Vec::new();
+        for _ in 0..max_moves {
+            let Some(player) = self.awaiting_player() else {
+                break;
+            };
+            if !self.get_player(&player).is_ok_and(|p| p.is_bot()) {
+                break;
+            }
+            let Ok(view) = self.view_for(&player) else {
+                break;
+            };
+            let agent = self
+                .agents
+                .0
+                .iter_mut()
+                .find(|(colour, _)| *colour == player);
+            let action = match agent {
+                Some((_, agent)) => agent.act(&view, rng),
+                None => RandomAgent::default().act(&view, rng),
+            };
+            if self.apply(player, action.clone()).is_err() {
+                break;
+            }
+            moves.push((player, action));
+        }
+        moves
+    }
+
+    /// Buy a development card from the bank for `player`
+    ///
+    /// fails with `BankError::DeckEmpty` once every card has been bought
+    #[cfg(feature = "rand")]
+    pub fn buy_development_card(&mut self, player: PlayerColour) -> Result<DevelopmentCard> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        if self.bank.is_deck_exhausted() {
+            return Err(BankError::DeckEmpty.into());
+        }
+
+        let cost = self.options.costs.development_card;
+        if !self.get_player(&player)?.resources().contains(&cost) {
+            return Err(anyhow!("Not enough resources to buy a development card"));
+        }
+
+        let card = self.draw_development_card(player)?;
+        self.pay_to_bank(player, cost)?;
+        self.emit(GameEvent::DevelopmentCardBought { player });
+        Ok(card)
+    }
+
+    /// Take the top card of the development deck and put it in `player`'s hand
+    #[cfg(feature = "rand")]
+    fn draw_development_card(&mut self, player: PlayerColour) -> Result<DevelopmentCard> {
+        if self.bank.is_deck_exhausted() {
+            return Err(BankError::DeckEmpty.into());
+        }
+        self.get_player(&player)?;
+
+        let card = self.bank.distribute_random_development_card()?;
+        self.get_player_mut(player)?
+            .development_cards_mut()
+            .push(card);
+        self.record_bought(card);
+        Ok(card)
+    }
+
+    /// Give everyone with a settlement or city on the shore of the lake a fish token for each
+    /// building there, unless the robber is on the lake
+    fn haul_fish(&mut self) {
+        let lakes: Vec<usize> = (0..self.board.tile_count())
+            .filter(|&idx| Some(idx) != self.board.robber())
+            .filter(|&idx| {
+                let kind = self.board.tile(idx).map(|tile| *tile.kind());
+                kind == Some(TileKind::Desert)
+            })
+            .collect();
+
+        let mut hauls: Vec<PlayerColour> = Vec::new();
+        for vertex in self.board.vertices() {
+            if let Some((owner, _)) = vertex.building() {
+                if vertex.tiles().iter().any(|tile| lakes.contains(tile)) {
+                    hauls.push(*owner);
+                }
+            }
+        }
+
+        for player in hauls {
+            #[cfg(feature = "rand")]
+            let fish = self.fish.draw_with(&mut self.rng.0);
+            #[cfg(not(feature = "rand"))]
+            let fish = self.fish.draw();
+            if let Ok(mut p) = self.get_player_mut(player) {
+                *p.fish_mut() += fish;
+            }
+            self.emit(GameEvent::FishHaul { player, fish });
+        }
+    }
+
+    /// Trade in some of `player`'s fish, in the Fishermen variant
+    pub fn spend_fish(&mut self, player: PlayerColour, purchase: FishPurchase) -> Result<()> {
+        if !self.options.fishermen {
+            return Err(not_allowed("This game isn't playing with fish"));
+        }
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        if self.get_player(&player)?.fish() < purchase.cost() {
+            return Err(anyhow!("Not enough fish"));
+        }
+
+        match purchase {
+            FishPurchase::RemoveRobber => {
+                if self.board.robber().is_none() {
+                    return Err(anyhow!("The robber isn't on the board"));
+                }
+                self.board.remove_robber();
+            }
+            FishPurchase::StealResource { victim } => {
+                if victim == player {
+                    return Err(anyhow!("Cannot steal from yourself"));
+                }
+                let hand = *self.get_player(&victim)?.resources();
+                let kind = random_card(&hand, &mut self.rng)
+                    .ok_or(anyhow!("That player has nothing to steal"))?;
+                self.get_player_mut(victim)?
+                    .resources_mut()
+                    .try_remove(kind, 1)?;
+                self.get_player_mut(player)?.resources_mut()[kind] += 1;
+            }
+            FishPurchase::TakeResource { resource } => {
+                let taken = self.bank.distribute_resource(resource, 1)?;
+                *self.get_player_mut(player)?.resources_mut() += taken;
+            }
+            FishPurchase::FreeRoad { edge } => self.place_road(player, edge)?,
+            #[cfg(feature = "rand")]
+            FishPurchase::DevelopmentCard => {
+                self.draw_development_card(player)?;
+            }
+            #[cfg(not(feature = "rand"))]
+            FishPurchase::DevelopmentCard => {
+                return Err(anyhow!("Development cards need the `rand` feature"))
+            }
+        }
+
+        *self.get_player_mut(player)?.fish_mut() -= purchase.cost();
+        Ok(())
+    }
+
+    /// Everything `player`'s fish could buy them right now
+    fn fish_purchases(&self, player: &PlayerColour) -> Vec<FishPurchase> {
+        let Ok(p) = self.get_player(player) else {
+            return Vec::new();
+        };
+        if !self.options.fishermen {
+            return Vec::new();
+        }
+
+        let mut purchases = Vec::new();
+        if self.board.robber().is_some() {
+            purchases.push(FishPurchase::RemoveRobber);
+        }
+        purchases.extend(
+            self.players
+                .iter()
+                .filter(|other| other.colour() != player)
+                .filter(|other| !other.resources().is_empty())
+                .map(|other| FishPurchase::StealResource {
+                    victim: *other.colour(),
+                }),
+        );
+        purchases.extend(
+            ResourceKind::ALL
+                .into_iter()
+                .filter(|kind| self.bank.remaining(*kind) > 0)
+                .map(|resource| FishPurchase::TakeResource { resource }),
+        );
+        if p.stock().remaining(Building::Road) > 0 {
+            purchases.extend(
+                self.board
+                    .edges()
+                    .iter()
+                    .filter(|edge| self.check_road(player, *edge.id()).is_ok())
+                    .map(|edge| FishPurchase::FreeRoad { edge: *edge.id() }),
+            );
+        }
+        if cfg!(feature = "rand") && !self.bank.is_deck_exhausted() {
+            purchases.push(FishPurchase::DevelopmentCard);
+        }
+
+        purchases.retain(|purchase| purchase.cost() <= p.fish());
+        purchases
+    }
+
+    /// Fails unless `player` could build a road along `edge`, i.e. it's empty and joins onto one
+    /// of their buildings or roads without passing through another player's building
+    pub(crate) fn check_road(&self, player: &PlayerColour, edge: EdgeId) -> Result<()> {
+        let edge = self
+            .board
+            .edge(edge)
+            .ok_or(anyhow!("No edge with that ID"))?;
+        if edge.road().is_some() {
+            return Err(anyhow!("There is already a road there"));
+        }
+
+        let connected = edge.vertices().iter().any(|&id| {
+            match self.board.vertex(id).and_then(|v| v.building()) {
+                Some((owner, _)) => owner == player,
+                None => self
+                    .board
+                    .edges()
+                    .iter()
+                    .any(|e| e.road() == Some(player) && e.vertices().contains(&id)),
+            }
+        });
+        if !connected {
+            return Err(anyhow!(
+                "Roads have to join onto your own roads or buildings"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails unless `player` could build a settlement at `vertex`, i.e. it keeps its distance from
+    /// every other building and joins onto one of their roads
+    pub(crate) fn check_settlement(&self, player: &PlayerColour, vertex: VertexId) -> Result<()> {
+        if !self.board.satisfies_distance_rule(vertex) {
+            return Err(anyhow!("Cannot place a settlement at that vertex"));
+        }
+        let on_road = self
+            .board
+            .edges()
+            .iter()
+            .any(|edge| edge.road() == Some(player) && edge.vertices().contains(&vertex));
+        if !on_road {
+            return Err(anyhow!("Settlements have to join onto one of your roads"));
+        }
+        Ok(())
+    }
+
+    /// Build a road for `player` along `edge`, without charging them for it
+    pub(crate) fn place_road(&mut self, player: PlayerColour, edge: EdgeId) -> Result<()> {
+        self.check_road(&player, edge)?;
+        self.get_player_mut(player)?
+            .stock_mut()
+            .take(Building::Road)?;
+        self.board.set_road(edge, Some(player))?;
+        self.update_awards();
+        Ok(())
+    }
+
+    /// Whether `player` holds a `card` they're allowed to play this turn
+    ///
+    /// only one development card can be played each turn, and never one bought that same turn
+    pub fn can_play_development_card(&self, player: &PlayerColour, card: DevelopmentCard) -> bool {
+        if self.development_card_played {
+            return false;
+        }
+        let Ok(p) = self.get_player(player) else {
+            return false;
+        };
+        let held = p.development_cards().iter().filter(|c| **c == card).count();
+        let bought = self.bought_this_turn.iter().filter(|c| **c == card).count();
+        held > bought
+    }
+
+    /// Play a knight for `player`, moving the robber to `tile` and stealing from `victim`
+    ///
+    /// unlike every other action, a knight may be played before the dice have been rolled
+    pub fn play_knight(
+        &mut self,
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+    ) -> Result<Option<ResourceKind>> {
+        self.play_knight_and_steal(player, tile, victim, None)
+    }
+
+    /// Play a knight like `play_knight`, naming the resource to steal if the game lets the
+    /// robber choose
+    pub fn play_knight_and_steal(
+        &mut self,
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+        resource: Option<ResourceKind>,
+    ) -> Result<Option<ResourceKind>> {
+        let phase = match self.phase {
+            TurnPhase::Roll => TurnPhase::Roll,
+            _ => TurnPhase::Main,
+        };
+        self.ensure_turn(&player, phase)?;
+        if !self.can_play_development_card(&player, DevelopmentCard::Knight) {
+            return Err(anyhow!("No knight which can be played this turn"));
+        }
+
+        let stolen = self.relocate_robber(player, tile, victim, resource)?;
+        {
+            let mut p = self.get_player_mut(player)?;
+            let idx = p
+                .development_cards()
+                .iter()
+                .position(|c| *c == DevelopmentCard::Knight)
+                .expect("knight is held");
+            p.development_cards_mut().remove(idx);
+            *p.knights_played_mut() += 1;
+        }
+        self.set_development_card_played(true);
+        // only announced once the robber has actually moved, so a rejected knight never counts
+        self.emit(GameEvent::KnightPlayed { player });
+        self.update_awards();
+        Ok(stolen)
+    }
+
+    /// Play a monopoly for `player`, taking every `resource` card the other players hold
+    ///
+    /// returns how many cards were taken
+    pub fn play_monopoly(&mut self, player: PlayerColour, resource: ResourceKind) -> Result<usize> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        if !self.can_play_development_card(&player, DevelopmentCard::Monopoly) {
+            return Err(anyhow!("No monopoly which can be played this turn"));
+        }
+
+        let from = self.take_from_opponents(player, resource)?;
+        let taken = from.iter().map(|(_, n)| n).sum();
+
+        {
+            let mut p = self.get_player_mut(player)?;
+            let idx = p
+                .development_cards()
+                .iter()
+                .position(|c| *c == DevelopmentCard::Monopoly)
+                .expect("monopoly is held");
+            p.development_cards_mut().remove(idx);
+        }
+        self.set_development_card_played(true);
+        self.emit(GameEvent::MonopolyPlayed {
+            player,
+            resource,
+            taken,
+            from,
+        });
+        Ok(taken)
+    }
+
+    /// Move every `resource` card the other players hold into `player`'s hand, all at once
+    ///
+    /// returns how many cards came from each of the other players, in turn order
+    pub fn take_from_opponents(
+        &mut self,
+        player: PlayerColour,
+        resource: ResourceKind,
+    ) -> Result<Vec<(PlayerColour, usize)>> {
+        self.get_player(&player)?;
+        let mut transfer = Transfer::new();
+        let mut from = Vec::new();
+        for victim in self.players.iter().filter(|p| *p.colour() != player) {
+            let mut cards = Resources::new();
+            cards[resource] = victim.resources()[resource];
+            transfer = transfer.between(*victim.colour(), player, cards);
+            from.push((*victim.colour(), cards[resource]));
+        }
+        self.transfer(&transfer)?;
+        Ok(from)
+    }
+
+    /// Play a Road Building card for `player`, letting them place two roads for free
+    ///
+    /// the turn moves to `TurnPhase::PlacingFreeRoads` until the roads are down with
+    /// `place_free_road`. If the player has fewer than two roads left in stock, or runs out of
+    /// places to put them, they get as many as they can place and the turn carries on. Returns
+    /// how many free roads they have to place
+    pub fn play_road_building(&mut self, player: PlayerColour) -> Result<u8> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        if !self.can_play_development_card(&player, DevelopmentCard::RoadBuilding) {
+            return Err(anyhow!(
+                "No road building card which can be played this turn"
+            ));
+        }
+
+        {
+            let mut p = self.get_player_mut(player)?;
+            let idx = p
+                .development_cards()
+                .iter()
+                .position(|c| *c == DevelopmentCard::RoadBuilding)
+                .expect("road building is held");
+            p.development_cards_mut().remove(idx);
+        }
+        self.set_development_card_played(true);
+        self.emit(GameEvent::RoadBuildingPlayed { player });
+
+        self.set_free_roads(2);
+        self.set_phase(TurnPhase::PlacingFreeRoads);
+        self.finish_free_roads_if_stuck(player);
+        Ok(self.free_roads)
+    }
+
+    /// Place one of the free roads from a Road Building card along `edge`
+    pub fn place_free_road(&mut self, player: PlayerColour, edge: EdgeId) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::PlacingFreeRoads)?;
+        self.place_road(player, edge)?;
+        self.emit(GameEvent::RoadBuilt { player });
+        self.set_free_roads(self.free_roads - 1);
+        self.finish_free_roads_if_stuck(player);
+        Ok(())
+    }
+
+    /// Edges `player` could place one of their free roads along
+    fn free_road_edges(&self, player: &PlayerColour) -> Vec<EdgeId> {
+        let stocked = self
+            .get_player(player)
+            .is_ok_and(|p| p.stock().remaining(Building::Road) > 0);
+        if !stocked {
+            return Vec::new();
+        }
+        self.board
+            .edges()
+            .iter()
+            .map(|edge| *edge.id())
+            .filter(|edge| self.check_road(player, *edge).is_ok())
+            .collect()
+    }
+
+    /// Go back to the main phase of the turn once every free road is placed, or none of the
+    /// rest can be
+    fn finish_free_roads_if_stuck(&mut self, player: PlayerColour) {
+        if self.free_roads == 0 || self.free_road_edges(&player).is_empty() {
+            self.set_free_roads(0);
+            self.set_phase(TurnPhase::Main);
+        }
+    }
+
+    /// Player currently holding `award`, if anyone
+    pub fn award_holder(&self, award: Award) -> Option<PlayerColour> {
+        match award {
+            Award::LongestRoad => self.longest_road,
+            Award::LargestArmy => self.largest_army,
+            Award::Harbormaster => self.harbormaster,
+        }
+    }
+
+    /// Hand the awards to whoever has earned them, moving victory points along with them
+    fn update_awards(&mut self) {
+        let mut awards = vec![Award::LongestRoad, Award::LargestArmy];
+        if self.options.harbormaster {
+            awards.push(Award::Harbormaster);
+        }
+
+        for award in awards {
+            let scores: Vec<_> = self
+                .players
+                .iter()
+                .map(|p| {
+                    let score = match award {
+                        Award::LongestRoad => self.board.longest_road(p.colour()),
+                        Award::LargestArmy => p.knights_played(),
+                        Award::Harbormaster => self.board.harbor_points(p.colour()),
+                    };
+                    (*p.colour(), score)
+                })
+                .collect();
+
+            let current = self.award_holder(award);
+            let holder = award.holder(current, &scores);
+            if holder == current {
+                continue;
+            }
+
+            match award {
+                Award::LongestRoad => self.longest_road = holder,
+                Award::LargestArmy => self.largest_army = holder,
+                Award::Harbormaster => self.harbormaster = holder,
+            }
+            self.emit(GameEvent::AwardChanged { award, holder });
+            if let Some(current) = current {
+                self.change_victory_points(current, |points| points - AWARD_POINTS);
+            }
+            if let Some(holder) = holder {
+                self.change_victory_points(holder, |points| points + AWARD_POINTS);
+            }
+        }
+    }
+
+    /// Every action `player` could take right now
+    ///
+    /// discards and trade offers aren't included, since there are far too many ways to choose
+    /// them, so check `players_awaiting_discard` for discards instead
+    pub fn legal_actions(&self, player: &PlayerColour) -> Vec<Action> {
+        let mut actions = Vec::new();
+
+        if self.state == GameState::Setup {
+            if self.setup_player().as_ref() == Some(player) {
+                actions.extend(
+                    self.board
+                        .vertices()
+                        .iter()
+                        .filter(|vertex| self.board.satisfies_distance_rule(*vertex.id()))
+                        .map(|vertex| Action::PlaceInitialSettlement {
+                            vertex: *vertex.id(),
+                        }),
+                );
+            }
+            return actions;
+        }
+
+        match self.current_player() {
+            Ok(current) if self.state == GameState::Running && current.colour() == player => {
+                current
+            }
+            // everyone else can take up the trades the current player offers them
+            Ok(current) if self.state == GameState::Running && self.phase == TurnPhase::Main => {
+                if self.get_player(player).is_ok() {
+                    actions.extend(
+                        self.trades
+                            .iter()
+                            .filter(|(_, trade)| {
+                                *trade.state() == Proposed
+                                    && trade.get_offering_player() == *current.colour()
+                                    && !trade.accepted_by().contains(player)
+                            })
+                            .map(|(id, _)| Action::AcceptTrade { trade: *id }),
+                    );
+                }
+                return actions;
+            }
+            _ => return actions,
+        };
+
+        match self.phase {
+            TurnPhase::Roll => {
+                actions.push(Action::RollDice);
+                actions.extend(self.knight_actions(player));
+            }
+            TurnPhase::Discard => (),
+            TurnPhase::PlacingFreeRoads => actions.extend(
+                self.free_road_edges(player)
+                    .into_iter()
+                    .map(|edge| Action::PlaceFreeRoad { edge }),
+            ),
+            TurnPhase::MoveRobber => actions.extend(
+                self.robber_moves(player)
+                    .into_iter()
+                    .flat_map(|(tile, victim)| {
+                        self.steal_choices(victim).into_iter().map(move |resource| {
+                            Action::MoveRobber {
+                                tile,
+                                victim,
+                                resource,
+                            }
+                        })
+                    }),
+            ),
+            TurnPhase::Main => {
+                let Ok(p) = self.get_player(player) else {
+                    return actions;
+                };
+
+                if p.resources().contains(&self.options.costs.settlement)
+                    && p.stock().remaining(Building::Settlement) > 0
+                {
+                    actions.extend(
+                        self.board
+                            .vertices()
+                            .iter()
+                            .filter(|vertex| self.check_settlement(player, *vertex.id()).is_ok())
+                            .map(|vertex| Action::BuildSettlement {
+                                vertex: *vertex.id(),
+                            }),
+                    );
+                }
+
+                if p.resources().contains(&self.options.costs.road)
+                    && p.stock().remaining(Building::Road) > 0
+                {
+                    actions.extend(
+                        self.board
+                            .edges()
+                            .iter()
+                            .filter(|edge| self.check_road(player, *edge.id()).is_ok())
+                            .map(|edge| Action::BuildRoad { edge: *edge.id() }),
+                    );
+                }
+
+                if p.resources().contains(&self.options.costs.city)
+                    && p.stock().remaining(Building::City) > 0
+                {
+                    actions.extend(
+                        self.board
+                            .vertices()
+                            .iter()
+                            .filter(|vertex| {
+                                matches!(vertex.building(), Some((owner, Building::Settlement)) if owner == player)
+                            })
+                            .map(|vertex| Action::UpgradeToCity { vertex: *vertex.id() }),
+                    );
+                }
+
+                if !self.bank.is_deck_exhausted()
+                    && p.resources().contains(&self.options.costs.development_card)
+                {
+                    actions.push(Action::BuyDevelopmentCard);
+                }
+
+                actions.extend(self.knight_actions(player));
+                if self.can_play_development_card(player, DevelopmentCard::RoadBuilding) {
+                    actions.push(Action::PlayRoadBuilding);
+                }
+                if self.can_play_development_card(player, DevelopmentCard::Monopoly) {
+                    actions.extend(
+                        ResourceKind::ALL
+                            .into_iter()
+                            .map(|resource| Action::PlayMonopoly { resource }),
+                    );
+                }
+                actions.extend(
+                    self.fish_purchases(player)
+                        .into_iter()
+                        .map(|purchase| Action::SpendFish { purchase }),
+                );
+                if let Some(scenario) = self.scenario() {
+                    actions.extend(
+                        scenario
+                            .extra_actions(self, player)
+                            .into_iter()
+                            .map(|action| Action::Scenario { action }),
+                    );
+                }
+                actions.extend(
+                    self.extension_actions(player)
+                        .into_iter()
+                        .map(|action| Action::Extension { action }),
+                );
+                for (id, trade) in self.trades.iter().filter(|(_, trade)| {
+                    *trade.state() == Proposed && trade.get_offering_player() == *player
+                }) {
+                    if p.resources().contains(trade.offering()) {
+                        actions.extend(
+                            trade
+                                .accepted_by()
+                                .iter()
+                                .filter(|partner| {
+                                    self.get_player(partner)
+                                        .is_ok_and(|o| o.resources().contains(trade.wants()))
+                                })
+                                .map(|partner| Action::FinalizeTrade {
+                                    trade: *id,
+                                    partner: *partner,
+                                }),
+                        );
+                    }
+                    actions.push(Action::WithdrawTrade { trade: *id });
+                }
+                actions.push(Action::EndTurn);
+            }
+        }
+
+        actions
+    }
+
+    /// Every tile `player` could move the robber to, along with each player they could steal from
+    /// there
+    fn robber_moves(&self, player: &PlayerColour) -> Vec<(usize, Option<PlayerColour>)> {
+        let mut moves = Vec::new();
+        for tile in (0..self.board.tile_count()).filter(|&t| Some(t) != self.board.robber()) {
+            let mut victims: Vec<PlayerColour> = Vec::new();
+            for vertex in self.board.vertices_of_tile(tile) {
+                match vertex.building() {
+                    Some((owner, _)) if owner != player && !victims.contains(owner) => {
+                        victims.push(*owner)
+                    }
+                    _ => (),
+                }
+            }
+
+            match victims.is_empty() {
+                true => moves.push((tile, None)),
+                false => moves.extend(victims.into_iter().map(|victim| (tile, Some(victim)))),
+            }
+        }
+        moves
+    }
+
+    fn knight_actions(&self, player: &PlayerColour) -> Vec<Action> {
+        if !self.can_play_development_card(player, DevelopmentCard::Knight) {
+            return Vec::new();
+        }
+        self.robber_moves(player)
+            .into_iter()
+            .flat_map(|(tile, victim)| {
+                self.steal_choices(victim)
+                    .into_iter()
+                    .map(move |resource| Action::PlayKnight {
+                        tile,
+                        victim,
+                        resource,
+                    })
+            })
+            .collect()
+    }
+
+    /// Resources which can be named when robbing `victim`
+    ///
+    /// when the robber gets to choose, every resource is offered whatever the victim holds, so
+    /// the robber's legal actions never give away the victim's hand
+    fn steal_choices(&self, victim: Option<PlayerColour>) -> Vec<Option<ResourceKind>> {
+        match victim.is_some() && self.options.choose_stolen_resource {
+            true => ResourceKind::ALL.into_iter().map(Some).collect(),
+            false => vec![None],
+        }
+    }
+
+    /// Carry out `action` on behalf of `player`
+    ///
+    /// fails with `ActionError::NotAllowedNow` if actions of that kind can't be taken right now, or
+    /// `ActionError::Illegal` if this particular action breaks the rules. The game ends as soon as
+    /// the current player reaches `VICTORY_POINTS_TO_WIN`
+    pub fn apply(&mut self, player: PlayerColour, action: Action) -> Result<(), ActionError> {
+        self.apply_at(player, action, now())
+    }
+
+    /// Apply `action` for `player` as of `now`, as far as the clock is concerned
+    fn apply_at(
+        &mut self,
+        player: PlayerColour,
+        action: Action,
+        now: SystemTime,
+    ) -> Result<(), ActionError> {
+        trace_span!(INFO, "apply", game = %self.id, %player, ?action);
+        let first_event = self.event_origins.len();
+        let result = match action {
+            Action::PlaceInitialSettlement { vertex } => {
+                self.place_initial_settlement(player, vertex)
+            }
+            Action::RollDice => match self.current_player() {
+                Ok(current) if *current.colour() != player => {
+                    Err(not_allowed("It's not that player's turn"))
+                }
+                _ => self.roll_dice().map(|_| ()),
+            },
+            Action::Discard { cards } => self.discard(player, cards),
+            Action::MoveRobber {
+                tile,
+                victim,
+                resource,
+            } => self
+                .move_robber_and_steal(player, tile, victim, resource)
+                .map(|_| ()),
+            Action::BuildSettlement { vertex } => self.build_settlement(player, vertex),
+            Action::BuildRoad { edge } => self.build_road(player, edge),
+            Action::UpgradeToCity { vertex } => self.upgrade_to_city(player, vertex),
+            Action::PlayKnight {
+                tile,
+                victim,
+                resource,
+            } => self
+                .play_knight_and_steal(player, tile, victim, resource)
+                .map(|_| ()),
+            Action::PlayMonopoly { resource } => self.play_monopoly(player, resource).map(|_| ()),
+            Action::PlayRoadBuilding => self.play_road_building(player).map(|_| ()),
+            Action::PlaceFreeRoad { edge } => self.place_free_road(player, edge),
+            #[cfg(feature = "rand")]
+            Action::BuyDevelopmentCard => self.buy_development_card(player).map(|_| ()),
+            #[cfg(not(feature = "rand"))]
+            Action::BuyDevelopmentCard => Err(anyhow!("Development cards need the `rand` feature")),
+            Action::SpendFish { purchase } => self.spend_fish(player, purchase),
+            Action::Scenario { action } => self
+                .with_scenario(|scenario, game| scenario.apply(game, player, action))
+                .unwrap_or_else(|| Err(not_allowed("This game isn't playing a scenario"))),
+            Action::Extension { action } => match action {
+                ExtensionAction::Build { id } => self.build_extension_building(player, &id),
+                ExtensionAction::BuyCard { id } => self.buy_extension_card(player, &id),
+                ExtensionAction::PlayCard { id } => self.play_extension_card(player, &id),
+            },
+            Action::ProposeTrade { offering, wants } => {
+                self.propose_trade(player, offering, wants).map(|_| ())
+            }
+            Action::AcceptTrade { trade } => self.accept_trade(trade, player),
+            Action::FinalizeTrade { trade, partner } => self.confirm_trade(trade, player, partner),
+            Action::WithdrawTrade { trade } => self.withdraw_trade(trade, player),
+            Action::EndTurn => self.end_turn(player),
+        };
+        if result.is_ok() {
+            self.check_for_winner();
+            self.skip_resigned(now);
+            self.pass_clock(Some(player), now);
+            #[cfg(feature = "debug-invariants")]
+            if let Err(violations) = self.check_invariants() {
+                panic!(
+                    "An action by {:?} broke the game's invariants: {:?}",
+                    player, violations
+                );
+            }
+        }
+
+        // events from default moves made along the way already have their own player
+        for origin in &mut self.event_origins[first_event..] {
+            origin.player.get_or_insert(player);
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(err) = &result {
+            tracing::debug!(error = %err, "action rejected");
+        }
+        result.map_err(|err| match err.downcast::<ActionError>() {
+            Ok(err) => err,
+            Err(err) => ActionError::Illegal(err.to_string()),
+        })
+    }
+
+    /// End the game if the current player has reached `VICTORY_POINTS_TO_WIN`
+    fn check_for_winner(&mut self) {
+        if self.state != GameState::Running {
+            return;
+        }
+        let Ok(player) = self.current_player().map(|p| *p.colour()) else {
+            return;
+        };
+        if self
+            .vp_breakdown(&player)
+            .is_ok_and(|vp| vp.total() >= VICTORY_POINTS_TO_WIN)
+        {
+            self.set_state(GameState::Complete);
+            self.set_winner(Some(player));
+            self.emit(GameEvent::GameWon { player });
+        }
+    }
+
+    pub fn winner(&self) -> Option<PlayerColour> {
+        self.winner
+    }
+
+    /// Each player's score and achievements, along with the winner once there is one
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            winner: self.winner,
+            players: self
+                .players
+                .iter()
+                .map(|p| PlayerSummary {
+                    colour: *p.colour(),
+                    victory_points: self.vp_breakdown(p.colour()).unwrap_or_default(),
+                    achievements: p.achievements().to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Players who still have to discard after a 7, and how many cards each of them owes
+    pub fn players_awaiting_discard(&self) -> &[(PlayerColour, usize)] {
+        self.discards.pending()
+    }
+
+    /// Discard the given cards from `player`'s hand, returning them to the bank
+    pub fn discard(&mut self, player: PlayerColour, cards: Resources) -> Result<()> {
+        self.ensure_not_paused()?;
+        let required = self
+            .discards
+            .required(&player)
+            .ok_or(not_allowed("That player doesn't need to discard"))?;
+        let count = cards.total();
+        if count != required {
+            return Err(anyhow!("Must discard exactly {} cards", required));
+        }
+
+        let left = {
+            let mut p = self.get_player_mut(player)?;
+            p.resources_mut()
+                .try_sub(cards)
+                .map_err(|_| anyhow!("Cannot discard cards that aren't in your hand"))?;
+            p.hand_size()
+        };
+
+        self.bank.return_resources(cards);
+        self.update_discards(|discards| discards.resolve(&player));
+        self.emit(GameEvent::Discarded { player, cards });
+        self.emit(GameEvent::HandSize {
+            player,
+            cards: left,
+        });
+        if !self.discards.is_pending() {
+            self.await_robber();
+        }
+        Ok(())
+    }
+
+    /// Wait for the current player to move the robber, starting their timer
+    fn await_robber(&mut self) {
+        self.set_phase(TurnPhase::MoveRobber);
+        self.robber_deadline = Some(now() + self.options.timeouts.move_robber);
+    }
+
+    /// Discard at random for every player who hasn't chosen their discards in time, returning
+    /// what was discarded for each of them
+    ///
+    /// does nothing until the discard timeout has passed
+    pub fn auto_discard_expired(
+        &mut self,
+        now: SystemTime,
+    ) -> Result<Vec<(PlayerColour, Resources)>> {
+        if self.state == GameState::Paused || !self.discards.is_expired(now) {
+            return Ok(Vec::new());
+        }
+
+        let mut discarded = Vec::new();
+        for (player, count) in self.discards.pending().to_vec() {
+            self.emit(GameEvent::TimedOut {
+                player,
+                decision: Decision::Discard,
+            });
+            let cards = self.random_discard(player, count)?;
+            self.discard(player, cards)?;
+            discarded.push((player, cards));
+        }
+        Ok(discarded)
+    }
+
+    /// `count` of `player`'s cards, picked at random
+    fn random_discard(&mut self, player: PlayerColour, count: usize) -> Result<Resources> {
+        let mut hand = *self.get_player(&player)?.resources();
+        let mut cards = Resources::new();
+        for _ in 0..count {
+            let Some(kind) = random_card(&hand, &mut self.rng) else {
+                break;
+            };
+            hand[kind] -= 1;
+            cards[kind] += 1;
+        }
+        Ok(cards)
+    }
+
+    /// Change how long players have to choose their own discards after a 7
+    pub fn set_discard_timeout(&mut self, timeout: Duration) {
+        self.options.timeouts.discard = timeout;
+        self.discards.set_timeout(timeout);
+    }
+
+    /// Make the default choice for every decision whose time ran out before `now`
+    ///
+    /// discards are chosen at random, the robber is moved to a random tile without stealing
+    /// anything, and trade offers nobody has accepted are withdrawn. Players who run out of time
+    /// on the clock are dealt with as `GameOptions::clock_expiry` says. A `TimedOut` event is
+    /// recorded for each player who ran out of time
+    pub fn resolve_timeouts(&mut self, now: SystemTime) -> Result<()> {
+        if self.state == GameState::Paused {
+            return Ok(());
+        }
+        trace_span!(DEBUG, "timeouts", game = %self.id);
+        self.auto_discard_expired(now)?;
+
+        if self.phase == TurnPhase::MoveRobber
+            && matches!(self.robber_deadline, Some(deadline) if now >= deadline)
+        {
+            let player = *self.current_player()?.colour();
+            let mut tiles: Vec<_> = self
+                .robber_moves(&player)
+                .into_iter()
+                .map(|(t, _)| t)
+                .collect();
+            tiles.dedup();
+            #[cfg(feature = "rand")]
+            let tile = tiles.choose(&mut self.rng.0);
+            #[cfg(not(feature = "rand"))]
+            let tile = tiles.first();
+
+            if let Some(tile) = tile.copied() {
+                self.emit(GameEvent::TimedOut {
+                    player,
+                    decision: Decision::MoveRobber,
+                });
+                self.move_robber(player, tile, None)?;
+            }
+        }
+
+        let (expired, open) = self
+            .trade_deadlines
+            .drain(..)
+            .partition(|(_, deadline)| now >= *deadline);
+        self.trade_deadlines = open;
+        for (trade_id, _) in expired {
+            let Some(trade) = self.trades.get_trade(trade_id) else {
+                continue;
+            };
+            if *trade.state() != Proposed {
+                continue;
+            }
+
+            let from = trade.get_offering_player();
+            let accepted_by = trade.accepted_by().to_vec();
+            let silent: Vec<_> = self
+                .players
+                .iter()
+                .map(|p| *p.colour())
+                .filter(|p| *p != from && !accepted_by.contains(p))
+                .collect();
+            for player in silent {
+                self.emit(GameEvent::TimedOut {
+                    player,
+                    decision: Decision::TradeResponse,
+                });
+            }
+            if accepted_by.is_empty() {
+                self.trades.cancel_trade(trade_id);
+                self.emit(GameEvent::TradeWithdrawn { trade: trade_id });
+            }
+        }
+        self.resolve_clock(now)
+    }
+
+    /// How long `player` has left to move, as of `now`, if the game is timed
+    ///
+    /// only the player the game is waiting on has their clock running
+    pub fn time_remaining(&self, player: &PlayerColour, now: SystemTime) -> Option<Duration> {
+        let now = match self.paused {
+            Some((_, paused_at)) => paused_at.min(now),
+            None => now,
+        };
+        let running = match self.clock {
+            Some((on_clock, since)) if on_clock == *player => {
+                now.duration_since(since).unwrap_or_default()
+            }
+            _ => Duration::ZERO,
+        };
+        match self.options.clock {
+            TurnClock::Untimed => None,
+            TurnClock::PerMove(limit) => Some(limit.saturating_sub(running)),
+            TurnClock::ChessClock { initial, .. } => {
+                let banked = self
+                    .clocks
+                    .iter()
+                    .find(|(colour, _)| colour == player)
+                    .map_or(initial, |(_, left)| *left);
+                Some(banked.saturating_sub(running))
+            }
+        }
+    }
+
+    /// Stop the clock of whoever was on it, if `mover` was them or the game has moved on to
+    /// somebody else, and start the clock of whoever the game is waiting on now
+    ///
+    /// with a chess clock, the time taken comes off the player's bank, and moving tops it up
+    fn pass_clock(&mut self, mover: Option<PlayerColour>, now: SystemTime) {
+        if self.options.clock == TurnClock::Untimed {
+            return;
+        }
+        let on_clock = self.clock.map(|(player, _)| player);
+        if on_clock.is_some() && on_clock != mover && on_clock == self.awaiting_player() {
+            return;
+        }
+        if let (Some(player), TurnClock::ChessClock { increment, .. }) =
+            (on_clock, self.options.clock)
+        {
+            let mut left = self.time_remaining(&player, now).unwrap_or_default();
+            if mover == Some(player) {
+                left += increment;
+            }
+            self.clocks.retain(|(colour, _)| *colour != player);
+            self.clocks.push((player, left));
+        }
+        self.clock = self.awaiting_player().map(|player| (player, now));
+    }
+
+    /// Deal with the player on the clock if their time has run out, starting the clock if it
+    /// isn't running yet
+    fn resolve_clock(&mut self, now: SystemTime) -> Result<()> {
+        if self.options.clock == TurnClock::Untimed {
+            return Ok(());
+        }
+        let Some((player, _)) = self.clock else {
+            self.pass_clock(None, now);
+            return Ok(());
+        };
+        if self.awaiting_player() != Some(player) {
+            self.pass_clock(None, now);
+            return Ok(());
+        }
+        if self.get_player(&player)?.is_bot()
+            || self.time_remaining(&player, now) > Some(Duration::ZERO)
+        {
+            return Ok(());
+        }
+
+        self.emit(GameEvent::TimedOut {
+            player,
+            decision: Decision::Move,
+        });
+        match self.options.clock_expiry {
+            ClockExpiry::DefaultMove => {
+                let action = self.default_move(player)?;
+                self.apply_at(player, action, now)?;
+            }
+            ClockExpiry::ReplaceWithBot => self.get_player_mut(player)?.set_bot(true),
+        }
+        Ok(())
+    }
+
+    /// The least `player` can do to keep the game going, which is discarding at random if they
+    /// owe a discard, otherwise ending their turn, rolling the dice, or the first thing they can
+    fn default_move(&mut self, player: PlayerColour) -> Result<Action> {
+        if let Some(count) = self.discards.required(&player) {
+            let cards = self.random_discard(player, count)?;
+            return Ok(Action::Discard { cards });
+        }
+        let legal = self.legal_actions(&player);
+        [Action::EndTurn, Action::RollDice]
+            .into_iter()
+            .find(|action| legal.contains(action))
+            .or(legal.into_iter().next())
+            .ok_or(anyhow!("{} has nothing they can do", player))
+    }
+
+    /// Offer a trade to the other players, who have until the trade response timeout to accept
+    pub fn propose_trade(
+        &mut self,
+        player: PlayerColour,
+        offering: Resources,
+        wants: Resources,
+    ) -> Result<Uuid> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        if !self.get_player(&player)?.resources().contains(&offering) {
+            return Err(anyhow!("Not enough resources to make this offer"));
+        }
+
+        let trade_id = self.trades.propose_trade(player, offering, wants);
+        let deadline = now() + self.options.timeouts.trade_response;
+        self.trade_deadlines.push((trade_id, deadline));
+        self.emit(GameEvent::TradeProposed {
+            trade: trade_id,
+            player,
+            offering,
+            wants,
+        });
+        Ok(trade_id)
+    }
+
+    /// Say `player` is willing to take the trade `trade_id`
+    pub fn accept_trade(&mut self, trade_id: Uuid, player: PlayerColour) -> Result<()> {
+        self.ensure_can_act()?;
+        self.get_player(&player)?;
+        match self.trades.get_trade(trade_id) {
+            Some(trade) if trade.get_offering_player() == player => {
+                return Err(anyhow!("Cannot accept your own trade"))
+            }
+            Some(trade) if trade.accepted_by().contains(&player) => {
+                return Err(anyhow!("Already accepted that trade"))
+            }
+            _ => (),
+        }
+        self.trades.accept_trade(trade_id, player)?;
+        self.emit(GameEvent::TradeAccepted {
+            trade: trade_id,
+            player,
+        });
+        Ok(())
+    }
+
+    /// Make the trade `trade_id`, which `player` offered, with `partner`, who has to have
+    /// accepted it
+    pub fn confirm_trade(
+        &mut self,
+        trade_id: Uuid,
+        player: PlayerColour,
+        partner: PlayerColour,
+    ) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        let trade = self
+            .trades
+            .get_trade(trade_id)
+            .ok_or(anyhow!("Could not find trade with that ID"))?;
+        if trade.get_offering_player() != player {
+            return Err(anyhow!("Only the player who offered a trade can make it"));
+        }
+        if !trade.accepted_by().contains(&partner) {
+            return Err(anyhow!("That player hasn't accepted the trade"));
+        }
+        // checked up front, so a trade which can't be made is left open rather than locked in
+        if !self
+            .get_player(&player)?
+            .resources()
+            .contains(trade.offering())
+            || !self
+                .get_player(&partner)?
+                .resources()
+                .contains(trade.wants())
+        {
+            return Err(anyhow!("Not enough resources to make this trade"));
+        }
+        self.trades.finalize_trade(trade_id, partner)?;
+        self.finalize_trade(trade_id)
+    }
+
+    /// Take back the trade `trade_id`, which `player` offered, before anyone takes it up
+    pub fn withdraw_trade(&mut self, trade_id: Uuid, player: PlayerColour) -> Result<()> {
+        self.ensure_can_act()?;
+        match self.trades.get_trade(trade_id) {
+            None => return Err(anyhow!("Could not find trade with that ID")),
+            Some(trade) if trade.get_offering_player() != player => {
+                return Err(anyhow!(
+                    "Only the player who offered a trade can withdraw it"
+                ))
+            }
+            Some(trade) if *trade.state() != Proposed => {
+                return Err(anyhow!("That trade can no longer be withdrawn"))
+            }
+            Some(_) => (),
+        }
+        self.trades.cancel_trade(trade_id);
+        self.trade_deadlines.retain(|(id, _)| *id != trade_id);
+        self.emit(GameEvent::TradeWithdrawn { trade: trade_id });
+        Ok(())
+    }
+
+    /// Resources each player would collect if `roll` came up, without handing anything out
+    ///
+    /// this ignores how much the bank has left to give, so it may promise more than
+    /// `roll_dice` would actually distribute
+    pub fn production_for_roll(&self, roll: u8) -> Vec<(PlayerColour, Resources)> {
+        let mut production: Vec<(PlayerColour, Resources)> = self
+            .players
+            .iter()
+            .map(|player| (*player.colour(), Resources::new()))
+            .collect();
+
+        if roll == ROBBER_ROLL {
+            return production;
+        }
+
+        for vertex in self.board.vertices() {
+            let Some((owner, building)) = vertex.building() else {
+                continue;
+            };
+            let Some((_, resources)) = production.iter_mut().find(|(p, _)| p == owner) else {
+                continue;
+            };
+
+            for tile in self.producing_tiles(vertex.tiles()) {
+                match tile.kind().resource() {
+                    Some(kind) if *tile.token() == roll as usize => {
+                        resources[kind] += building.production()
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        production
+    }
+
+    /// Resources `player` can expect to collect over 36 rolls, given where they've built
+    pub fn expected_production(&self, player: &PlayerColour) -> Result<Resources> {
+        self.get_player(player)?;
+
+        let mut expected = Resources::new();
+        for vertex in self.board.vertices() {
+            let building = match vertex.building() {
+                Some((owner, building)) if owner == player => building,
+                _ => continue,
+            };
+
+            for tile in self.producing_tiles(vertex.tiles()) {
+                let roll = *tile.token() as u8;
+                match tile.kind().resource() {
+                    Some(kind) if roll != ROBBER_ROLL => {
+                        expected[kind] += combinations_for(roll) * building.production()
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(expected)
+    }
+
+    /// The tiles out of `tiles` which aren't being blocked by the robber
+    fn producing_tiles<'a>(&'a self, tiles: &'a [usize]) -> impl Iterator<Item = &'a Tile> {
+        tiles
+            .iter()
+            .filter(|&&idx| Some(idx) != self.board.robber())
+            .filter_map(|&idx| self.board.tile(idx))
+    }
+
+    /// Move the robber to `tile`, stealing a random resource from `victim` if one is named
+    ///
+    /// the robber has to move to a different tile, and the victim must have a settlement or city
+    /// on the destination. Robbing a player with no cards in hand steals nothing, rather than
+    /// failing, so the turn can carry on
+    pub fn move_robber(
+        &mut self,
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+    ) -> Result<Option<ResourceKind>> {
+        self.move_robber_and_steal(player, tile, victim, None)
+    }
+
+    /// Move the robber like `move_robber`, naming the resource to steal if the game lets the
+    /// robber choose
+    ///
+    /// naming a resource the victim doesn't hold steals nothing, so all the robber learns is
+    /// whether they held one. Without a name, a random card is stolen as usual
+    pub fn move_robber_and_steal(
+        &mut self,
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+        resource: Option<ResourceKind>,
+    ) -> Result<Option<ResourceKind>> {
+        self.ensure_turn(&player, TurnPhase::MoveRobber)?;
+        let stolen = self.relocate_robber(player, tile, victim, resource)?;
+        self.set_phase(TurnPhase::Main);
+        self.robber_deadline = None;
+        Ok(stolen)
+    }
+
+    /// Move the robber to `tile` and steal `resource`, or a random card, from `victim`, without
+    /// touching the turn phase
+    fn relocate_robber(
+        &mut self,
+        player: PlayerColour,
+        tile: usize,
+        victim: Option<PlayerColour>,
+        resource: Option<ResourceKind>,
+    ) -> Result<Option<ResourceKind>> {
+        trace_span!(DEBUG, "robber", %player, tile, ?victim, ?resource);
+        if resource.is_some() && !self.options.choose_stolen_resource {
+            return Err(anyhow!(
+                "The robber can't choose what to steal in this game"
+            ));
+        }
+        if resource.is_some() && victim.is_none() {
+            return Err(anyhow!("Nobody to steal from"));
+        }
+        if self.board.tile(tile).is_none() {
+            return Err(anyhow!("Could not find that tile"));
+        }
+        if self.board.robber() == Some(tile) {
+            return Err(anyhow!("The robber must be moved to a different tile"));
+        }
+
+        if let Some(victim) = victim {
+            if victim == player {
+                return Err(anyhow!("Cannot steal from yourself"));
+            }
+            self.get_player(&victim)?;
+
+            let has_building = self
+                .board
+                .vertices_of_tile(tile)
+                .any(|vertex| matches!(vertex.building(), Some((owner, _)) if *owner == victim));
+            if !has_building {
+                return Err(anyhow!("That player has no buildings next to this tile"));
+            }
+        }
+
+        self.board.set_robber(tile)?;
+
+        let stolen = match victim {
+            Some(victim) => {
+                let hand = *self.get_player(&victim)?.resources();
+                match resource {
+                    Some(kind) => (hand[kind] > 0).then_some(kind),
+                    None => random_card(&hand, &mut self.rng),
+                }
+            }
+            None => None,
+        };
+        // which card was stolen isn't in the events, since only the two players get to see it
+        trace_event!(DEBUG, ?stolen, "robbed");
+        if let (Some(victim), Some(kind)) = (victim, stolen) {
+            self.get_player_mut(victim)?
+                .resources_mut()
+                .try_remove(kind, 1)?;
+            self.get_player_mut(player)?.resources_mut()[kind] += 1;
+        }
+        self.emit(GameEvent::RobberMoved {
+            player,
+            tile,
+            victim,
+            stole_card: stolen.is_some(),
+        });
+        Ok(stolen)
+    }
+
+    /// Hand out the resources produced by `roll`
+    ///
+    /// if the bank can't cover everyone owed a resource, nobody receives it, unless only one
+    /// player is owed that resource, in which case they get whatever the bank has left
+    fn distribute_production(&mut self, roll: u8) {
+        trace_span!(DEBUG, "production", roll);
+        let mut production = self.production_for_roll(roll);
+
+        for kind in [
+            ResourceKind::Ore,
+            ResourceKind::Grain,
+            ResourceKind::Wool,
+            ResourceKind::Brick,
+            ResourceKind::Lumber,
+        ] {
+            let owed: Vec<_> = production
+                .iter()
+                .filter(|(_, resources)| resources[kind] > 0)
+                .map(|(player, resources)| (*player, resources[kind]))
+                .collect();
+            let total: usize = owed.iter().map(|(_, amount)| amount).sum();
+            let available = self.bank.remaining(kind);
+            if total > available {
+                trace_event!(DEBUG, ?kind, total, available, "the bank is short");
+            }
+
+            match owed.as_slice() {
+                _ if total <= available => (),
+                [(player, _)] => {
+                    if let Some((_, resources)) = production.iter_mut().find(|(p, _)| p == player) {
+                        resources[kind] = available;
+                    }
+                }
+                _ => production
+                    .iter_mut()
+                    .for_each(|(_, resources)| resources[kind] = 0),
+            };
+        }
+
+        for (player, resources) in production {
+            if resources == Resources::new() {
+                continue;
+            }
+            let paid = self.pay_from_bank(player, resources);
+            self.luck_mut(player).produce(paid.total());
+        }
+    }
+
+    /// Move resources from the bank into a player's hand, recording the production
+    ///
+    /// with a hand limit, the player only takes as many cards as they have room for, in the
+    /// order the kinds are listed, and the rest stay in the bank
+    fn pay_from_bank(&mut self, player: PlayerColour, resources: Resources) -> Resources {
+        let held: usize = match self.get_player(&player) {
+            Ok(p) => p.hand_size(),
+            Err(_) => 0,
+        };
+        let mut room = self
+            .options
+            .hand_limit
+            .map(|limit| limit.saturating_sub(held));
+
+        let mut paid = Resources::new();
+        for (kind, mut amount) in resources {
+            if let Some(room) = room.as_mut() {
+                amount = amount.min(*room);
+                *room -= amount;
+            }
+            if let Ok(distributed) = self.bank.distribute_resource(kind, amount) {
+                paid += distributed;
+            }
+        }
+
+        if let Ok(mut p) = self.get_player_mut(player) {
+            *p.resources_mut() += paid;
+        }
+        self.emit(GameEvent::Production {
+            player,
+            resources: paid,
+        });
+        paid
+    }
+
+    /// Count a roll towards every player's expected production, from the buildings they have now
+    fn expect_production(&mut self) {
+        let by_total: Vec<_> = (MIN_ROLL..=MAX_ROLL)
+            .map(|total| self.production_for_roll(total))
+            .collect();
+        let players: Vec<_> = self.players.iter().map(|p| *p.colour()).collect();
+        for player in players {
+            let production = by_total.iter().map(|production| {
+                production
+                    .iter()
+                    .find(|(p, _)| *p == player)
+                    .map_or(0, |(_, resources)| resources.total())
+            });
+            self.luck_mut(player).expect(production);
+        }
+    }
+
+    fn luck_mut(&mut self, player: PlayerColour) -> &mut Luck {
+        let idx = match self.luck.iter().position(|luck| *luck.player() == player) {
+            Some(idx) => idx,
+            None => {
+                self.luck.push(Luck::new(player));
+                self.luck.len() - 1
+            }
+        };
+        &mut self.luck[idx]
+    }
+
+    /// Take `cost` out of `player`'s hand and put it back in the bank, failing if they can't
+    /// afford it
+    pub(crate) fn pay_to_bank(&mut self, player: PlayerColour, cost: Resources) -> Result<()> {
+        self.transfer(&Transfer::new().pay_bank(player, cost))
+    }
+
+    /// Place one of `player`'s two starting settlements, during setup
+    ///
+    /// players place their settlements in turn order, then again in reverse order. Each player
+    /// collects one resource from every tile next to their second settlement, and once the
+    /// last settlement is down the game starts
+    pub fn place_initial_settlement(
+        &mut self,
+        player: PlayerColour,
+        vertex_id: VertexId,
+    ) -> Result<()> {
+        self.ensure_not_paused()?;
+        if self.state != GameState::Setup {
+            return Err(not_allowed(
+                "Starting settlements can only be placed during setup",
+            ));
+        }
+        let expected = self
+            .setup_player()
+            .ok_or(not_allowed("All starting settlements have been placed"))?;
+        if expected != player {
+            return Err(not_allowed(
+                "It's not that player's turn to place a settlement",
+            ));
+        }
+        if !self.board.satisfies_distance_rule(vertex_id) {
+            return Err(anyhow!("Cannot place a settlement at that vertex"));
+        }
+
+        self.get_player_mut(player)?
+            .stock_mut()
+            .take(Building::Settlement)?;
+        self.board
+            .set_building(vertex_id, Some((player, Building::Settlement)))?;
+        self.emit(GameEvent::SettlementBuilt { player });
+        self.change_victory_points(player, |points| points + 1);
+        self.update_awards();
+        self.with_scenario(|scenario, game| scenario.on_settlement_built(game, player, vertex_id));
+
+        let is_second_settlement = self.setup_placements >= self.players.len();
+        if is_second_settlement {
+            let mut resources = Resources::new();
+            let tiles = self.board.vertex(vertex_id).map(|v| v.tiles().to_vec());
+            for tile in self.producing_tiles(&tiles.unwrap_or_default()) {
+                if let Some(kind) = tile.kind().resource() {
+                    resources[kind] += 1;
+                }
+            }
+            self.pay_from_bank(player, resources);
+        }
+
+        self.setup_placements += 1;
+        if self.setup_player().is_none() {
+            self.set_state(GameState::Running);
+            self.set_turn_no(0);
+            self.set_phase(TurnPhase::Roll);
+        }
+        Ok(())
+    }
+
+    /// Shuffle the order players take their turns in
+    #[cfg(feature = "rand")]
+    pub fn randomize_turn_order(&mut self, rng: &mut impl Rng) -> Result<()> {
+        self.ensure_seating_open()?;
+        self.update_players(|players| players.reorder(|players| players.shuffle(rng)));
+        self.emit_turn_order();
+        Ok(())
+    }
+
+    /// Have every player roll the dice, seating them from the highest roll to the lowest
+    ///
+    /// players who tie roll again between themselves to decide who sits first
+    pub fn dice_off(&mut self) -> Result<()> {
+        self.ensure_seating_open()?;
+        let players: Vec<_> = self.players.iter().map(|p| *p.colour()).collect();
+        let order = self.dice_off_round(players, 0);
+        self.update_players(|players| {
+            players.reorder(|players| {
+                players.sort_by_key(|p| order.iter().position(|c| c == p.colour()))
+            })
+        });
+        self.emit_turn_order();
+        Ok(())
+    }
+
+    fn dice_off_round(&mut self, players: Vec<PlayerColour>, round: usize) -> Vec<PlayerColour> {
+        if players.len() < 2 || round == MAX_DICE_OFF_ROUNDS {
+            return players;
+        }
+
+        let mut rolls = Vec::new();
+        for player in players {
+            let dice = self.dice.roll();
+            self.emit(GameEvent::DiceOffRoll { player, dice });
+            rolls.push((player, dice.0 + dice.1));
+        }
+
+        let mut totals: Vec<_> = rolls.iter().map(|(_, total)| *total).collect();
+        totals.sort_by(|a, b| b.cmp(a));
+        totals.dedup();
+
+        let mut order = Vec::new();
+        for total in totals {
+            let tied = rolls
+                .iter()
+                .filter(|(_, t)| *t == total)
+                .map(|(player, _)| *player)
+                .collect();
+            order.extend(self.dice_off_round(tied, round + 1));
+        }
+        order
+    }
+
+    /// Whether players can still join or be reseated, i.e. nobody has placed anything yet
+    pub fn is_seating_open(&self) -> bool {
+        self.state == GameState::Setup && self.setup_placements == 0
+    }
+
+    /// Fails once the seating order can no longer change, i.e. after the first settlement
+    fn ensure_seating_open(&self) -> Result<()> {
+        if !self.is_seating_open() {
+            return Err(anyhow!("The turn order is fixed once setup has begun"));
+        }
+        Ok(())
+    }
+
+    fn emit_turn_order(&mut self) {
+        let players = self.players.iter().map(|p| *p.colour()).collect();
+        self.emit(GameEvent::TurnOrder { players });
+    }
+
+    /// The player who places the next starting settlement, in snake order
+    fn setup_player(&self) -> Option<PlayerColour> {
+        let n = self.players.len();
+        let idx = match self.setup_placements {
+            i if i < n => i,
+            i if i < 2 * n => 2 * n - 1 - i,
+            _ => return None,
+        };
+        Some(*self.players[idx].colour())
+    }
+
+    /// Everything which has happened so far, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Every event from sequence number `since` onwards, along with its sequence number
+    ///
+    /// events are numbered from 0 in the order they happened, so a subscriber can pick up where
+    /// it left off by passing one more than the last number it saw
+    pub fn subscribe(&self, since: usize) -> impl Iterator<Item = (usize, &GameEvent)> {
+        self.events.iter().enumerate().skip(since)
+    }
+
+    /// Sequence number the next event will get
+    pub fn next_event(&self) -> usize {
+        self.events.len()
+    }
+
+    /// When event `seq` happened and whose action set it off, unless the event came from
+    /// somewhere the game didn't see it happen, like a `GameDelta`
+    pub(crate) fn event_origin(&self, seq: usize) -> Option<&EventOrigin> {
+        self.event_origins.get(seq)
+    }
+
+    /// Update `player`'s victory points with `f`, announcing their new total
+    fn change_victory_points(&mut self, player: PlayerColour, f: impl FnOnce(usize) -> usize) {
+        let Ok(mut p) = self.get_player_mut(player) else {
+            return;
+        };
+        let points = f(p.victory_points());
+        *p.victory_points_mut() = points;
+        drop(p);
+        self.emit(GameEvent::VictoryPointsChanged { player, points });
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        trace_event!(DEBUG, seq = self.events.len(), ?event, "event");
+        self.events.push(event.clone());
+        self.event_origins.push(EventOrigin {
+            at: now(),
+            player: None,
+        });
+        self.with_extensions(|ext, game| ext.on_event(game, &event));
+
+        if let Some((player, achievement)) = self.achievements.observe(&event) {
+            if let Ok(mut p) = self.get_player_mut(player) {
+                p.achievements_mut().push(achievement);
+            }
+            self.emit(GameEvent::AchievementEarned {
+                player,
+                achievement,
+            });
+        }
+    }
+
+    pub fn roll_history(&self) -> &RollHistory {
+        &self.roll_history
+    }
+
+    /// How many times each total has been rolled, as `(total, count)` pairs from `MIN_ROLL` up
+    pub fn dice_histogram(&self) -> Vec<(u8, usize)> {
+        (MIN_ROLL..=MAX_ROLL)
+            .zip(self.roll_history.counts())
+            .collect()
+    }
+
+    /// Each player's production from rolls against what they'd expect from their buildings, once
+    /// the dice have been rolled
+    pub fn luck(&self) -> &[Luck] {
+        &self.luck
+    }
+
+    /// A copy of the game to search or play out from, leaving behind its event log and roll
+    /// history
+    ///
+    /// everything deciding what happens next is copied, so the fork plays on exactly as the game
+    /// would, but it doesn't drag along a history which grows with every move
+    pub fn fork(&self) -> Game {
+        Game {
+            players: self.players.clone(),
+            board: self.board.clone(),
+            bank: self.bank.clone(),
+            state: self.state,
+            turn_no: self.turn_no,
+            phase: self.phase,
+            dice: self.dice.clone(),
+            roll_history: RollHistory::new(),
+            luck: Vec::new(),
+            discards: self.discards.clone(),
+            trades: self.trades.clone(),
+            setup_placements: self.setup_placements,
+            events: Vec::new(),
+            event_origins: Vec::new(),
+            bought_this_turn: self.bought_this_turn.clone(),
+            development_card_played: self.development_card_played,
+            free_roads: self.free_roads,
+            longest_road: self.longest_road,
+            largest_army: self.largest_army,
+            harbormaster: self.harbormaster,
+            paused: self.paused,
+            host: self.host,
+            kick_votes: self.kick_votes.clone(),
+            options: self.options,
+            fish: self.fish.clone(),
+            scenario: self.scenario.clone(),
+            extensions: self.extensions.clone(),
+            extension_cards: self.extension_cards.clone(),
+            achievements: self.achievements,
+            winner: self.winner,
+            robber_deadline: self.robber_deadline,
+            trade_deadlines: self.trade_deadlines.clone(),
+            clock: self.clock,
+            clocks: self.clocks.clone(),
+            resigned: self.resigned.clone(),
+            agents: self.agents.clone(),
+            id: self.id,
+            rematch_of: self.rematch_of,
+            hash: self.hash,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// A new game for the same players, in the same seats and with the same options, linked back
+    /// to this one through `rematch_of`
+    ///
+    /// with `same_board` it's played on this game's tiles, cleared of everything built on them,
+    /// otherwise on a new board, which is seeded afresh if this one was seeded. Bots stay bots,
+    /// and the host keeps hosting, but any scenario has to be set again
+    #[cfg(feature = "rand")]
+    pub fn rematch(&self, same_board: bool) -> Game {
+        let mut options = self.options;
+        if !same_board {
+            options.board_seed = options.board_seed.map(|_| entropy_rng().gen());
+        }
+        let mut game = Game::with_options(options);
+        if same_board {
+            game.board = self.board.cleared();
+        }
+        for player in self.players.iter() {
+            let mut seat = Player::new(*player.colour());
+            seat.set_bot(player.is_bot());
+            game.players.push(seat);
+        }
+        game.host = self.host;
+        game.extensions = self.extensions.clone();
+        game.rematch_of = Some(self.id);
+        game.hash = game.turn_hash();
+        game
+    }
+
+    /// Replace the `DiceRoller` used for every subsequent roll
+    pub fn set_dice_roller(&mut self, roller: impl DiceRoller + 'static) {
+        self.dice = Dice::new(roller);
+    }
+
+    /// Make everything left to chance besides the dice, like which card the robber steals,
+    /// reproducible from `seed`
+    #[cfg(feature = "rand")]
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = GameRng(StdRng::seed_from_u64(seed));
+    }
+
+    /// Every player, in turn order
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// The player the game is waiting on to do something next, if anyone
+    ///
+    /// this is whoever places the next starting settlement during setup, the first player who
+    /// still owes a discard after a 7, or otherwise the current player
+    pub fn awaiting_player(&self) -> Option<PlayerColour> {
+        match self.state {
+            GameState::Setup => self.setup_player(),
+            GameState::Complete => None,
+            GameState::Running | GameState::Paused => match self.discards.pending().first() {
+                Some((player, _)) => Some(*player),
+                None => self.current_player().ok().map(|p| *p.colour()),
+            },
+        }
+    }
+
+    /// Everything `player` is allowed to see about the game
+    pub fn view_for(&self, player: &PlayerColour) -> Result<PlayerView> {
+        let now = now();
+        let me = self.get_player(player)?;
+        let opponents = self
+            .players
+            .iter()
+            .filter(|p| p.colour() != player)
+            .map(|p| OpponentView {
+                colour: *p.colour(),
+                cards: p.hand_size(),
+                development_cards: p.development_cards().len(),
+                victory_points: self.vp_breakdown(p.colour()).map_or(0, |vp| vp.public()),
+                knights_played: p.knights_played(),
+                time_remaining: self.time_remaining(p.colour(), now),
+                resigned: self.is_resigned(p.colour()),
+            })
+            .collect();
+
+        Ok(PlayerView {
+            viewer: *player,
+            state: self.state,
+            phase: self.phase,
+            current_player: self.current_player().ok().map(|p| *p.colour()),
+            board: self.board.clone(),
+            bank: self.bank.summary(),
+            hand: *me.resources(),
+            discard: self.discards.required(player),
+            development_cards: me.development_cards().to_vec(),
+            victory_points: self.vp_breakdown(player)?,
+            opponents,
+            legal_actions: self.legal_actions(player),
+            time_remaining: self.time_remaining(player, now),
+        })
+    }
+
+    /// `player`'s view of the game as a fixed size vector of numbers, see `PlayerView::encode`
+    pub fn encode_observation(&self, player: &PlayerColour) -> Result<Vec<f32>> {
+        self.view_for(player)?.encode()
+    }
+
+    pub fn get_player(&self, colour: &PlayerColour) -> Result<&Player> {
+        self.players
+            .get(colour)
+            .ok_or(anyhow!("Could not find that player"))
+    }
+
+    pub fn get_player_mut(&mut self, colour: PlayerColour) -> Result<PlayerMut<'_>> {
+        self.players
+            .get_mut(&colour)
+            .ok_or(anyhow!("Could not find that player"))
+    }
+
+    /// Handle the final step of trading, moving the resources between the two players
+    pub fn finalize_trade(&mut self, trade_id: Uuid) -> Result<()> {
+        trace_span!(DEBUG, "trade", %trade_id);
+        self.ensure_can_act()?;
+        let trade = match self.trades.get_trade(trade_id) {
+            Some(trade) => trade,
+            None => return Err(anyhow!("Could not find trade with that ID")),
+        };
+
+        match trade.state() {
+            LockedIn => (),
+            Accepted | Proposed => return Err(anyhow!("Cannot finalize trade at this time")),
+        };
+
+        let offering: Resources = *trade.offering();
+        let wants: Resources = *trade.wants();
+        let offering_player = trade.get_offering_player();
+        let trade_partner = trade.get_trade_partner()?;
+        trace_event!(
+            DEBUG,
+            from = %offering_player,
+            to = %trade_partner,
+            ?offering,
+            ?wants,
+            "trading"
+        );
+
+        let transfer = Transfer::new()
+            .between(offering_player, trade_partner, offering)
+            .between(trade_partner, offering_player, wants);
+        self.transfer(&transfer)
+            .map_err(|_| anyhow!("Not enough resources to make this trade"))?;
+        if let Some(trade) = self.trades.get_trade_mut(trade_id) {
+            *trade.state_mut() = Accepted;
+        }
+        self.trade_deadlines.retain(|(id, _)| *id != trade_id);
+        self.emit(GameEvent::TradeCompleted {
+            trade: trade_id,
+            from: offering_player,
+            to: trade_partner,
+        });
+        Ok(())
+    }
+
+    /// Everything that changed since `previous`, to bring a copy of that position up to date
+    /// with `GameDelta::apply`
+    ///
+    /// pieces are compared one by one, unless the board's shape changed or the robber was taken
+    /// off, in which case the whole board is sent. New events are only included if `previous`'s
+    /// events are the start of this game's
+    pub fn diff(&self, previous: &Game) -> GameDelta {
+        let mut delta = GameDelta::default();
+        if self.state != previous.state {
+            delta.state = Some(self.state);
+        }
+        if self.phase != previous.phase {
+            delta.phase = Some(self.phase);
+        }
+        if self.turn_no != previous.turn_no {
+            delta.turn_no = Some(self.turn_no);
+        }
+        if self.free_roads != previous.free_roads {
+            delta.free_roads = Some(self.free_roads);
+        }
+
+        let (board, old) = (&self.board, &previous.board);
+        let reshaped = board.tile_count() != old.tile_count()
+            || board.vertices().len() != old.vertices().len()
+            || board.edges().len() != old.edges().len()
+            || (board.robber().is_none() && old.robber().is_some());
+        if reshaped {
+            delta.board = Some(board.clone());
+        } else {
+            for idx in 0..board.tile_count() {
+                if board.tile(idx) != old.tile(idx) {
+                    delta.tiles.push((idx, *board.tile(idx).unwrap()));
+                }
+            }
+            if board.robber() != old.robber() {
+                delta.robber = board.robber();
+            }
+            for (vertex, before) in board.vertices().iter().zip(old.vertices()) {
+                if vertex.building() != before.building() {
+                    delta
+                        .buildings
+                        .push((*vertex.id(), vertex.building().copied()));
+                }
+            }
+            for (edge, before) in board.edges().iter().zip(old.edges()) {
+                if edge.road() != before.road() {
+                    delta.roads.push((*edge.id(), edge.road().copied()));
+                }
+            }
+        }
+
+        let colours = |game: &Game| -> Vec<PlayerColour> {
+            game.players.iter().map(|player| *player.colour()).collect()
+        };
+        if colours(self) != colours(previous) {
+            delta.turn_order = Some(colours(self));
+        }
+        for player in self.players.iter() {
+            if previous.players.get(player.colour()) != Some(player) {
+                delta.players.push(player.clone());
+            }
+        }
+
+        if self.bank.resources() != previous.bank.resources() {
+            delta.bank = Some(*self.bank.resources());
+        }
+        for kind in DevelopmentCard::ALL {
+            let remaining = self.bank.development_cards_remaining(kind);
+            if remaining != previous.bank.development_cards_remaining(kind) {
+                delta.development_cards.push((kind, remaining));
+            }
+        }
+
+        if self.events.starts_with(&previous.events) {
+            delta.events = self.events[previous.events.len()..].to_vec();
+        }
+        delta
+    }
+
+    /// Bring this game up to date with `delta`, working on a copy so nothing changes if any part
+    /// of it doesn't fit
+    pub(crate) fn apply_delta(&mut self, delta: &GameDelta) -> Result<()> {
+        let mut next = self.clone();
+        if let Some(state) = delta.state {
+            next.state = state;
+        }
+        if let Some(phase) = delta.phase {
+            next.phase = phase;
+        }
+        if let Some(turn_no) = delta.turn_no {
+            next.turn_no = turn_no;
+        }
+        if let Some(free_roads) = delta.free_roads {
+            next.free_roads = free_roads;
+        }
+
+        if let Some(board) = &delta.board {
+            next.board = board.clone();
+        }
+        for (idx, tile) in &delta.tiles {
+            next.board.set_tile(*idx, *tile)?;
+        }
+        if let Some(tile) = delta.robber {
+            next.board.set_robber(tile)?;
+        }
+        for (vertex, building) in &delta.buildings {
+            next.board.set_building(*vertex, *building)?;
+        }
+        for (edge, road) in &delta.roads {
+            next.board.set_road(*edge, *road)?;
+        }
+
+        let changed = |colour: &PlayerColour| delta.players.iter().find(|p| p.colour() == colour);
+        if let Some(order) = &delta.turn_order {
+            let mut players = PlayerMap::new();
+            for colour in order {
+                let player = changed(colour)
+                    .or(self.players.get(colour))
+                    .ok_or(anyhow!("No player with that colour"))?;
+                players.push(player.clone());
+            }
+            next.players = players;
+        }
+        for player in &delta.players {
+            *next
+                .players
+                .get_mut(player.colour())
+                .ok_or(anyhow!("No player with that colour"))? = player.clone();
+        }
+
+        if delta.bank.is_some() || !delta.development_cards.is_empty() {
+            let resources = delta.bank.unwrap_or(*next.bank.resources());
+            let cards: Vec<(DevelopmentCard, usize)> = DevelopmentCard::ALL
+                .into_iter()
+                .map(
+                    |kind| match delta.development_cards.iter().find(|(k, _)| *k == kind) {
+                        Some(&(_, count)) => (kind, count),
+                        None => (kind, next.bank.development_cards_remaining(kind)),
+                    },
+                )
+                .collect();
+            next.bank.restock(resources, cards);
+        }
+        next.events.extend(delta.events.iter().cloned());
+
+        next.hash = next.turn_hash();
+        *self = next;
+        Ok(())
+    }
+
+    /// Zobrist hash of the position, for transposition tables and spotting repeated positions
+    ///
+    /// the board, the players' cards and the turn each keep their share of the hash up to date as
+    /// they change, so reading it is free. Equal positions always hash the same, without
+    /// serialising anything, and positions with different moves available never knowingly share
+    /// a hash
+    pub fn zobrist(&self) -> u64 {
+        self.board.zobrist() ^ self.players.zobrist() ^ self.hash
+    }
+
+    /// The same as `zobrist`, under the name clients and simulators look for when caching or
+    /// deduplicating positions
+    pub fn state_hash(&self) -> u64 {
+        self.zobrist()
+    }
+
+    /// Work the turn's share of the hash out from scratch: the state, phase and whose turn it is,
+    /// along with any free roads, discards owed, and cards played or bought this turn
+    fn turn_hash(&self) -> u64 {
+        key(Feature::State, &[self.state as u64])
+            ^ key(Feature::Phase, &[self.phase as u64])
+            ^ free_roads_key(self.free_roads)
+            ^ self.turn_key()
+            ^ self.discards_key()
+            ^ card_played_key(self.development_card_played)
+            ^ bought_key(&self.bought_this_turn)
+            ^ winner_key(self.winner)
+    }
+
+    fn turn_key(&self) -> u64 {
+        self.current_player().map_or(0, |player| {
+            key(Feature::Turn, &[colour_code(player.colour())])
+        })
+    }
+
+    fn discards_key(&self) -> u64 {
+        self.discards
+            .pending()
+            .iter()
+            .fold(0, |hash, (player, count)| {
+                hash ^ key(Feature::Discard, &[colour_code(player), *count as u64])
+            })
+    }
+
+    fn set_state(&mut self, state: GameState) {
+        self.hash ^=
+            key(Feature::State, &[self.state as u64]) ^ key(Feature::State, &[state as u64]);
+        self.state = state;
+    }
+
+    fn set_phase(&mut self, phase: TurnPhase) {
+        self.hash ^=
+            key(Feature::Phase, &[self.phase as u64]) ^ key(Feature::Phase, &[phase as u64]);
+        self.phase = phase;
+    }
+
+    fn set_free_roads(&mut self, free_roads: u8) {
+        self.hash ^= free_roads_key(self.free_roads) ^ free_roads_key(free_roads);
+        self.free_roads = free_roads;
+    }
+
+    fn set_development_card_played(&mut self, played: bool) {
+        self.hash ^= card_played_key(self.development_card_played) ^ card_played_key(played);
+        self.development_card_played = played;
+    }
+
+    fn set_winner(&mut self, winner: Option<PlayerColour>) {
+        self.hash ^= winner_key(self.winner) ^ winner_key(winner);
+        self.winner = winner;
+    }
+
+    /// Note down `card` as bought this turn, so it can't be played until a later one
+    fn record_bought(&mut self, card: DevelopmentCard) {
+        self.bought_this_turn.push(card);
+        let n = self.bought_this_turn.iter().filter(|c| **c == card).count();
+        self.hash ^= key(Feature::CardBought, &[card as u64, n as u64]);
+    }
+
+    /// Forget the cards bought this turn, and that one was played, ready for the next turn
+    fn clear_turn_cards(&mut self) {
+        self.hash ^= bought_key(&self.bought_this_turn);
+        self.bought_this_turn.clear();
+        self.set_development_card_played(false);
+    }
+
+    fn set_turn_no(&mut self, turn_no: usize) {
+        let before = self.turn_key();
+        self.turn_no = turn_no;
+        self.hash ^= before ^ self.turn_key();
+    }
+
+    /// Add, remove or reorder players with `f`, keeping track of whose turn that makes it
+    fn update_players<T>(&mut self, f: impl FnOnce(&mut PlayerMap) -> T) -> T {
+        let before = self.turn_key();
+        let result = f(&mut self.players);
+        self.hash ^= before ^ self.turn_key();
+        result
+    }
+
+    /// Change who owes discards with `f`
+    fn update_discards<T>(&mut self, f: impl FnOnce(&mut DiscardManager) -> T) -> T {
+        let before = self.discards_key();
+        let result = f(&mut self.discards);
+        self.hash ^= before ^ self.discards_key();
+        result
+    }
+
+    /// The position as a dense byte string, for transposition tables, replay buffers and sending
+    /// over the network, and much smaller than the JSON
+    ///
+    /// this covers what's needed to carry on playing: the board, every player's hand, cards and
+    /// pieces, the bank, whose turn it is and how far through it they are, awards and owed
+    /// discards. The event log, roll history, open trades, options, timers, scenario and
+    /// extensions are left out, and `decode_compact` starts them afresh
+    pub fn encode_compact(&self) -> Vec<u8> {
+        let colours: Vec<_> = self.players.iter().map(|p| *p.colour()).collect();
+        let index = |player| encode_player_index(&colours, player);
+
+        let mut out = vec![
+            COMPACT_VERSION,
+            self.state as u8,
+            self.phase as u8,
+            small(self.setup_placements),
+            self.development_card_played as u8,
+            self.free_roads,
+        ];
+        out.extend((self.turn_no as u32).to_le_bytes());
+        out.push(small(self.players.len()));
+        for player in self.players.iter() {
+            encode_player(player, &mut out);
+        }
+        for holder in [
+            self.longest_road,
+            self.largest_army,
+            self.harbormaster,
+            self.winner,
+        ] {
+            out.push(index(holder));
+        }
+        out.push(small(self.bought_this_turn.len()));
+        out.extend(self.bought_this_turn.iter().map(|card| *card as u8));
+        out.push(small(self.discards.pending().len()));
+        for (player, owed) in self.discards.pending() {
+            out.extend([index(Some(*player)), small(*owed)]);
+        }
+
+        encode_resources(self.bank.resources(), &mut out);
+        for card in DevelopmentCard::ALL {
+            out.push(small(self.bank.development_cards_remaining(card)));
+        }
+        encode_board(&self.board, &colours, &mut out);
+        out
+    }
+
+    /// Rebuild a game from the bytes of `encode_compact`
+    pub fn decode_compact(bytes: &[u8]) -> Result<Self> {
+        const STATES: [GameState; 4] = [
+            GameState::Setup,
+            GameState::Running,
+            GameState::Paused,
+            GameState::Complete,
+        ];
+        const PHASES: [TurnPhase; 5] = [
+            TurnPhase::Roll,
+            TurnPhase::Discard,
+            TurnPhase::MoveRobber,
+            TurnPhase::Main,
+            TurnPhase::PlacingFreeRoads,
+        ];
+
+        let mut reader = CompactReader::new(bytes);
+        let version = reader.byte()?;
+        if version != COMPACT_VERSION {
+            return Err(anyhow!("Unsupported compact encoding version {}", version));
+        }
+        let state = *STATES
+            .get(reader.count()?)
+            .ok_or(anyhow!("Unknown game state"))?;
+        let phase = *PHASES
+            .get(reader.count()?)
+            .ok_or(anyhow!("Unknown turn phase"))?;
+        let mut game = Game {
+            state,
+            phase,
+            setup_placements: reader.count()?,
+            development_card_played: reader.byte()? != 0,
+            free_roads: reader.byte()?,
+            turn_no: u32::from_le_bytes(reader.array()?) as usize,
+            ..Game::default()
+        };
+
+        for _ in 0..reader.count()? {
+            game.players.push(decode_player(&mut reader)?);
+        }
+        game.host = game.players.first().map(|p| *p.colour());
+        let colours: Vec<_> = game.players.iter().map(|p| *p.colour()).collect();
+        let mut holder = || decode_player_index(&colours, reader.byte()?);
+        game.longest_road = holder()?;
+        game.largest_army = holder()?;
+        game.harbormaster = holder()?;
+        game.winner = holder()?;
+
+        for _ in 0..reader.count()? {
+            let card = decode_development_card(reader.byte()?)?;
+            game.bought_this_turn.push(card);
+        }
+        let mut pending = Vec::new();
+        for _ in 0..reader.count()? {
+            let player = decode_player_index(&colours, reader.byte()?)?
+                .ok_or(anyhow!("Discard owed by nobody"))?;
+            pending.push((player, reader.count()?));
+        }
+        game.discards.restore(pending, now());
+
+        let resources = decode_resources(&mut reader)?;
+        let mut cards = Vec::new();
+        for card in DevelopmentCard::ALL {
+            cards.push((card, reader.count()?));
+        }
+        game.bank.restock(resources, cards);
+        game.board = decode_board(&mut reader, &colours)?;
+        game.hash = game.turn_hash();
+        reader.finish()?;
+        Ok(game)
+    }
+
+    /// Where each of `player`'s victory points came from, including their hidden cards
+    pub fn vp_breakdown(&self, player: &PlayerColour) -> Result<VpBreakdown> {
+        let p = self.get_player(player)?;
+        let mut vp = VpBreakdown {
+            chits: p.chits(),
+            hidden_cards: p
+                .development_cards()
+                .iter()
+                .filter(|card| **card == DevelopmentCard::HiddenVictoryPoint)
+                .count(),
+            ..VpBreakdown::default()
+        };
+
+        for vertex in self.board.vertices() {
+            match vertex.building() {
+                Some((owner, Building::Settlement)) if owner == player => vp.settlements += 1,
+                Some((owner, Building::City)) if owner == player => vp.cities += 2,
+                _ => (),
+            }
+        }
+        if self.longest_road == Some(*player) {
+            vp.longest_road = AWARD_POINTS;
+        }
+        if self.largest_army == Some(*player) {
+            vp.largest_army = AWARD_POINTS;
+        }
+        if self.harbormaster == Some(*player) {
+            vp.harbormaster = AWARD_POINTS;
+        }
+        Ok(vp)
+    }
+
+    /// Build `building` for `player` at `location`, paying for it from their hand
+    ///
+    /// checks it's their turn to build, that the piece can go there and that they have one left
+    /// to place, and the cost goes back to the bank. Settlements and cities go on vertices and
+    /// roads on edges, cities replacing one of the player's settlements
+    pub fn build(
+        &mut self,
+        player: PlayerColour,
+        building: Building,
+        location: Location,
+    ) -> Result<()> {
+        match (building, location) {
+            (Building::Settlement, Location::Vertex(vertex)) => {
+                self.build_settlement(player, vertex)
+            }
+            (Building::City, Location::Vertex(vertex)) => self.upgrade_to_city(player, vertex),
+            (Building::Road, Location::Edge(edge)) => self.build_road(player, edge),
+            (Building::Road, _) => Err(anyhow!("Roads are built on edges")),
+            (_, _) => Err(anyhow!("Settlements and cities are built on vertices")),
+        }
+    }
+
+    /// Build a settlement for `player` at `vertex`, on one of their roads
+    pub fn build_settlement(&mut self, player: PlayerColour, vertex: VertexId) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        self.check_settlement(&player, vertex)?;
+        if self
+            .get_player(&player)?
+            .stock()
+            .remaining(Building::Settlement)
+            == 0
+        {
+            return Err(anyhow!("No pieces of that kind left to place"));
+        }
+        self.pay_to_bank(player, self.options.costs.settlement)
+            .map_err(|_| anyhow!("Not enough resources to build a settlement"))?;
+        self.get_player_mut(player)?
+            .stock_mut()
+            .take(Building::Settlement)?;
+
+        self.board
+            .set_building(vertex, Some((player, Building::Settlement)))?;
+        self.emit(GameEvent::SettlementBuilt { player });
+        self.change_victory_points(player, |points| points + 1);
+        self.update_awards();
+        self.with_scenario(|scenario, game| scenario.on_settlement_built(game, player, vertex));
+        Ok(())
+    }
+
+    /// Build a road for `player` along `edge`, joining onto their roads or buildings
+    pub fn build_road(&mut self, player: PlayerColour, edge: EdgeId) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        self.check_road(&player, edge)?;
+        if self.get_player(&player)?.stock().remaining(Building::Road) == 0 {
+            return Err(anyhow!("No pieces of that kind left to place"));
+        }
+        self.pay_to_bank(player, self.options.costs.road)
+            .map_err(|_| anyhow!("Not enough resources to build a road"))?;
+        self.place_road(player, edge)?;
+        self.emit(GameEvent::RoadBuilt { player });
+        Ok(())
+    }
+
+    /// Upgrade one of `player`'s settlements to a city, paying for it from their hand
+    ///
+    /// the settlement piece goes back into the player's stock, and they gain the extra victory
+    /// point a city is worth
+    pub fn upgrade_to_city(&mut self, player: PlayerColour, vertex_id: VertexId) -> Result<()> {
+        self.ensure_turn(&player, TurnPhase::Main)?;
+        match self.board.vertex(vertex_id).map(|vertex| vertex.building()) {
+            None => return Err(anyhow!("Could not find that vertex")),
+            Some(Some((owner, Building::Settlement))) if *owner == player => (),
+            Some(Some((_, Building::Settlement))) => {
+                return Err(anyhow!("Cannot upgrade another player's settlement"))
+            }
+            Some(_) => return Err(anyhow!("There is no settlement at that vertex")),
+        };
+
+        if self.get_player(&player)?.stock().remaining(Building::City) == 0 {
+            return Err(anyhow!("No pieces of that kind left to place"));
+        }
+        self.pay_to_bank(player, self.options.costs.city)
+            .map_err(|_| anyhow!("Not enough resources to build a city"))?;
+        {
+            let mut p = self.get_player_mut(player)?;
+            p.stock_mut().take(Building::City)?;
+            p.stock_mut().put_back(Building::Settlement);
+        }
+
+        self.board
+            .set_building(vertex_id, Some((player, Building::City)))?;
+        self.emit(GameEvent::CityBuilt { player });
+        self.change_victory_points(player, |points| points + 1);
+        self.update_awards();
+        Ok(())
+    }
+
+    pub fn get_bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn get_bank_mut(&mut self) -> &mut Bank {
+        &mut self.bank
+    }
+
+    pub fn get_trades(&self) -> &TradeManager {
+        &self.trades
+    }
+
+    pub fn get_trades_mut(&mut self) -> &mut TradeManager {
+        &mut self.trades
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+}
+
+/// An error for an action which can't be taken at this point in the game
+fn not_allowed(reason: &str) -> anyhow::Error {
+    ActionError::NotAllowedNow(reason.into()).into()
+}
+
+fn free_roads_key(free_roads: u8) -> u64 {
+    match free_roads {
+        0 => 0,
+        n => key(Feature::FreeRoads, &[n as u64]),
+    }
+}
+
+fn card_played_key(played: bool) -> u64 {
+    match played {
+        true => key(Feature::CardPlayed, &[]),
+        false => 0,
+    }
+}
+
+fn winner_key(winner: Option<PlayerColour>) -> u64 {
+    winner.map_or(0, |winner| key(Feature::Winner, &[colour_code(&winner)]))
+}
+
+/// Key for the cards bought this turn, folding in one key for each copy of a card, so buying
+/// another only has to fold in one more
+fn bought_key(cards: &[DevelopmentCard]) -> u64 {
+    cards.iter().enumerate().fold(0, |hash, (idx, card)| {
+        let n = cards[..=idx].iter().filter(|c| *c == card).count();
+        hash ^ key(Feature::CardBought, &[*card as u64, n as u64])
+    })
+}
+
+/// Where a game gets its chance from apart from the dice, like which card the robber steals
+///
+/// every generator compares equal, so games in the same position are equal whatever state
+/// their generators are in. It isn't serialised, so a restored game starts a fresh one
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "rand"), derive(Default))]
+pub(crate) struct GameRng(#[cfg(feature = "rand")] StdRng);
+
+#[cfg(feature = "rand")]
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(entropy_rng())
+    }
+}
+
+impl PartialEq for GameRng {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for GameRng {}
+
+/// When an event happened, and whose action set it off if it came from one
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct EventOrigin {
+    pub(crate) at: SystemTime,
+    pub(crate) player: Option<PlayerColour>,
+}
+
+/// Agents playing for players handed to them by `Game::replace_with_bot`
+///
+/// like `GameRng` they all compare equal, and aren't serialised, so a restored game's bots play
+/// at random. Clones of a game get their own copies of its agents
+#[derive(Debug, Default)]
+pub(crate) struct BotAgents(
+    #[cfg(all(feature = "rand", feature = "serde"))] Vec<(PlayerColour, Box<dyn BotAgent>)>,
+);
+
+/// An agent a game can clone along with itself
+#[cfg(all(feature = "rand", feature = "serde"))]
+trait BotAgent: Agent + Send {
+    fn clone_box(&self) -> Box<dyn BotAgent>;
+}
+
+#[cfg(all(feature = "rand", feature = "serde"))]
+impl<A: Agent + Clone + Send + 'static> BotAgent for A {
+    fn clone_box(&self) -> Box<dyn BotAgent> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for BotAgents {
+    fn clone(&self) -> Self {
+        #[cfg(all(feature = "rand", feature = "serde"))]
+        return Self(
+            self.0
+                .iter()
+                .map(|(player, agent)| (*player, agent.clone_box()))
+                .collect(),
+        );
+        #[cfg(not(all(feature = "rand", feature = "serde")))]
+        Self()
+    }
+}
+
+impl PartialEq for BotAgents {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for BotAgents {}
+
+/// Pick a card at random from a hand, or nothing if the hand is empty
+#[cfg_attr(not(feature = "rand"), allow(unused_variables))]
+fn random_card(hand: &Resources, rng: &mut GameRng) -> Option<ResourceKind> {
+    let total = hand.total();
+    if total == 0 {
+        return None;
+    }
+
+    #[cfg(feature = "rand")]
+    let mut pick = rng.0.gen_range(0..total);
+    #[cfg(not(feature = "rand"))]
+    let mut pick = 0;
+
+    hand.into_iter().find_map(|(kind, count)| {
+        if pick < count {
+            Some(kind)
+        } else {
+            pick -= count;
+            None
+        }
+    })
+}
+
+/// Hashes the game's compact encoding, so games in the same position hash the same however
+/// they got there
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.encode_compact().hash(state);
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        let mut game = Self {
+            players: PlayerMap::new(),
+            board: Board::default(),
+            bank: Bank::new(),
+            state: GameState::Setup,
+            turn_no: 0,
+            phase: TurnPhase::Roll,
+            dice: Dice::default(),
+            roll_history: RollHistory::new(),
+            luck: Vec::new(),
+            discards: DiscardManager::new(),
+            trades: TradeManager::new(),
+            setup_placements: 0,
+            events: Vec::new(),
+            event_origins: Vec::new(),
+            bought_this_turn: Vec::new(),
+            development_card_played: false,
+            free_roads: 0,
+            longest_road: None,
+            largest_army: None,
+            harbormaster: None,
+            paused: None,
+            host: None,
+            kick_votes: Vec::new(),
+            options: GameOptions::default(),
+            fish: FishBag::new(),
+            scenario: ScenarioHandle::default(),
+            extensions: Extensions::default(),
+            extension_cards: Vec::new(),
+            achievements: AchievementTracker::new(),
+            winner: None,
+            robber_deadline: None,
+            trade_deadlines: Vec::new(),
+            clock: None,
+            clocks: Vec::new(),
+            resigned: Vec::new(),
+            agents: BotAgents::default(),
+            id: Uuid::new_v4(),
+            rematch_of: None,
+            hash: 0,
+            rng: GameRng::default(),
+        };
+        game.hash = game.turn_hash();
+        game
+    }
+}
+
+/// Ways for tests to set up a position without creating or losing cards and pieces, so the
+/// game still passes `check_invariants` with `debug-invariants` on
+#[cfg(test)]
+impl Game {
+    /// Swap `player`'s hand for `hand`, putting their old cards back in the bank and taking the
+    /// new ones out of it
+    pub(crate) fn set_hand(&mut self, player: PlayerColour, hand: Resources) {
+        let old = std::mem::replace(self.get_player_mut(player).unwrap().resources_mut(), hand);
+        self.bank.return_resources(old);
+        self.bank.take_resources(hand).unwrap();
+    }
+
+    /// Hand `player` a development card of `kind` from the deck
+    pub(crate) fn give_development_card(&mut self, player: PlayerColour, kind: DevelopmentCard) {
+        self.bank.take_dev_card(kind);
+        self.get_player_mut(player)
+            .unwrap()
+            .development_cards_mut()
+            .push(kind);
+    }
+
+    /// Put `building` on `vertex` for `player` as if they'd built it, out of their own stock
+    /// and with the points it's worth
+    pub(crate) fn set_building(
+        &mut self,
+        player: PlayerColour,
+        vertex: VertexId,
+        building: Building,
+    ) {
+        self.board
+            .set_building(vertex, Some((player, building)))
+            .unwrap();
+        let mut p = self.get_player_mut(player).unwrap();
+        p.stock_mut().take(building).unwrap();
+        *p.victory_points_mut() += match building {
+            Building::Settlement => 1,
+            Building::City => 2,
+            Building::Road => 0,
+        };
+    }
+}
+
+/// A two player game just past setup, with red and blue each on two settlements
+#[cfg(all(test, feature = "rand"))]
+pub(crate) fn running_game() -> Game {
+    let mut g = Game::new();
+    g.add_player(PlayerColour::Red);
+    g.add_player(PlayerColour::Blue);
+    for (player, vertex) in [
+        (PlayerColour::Red, 2),
+        (PlayerColour::Blue, 30),
+        (PlayerColour::Blue, 40),
+        (PlayerColour::Red, 10),
+    ] {
+        g.place_initial_settlement(player, VertexId::new(vertex))
+            .unwrap();
+    }
+    g
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use crate::achievements::Achievement;
+    use crate::events::Decision;
+    use crate::options::CostTable;
+    use crate::{bank::*, board::*, dice::FixedDice, fish::FishPurchase, game::*};
+    use rand::{rngs::StdRng, SeedableRng};
+    #[test]
+    fn test_init() {
+        let g = Game::default();
+        // the deck is shuffled, so only what's in it can be compared
+        assert_eq!(
+            g.get_bank().remaining_dev_cards(),
+            Bank::new().remaining_dev_cards()
+        );
+        assert_eq!(
+            g,
+            Game {
+                players: PlayerMap::new(),
+                board: Board::default(),
+                bank: g.get_bank().clone(),
+                state: GameState::Setup,
+                turn_no: 0,
+                phase: TurnPhase::Roll,
+                dice: Dice::default(),
+                roll_history: RollHistory::new(),
+                luck: Vec::new(),
+                discards: DiscardManager::new(),
+                trades: TradeManager::new(),
+                setup_placements: 0,
+                events: Vec::new(),
+                event_origins: Vec::new(),
+                bought_this_turn: Vec::new(),
+                development_card_played: false,
+                free_roads: 0,
+                longest_road: None,
+                largest_army: None,
+                harbormaster: None,
+                paused: None,
+                host: None,
+                kick_votes: Vec::new(),
+                options: GameOptions::default(),
+                fish: FishBag::new(),
+                scenario: ScenarioHandle::default(),
+                extensions: Extensions::default(),
+                extension_cards: Vec::new(),
+                achievements: AchievementTracker::new(),
+                winner: None,
+                robber_deadline: None,
+                trade_deadlines: Vec::new(),
+                clock: None,
+                clocks: Vec::new(),
+                resigned: Vec::new(),
+                agents: BotAgents::default(),
+                id: g.id,
+                rematch_of: None,
+                hash: key(Feature::State, &[GameState::Setup as u64])
+                    ^ key(Feature::Phase, &[TurnPhase::Roll as u64]),
+                rng: GameRng::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_deck_options() {
+        let options = GameOptions {
+            development_cards: [(DevelopmentCard::Knight, 20)].into_iter().collect(),
+            deck_seed: Some(9),
+            board_seed: Some(9),
+            ..GameOptions::default()
+        };
+        let g = Game::with_options(options);
+        let same = Game::with_options(options);
+        assert_eq!(g.get_bank().summary().development_cards, 20);
+        assert_eq!(g.get_bank(), same.get_bank());
+        assert_eq!(g.get_board(), same.get_board());
+        assert_eq!(g.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_add_player() {
+        let mut g = Game::default();
+        assert_eq!(
+            g,
+            Game {
+                players: PlayerMap::new(),
+                board: Board::default(),
+                bank: g.get_bank().clone(),
+                state: GameState::Setup,
+                turn_no: 0,
+                phase: TurnPhase::Roll,
+                dice: Dice::default(),
+                roll_history: RollHistory::new(),
+                luck: Vec::new(),
+                discards: DiscardManager::new(),
+                trades: TradeManager::new(),
+                setup_placements: 0,
+                events: Vec::new(),
+                event_origins: Vec::new(),
+                bought_this_turn: Vec::new(),
+                development_card_played: false,
+                free_roads: 0,
+                longest_road: None,
+                largest_army: None,
+                harbormaster: None,
+                paused: None,
+                host: None,
+                kick_votes: Vec::new(),
+                options: GameOptions::default(),
+                fish: FishBag::new(),
+                scenario: ScenarioHandle::default(),
+                extensions: Extensions::default(),
+                extension_cards: Vec::new(),
+                achievements: AchievementTracker::new(),
+                winner: None,
+                robber_deadline: None,
+                trade_deadlines: Vec::new(),
+                clock: None,
+                clocks: Vec::new(),
+                resigned: Vec::new(),
+                agents: BotAgents::default(),
+                id: g.id,
+                rematch_of: None,
+                hash: key(Feature::State, &[GameState::Setup as u64])
+                    ^ key(Feature::Phase, &[TurnPhase::Roll as u64]),
+                rng: GameRng::default(),
+            }
+        );
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Green);
+        g.add_player(PlayerColour::Blue);
+        g.add_player(PlayerColour::Purple);
+
+        assert_eq!(
+            g,
+            Game {
+                players: vec![
+                    Player::new(PlayerColour::Red),
+                    Player::new(PlayerColour::Green),
+                    Player::new(PlayerColour::Blue),
+                    Player::new(PlayerColour::Purple)
+                ]
+                .into(),
+                board: Board::default(),
+                bank: g.get_bank().clone(),
+                state: GameState::Setup,
+                turn_no: 0,
+                phase: TurnPhase::Roll,
+                dice: Dice::default(),
+                roll_history: RollHistory::new(),
+                luck: Vec::new(),
+                discards: DiscardManager::new(),
+                trades: TradeManager::new(),
+                setup_placements: 0,
+                events: Vec::new(),
+                event_origins: Vec::new(),
+                bought_this_turn: Vec::new(),
+                development_card_played: false,
+                free_roads: 0,
+                longest_road: None,
+                largest_army: None,
+                harbormaster: None,
+                paused: None,
+                host: Some(PlayerColour::Red),
+                kick_votes: Vec::new(),
+                options: GameOptions::default(),
+                fish: FishBag::new(),
+                scenario: ScenarioHandle::default(),
+                extensions: Extensions::default(),
+                extension_cards: Vec::new(),
+                achievements: AchievementTracker::new(),
+                winner: None,
+                robber_deadline: None,
+                trade_deadlines: Vec::new(),
+                clock: None,
+                clocks: Vec::new(),
+                resigned: Vec::new(),
+                agents: BotAgents::default(),
+                id: g.id,
+                rematch_of: None,
+                hash: key(Feature::State, &[GameState::Setup as u64])
+                    ^ key(Feature::Phase, &[TurnPhase::Roll as u64])
+                    ^ key(Feature::Turn, &[colour_code(&PlayerColour::Red)]),
+                rng: GameRng::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_id() {
+        let g = Game::new();
+        let game_id = g.get_game_id();
+
+        assert!(game_id.is_ok());
+        let game_id = g.get_game_id().unwrap();
+        assert!(Uuid::parse_str(&game_id.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_roll_dice() {
+        let mut g = Game::new();
+        assert!(g.roll_dice().is_err());
+
+        g.add_player(PlayerColour::Red);
+        start_turn(&mut g, TurnPhase::Roll);
+        let (d1, d2) = g.roll_dice().unwrap();
+        let roll = d1 + d2;
+
+        assert!((2..=12).contains(&roll));
+    }
+
+    #[test]
+    fn test_set_dice_roller() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.set_dice_roller(FixedDice::new([(6, 6), (1, 3)]));
+        start_turn(&mut g, TurnPhase::Roll);
+
+        assert_eq!(g.roll_dice().unwrap(), (6, 6));
+        g.end_turn(PlayerColour::Red).unwrap();
+        assert_eq!(g.roll_dice().unwrap(), (1, 3));
+    }
+
+    #[test]
+    fn test_upgrade_to_city() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+
+        let vertex = VertexId::new(4);
+        g.board
+            .set_building(vertex, Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        {
+            let mut red = g.get_player_mut(PlayerColour::Red).unwrap();
+            *red.resources_mut() = Resources::new_explicit(3, 2, 1, 0, 0);
+            *red.victory_points_mut() = 1;
+            red.stock_mut().take(Building::Settlement).unwrap();
+        }
+        start_turn(&mut g, TurnPhase::Main);
+
+        assert!(g.upgrade_to_city(PlayerColour::Blue, vertex).is_err());
+        assert!(g
+            .upgrade_to_city(PlayerColour::Red, VertexId::new(5))
+            .is_err());
+        assert!(g
+            .upgrade_to_city(PlayerColour::Red, VertexId::new(100))
+            .is_err());
+
+        g.upgrade_to_city(PlayerColour::Red, vertex).unwrap();
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(0, 0, 1, 0, 0));
+        assert_eq!(red.victory_points(), 2);
+        assert_eq!(red.stock().remaining(Building::Settlement), 5);
+        assert_eq!(red.stock().remaining(Building::City), 3);
+        assert_eq!(
+            g.board.vertex(vertex).unwrap().building(),
+            Some(&(PlayerColour::Red, Building::City))
+        );
+
+        // can't upgrade the same vertex twice
+        assert!(g.upgrade_to_city(PlayerColour::Red, vertex).is_err());
+    }
+
+    #[test]
+    fn test_vp_breakdown() {
+        let mut g = production_game();
+        assert!(g.vp_breakdown(&PlayerColour::Green).is_err());
+
+        {
+            let mut red = g.get_player_mut(PlayerColour::Red).unwrap();
+            red.development_cards_mut()
+                .extend([DevelopmentCard::HiddenVictoryPoint, DevelopmentCard::Knight]);
+            *red.chits_mut() = 1;
+        }
+        g.largest_army = Some(PlayerColour::Red);
+
+        assert_eq!(
+            g.vp_breakdown(&PlayerColour::Red).unwrap(),
+            VpBreakdown {
+                settlements: 1,
+                cities: 0,
+                longest_road: 0,
+                largest_army: 2,
+                harbormaster: 0,
+                hidden_cards: 1,
+                chits: 1,
+            }
+        );
+        let blue = g.vp_breakdown(&PlayerColour::Blue).unwrap();
+        assert_eq!(blue.cities, 2);
+        assert_eq!(blue.total(), 2);
+    }
+
+    #[test]
+    fn test_upgrade_to_city_requires_resources() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+
+        let vertex = VertexId::new(0);
+        g.board
+            .set_building(vertex, Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(2, 2, 0, 0, 0));
+        start_turn(&mut g, TurnPhase::Main);
+
+        assert!(g.upgrade_to_city(PlayerColour::Red, vertex).is_err());
+        assert_eq!(
+            g.board.vertex(vertex).unwrap().building(),
+            Some(&(PlayerColour::Red, Building::Settlement))
+        );
+    }
+
+    #[test]
+    fn test_custom_costs() {
+        let cheap_city = Resources::new_explicit(2, 2, 0, 0, 0);
+        let mut g = Game::with_options(GameOptions {
+            costs: CostTable {
+                city: cheap_city,
+                ..CostTable::default()
+            },
+            ..GameOptions::default()
+        });
+        g.add_player(PlayerColour::Red);
+
+        let vertex = VertexId::new(0);
+        g.board
+            .set_building(vertex, Some((PlayerColour::Red, Building::Settlement)))
+            .unwrap();
+        g.set_hand(PlayerColour::Red, cheap_city);
+        start_turn(&mut g, TurnPhase::Main);
+
+        let upgrade = Action::UpgradeToCity { vertex };
+        assert!(g.legal_actions(&PlayerColour::Red).contains(&upgrade));
+        g.upgrade_to_city(PlayerColour::Red, vertex).unwrap();
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new());
+    }
+
+    #[test]
+    fn test_build() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+
+        let home = VertexId::new(4);
+        g.set_building(PlayerColour::Red, home, Building::Settlement);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(0, 1, 1, 3, 3));
+        start_turn(&mut g, TurnPhase::Main);
+
+        let next = g.board.adjacent_vertices(home).next().unwrap();
+        let far = g
+            .board
+            .adjacent_vertices(next)
+            .find(|v| *v != home)
+            .unwrap();
+        let first = g.board.edge_between(home, next).unwrap();
+        let second = g.board.edge_between(next, far).unwrap();
+
+        assert!(g
+            .build(PlayerColour::Red, Building::Road, Location::Vertex(next))
+            .is_err());
+        assert!(g
+            .build(
+                PlayerColour::Red,
+                Building::Settlement,
+                Location::Vertex(far)
+            )
+            .is_err());
+        assert!(g.build_road(PlayerColour::Red, second).is_err());
+        assert!(g.build_road(PlayerColour::Blue, first).is_err());
+
+        g.build(PlayerColour::Red, Building::Road, Location::Edge(first))
+            .unwrap();
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::BuildRoad { edge: second }));
+        g.apply(PlayerColour::Red, Action::BuildRoad { edge: second })
+            .unwrap();
+        // too close to the first settlement
+        assert!(g.build_settlement(PlayerColour::Red, next).is_err());
+        g.build(
+            PlayerColour::Red,
+            Building::Settlement,
+            Location::Vertex(far),
+        )
+        .unwrap();
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new());
+        assert_eq!(red.victory_points(), 2);
+        assert_eq!(red.stock().remaining(Building::Road), 13);
+        assert_eq!(
+            g.board.vertex(far).unwrap().building(),
+            Some(&(PlayerColour::Red, Building::Settlement))
+        );
+        assert_eq!(g.get_bank().remaining(ResourceKind::Brick), 19);
+        assert!(g.events().contains(&GameEvent::RoadBuilt {
+            player: PlayerColour::Red
+        }));
+
+        // nothing left to pay with
+        let third = g
+            .board
+            .edges_of_tile(0)
+            .find(|e| e.road().is_none())
+            .unwrap();
+        assert!(g.build_road(PlayerColour::Red, *third.id()).is_err());
+    }
+
+    #[test]
+    fn test_road_building() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        let home = VertexId::new(4);
+        g.set_building(PlayerColour::Red, home, Building::Settlement);
+        g.give_development_card(PlayerColour::Red, DevelopmentCard::RoadBuilding);
+        start_turn(&mut g, TurnPhase::Main);
+
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::PlayRoadBuilding));
+        assert_eq!(g.play_road_building(PlayerColour::Red).unwrap(), 2);
+        assert_eq!(g.phase(), TurnPhase::PlacingFreeRoads);
+        assert!(g.end_turn(PlayerColour::Red).is_err());
+
+        let actions = g.legal_actions(&PlayerColour::Red);
+        assert!(actions
+            .iter()
+            .all(|a| matches!(a, Action::PlaceFreeRoad { .. })));
+        let next = g.board.adjacent_vertices(home).next().unwrap();
+        let first = g.board.edge_between(home, next).unwrap();
+        assert!(actions.contains(&Action::PlaceFreeRoad { edge: first }));
+
+        g.apply(PlayerColour::Red, Action::PlaceFreeRoad { edge: first })
+            .unwrap();
+        assert_eq!(g.phase(), TurnPhase::PlacingFreeRoads);
+        let second = g.free_road_edges(&PlayerColour::Red)[0];
+        g.place_free_road(PlayerColour::Red, second).unwrap();
+        assert_eq!(g.phase(), TurnPhase::Main);
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.stock().remaining(Building::Road), 13);
+        assert_eq!(*red.resources(), Resources::new());
+        assert!(red.development_cards().is_empty());
+        assert!(g.place_free_road(PlayerColour::Red, second).is_err());
+    }
+
+    #[test]
+    fn test_road_building_without_room() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.board
+            .set_building(
+                VertexId::new(4),
+                Some((PlayerColour::Red, Building::Settlement)),
+            )
+            .unwrap();
+        {
+            let mut red = g.get_player_mut(PlayerColour::Red).unwrap();
+            red.development_cards_mut()
+                .extend([DevelopmentCard::RoadBuilding; 2]);
+            while red.stock().remaining(Building::Road) > 1 {
+                red.stock_mut().take(Building::Road).unwrap();
+            }
+        }
+        start_turn(&mut g, TurnPhase::Main);
+
+        // only one road left in stock, so the turn carries on once it's down
+        assert_eq!(g.play_road_building(PlayerColour::Red).unwrap(), 2);
+        let edge = g.free_road_edges(&PlayerColour::Red)[0];
+        g.place_free_road(PlayerColour::Red, edge).unwrap();
+        assert_eq!(g.phase(), TurnPhase::Main);
+
+        // and with none left, the card has nothing to place
+        g.set_development_card_played(false);
+        assert_eq!(g.play_road_building(PlayerColour::Red).unwrap(), 0);
+        assert_eq!(g.phase(), TurnPhase::Main);
+    }
+
+    /// Skip setup, and jump straight to `phase` of the first player's turn
+    fn start_turn(g: &mut Game, phase: TurnPhase) {
+        g.set_state(GameState::Running);
+        g.set_phase(phase);
+    }
+
+    /// A board where the top left tiles produce ore on 8, grain on 8 and wool on 6
+    fn production_board() -> Board {
+        Board::from_tiles(std::array::from_fn(|idx| match idx {
+            0 => Tile::new(TileKind::Resource(ResourceKind::Ore), 8),
+            1 => Tile::new(TileKind::Resource(ResourceKind::Grain), 8),
+            4 => Tile::new(TileKind::Resource(ResourceKind::Wool), 6),
+            _ => Tile::new(TileKind::Desert, 2),
+        }))
+    }
+
+    fn production_game() -> Game {
+        let mut g = Game::new();
+        g.board = production_board();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+
+        // vertex 2 is shared by tiles 0, 1 and 4, vertex 0 only touches tile 0
+        g.set_building(PlayerColour::Red, VertexId::new(2), Building::Settlement);
+        g.set_building(PlayerColour::Blue, VertexId::new(0), Building::City);
+        start_turn(&mut g, TurnPhase::Roll);
+        g
+    }
+
+    #[test]
+    fn test_production_for_roll() {
+        let g = production_game();
+
+        let production = g.production_for_roll(8);
+        assert_eq!(
+            production,
+            vec![
+                (PlayerColour::Red, Resources::new_explicit(1, 1, 0, 0, 0)),
+                (PlayerColour::Blue, Resources::new_explicit(2, 0, 0, 0, 0)),
+            ]
+        );
+
+        let production = g.production_for_roll(6);
+        assert_eq!(production[0].1, Resources::new_explicit(0, 0, 1, 0, 0));
+        assert_eq!(production[1].1, Resources::new());
+
+        assert!(g
+            .production_for_roll(ROBBER_ROLL)
+            .iter()
+            .all(|(_, resources)| *resources == Resources::new()));
+
+        // previewing doesn't hand anything out
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new()
+        );
+    }
+
+    #[test]
+    fn test_expected_production() {
+        let g = production_game();
+
+        assert_eq!(
+            g.expected_production(&PlayerColour::Red).unwrap(),
+            Resources::new_explicit(5, 5, 5, 0, 0)
+        );
+        assert_eq!(
+            g.expected_production(&PlayerColour::Blue).unwrap(),
+            Resources::new_explicit(10, 0, 0, 0, 0)
+        );
+        assert!(g.expected_production(&PlayerColour::Green).is_err());
+    }
+
+    #[test]
+    fn test_roll_distributes_production() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(4, 4)]));
+        g.roll_dice().unwrap();
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(1, 1, 0, 0, 0));
+        let blue = g.get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(*blue.resources(), Resources::new_explicit(2, 0, 0, 0, 0));
+        assert_eq!(g.get_bank().remaining(ResourceKind::Ore), 16);
+        assert_eq!(
+            g.events(),
+            &[
+                GameEvent::DiceRolled {
+                    player: PlayerColour::Red,
+                    dice: (4, 4),
+                },
+                GameEvent::Production {
+                    player: PlayerColour::Red,
+                    resources: Resources::new_explicit(1, 1, 0, 0, 0),
+                },
+                GameEvent::Production {
+                    player: PlayerColour::Blue,
+                    resources: Resources::new_explicit(2, 0, 0, 0, 0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_production_bank_shortage() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(4, 4)]));
+        g.get_bank_mut()
+            .distribute_resource(ResourceKind::Ore, 17)
+            .unwrap();
+        g.roll_dice().unwrap();
+
+        // both players are owed ore but the bank can't cover it, so neither get any
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(0, 1, 0, 0, 0));
+        let blue = g.get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(*blue.resources(), Resources::new());
+        assert_eq!(g.get_bank().remaining(ResourceKind::Ore), 2);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(4, 4)]));
+        g.roll_dice().unwrap();
+        let seen = g.next_event();
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(3, 2, 0, 0, 0));
+        g.upgrade_to_city(PlayerColour::Red, VertexId::new(2))
+            .unwrap();
+
+        let events: Vec<_> = g.subscribe(seen).collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    seen,
+                    &GameEvent::CityBuilt {
+                        player: PlayerColour::Red
+                    }
+                ),
+                (
+                    seen + 1,
+                    &GameEvent::AchievementEarned {
+                        player: PlayerColour::Red,
+                        achievement: Achievement::FirstCity,
+                    }
+                ),
+                (
+                    seen + 2,
+                    &GameEvent::VictoryPointsChanged {
+                        player: PlayerColour::Red,
+                        points: 2,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(g.subscribe(0).count(), g.next_event());
+        assert_eq!(g.subscribe(g.next_event()).count(), 0);
+    }
+
+    #[test]
+    fn test_hand_limit() {
+        let mut g = production_game();
+        g.options.hand_limit = Some(2);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(0, 0, 0, 0, 1));
+        g.set_dice_roller(FixedDice::new([(4, 4)]));
+        g.roll_dice().unwrap();
+
+        // red only has room for the ore, so the grain stays in the bank
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(1, 0, 0, 0, 1));
+        let blue = g.get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(*blue.resources(), Resources::new_explicit(2, 0, 0, 0, 0));
+        assert_eq!(g.get_bank().remaining(ResourceKind::Grain), 19);
+    }
+
+    #[test]
+    fn test_discard_threshold_option() {
+        let g = Game::with_options(GameOptions {
+            discard_threshold: 9,
+            ..GameOptions::default()
+        });
+        assert_eq!(g.discards.threshold(), 9);
+    }
+
+    #[test]
+    fn test_robber_blocks_production() {
+        let mut g = production_game();
+        g.board.set_robber(0).unwrap();
+
+        let production = g.production_for_roll(8);
+        assert_eq!(production[0].1, Resources::new_explicit(0, 1, 0, 0, 0));
+        assert_eq!(production[1].1, Resources::new());
+        assert_eq!(
+            g.expected_production(&PlayerColour::Blue).unwrap(),
+            Resources::new()
+        );
+    }
+
+    #[test]
+    fn test_move_robber() {
+        let mut g = production_game();
+        g.set_phase(TurnPhase::MoveRobber);
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 0, 2, 0));
+
+        // blue has a city on tile 0, but nothing on tile 4
+        assert!(g
+            .move_robber(PlayerColour::Red, 4, Some(PlayerColour::Blue))
+            .is_err());
+        assert!(g
+            .move_robber(PlayerColour::Red, 0, Some(PlayerColour::Red))
+            .is_err());
+        assert!(g.move_robber(PlayerColour::Red, 19, None).is_err());
+        assert_eq!(g.board.robber(), Some(2));
+
+        let stolen = g
+            .move_robber(PlayerColour::Red, 0, Some(PlayerColour::Blue))
+            .unwrap();
+        assert_eq!(stolen, Some(ResourceKind::Brick));
+        assert_eq!(g.board.robber(), Some(0));
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new_explicit(0, 0, 0, 1, 0)
+        );
+        assert_eq!(
+            *g.get_player(&PlayerColour::Blue).unwrap().resources(),
+            Resources::new_explicit(0, 0, 0, 1, 0)
+        );
+
+        // the robber has to go somewhere else
+        g.set_phase(TurnPhase::MoveRobber);
+        assert!(g.move_robber(PlayerColour::Red, 0, None).is_err());
+    }
+
+    #[test]
+    fn test_move_robber_empty_hand() {
+        let mut g = production_game();
+        g.set_turn_no(1);
+        g.set_phase(TurnPhase::MoveRobber);
+
+        let stolen = g
+            .move_robber(PlayerColour::Blue, 1, Some(PlayerColour::Red))
+            .unwrap();
+        assert_eq!(stolen, None);
+        assert_eq!(g.board.robber(), Some(1));
+    }
+
+    #[test]
+    fn test_choose_stolen_resource() {
+        let mut g = production_game();
+        g.set_phase(TurnPhase::MoveRobber);
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 2, 1, 0));
+
+        // naming a resource is only allowed with the house rule
+        assert!(g
+            .move_robber_and_steal(
+                PlayerColour::Red,
+                0,
+                Some(PlayerColour::Blue),
+                Some(ResourceKind::Wool)
+            )
+            .is_err());
+        g.options.choose_stolen_resource = true;
+
+        // every resource is on offer, so the legal actions don't give away blue's hand
+        let robbing_blue: Vec<_> = g
+            .legal_actions(&PlayerColour::Red)
+            .into_iter()
+            .filter(|a| matches!(a, Action::MoveRobber { tile: 0, .. }))
+            .collect();
+        assert_eq!(robbing_blue.len(), ResourceKind::COUNT);
+        g.set_hand(PlayerColour::Blue, Resources::new());
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .iter()
+            .filter(|a| matches!(a, Action::MoveRobber { tile: 0, .. }))
+            .eq(robbing_blue.iter()));
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 2, 1, 0));
+
+        let mut missing = g.clone();
+        let stolen = missing
+            .move_robber_and_steal(
+                PlayerColour::Red,
+                0,
+                Some(PlayerColour::Blue),
+                Some(ResourceKind::Ore),
+            )
+            .unwrap();
+        assert_eq!(stolen, None);
+
+        let action = Action::MoveRobber {
+            tile: 0,
+            victim: Some(PlayerColour::Blue),
+            resource: Some(ResourceKind::Wool),
+        };
+        g.apply(PlayerColour::Red, action).unwrap();
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new_explicit(0, 0, 1, 0, 0)
+        );
+        assert_eq!(g.phase(), TurnPhase::Main);
+    }
+
+    /// A game where red has 9 cards, blue has 8 and green has 7, just after rolling a 7
+    fn discard_game() -> Game {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(5, 4, 0, 0, 0));
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 8, 0, 0));
+        g.set_hand(PlayerColour::Green, Resources::new_explicit(1, 1, 1, 1, 3));
+        g.set_dice_roller(FixedDice::new([(3, 4)]));
+        g.roll_dice().unwrap();
+        g
+    }
+
+    #[test]
+    fn test_players_awaiting_discard() {
+        let mut g = discard_game();
+        assert_eq!(
+            g.players_awaiting_discard(),
+            &[(PlayerColour::Red, 4), (PlayerColour::Blue, 4)]
+        );
+
+        // nothing else can happen until everyone has discarded
+        assert!(g.roll_dice().is_err());
+        assert!(g.move_robber(PlayerColour::Red, 1, None).is_err());
+
+        // wrong number of cards, or cards the player doesn't have
+        assert!(g
+            .discard(PlayerColour::Red, Resources::new_explicit(3, 0, 0, 0, 0))
+            .is_err());
+        assert!(g
+            .discard(PlayerColour::Red, Resources::new_explicit(0, 0, 4, 0, 0))
+            .is_err());
+        assert!(g
+            .discard(PlayerColour::Green, Resources::new_explicit(1, 1, 1, 0, 0))
+            .is_err());
+
+        g.discard(PlayerColour::Red, Resources::new_explicit(2, 2, 0, 0, 0))
+            .unwrap();
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new_explicit(3, 2, 0, 0, 0)
+        );
+        assert_eq!(g.get_bank().remaining(ResourceKind::Ore), 15);
+        assert_eq!(
+            g.events()[g.events().len() - 2..],
+            [
+                GameEvent::Discarded {
+                    player: PlayerColour::Red,
+                    cards: Resources::new_explicit(2, 2, 0, 0, 0)
+                },
+                GameEvent::HandSize {
+                    player: PlayerColour::Red,
+                    cards: 5
+                }
+            ]
+        );
+        assert!(g.roll_dice().is_err());
+
+        g.discard(PlayerColour::Blue, Resources::new_explicit(0, 0, 4, 0, 0))
+            .unwrap();
+        assert!(g.players_awaiting_discard().is_empty());
+        assert!(g.move_robber(PlayerColour::Red, 1, None).is_ok());
+    }
+
+    #[test]
+    fn test_remove_player_during_discard() {
+        let mut g = discard_game();
+        g.discard(PlayerColour::Red, Resources::new_explicit(2, 2, 0, 0, 0))
+            .unwrap();
+        g.remove_player(PlayerColour::Blue).unwrap();
+        assert!(g.players_awaiting_discard().is_empty());
+        assert_eq!(g.phase(), TurnPhase::MoveRobber);
+        assert_eq!(g.awaiting_player(), Some(PlayerColour::Red));
+        assert!(!g.legal_actions(&PlayerColour::Red).is_empty());
+        g.move_robber(PlayerColour::Red, 1, None).unwrap();
+
+        // kicking the player who rolled the 7 lets everyone off their discards
+        let mut g = discard_game();
+        g.remove_player(PlayerColour::Red).unwrap();
+        assert!(g.players_awaiting_discard().is_empty());
+        assert_eq!(g.phase(), TurnPhase::Roll);
+        assert_eq!(g.awaiting_player(), Some(PlayerColour::Blue));
+        g.roll_dice().unwrap();
+    }
+
+    #[test]
+    fn test_remove_player_with_trades() {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(1, 0, 0, 0, 0));
+        let offer = Resources::new_explicit(1, 0, 0, 0, 0);
+        let wants = Resources::new_explicit(0, 1, 0, 0, 0);
+        let trade_id = g.propose_trade(PlayerColour::Red, offer, wants).unwrap();
+        g.clocks.push((PlayerColour::Red, Duration::from_secs(60)));
+
+        g.remove_player(PlayerColour::Red).unwrap();
+        assert!(g.get_trades().get_trade(trade_id).is_none());
+        assert!(g.trade_deadlines.is_empty());
+        assert!(g.clocks.is_empty());
+    }
+
+    #[test]
+    fn test_auto_discard() {
+        let mut g = discard_game();
+        g.discard(PlayerColour::Blue, Resources::new_explicit(0, 0, 4, 0, 0))
+            .unwrap();
+
+        let deadline = g.discards.deadline().unwrap();
+        assert!(g
+            .auto_discard_expired(deadline - Duration::from_secs(1))
+            .unwrap()
+            .is_empty());
+
+        let discarded = g.auto_discard_expired(deadline).unwrap();
+        assert_eq!(discarded.len(), 1);
+        assert_eq!(discarded[0].0, PlayerColour::Red);
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        let remaining: usize = red.resources().into_iter().map(|(_, n)| n).sum();
+        assert_eq!(remaining, 5);
+        assert!(g.players_awaiting_discard().is_empty());
+        assert!(g.events().contains(&GameEvent::TimedOut {
+            player: PlayerColour::Red,
+            decision: Decision::Discard
+        }));
+    }
+
+    #[test]
+    fn test_resolve_timeouts() {
+        let mut g = discard_game();
+        let deadline = g.discards.deadline().unwrap();
+        g.resolve_timeouts(deadline).unwrap();
+        assert!(g.players_awaiting_discard().is_empty());
+        assert_eq!(g.phase, TurnPhase::MoveRobber);
+
+        let robber = g.get_board().robber();
+        let deadline = g.robber_deadline.unwrap();
+        g.resolve_timeouts(deadline - Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(g.phase, TurnPhase::MoveRobber);
+
+        g.resolve_timeouts(deadline).unwrap();
+        assert_eq!(g.phase, TurnPhase::Main);
+        assert_ne!(g.get_board().robber(), robber);
+        assert_eq!(
+            g.events()[g.events().len() - 2..],
+            [
+                GameEvent::TimedOut {
+                    player: PlayerColour::Red,
+                    decision: Decision::MoveRobber
+                },
+                GameEvent::RobberMoved {
+                    player: PlayerColour::Red,
+                    tile: g.get_board().robber().unwrap(),
+                    victim: None,
+                    stole_card: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trade_actions() {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(1, 0, 0, 0, 0));
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 1, 0, 0, 0));
+        let offering = Resources::new_explicit(1, 0, 0, 0, 0);
+        let wants = Resources::new_explicit(0, 1, 0, 0, 0);
+
+        g.apply(PlayerColour::Red, Action::ProposeTrade { offering, wants })
+            .unwrap();
+        let trade = *g.get_trades().iter().next().unwrap().0;
+        assert!(g
+            .legal_actions(&PlayerColour::Blue)
+            .contains(&Action::AcceptTrade { trade }));
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::WithdrawTrade { trade }));
+        assert!(g
+            .apply(PlayerColour::Red, Action::AcceptTrade { trade })
+            .is_err());
+
+        for player in [PlayerColour::Blue, PlayerColour::Green] {
+            g.apply(player, Action::AcceptTrade { trade }).unwrap();
+            assert!(g.legal_actions(&player).is_empty());
+        }
+        // green accepted, but doesn't have the grain red wants
+        let legal = g.legal_actions(&PlayerColour::Red);
+        let with_blue = Action::FinalizeTrade {
+            trade,
+            partner: PlayerColour::Blue,
+        };
+        let with_green = Action::FinalizeTrade {
+            trade,
+            partner: PlayerColour::Green,
+        };
+        assert!(legal.contains(&with_blue));
+        assert!(!legal.contains(&with_green));
+        assert!(g.apply(PlayerColour::Red, with_green).is_err());
+        assert!(g.apply(PlayerColour::Blue, with_blue.clone()).is_err());
+
+        g.apply(PlayerColour::Red, with_blue).unwrap();
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            wants
+        );
+        assert_eq!(
+            *g.get_player(&PlayerColour::Blue).unwrap().resources(),
+            offering
+        );
+        assert!(g
+            .apply(PlayerColour::Red, Action::WithdrawTrade { trade })
+            .is_err());
+
+        g.apply(
+            PlayerColour::Red,
+            Action::ProposeTrade {
+                offering: wants,
+                wants: offering,
+            },
+        )
+        .unwrap();
+        let (&withdrawn, _) = g.get_trades().iter().find(|(id, _)| **id != trade).unwrap();
+        assert!(g
+            .apply(
+                PlayerColour::Blue,
+                Action::WithdrawTrade { trade: withdrawn }
+            )
+            .is_err());
+        g.apply(
+            PlayerColour::Red,
+            Action::WithdrawTrade { trade: withdrawn },
+        )
+        .unwrap();
+        assert!(g.get_trades().get_trade(withdrawn).is_none());
+        assert!(g.trade_deadlines.is_empty());
+        assert_eq!(
+            g.events().last(),
+            Some(&GameEvent::TradeWithdrawn { trade: withdrawn })
+        );
+    }
+
+    #[test]
+    fn test_trade_timeout() {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(1, 0, 0, 0, 0));
+
+        let offer = Resources::new_explicit(1, 0, 0, 0, 0);
+        let wants = Resources::new_explicit(0, 1, 0, 0, 0);
+        assert!(g
+            .propose_trade(PlayerColour::Red, offer * 2, wants)
+            .is_err());
+        let ignored = g.propose_trade(PlayerColour::Red, offer, wants).unwrap();
+        let accepted = g.propose_trade(PlayerColour::Red, offer, wants).unwrap();
+        g.trades.accept_trade(accepted, PlayerColour::Blue).unwrap();
+
+        let (_, deadline) = g.trade_deadlines[1];
+        g.resolve_timeouts(deadline).unwrap();
+        assert!(g.get_trades().get_trade(ignored).is_none());
+        assert!(g.get_trades().get_trade(accepted).is_some());
+        assert!(g.trade_deadlines.is_empty());
+
+        // green never answered either offer, blue only ignored the first
+        let timed_out: Vec<_> = g
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::TimedOut {
+                    player,
+                    decision: Decision::TradeResponse,
+                } => Some(*player),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(timed_out.len(), 3);
+        assert_eq!(
+            timed_out
+                .iter()
+                .filter(|p| **p == PlayerColour::Blue)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_per_move_clock() {
+        let mut g = production_game();
+        g.options.clock = TurnClock::PerMove(Duration::from_secs(30));
+        start_turn(&mut g, TurnPhase::Main);
+        let start = now();
+        let secs = Duration::from_secs;
+        assert_eq!(g.time_remaining(&PlayerColour::Red, start), Some(secs(30)));
+
+        g.resolve_timeouts(start).unwrap();
+        assert_eq!(
+            g.time_remaining(&PlayerColour::Red, start + secs(10)),
+            Some(secs(20))
+        );
+        assert_eq!(
+            g.time_remaining(&PlayerColour::Blue, start + secs(10)),
+            Some(secs(30))
+        );
+
+        // red runs out of time, so their turn is ended for them and blue's clock starts
+        g.resolve_timeouts(start + secs(30)).unwrap();
+        assert!(g.events().contains(&GameEvent::TimedOut {
+            player: PlayerColour::Red,
+            decision: Decision::Move,
+        }));
+        assert_eq!(*g.current_player().unwrap().colour(), PlayerColour::Blue);
+        assert_eq!(
+            g.time_remaining(&PlayerColour::Blue, start + secs(40)),
+            Some(secs(20))
+        );
+    }
+
+    #[test]
+    fn test_chess_clock() {
+        let mut g = production_game();
+        g.options.clock = TurnClock::ChessClock {
+            initial: Duration::from_secs(60),
+            increment: Duration::from_secs(5),
+        };
+        g.options.clock_expiry = ClockExpiry::ReplaceWithBot;
+        start_turn(&mut g, TurnPhase::Main);
+        let start = now();
+        let secs = Duration::from_secs;
+        g.resolve_timeouts(start).unwrap();
+
+        // the 20 seconds red took come off their clock, and moving puts 5 back
+        g.apply_at(PlayerColour::Red, Action::EndTurn, start + secs(20))
+            .unwrap();
+        assert_eq!(
+            g.time_remaining(&PlayerColour::Red, start + secs(100)),
+            Some(secs(45))
+        );
+        assert_eq!(
+            g.time_remaining(&PlayerColour::Blue, start + secs(30)),
+            Some(secs(50))
+        );
+
+        // blue runs out of time, so a bot takes their seat
+        g.resolve_timeouts(start + secs(79)).unwrap();
+        assert!(!g.get_player(&PlayerColour::Blue).unwrap().is_bot());
+        g.resolve_timeouts(start + secs(80)).unwrap();
+        assert!(g.get_player(&PlayerColour::Blue).unwrap().is_bot());
+        assert_eq!(
+            g.events().last(),
+            Some(&GameEvent::TimedOut {
+                player: PlayerColour::Blue,
+                decision: Decision::Move,
+            })
+        );
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut g = discard_game();
+        g.resign(PlayerColour::Blue).unwrap();
+        assert!(g.resign(PlayerColour::Blue).is_err());
+        assert!(g.view_for(&PlayerColour::Red).unwrap().opponents[0].resigned);
+
+        // blue's discard is made for them once the game gets to them
+        let cards = Resources::new_explicit(4, 0, 0, 0, 0);
+        g.apply(PlayerColour::Red, Action::Discard { cards })
+            .unwrap();
+        assert!(g.players_awaiting_discard().is_empty());
+        let blue = g.get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(blue.resources().total(), 4);
+
+        // and their turns are passed over, with their pieces left on the board
+        start_turn(&mut g, TurnPhase::Main);
+        g.apply(PlayerColour::Red, Action::EndTurn).unwrap();
+        assert_eq!(*g.current_player().unwrap().colour(), PlayerColour::Green);
+        assert_eq!(g.phase(), TurnPhase::Roll);
+        let city = g.board.vertex(VertexId::new(0)).unwrap().building();
+        assert_eq!(city.map(|(owner, _)| *owner), Some(PlayerColour::Blue));
+
+        // which leaves red as the last one standing once green gives up too
+        g.resign(PlayerColour::Green).unwrap();
+        assert_eq!(g.state(), GameState::Complete);
+        assert_eq!(g.winner(), Some(PlayerColour::Red));
+        assert!(g.events().contains(&GameEvent::GameWon {
+            player: PlayerColour::Red
+        }));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_replace_with_bot() {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        g.options.resignation = Resignation::Bot;
+        start_turn(&mut g, TurnPhase::Main);
+
+        g.resign(PlayerColour::Blue).unwrap();
+        assert!(g.get_player(&PlayerColour::Blue).unwrap().is_bot());
+        g.replace_with_bot(PlayerColour::Green, RandomAgent::default())
+            .unwrap();
+        assert!(g.get_player(&PlayerColour::Green).unwrap().is_bot());
+
+        // the bots play their turns until it comes back round to red
+        g.apply(PlayerColour::Red, Action::EndTurn).unwrap();
+        let moves = g.play_bots(&mut StdRng::seed_from_u64(7), 1000);
+        assert!(moves
+            .iter()
+            .any(|(player, _)| *player == PlayerColour::Blue));
+        assert!(moves
+            .iter()
+            .any(|(player, _)| *player == PlayerColour::Green));
+        assert!(moves.iter().all(|(player, _)| *player != PlayerColour::Red));
+        assert_eq!(g.awaiting_player(), Some(PlayerColour::Red));
+    }
+
+    #[test]
+    fn test_end() {
+        let mut g = production_game();
+        g.pause().unwrap();
+        assert!(g.end(Some(PlayerColour::Green)).is_err());
+
+        g.end(Some(PlayerColour::Blue)).unwrap();
+        assert_eq!(g.state(), GameState::Complete);
+        assert_eq!(g.winner(), Some(PlayerColour::Blue));
+        assert_eq!(g.awaiting_player(), None);
+        assert_eq!(
+            g.events().last(),
+            Some(&GameEvent::GameEnded {
+                winner: Some(PlayerColour::Blue)
+            })
+        );
+        assert!(g.end(None).is_err());
+    }
+
+    #[test]
+    fn test_rematch() {
+        let mut g = production_game();
+        g.options.discard_threshold = 9;
+        g.get_player_mut(PlayerColour::Blue).unwrap().set_bot(true);
+        g.resign(PlayerColour::Blue).unwrap();
+        assert_eq!(g.state(), GameState::Complete);
+
+        let rematch = g.rematch(true);
+        let id = g.get_game_id().unwrap();
+        assert_eq!(rematch.rematch_of(), Some(id));
+        assert_ne!(rematch.get_game_id().unwrap(), id);
+        assert_eq!(rematch.state(), GameState::Setup);
+        assert_eq!(rematch.options(), g.options());
+        assert_eq!(rematch.host(), Some(PlayerColour::Red));
+        let colours: Vec<_> = rematch.players().iter().map(|p| *p.colour()).collect();
+        assert_eq!(colours, [PlayerColour::Red, PlayerColour::Blue]);
+        assert!(rematch.players()[1].is_bot());
+        assert!(!rematch.is_resigned(&PlayerColour::Blue));
+        assert_eq!(rematch.board, g.board.cleared());
+
+        // a new board gets a new seed, if the old one had one
+        g.options.board_seed = Some(1);
+        let rematch = g.rematch(false);
+        assert!(rematch.options().board_seed.is_some());
+        assert_ne!(rematch.board, g.board.cleared());
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut g = discard_game();
+        assert!(g.resume().is_err());
+        g.pause().unwrap();
+        assert_eq!(g.state(), GameState::Paused);
+        assert!(g.pause().is_err());
+
+        assert!(matches!(
+            g.apply(
+                PlayerColour::Blue,
+                Action::Discard {
+                    cards: Resources::new_explicit(0, 0, 4, 0, 0)
+                }
+            ),
+            Err(ActionError::NotAllowedNow(_))
+        ));
+        let deadline = g.discards.deadline().unwrap();
+        assert!(g.auto_discard_expired(deadline).unwrap().is_empty());
+
+        // pretend the game was paused for a minute
+        let (state, paused_at) = g.paused.unwrap();
+        g.paused = Some((state, paused_at - Duration::from_secs(60)));
+        g.resume().unwrap();
+        assert_eq!(g.state(), GameState::Running);
+        assert!(g.discards.deadline().unwrap() >= deadline + Duration::from_secs(60));
+
+        g.discard(PlayerColour::Blue, Resources::new_explicit(0, 0, 4, 0, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_host() {
+        let mut g = production_game();
+        assert_eq!(g.host(), Some(PlayerColour::Red));
+        assert!(g
+            .transfer_host(PlayerColour::Blue, PlayerColour::Blue)
+            .is_err());
+        assert!(g.kick(PlayerColour::Blue, PlayerColour::Red).is_err());
+
+        g.transfer_host(PlayerColour::Red, PlayerColour::Blue)
+            .unwrap();
+        assert_eq!(g.host(), Some(PlayerColour::Blue));
+        assert!(g.kick(PlayerColour::Blue, PlayerColour::Blue).is_err());
+    }
+
+    #[test]
+    fn test_vote_kick() {
+        let mut g = discard_game();
+        g.add_player(PlayerColour::Purple);
+        *g.get_player_mut(PlayerColour::Blue)
+            .unwrap()
+            .development_cards_mut() = vec![DevelopmentCard::Knight];
+        g.discard(PlayerColour::Red, Resources::new_explicit(2, 2, 0, 0, 0))
+            .unwrap();
+        g.discard(PlayerColour::Blue, Resources::new_explicit(0, 0, 4, 0, 0))
+            .unwrap();
+        g.move_robber(PlayerColour::Red, 1, None).unwrap();
+        g.end_turn(PlayerColour::Red).unwrap();
+        let turn = g.turn_no;
+        let wool = g.get_bank().remaining(ResourceKind::Wool);
+
+        // blue's turn, and three other players have to agree to kick them
+        assert!(g.vote_kick(PlayerColour::Blue, PlayerColour::Blue).is_err());
+        assert!(!g.vote_kick(PlayerColour::Red, PlayerColour::Blue).unwrap());
+        assert!(!g.vote_kick(PlayerColour::Red, PlayerColour::Blue).unwrap());
+        assert!(g
+            .vote_kick(PlayerColour::Green, PlayerColour::Blue)
+            .unwrap());
+
+        assert!(g.get_player(&PlayerColour::Blue).is_err());
+        assert!(g.vp_breakdown(&PlayerColour::Blue).is_err());
+        assert!(!g
+            .board
+            .vertices()
+            .iter()
+            .any(|v| matches!(v.building(), Some((PlayerColour::Blue, _)))));
+        assert_eq!(g.get_bank().remaining(ResourceKind::Wool), wool + 4);
+
+        // the turn passes to green, who sat after blue
+        assert!(g.turn_no >= turn);
+        assert_eq!(*g.current_player().unwrap().colour(), PlayerColour::Green);
+        assert_eq!(g.phase(), TurnPhase::Roll);
+    }
+
+    #[test]
+    fn test_vote_kick_during_setup() {
+        let mut g = Game::new();
+        g.board = production_board();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.place_initial_settlement(PlayerColour::Red, VertexId::new(0))
+            .unwrap();
+
+        assert!(g.vote_kick(PlayerColour::Red, PlayerColour::Blue).unwrap());
+        assert!(g.get_player(&PlayerColour::Blue).unwrap().is_bot());
+    }
+
+    #[test]
+    fn test_randomize_turn_order() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Green);
+        g.add_player(PlayerColour::Blue);
+
+        g.randomize_turn_order(&mut StdRng::seed_from_u64(7))
+            .unwrap();
+        let order: Vec<_> = g.players.iter().map(|p| *p.colour()).collect();
+        assert_eq!(order.len(), 3);
+        for colour in [PlayerColour::Red, PlayerColour::Green, PlayerColour::Blue] {
+            assert!(order.contains(&colour));
         }
+        assert_eq!(g.events(), &[GameEvent::TurnOrder { players: order }]);
     }
 
-    pub fn get_game_id(&self) -> Result<Uuid> {
-        match self.state {
-            GameState::Setup => Ok(Uuid::new_v4()),
-            GameState::Running => Err(anyhow!("Cannot get Uuid for a game currently in progress")),
-            GameState::Complete => Err(anyhow!("Cannot get Uuid for a finished game")),
-        }
+    #[test]
+    fn test_dice_off() {
+        let mut g = Game::new();
+        g.board = production_board();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Green);
+        g.add_player(PlayerColour::Blue);
+
+        // red and blue tie on 12, then red wins the re-roll
+        g.set_dice_roller(FixedDice::new([(6, 6), (1, 1), (6, 6), (2, 2), (1, 2)]));
+        g.dice_off().unwrap();
+
+        let order = vec![PlayerColour::Red, PlayerColour::Blue, PlayerColour::Green];
+        assert_eq!(
+            g.players.iter().map(|p| *p.colour()).collect::<Vec<_>>(),
+            order
+        );
+        assert_eq!(g.events().len(), 6);
+        assert_eq!(
+            g.events()[3],
+            GameEvent::DiceOffRoll {
+                player: PlayerColour::Red,
+                dice: (2, 2)
+            }
+        );
+        assert_eq!(g.events()[5], GameEvent::TurnOrder { players: order });
+
+        g.place_initial_settlement(PlayerColour::Red, VertexId::new(0))
+            .unwrap();
+        assert!(g.dice_off().is_err());
     }
 
-    pub fn add_player(&mut self, colour: PlayerColour) {
-        self.players.push(Player::new(colour));
+    #[test]
+    fn test_fish_haul() {
+        let mut g = production_game();
+        g.options.fishermen = true;
+        g.board
+            .set_building(
+                VertexId::new(4),
+                Some((PlayerColour::Red, Building::Settlement)),
+            )
+            .unwrap();
+        g.set_dice_roller(FixedDice::new([(1, 1)]));
+
+        g.roll_dice().unwrap();
+        let fish = g.get_player(&PlayerColour::Red).unwrap().fish();
+        assert!((1..=3).contains(&fish));
+        assert!(g.events().contains(&GameEvent::FishHaul {
+            player: PlayerColour::Red,
+            fish
+        }));
+        assert_eq!(g.get_player(&PlayerColour::Blue).unwrap().fish(), 0);
     }
 
-    pub fn roll_dice() -> (u8, u8) {
-        let mut rng = thread_rng();
-        (rng.gen_range(1..6), rng.gen_range(1..6))
+    #[test]
+    fn test_spend_fish() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        assert!(matches!(
+            g.apply(
+                PlayerColour::Red,
+                Action::SpendFish {
+                    purchase: FishPurchase::RemoveRobber
+                }
+            ),
+            Err(ActionError::NotAllowedNow(_))
+        ));
+
+        g.options.fishermen = true;
+        *g.get_player_mut(PlayerColour::Red).unwrap().fish_mut() = 9;
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::SpendFish {
+                purchase: FishPurchase::RemoveRobber
+            }));
+
+        g.spend_fish(PlayerColour::Red, FishPurchase::RemoveRobber)
+            .unwrap();
+        assert_eq!(g.board.robber(), None);
+        assert!(g
+            .spend_fish(PlayerColour::Red, FishPurchase::RemoveRobber)
+            .is_err());
+
+        // blue has nothing to steal
+        let steal = FishPurchase::StealResource {
+            victim: PlayerColour::Blue,
+        };
+        assert!(g.spend_fish(PlayerColour::Red, steal).is_err());
+
+        let edge = g
+            .board
+            .edge_between(VertexId::new(2), VertexId::new(3))
+            .unwrap();
+        g.spend_fish(PlayerColour::Red, FishPurchase::FreeRoad { edge })
+            .unwrap();
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.fish(), 2);
+        assert_eq!(red.stock().remaining(Building::Road), 14);
+        assert_eq!(g.board.edge(edge).unwrap().road(), Some(&PlayerColour::Red));
+
+        // only two fish left, and the robber's already gone
+        assert!(!g
+            .legal_actions(&PlayerColour::Red)
+            .iter()
+            .any(|a| matches!(a, Action::SpendFish { .. })));
     }
 
-    pub fn get_player(&self, colour: &PlayerColour) -> Result<&Player> {
-        self.players
+    #[test]
+    fn test_initial_settlements() {
+        let mut g = Game::new();
+        g.board = production_board();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+
+        // not blue's turn yet
+        assert!(g
+            .place_initial_settlement(PlayerColour::Blue, VertexId::new(0))
+            .is_err());
+        g.place_initial_settlement(PlayerColour::Red, VertexId::new(0))
+            .unwrap();
+
+        // too close to red's settlement
+        assert!(g
+            .place_initial_settlement(PlayerColour::Blue, VertexId::new(1))
+            .is_err());
+        g.place_initial_settlement(PlayerColour::Blue, VertexId::new(4))
+            .unwrap();
+
+        // the first settlements don't produce anything
+        assert!(!g
+            .events()
             .iter()
-            .find(|player| player.colour() == colour)
-            .ok_or(anyhow!("Could not find that player"))
+            .any(|e| matches!(e, GameEvent::Production { .. })));
+
+        // snake order, so blue goes again, then red
+        assert!(g
+            .place_initial_settlement(PlayerColour::Red, VertexId::new(2))
+            .is_err());
+        g.place_initial_settlement(PlayerColour::Blue, VertexId::new(10))
+            .unwrap();
+        g.place_initial_settlement(PlayerColour::Red, VertexId::new(2))
+            .unwrap();
+
+        // vertex 2 touches the ore, grain and wool tiles
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(1, 1, 1, 0, 0));
+        assert_eq!(red.victory_points(), 2);
+        assert_eq!(red.stock().remaining(Building::Settlement), 3);
+        assert_eq!(g.get_bank().remaining(ResourceKind::Grain), 18);
+        assert_eq!(
+            g.events().last(),
+            Some(&GameEvent::Production {
+                player: PlayerColour::Red,
+                resources: Resources::new_explicit(1, 1, 1, 0, 0),
+            })
+        );
+
+        assert_eq!(g.state, GameState::Running);
+        assert!(g
+            .place_initial_settlement(PlayerColour::Red, VertexId::new(40))
+            .is_err());
     }
 
-    pub fn get_player_mut(&mut self, colour: PlayerColour) -> Result<&mut Player> {
-        self.players
-            .iter_mut()
-            .find(|player| *player.colour() == colour)
-            .ok_or(anyhow!("Could not find that player"))
+    #[test]
+    fn test_buy_development_card() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(1, 1, 1, 0, 0));
+
+        assert!(g.buy_development_card(PlayerColour::Blue).is_err());
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::BuyDevelopmentCard));
+
+        g.buy_development_card(PlayerColour::Red).unwrap();
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.development_cards().len(), 1);
+        assert_eq!(*red.resources(), Resources::new());
+
+        // can't afford another one
+        assert!(g.buy_development_card(PlayerColour::Red).is_err());
+        assert!(!g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::BuyDevelopmentCard));
     }
 
-    /// Handle the final step of trading, moving the resources between the two players
-    pub fn finalize_trade(&mut self, trade_id: Uuid) -> Result<()> {
-        let mut trade = match self.bank.get_trade_mut(trade_id) {
-            Some(trade) => trade.clone(),
-            None => return Err(anyhow!("Could not find trade with that ID")),
-        };
+    #[test]
+    fn test_buy_development_card_deck_empty() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(1, 1, 1, 0, 0));
+        while !g.get_bank().is_deck_exhausted() {
+            g.get_bank_mut()
+                .distribute_random_development_card()
+                .unwrap();
+        }
 
-        match trade.state() {
-            LockedIn => (),
-            Accepted | Proposed => return Err(anyhow!("Cannot finalize trade at this time")),
-        };
+        assert!(!g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::BuyDevelopmentCard));
+        let err = g.buy_development_card(PlayerColour::Red).unwrap_err();
+        assert_eq!(err.downcast_ref::<BankError>(), Some(&BankError::DeckEmpty));
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new_explicit(1, 1, 1, 0, 0)
+        );
+    }
 
-        *trade.state_mut() = Accepted;
+    #[test]
+    fn test_turn_phases() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
 
-        let offering: Resources = *trade.offering();
-        let wants: Resources = *trade.wants();
-        let offering_player = trade.get_offering_player();
-        let trade_partner = trade.get_trade_partner()?;
+        assert_eq!(g.legal_actions(&PlayerColour::Red), vec![Action::RollDice]);
+        assert!(g.legal_actions(&PlayerColour::Blue).is_empty());
+        assert!(g.apply(PlayerColour::Blue, Action::RollDice).is_err());
+        assert!(g.end_turn(PlayerColour::Red).is_err());
 
-        {
-            let from = self.get_player_mut(offering_player)?;
-            if *from.resources() < offering {
-                return Err(anyhow!("Not enough resources to make this trade"));
-            } else {
-                *from.resources_mut() += wants;
-                *from.resources_mut() -= offering;
+        g.apply(PlayerColour::Red, Action::RollDice).unwrap();
+        assert_eq!(g.phase(), TurnPhase::Main);
+        assert!(g.roll_dice().is_err());
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::EndTurn));
+
+        g.apply(PlayerColour::Red, Action::EndTurn).unwrap();
+        assert_eq!(g.phase(), TurnPhase::Roll);
+        assert_eq!(*g.current_player().unwrap().colour(), PlayerColour::Blue);
+    }
+
+    #[test]
+    fn test_zobrist() {
+        let mut g = production_game();
+        let start = g.zobrist();
+        assert_eq!(g.clone().zobrist(), start);
+
+        g.roll_dice().unwrap();
+        let rolled = g.zobrist();
+        assert_ne!(rolled, start);
+
+        // the same cards reached a different way hash the same
+        let mut other = g.clone();
+        *other
+            .get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .resources_mut() += Resources::new_explicit(1, 0, 0, 0, 0);
+        *g.get_player_mut(PlayerColour::Red).unwrap().resources_mut() +=
+            Resources::new_explicit(2, 0, 0, 0, 0);
+        *g.get_player_mut(PlayerColour::Red).unwrap().resources_mut() -=
+            Resources::new_explicit(1, 0, 0, 0, 0);
+        assert_ne!(other.zobrist(), rolled);
+        assert_eq!(other.zobrist(), g.zobrist());
+
+        // as do cards still to be discarded, or a development card already played this turn
+        let mut owing = g.clone();
+        owing.update_discards(|discards| discards.restore(vec![(PlayerColour::Red, 4)], now()));
+        assert_ne!(owing.zobrist(), g.zobrist());
+        let mut played = g.clone();
+        played.set_development_card_played(true);
+        assert_ne!(played.zobrist(), g.zobrist());
+        played.record_bought(DevelopmentCard::Knight);
+        assert_ne!(played.zobrist(), g.zobrist());
+    }
+
+    #[test]
+    fn test_state_hash_free_roads() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::PlacingFreeRoads);
+        g.set_free_roads(2);
+        let mut other = g.clone();
+        other.set_free_roads(1);
+        assert_eq!(g.state_hash(), g.zobrist());
+        assert_ne!(other.state_hash(), g.state_hash());
+
+        other.set_free_roads(2);
+        assert_eq!(other.state_hash(), g.state_hash());
+    }
+
+    #[test]
+    fn test_zobrist_incremental() {
+        use rand::seq::SliceRandom;
+
+        let mut g = Game::new();
+        for colour in [PlayerColour::Red, PlayerColour::Blue, PlayerColour::Green] {
+            g.add_player(colour);
+        }
+        g.set_dice_roller(crate::dice::FairDice::seeded(5));
+        g.seed_rng(5);
+        let full_hash = |g: &Game| g.board.full_hash() ^ g.players.full_hash() ^ g.turn_hash();
+        g.dice_off().unwrap();
+        assert_eq!(g.zobrist(), full_hash(&g), "after the dice-off");
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut moves = 0;
+        while let Some(player) = g.awaiting_player() {
+            // discards aren't listed among the legal actions, so fall back on the timeout's
+            let action = match g.legal_actions(&player).choose(&mut rng) {
+                Some(action) => action.clone(),
+                None => g.default_move(player).unwrap(),
+            };
+            g.apply(player, action).unwrap();
+            assert_eq!(g.zobrist(), full_hash(&g), "after move {}", moves);
+            moves += 1;
+            if moves == 2000 {
+                break;
             }
         }
+        assert!(moves > 100);
+    }
 
-        {
-            let to = self.get_player_mut(trade_partner)?;
-            if *to.resources() < wants {
-                return Err(anyhow!("Not enough resources to make this trade"));
-            } else {
-                *to.resources_mut() += offering;
-                *to.resources_mut() -= wants;
+    #[test]
+    fn test_hash() {
+        use std::collections::HashSet;
+
+        let g = production_game();
+        let mut rolled = g.clone();
+        rolled.roll_dice().unwrap();
+
+        let mut seen = HashSet::new();
+        assert!(seen.insert(g.clone()));
+        assert!(!seen.insert(g.clone()));
+        assert!(seen.insert(rolled));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_compact_round_trip() {
+        let mut g = discard_game();
+        g.board
+            .set_road(EdgeId::new(3), Some(PlayerColour::Blue))
+            .unwrap();
+        g.get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .development_cards_mut()
+            .push(DevelopmentCard::Monopoly);
+
+        let bytes = g.encode_compact();
+        let decoded = Game::decode_compact(&bytes).unwrap();
+        assert_eq!(decoded.encode_compact(), bytes);
+        assert_eq!(decoded.zobrist(), g.zobrist());
+        assert_eq!(
+            decoded.players_awaiting_discard(),
+            g.players_awaiting_discard()
+        );
+        assert_eq!(decoded.phase(), TurnPhase::Discard);
+        assert!(bytes.len() * 10 < serde_json::to_vec(&g).unwrap().len());
+    }
+
+    #[test]
+    fn test_compact_errors() {
+        let bytes = production_game().encode_compact();
+        assert!(Game::decode_compact(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Game::decode_compact(&[bytes.as_slice(), &[0]].concat()).is_err());
+
+        let mut newer = bytes.clone();
+        newer[0] += 1;
+        assert!(Game::decode_compact(&newer).is_err());
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+
+        let not_allowed = |result| matches!(result, Err(ActionError::NotAllowedNow(_)));
+        let illegal = |result| matches!(result, Err(ActionError::Illegal(_)));
+
+        assert!(not_allowed(g.apply(PlayerColour::Blue, Action::RollDice)));
+        assert!(not_allowed(g.apply(PlayerColour::Red, Action::EndTurn)));
+        assert!(not_allowed(g.apply(
+            PlayerColour::Red,
+            Action::PlaceInitialSettlement {
+                vertex: VertexId::new(20)
             }
+        )));
+
+        g.apply(PlayerColour::Red, Action::RollDice).unwrap();
+        assert!(illegal(g.apply(
+            PlayerColour::Red,
+            Action::UpgradeToCity {
+                vertex: VertexId::new(0)
+            }
+        )));
+        let err = g
+            .apply(PlayerColour::Red, Action::BuyDevelopmentCard)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ActionError::Illegal("Not enough resources to buy a development card".into())
+        );
+        assert!(not_allowed(g.apply(
+            PlayerColour::Red,
+            Action::MoveRobber {
+                tile: 1,
+                victim: None,
+                resource: None,
+            }
+        )));
+    }
+
+    #[test]
+    fn test_play_knight_before_roll() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        g.get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .development_cards_mut()
+            .push(DevelopmentCard::Knight);
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 0, 0, 1));
+
+        let actions = g.legal_actions(&PlayerColour::Red);
+        assert!(actions.contains(&Action::RollDice));
+        assert!(actions.contains(&Action::PlayKnight {
+            tile: 0,
+            victim: Some(PlayerColour::Blue),
+            resource: None,
+        }));
+        assert!(!actions.contains(&Action::EndTurn));
+
+        let stolen = g
+            .play_knight(PlayerColour::Red, 0, Some(PlayerColour::Blue))
+            .unwrap();
+        assert_eq!(stolen, Some(ResourceKind::Lumber));
+        assert_eq!(g.board.robber(), Some(0));
+        assert_eq!(g.phase(), TurnPhase::Roll);
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.knights_played(), 1);
+        assert!(red.development_cards().is_empty());
+
+        // nothing but the knight can jump ahead of the roll
+        assert_eq!(g.legal_actions(&PlayerColour::Red), vec![Action::RollDice]);
+        assert!(g.end_turn(PlayerColour::Red).is_err());
+        g.roll_dice().unwrap();
+        assert_eq!(g.phase(), TurnPhase::Main);
+    }
+
+    #[test]
+    fn test_play_knight_limits() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        {
+            let mut red = g.get_player_mut(PlayerColour::Red).unwrap();
+            *red.resources_mut() = Resources::new_explicit(1, 1, 1, 0, 0);
+            red.development_cards_mut().push(DevelopmentCard::Knight);
         }
 
-        Ok(())
+        // a knight has to move the robber somewhere new
+        let events = g.events().len();
+        assert!(g.play_knight(PlayerColour::Red, 2, None).is_err());
+        assert!(g.play_knight(PlayerColour::Red, 99, None).is_err());
+        assert!(g.play_knight(PlayerColour::Blue, 1, None).is_err());
+        assert!(g
+            .get_player(&PlayerColour::Red)
+            .unwrap()
+            .development_cards()
+            .contains(&DevelopmentCard::Knight));
+        assert_eq!(g.events().len(), events);
+
+        g.play_knight(PlayerColour::Red, 1, None).unwrap();
+        assert_eq!(g.phase(), TurnPhase::Main);
+        assert!(g.events()[events..].contains(&GameEvent::KnightPlayed {
+            player: PlayerColour::Red
+        }));
+
+        // only one development card a turn
+        g.get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .development_cards_mut()
+            .push(DevelopmentCard::Knight);
+        assert!(g.play_knight(PlayerColour::Red, 3, None).is_err());
+        assert!(!g
+            .legal_actions(&PlayerColour::Red)
+            .iter()
+            .any(|a| matches!(a, Action::PlayKnight { .. })));
     }
 
-    pub fn get_bank(&self) -> &Bank {
-        &self.bank
+    #[test]
+    fn test_knight_bought_this_turn() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        g.record_bought(DevelopmentCard::Knight);
+        g.get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .development_cards_mut()
+            .push(DevelopmentCard::Knight);
+
+        assert!(!g.can_play_development_card(&PlayerColour::Red, DevelopmentCard::Knight));
+        assert!(g.play_knight(PlayerColour::Red, 1, None).is_err());
+
+        g.end_turn(PlayerColour::Red).unwrap();
+        g.set_turn_no(g.turn_no + g.players.len() - 1);
+        assert!(g.can_play_development_card(&PlayerColour::Red, DevelopmentCard::Knight));
     }
 
-    pub fn get_bank_mut(&mut self) -> &mut Bank {
-        &mut self.bank
+    #[test]
+    fn test_largest_army() {
+        let mut g = production_game();
+        for (player, knights) in [(PlayerColour::Red, 2), (PlayerColour::Blue, 3)] {
+            *g.get_player_mut(player).unwrap().knights_played_mut() = knights;
+        }
+        g.get_player_mut(PlayerColour::Red)
+            .unwrap()
+            .development_cards_mut()
+            .extend([DevelopmentCard::Knight; 2]);
+        g.update_awards();
+        assert_eq!(g.award_holder(Award::LargestArmy), Some(PlayerColour::Blue));
+        let blue_points = g.get_player(&PlayerColour::Blue).unwrap().victory_points();
+
+        // catching up isn't enough to take the award
+        g.play_knight(PlayerColour::Red, 1, None).unwrap();
+        assert_eq!(g.award_holder(Award::LargestArmy), Some(PlayerColour::Blue));
+
+        g.set_development_card_played(false);
+        g.play_knight(PlayerColour::Red, 3, None).unwrap();
+        assert_eq!(g.award_holder(Award::LargestArmy), Some(PlayerColour::Red));
+        assert_eq!(
+            g.get_player(&PlayerColour::Blue).unwrap().victory_points(),
+            blue_points - AWARD_POINTS
+        );
+        assert_eq!(
+            g.get_player(&PlayerColour::Red).unwrap().victory_points(),
+            1 + AWARD_POINTS
+        );
     }
-}
 
-impl Default for Game {
-    fn default() -> Self {
-        Self {
-            players: Vec::new(),
-            board: Board::default(),
-            bank: Bank::new(),
-            state: GameState::Setup,
-            turn_no: 0,
+    #[test]
+    fn test_longest_road_returns_to_supply() {
+        let mut g = production_game();
+        let ring: Vec<_> = g.board.edges_of_tile(9).map(|edge| *edge.id()).collect();
+        for edge in &ring[..5] {
+            g.board.set_road(*edge, Some(PlayerColour::Red)).unwrap();
         }
+        g.update_awards();
+        assert_eq!(g.award_holder(Award::LongestRoad), Some(PlayerColour::Red));
+
+        g.board.set_road(ring[2], None).unwrap();
+        g.update_awards();
+        assert_eq!(g.award_holder(Award::LongestRoad), None);
+        assert_eq!(
+            g.get_player(&PlayerColour::Red).unwrap().victory_points(),
+            1
+        );
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::{bank::*, board::*, game::*};
     #[test]
-    fn test_init() {
-        let g = Game::default();
+    fn test_play_monopoly() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(0, 0, 6, 0, 0));
+        g.give_development_card(PlayerColour::Red, DevelopmentCard::Monopoly);
+
+        let monopoly = Action::PlayMonopoly {
+            resource: ResourceKind::Wool,
+        };
+        assert!(g.legal_actions(&PlayerColour::Red).contains(&monopoly));
+        g.apply(PlayerColour::Red, monopoly.clone()).unwrap();
+
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.resources()[ResourceKind::Wool], 6);
+        assert!(red.development_cards().is_empty());
+        assert_eq!(red.achievements(), &[Achievement::BigMonopoly]);
         assert_eq!(
-            g,
-            Game {
-                players: Vec::new(),
-                board: Board::new(),
-                bank: Bank::new(),
-                state: GameState::Setup,
-                turn_no: 0,
-            }
+            g.get_player(&PlayerColour::Blue).unwrap().resources()[ResourceKind::Wool],
+            0
         );
+        assert!(g.apply(PlayerColour::Red, monopoly).is_err());
+        assert!(g.events().contains(&GameEvent::MonopolyPlayed {
+            player: PlayerColour::Red,
+            resource: ResourceKind::Wool,
+            taken: 6,
+            from: vec![(PlayerColour::Blue, 6)],
+        }));
     }
 
     #[test]
-    fn test_add_player() {
-        let mut g = Game::default();
+    fn test_take_from_opponents() {
+        let mut g = production_game();
+        g.add_player(PlayerColour::Green);
+        for (player, ore) in [(PlayerColour::Blue, 2), (PlayerColour::Green, 3)] {
+            g.get_player_mut(player).unwrap().resources_mut()[ResourceKind::Ore] = ore;
+        }
+
+        let from = g
+            .take_from_opponents(PlayerColour::Red, ResourceKind::Ore)
+            .unwrap();
         assert_eq!(
-            g,
-            Game {
-                players: Vec::new(),
-                board: Board::default(),
-                bank: Bank::new(),
-                state: GameState::Setup,
-                turn_no: 0,
-            }
+            from,
+            vec![(PlayerColour::Blue, 2), (PlayerColour::Green, 3)]
+        );
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(red.resources()[ResourceKind::Ore], 5);
+        assert!(g
+            .take_from_opponents(PlayerColour::Purple, ResourceKind::Ore)
+            .is_err());
+    }
+
+    #[test]
+    fn test_winning() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        *g.get_player_mut(PlayerColour::Red).unwrap().chits_mut() = VICTORY_POINTS_TO_WIN - 2;
+        g.set_hand(PlayerColour::Red, Building::City.get_resource_cost());
+
+        g.apply(
+            PlayerColour::Red,
+            Action::UpgradeToCity {
+                vertex: VertexId::new(2),
+            },
+        )
+        .unwrap();
+        assert_eq!(g.state, GameState::Complete);
+        assert_eq!(g.winner(), Some(PlayerColour::Red));
+        assert!(g.events().contains(&GameEvent::GameWon {
+            player: PlayerColour::Red
+        }));
+
+        let summary = g.summary();
+        assert_eq!(summary.winner, Some(PlayerColour::Red));
+        assert_eq!(
+            summary.players[0].victory_points.total(),
+            VICTORY_POINTS_TO_WIN
+        );
+        assert_eq!(
+            summary.players[0].achievements,
+            vec![Achievement::FirstCity, Achievement::WonWithoutLongestRoad]
         );
+        assert!(summary.players[1].achievements.is_empty());
+    }
+
+    #[test]
+    fn test_harbormaster() {
+        let harbor = TileKind::ResourceWithHarbor(HarborKind::Generic, ResourceKind::Ore);
+        let board = Board::from_tiles(std::array::from_fn(|idx| match idx {
+            0 | 2 => Tile::new(harbor, 5),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Grain), 6),
+        }));
+        let harbors: Vec<_> = board
+            .vertices()
+            .iter()
+            .map(|vertex| *vertex.id())
+            .filter(|vertex| board.harbor(*vertex).is_some())
+            .collect();
+
+        for harbormaster in [false, true] {
+            let mut g = Game::with_options(GameOptions {
+                harbormaster,
+                ..GameOptions::default()
+            });
+            g.board = board.clone();
+            g.add_player(PlayerColour::Red);
+            g.add_player(PlayerColour::Blue);
+            start_turn(&mut g, TurnPhase::Main);
+            for vertex in &harbors[..2] {
+                g.board
+                    .set_building(*vertex, Some((PlayerColour::Red, Building::Settlement)))
+                    .unwrap();
+            }
+            g.set_hand(PlayerColour::Red, Building::City.get_resource_cost());
+
+            // two settlements aren't enough, but upgrading one of them brings red up to three
+            g.update_awards();
+            assert_eq!(g.award_holder(Award::Harbormaster), None);
+            g.upgrade_to_city(PlayerColour::Red, harbors[0]).unwrap();
+
+            let holder = harbormaster.then_some(PlayerColour::Red);
+            assert_eq!(g.award_holder(Award::Harbormaster), holder);
+            let vp = g.vp_breakdown(&PlayerColour::Red).unwrap();
+            assert_eq!(vp.harbormaster, if harbormaster { AWARD_POINTS } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_settlement_breaks_longest_road() {
+        let mut g = Game::new();
+        g.board = production_board();
         g.add_player(PlayerColour::Red);
-        g.add_player(PlayerColour::Green);
         g.add_player(PlayerColour::Blue);
-        g.add_player(PlayerColour::Purple);
 
+        let ring: Vec<_> = g.board.edges_of_tile(9).map(|edge| *edge.id()).collect();
+        for edge in &ring[..5] {
+            g.board.set_road(*edge, Some(PlayerColour::Red)).unwrap();
+        }
+        g.place_initial_settlement(PlayerColour::Red, VertexId::new(0))
+            .unwrap();
+        assert_eq!(g.award_holder(Award::LongestRoad), Some(PlayerColour::Red));
+
+        // blue settles at a vertex between two of red's road segments
+        let gap = *g.board.edge(ring[5]).unwrap().vertices();
+        let middle = *g
+            .board
+            .edge(ring[0])
+            .unwrap()
+            .vertices()
+            .iter()
+            .find(|v| !gap.contains(v))
+            .unwrap();
+        g.place_initial_settlement(PlayerColour::Blue, middle)
+            .unwrap();
+        assert_eq!(g.award_holder(Award::LongestRoad), None);
         assert_eq!(
-            g,
-            Game {
-                players: vec![
-                    Player::new(PlayerColour::Red),
-                    Player::new(PlayerColour::Green),
-                    Player::new(PlayerColour::Blue),
-                    Player::new(PlayerColour::Purple)
-                ],
-                board: Board::default(),
-                bank: Bank::new(),
-                state: GameState::Setup,
-                turn_no: 0,
-            }
+            g.get_player(&PlayerColour::Red).unwrap().victory_points(),
+            1
         );
     }
 
     #[test]
-    fn test_get_id() {
-        let g = Game::new();
-        let game_id = g.get_game_id();
+    fn test_roll_history() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.set_dice_roller(FixedDice::new([(3, 4), (2, 2)]));
+        start_turn(&mut g, TurnPhase::Roll);
 
-        assert!(game_id.is_ok());
-        let game_id = g.get_game_id().unwrap();
-        assert!(Uuid::parse_str(&game_id.to_string()).is_ok());
+        g.roll_dice().unwrap();
+        let tile = match g.board.robber() {
+            Some(0) => 1,
+            _ => 0,
+        };
+        g.move_robber(PlayerColour::Red, tile, None).unwrap();
+        g.end_turn(PlayerColour::Red).unwrap();
+        g.roll_dice().unwrap();
+        g.end_turn(PlayerColour::Red).unwrap();
+        g.roll_dice().unwrap();
+
+        let history = g.roll_history();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.count(7), 2);
+        assert_eq!(history.count(4), 1);
+        assert_eq!(*history.records()[0].player(), PlayerColour::Red);
+        assert_eq!(history.records()[0].turn(), 0);
     }
 
     #[test]
-    fn test_roll_dice() {
-        let (d1, d2) = Game::roll_dice();
-        let roll = d1 + d2;
+    fn test_luck() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(3, 5)]));
+        g.roll_dice().unwrap();
+
+        let histogram = g.dice_histogram();
+        assert_eq!(histogram.len(), 11);
+        assert_eq!(histogram[6], (8, 1));
+        assert_eq!(histogram.iter().map(|(_, count)| count).sum::<usize>(), 1);
+
+        // red expects two cards on an 8 and one on a 6, blue's city two on an 8
+        let luck = |player| *g.luck().iter().find(|l| *l.player() == player).unwrap();
+        let red = luck(PlayerColour::Red);
+        assert_eq!(red.rolls(), 1);
+        assert_eq!(red.produced(), 2);
+        assert!((red.expected() - 15.0 / 36.0).abs() < 1e-9);
+        let blue = luck(PlayerColour::Blue);
+        assert_eq!(blue.produced(), 2);
+        assert!((blue.difference() - (2.0 - 10.0 / 36.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut g = production_game();
+        g.set_dice_roller(FixedDice::new([(3, 5)]));
+        g.roll_dice().unwrap();
+        assert!(!g.events().is_empty());
+
+        let mut fork = g.fork();
+        assert!(fork.events().is_empty());
+        assert!(fork.roll_history().is_empty());
+        assert_eq!(fork.encode_compact(), g.encode_compact());
+        assert_eq!(fork.zobrist(), g.zobrist());
 
-        assert!(roll > 0 && roll < 12);
+        let player = g.awaiting_player().unwrap();
+        assert_eq!(fork.legal_actions(&player), g.legal_actions(&player));
+        fork.apply(player, Action::EndTurn).unwrap();
+        g.apply(player, Action::EndTurn).unwrap();
+        assert_eq!(fork.encode_compact(), g.encode_compact());
     }
 
     #[test]
@@ -227,32 +5691,72 @@ mod test {
         g.add_player(PlayerColour::Purple);
 
         {
-            let red = g.get_player_mut(PlayerColour::Red).unwrap();
+            let mut red = g.get_player_mut(PlayerColour::Red).unwrap();
             *red.resources_mut() = Resources::new_explicit(0, 1, 1, 0, 0);
         }
 
         {
-            let blue = g.get_player_mut(PlayerColour::Blue).unwrap();
+            let mut blue = g.get_player_mut(PlayerColour::Blue).unwrap();
             *blue.resources_mut() = Resources::new_explicit(2, 0, 0, 0, 0);
         }
 
-        let b = g.get_bank_mut();
-        let trade_id = b.propose_trade(
+        let t = g.get_trades_mut();
+        let trade_id = t.propose_trade(
             PlayerColour::Red,
             Resources::new_explicit(0, 1, 1, 0, 0),
             Resources::new_explicit(2, 0, 0, 0, 0),
         );
 
-        b.accept_trade(trade_id, PlayerColour::Blue)
+        t.accept_trade(trade_id, PlayerColour::Blue)
             .expect("Could not find trade with that ID");
-        b.finalize_trade(trade_id, PlayerColour::Blue)
+        t.finalize_trade(trade_id, PlayerColour::Blue)
             .expect("Could not find trade with that ID");
-        println!("{:#?}", g.get_bank());
         g.finalize_trade(trade_id).unwrap();
 
         let red = g.get_player(&PlayerColour::Red).unwrap();
         assert_eq!(*red.resources(), Resources::new_explicit(2, 0, 0, 0, 0));
         let blue = g.get_player(&PlayerColour::Blue).unwrap();
         assert_eq!(*blue.resources(), Resources::new_explicit(0, 1, 1, 0, 0));
+        assert_eq!(
+            g.events().last(),
+            Some(&GameEvent::TradeCompleted {
+                trade: trade_id,
+                from: PlayerColour::Red,
+                to: PlayerColour::Blue,
+            })
+        );
+    }
+
+    #[test]
+    fn test_finalize_trade_twice() {
+        let mut g = production_game();
+        start_turn(&mut g, TurnPhase::Main);
+        g.set_hand(PlayerColour::Red, Resources::new_explicit(0, 2, 2, 0, 0));
+        g.set_hand(PlayerColour::Blue, Resources::new_explicit(4, 0, 0, 0, 0));
+
+        let trade_id = g
+            .propose_trade(
+                PlayerColour::Red,
+                Resources::new_explicit(0, 1, 1, 0, 0),
+                Resources::new_explicit(2, 0, 0, 0, 0),
+            )
+            .unwrap();
+        let t = g.get_trades_mut();
+        t.accept_trade(trade_id, PlayerColour::Blue).unwrap();
+        t.finalize_trade(trade_id, PlayerColour::Blue).unwrap();
+        g.finalize_trade(trade_id).unwrap();
+        assert!(g.trade_deadlines.is_empty());
+        assert_eq!(
+            *g.get_trades().get_trade(trade_id).unwrap().state(),
+            Accepted
+        );
+
+        let events = g.events().len();
+        assert!(g.finalize_trade(trade_id).is_err());
+        let red = g.get_player(&PlayerColour::Red).unwrap();
+        assert_eq!(*red.resources(), Resources::new_explicit(2, 1, 1, 0, 0));
+        let blue = g.get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(*blue.resources(), Resources::new_explicit(2, 1, 1, 0, 0));
+        assert_eq!(g.events().len(), events);
     }
 }
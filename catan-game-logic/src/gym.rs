@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::actions::Action;
+use crate::driver::DEFAULT_MAX_STEPS;
+use crate::env::{Env, Observation, Reward};
+use crate::game::{Game, GameState};
+use crate::options::GameOptions;
+use crate::player::PlayerColour;
+use crate::rollout::random_discard;
+use crate::selfplay::Agent;
+
+/// An `Env` for reinforcement learning frameworks, which speaks in encoded observations and
+/// action indices rather than views and `Action`s
+///
+/// seats can be handed to built in agents with `with_agent`, which then play their turns in
+/// between the caller's steps, so an observation is only ever for one of the caller's seats.
+/// Discards aren't in the action space, so the caller's seats discard at random
+#[derive(Debug)]
+pub struct CatanEnv {
+    env: Env,
+    agents: Vec<(PlayerColour, Box<dyn Agent>)>,
+    reward: Reward,
+    rng: StdRng,
+}
+
+impl CatanEnv {
+    /// An environment where the caller plays every one of `players`
+    pub fn new(players: Vec<PlayerColour>, options: GameOptions, reward: Reward) -> Self {
+        Self {
+            env: Env::new(players, options, reward),
+            agents: Vec::new(),
+            reward,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Let `agent` play `player`'s turns from the next `reset`, for self-play against it
+    pub fn with_agent(mut self, player: PlayerColour, agent: impl Agent + 'static) -> Self {
+        self.agents.retain(|(colour, _)| *colour != player);
+        self.agents.push((player, Box::new(agent)));
+        self
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn game(&self) -> &Game {
+        self.env.game()
+    }
+
+    /// Start a fresh game from `seed`, returning the first observation for one of the caller's
+    /// seats
+    ///
+    /// the built in agents' choices are seeded from `seed` too
+    pub fn reset(&mut self, seed: u64) -> Result<Vec<f32>> {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.env.reset(seed)?;
+        self.advance()?;
+        self.encode()
+    }
+
+    /// Take the action at `action_index` for the seat the last observation was for, returning
+    /// the next observation, the reward for the step and whether the game is over
+    ///
+    /// with `Reward::Win`, a step after which somebody else wins the game is worth -1
+    pub fn step(&mut self, action_index: usize) -> Result<(Vec<f32>, f64, bool)> {
+        let view = self.observation()?;
+        let action = view
+            .action_at(action_index)
+            .filter(|action| view.legal_actions.contains(action))
+            .ok_or(anyhow!("Action {} isn't legal right now", action_index))?;
+        let (_, mut reward, _) = self.env.step(action)?;
+        reward += self.advance()?;
+
+        let done = self.game().state() == GameState::Complete;
+        if done && self.reward == Reward::Win && self.game().winner() != Some(view.viewer) {
+            reward -= 1.0;
+        }
+        Ok((self.encode()?, reward, done))
+    }
+
+    /// Which indices of the action space are legal for the seat being observed
+    pub fn action_mask(&self) -> Result<Vec<bool>> {
+        Ok(self.observation()?.action_mask())
+    }
+
+    /// The full view behind the latest observation
+    ///
+    /// once the game is over, this is for whoever took the final step
+    pub fn observation(&self) -> Result<Observation> {
+        match self.env.game().awaiting_player() {
+            Some(_) => self.env.observe(),
+            None => {
+                let player = self.game().winner().ok_or(anyhow!("Nothing to observe"))?;
+                self.game().view_for(&player)
+            }
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<f32>> {
+        self.observation()?.encode()
+    }
+
+    /// Play on until the game is waiting on one of the caller's seats for something other than
+    /// a discard, returning what the caller's discards were worth
+    fn advance(&mut self) -> Result<f64> {
+        let mut reward = 0.0;
+        for _ in 0..DEFAULT_MAX_STEPS {
+            if self.game().state() == GameState::Complete {
+                return Ok(reward);
+            }
+            let view = self.env.observe()?;
+            let agent = self
+                .agents
+                .iter_mut()
+                .find(|(colour, _)| *colour == view.viewer);
+            let callers = agent.is_none();
+            let action = match (agent, view.discard) {
+                (Some((_, agent)), _) => agent.act(&view, &mut self.rng),
+                (None, Some(count)) => Action::Discard {
+                    cards: random_discard(view.hand, count, &mut self.rng),
+                },
+                (None, None) => return Ok(reward),
+            };
+            let (_, step_reward, _) = self.env.step(action)?;
+            if callers {
+                reward += step_reward;
+            }
+        }
+        Err(anyhow!(
+            "The agents made {} moves in a row",
+            DEFAULT_MAX_STEPS
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use super::*;
+    use crate::encoding::{ACTION_SPACE, OBSERVATION_SIZE};
+    use crate::selfplay::RandomAgent;
+
+    fn self_play() -> CatanEnv {
+        CatanEnv::new(
+            vec![PlayerColour::Red, PlayerColour::Blue],
+            GameOptions::default(),
+            Reward::Win,
+        )
+        .with_agent(PlayerColour::Blue, RandomAgent::default())
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut env = self_play();
+        let obs = env.reset(2).unwrap();
+        assert_eq!(obs.len(), OBSERVATION_SIZE);
+        assert_eq!(obs, self_play().reset(2).unwrap());
+        assert_eq!(env.observation().unwrap().viewer, PlayerColour::Red);
+
+        let mask = env.action_mask().unwrap();
+        assert_eq!(mask.len(), ACTION_SPACE);
+        let illegal = mask.iter().position(|legal| !legal).unwrap();
+        assert!(env.step(illegal).is_err());
+    }
+
+    #[test]
+    fn test_self_play() {
+        let mut env = self_play();
+        env.reset(6).unwrap();
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..2000 {
+            // the caller only ever sees their own seat
+            assert_eq!(env.observation().unwrap().viewer, PlayerColour::Red);
+            let mask = env.action_mask().unwrap();
+            let legal: Vec<usize> = (0..ACTION_SPACE).filter(|idx| mask[*idx]).collect();
+            let (obs, reward, done) = env.step(legal[rng.gen_range(0..legal.len())]).unwrap();
+            assert_eq!(obs.len(), OBSERVATION_SIZE);
+            if done {
+                let won = env.game().winner() == Some(PlayerColour::Red);
+                assert_eq!(reward, if won { 1.0 } else { -1.0 });
+                return;
+            }
+            assert_eq!(reward, 0.0);
+        }
+    }
+}
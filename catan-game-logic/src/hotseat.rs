@@ -0,0 +1,190 @@
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+
+use crate::actions::{Action, ActionError};
+use crate::game::Game;
+use crate::player::PlayerColour;
+use crate::view::PlayerView;
+
+/// Who the device is with, when several players share one
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Seat {
+    /// `player` has the device, and can see their own hand
+    Playing(PlayerColour),
+    /// The device should be passed on to `to`, and nobody's secrets are shown until they confirm
+    /// they have it
+    HandingOver {
+        from: Option<PlayerColour>,
+        to: PlayerColour,
+    },
+    /// The game is over, or nobody has anything to do
+    Finished,
+}
+
+/// Pass-and-play on a single device, taking care of handing the device from one player to the
+/// next
+///
+/// whenever a different player needs to act, the seat switches to `Seat::HandingOver`, hiding
+/// everyone's view until the next player calls `confirm_handover`
+#[derive(Debug, Clone)]
+pub struct HotSeat {
+    game: Game,
+    seat: Seat,
+}
+
+impl HotSeat {
+    pub fn new(game: Game) -> Self {
+        let mut hot_seat = Self {
+            game,
+            seat: Seat::Finished,
+        };
+        hot_seat.update_seat();
+        hot_seat
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Stop passing the device around, and get the game back
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    pub fn seat(&self) -> Seat {
+        self.seat
+    }
+
+    /// What the player holding the device can see, or nothing while it's being handed over
+    pub fn view(&self) -> Option<PlayerView> {
+        match self.seat {
+            Seat::Playing(player) => self.game.view_for(&player).ok(),
+            Seat::HandingOver { .. } | Seat::Finished => None,
+        }
+    }
+
+    /// Called once the next player has the device, showing them their view of the game
+    pub fn confirm_handover(&mut self) -> Result<PlayerView> {
+        let Seat::HandingOver { to, .. } = self.seat else {
+            return Err(anyhow!("The device isn't being handed over"));
+        };
+        self.seat = Seat::Playing(to);
+        self.game.view_for(&to)
+    }
+
+    /// Carry out `action` for the player holding the device
+    pub fn apply(&mut self, action: Action) -> Result<(), ActionError> {
+        let Seat::Playing(player) = self.seat else {
+            return Err(ActionError::NotAllowedNow(
+                "Waiting for the device to be handed over".into(),
+            ));
+        };
+        let result = self.game.apply(player, action);
+        self.update_seat();
+        result
+    }
+
+    /// Let the game make any decisions which have run out of time, see `Game::resolve_timeouts`
+    pub fn resolve_timeouts(&mut self, now: SystemTime) -> Result<()> {
+        let result = self.game.resolve_timeouts(now);
+        self.update_seat();
+        result
+    }
+
+    /// Hand the device over if the game is now waiting on somebody else
+    fn update_seat(&mut self) {
+        let from = match self.seat {
+            Seat::Playing(player) => Some(player),
+            Seat::HandingOver { from, .. } => from,
+            Seat::Finished => None,
+        };
+        self.seat = match self.game.awaiting_player() {
+            None => Seat::Finished,
+            Some(to) if Some(to) == from && matches!(self.seat, Seat::Playing(_)) => self.seat,
+            Some(to) => Seat::HandingOver { from, to },
+        };
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::board::VertexId;
+    use crate::dice::FixedDice;
+
+    fn hot_seat() -> HotSeat {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        HotSeat::new(g)
+    }
+
+    fn settle(h: &mut HotSeat, vertex: usize) -> Result<(), ActionError> {
+        h.apply(Action::PlaceInitialSettlement {
+            vertex: VertexId::new(vertex),
+        })
+    }
+
+    #[test]
+    fn test_handover() {
+        let mut h = hot_seat();
+        assert_eq!(
+            h.seat(),
+            Seat::HandingOver {
+                from: None,
+                to: PlayerColour::Red
+            }
+        );
+        assert!(h.view().is_none());
+        assert!(matches!(
+            settle(&mut h, 2),
+            Err(ActionError::NotAllowedNow(_))
+        ));
+
+        let view = h.confirm_handover().unwrap();
+        assert_eq!(view.viewer, PlayerColour::Red);
+        assert!(h.confirm_handover().is_err());
+        settle(&mut h, 2).unwrap();
+
+        // blue places both of their settlements without handing the device back
+        assert_eq!(
+            h.seat(),
+            Seat::HandingOver {
+                from: Some(PlayerColour::Red),
+                to: PlayerColour::Blue
+            }
+        );
+        h.confirm_handover().unwrap();
+        settle(&mut h, 30).unwrap();
+        assert_eq!(h.seat(), Seat::Playing(PlayerColour::Blue));
+        settle(&mut h, 40).unwrap();
+
+        h.confirm_handover().unwrap();
+        settle(&mut h, 10).unwrap();
+        assert_eq!(h.seat(), Seat::Playing(PlayerColour::Red));
+        assert_eq!(h.view().unwrap().viewer, PlayerColour::Red);
+    }
+
+    #[test]
+    fn test_view_hides_opponents() {
+        let mut h = hot_seat();
+        for vertex in [2, 30, 40, 10] {
+            if matches!(h.seat(), Seat::HandingOver { .. }) {
+                h.confirm_handover().unwrap();
+            }
+            settle(&mut h, vertex).unwrap();
+        }
+
+        let view = h.view().unwrap();
+        let blue = h.game().get_player(&PlayerColour::Blue).unwrap();
+        assert_eq!(view.opponents.len(), 1);
+        assert_eq!(view.opponents[0].colour, PlayerColour::Blue);
+        assert_eq!(
+            view.opponents[0].cards,
+            blue.resources().into_iter().map(|(_, n)| n).sum::<usize>()
+        );
+        assert!(view.legal_actions.contains(&Action::RollDice));
+    }
+}
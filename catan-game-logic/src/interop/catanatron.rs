@@ -0,0 +1,374 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::actions::Action;
+use crate::board::{tile_coordinates, Board, Tile, TileKind, VertexId};
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+
+/// Catanatron's names for each resource
+const RESOURCE_NAMES: [(ResourceKind, &str); ResourceKind::COUNT] = [
+    (ResourceKind::Lumber, "WOOD"),
+    (ResourceKind::Brick, "BRICK"),
+    (ResourceKind::Wool, "SHEEP"),
+    (ResourceKind::Grain, "WHEAT"),
+    (ResourceKind::Ore, "ORE"),
+];
+
+/// Catanatron's names for the colours both engines have in common
+const COLOUR_NAMES: [(PlayerColour, &str); 2] =
+    [(PlayerColour::Red, "RED"), (PlayerColour::Blue, "BLUE")];
+
+fn resource_name(kind: ResourceKind) -> &'static str {
+    RESOURCE_NAMES
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, name)| *name)
+        .expect("every resource has a name")
+}
+
+fn resource_for(name: &str) -> Result<ResourceKind> {
+    RESOURCE_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(kind, _)| *kind)
+        .ok_or(anyhow!("Unknown resource {}", name))
+}
+
+fn colour_name(colour: PlayerColour) -> Result<&'static str> {
+    COLOUR_NAMES
+        .iter()
+        .find(|(c, _)| *c == colour)
+        .map(|(_, name)| *name)
+        .ok_or(anyhow!("Catanatron has no name for {:?}", colour))
+}
+
+fn colour_for(name: &str) -> Result<PlayerColour> {
+    COLOUR_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(colour, _)| *colour)
+        .ok_or(anyhow!("Unknown colour {}", name))
+}
+
+/// Catanatron's cube coordinates for the tile at `idx`
+fn cube_for(idx: usize) -> [i32; 3] {
+    let (q, r) = tile_coordinates()[idx];
+    [q, -q - r, r]
+}
+
+fn tile_for(cube: [i32; 3]) -> Result<usize> {
+    tile_coordinates()
+        .iter()
+        .position(|(q, r)| cube == [*q, -q - r, *r])
+        .ok_or(anyhow!("{:?} isn't a land tile", cube))
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CatanatronTile {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub number: Option<u8>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CatanatronTileEntry {
+    pub coordinate: [i32; 3],
+    pub tile: CatanatronTile,
+}
+
+/// A board in the layout catanatron's game encoder writes out
+///
+/// catanatron puts harbors on the water around the board, facing the land, while this crate
+/// keeps them on the land tiles, so only land tiles are carried across and ports and water are
+/// ignored
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CatanatronBoard {
+    pub tiles: Vec<CatanatronTileEntry>,
+    pub robber_coordinate: Option<[i32; 3]>,
+}
+
+impl CatanatronBoard {
+    pub fn from_board(board: &Board) -> Self {
+        let tiles = (0..board.tile_count().min(tile_coordinates().len()))
+            .map(|idx| {
+                let tile = board.tile(idx).expect("tile is on the board");
+                let tile = match tile.kind().resource() {
+                    Some(kind) => CatanatronTile {
+                        kind: "RESOURCE_TILE".into(),
+                        resource: Some(resource_name(kind).into()),
+                        number: Some(*tile.token() as u8),
+                    },
+                    None => CatanatronTile {
+                        kind: "DESERT".into(),
+                        resource: None,
+                        number: None,
+                    },
+                };
+                CatanatronTileEntry {
+                    coordinate: cube_for(idx),
+                    tile,
+                }
+            })
+            .collect();
+
+        Self {
+            tiles,
+            robber_coordinate: board.robber().map(cube_for),
+        }
+    }
+
+    /// Build the board this layout describes, which has to cover every land tile of the default
+    /// board
+    pub fn to_board(&self) -> Result<Board> {
+        let mut tiles = vec![None; tile_coordinates().len()];
+        for entry in &self.tiles {
+            let tile = match entry.tile.kind.as_str() {
+                "RESOURCE_TILE" => {
+                    let resource = entry
+                        .tile
+                        .resource
+                        .as_deref()
+                        .ok_or(anyhow!("Resource tile without a resource"))?;
+                    let number = entry
+                        .tile
+                        .number
+                        .ok_or(anyhow!("Resource tile without a number"))?;
+                    Tile::new(TileKind::Resource(resource_for(resource)?), number as usize)
+                }
+                // the desert has no number, so give it the robber's
+                "DESERT" => Tile::new(TileKind::Desert, 7),
+                _ => continue,
+            };
+            tiles[tile_for(entry.coordinate)?] = Some(tile);
+        }
+
+        let tiles: Vec<_> = tiles
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or(anyhow!("Every land tile of the board has to be given"))?;
+        let mut board = Board::from_tiles(tiles.try_into().expect("one tile per coordinate"));
+        if let Some(robber) = self.robber_coordinate {
+            board.set_robber(tile_for(robber)?)?;
+        }
+        Ok(board)
+    }
+}
+
+/// An action as catanatron records it, a `[colour, action type, value]` triple
+///
+/// catanatron numbers the corners of the board differently to this crate, so converting actions
+/// which build on a corner needs `nodes`, pairing each catanatron node id with a vertex
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatanatronAction(pub String, pub String, pub Value);
+
+impl CatanatronAction {
+    pub fn from_action(
+        player: PlayerColour,
+        action: &Action,
+        nodes: &[(usize, VertexId)],
+    ) -> Result<Self> {
+        let node_for = |vertex: &VertexId| {
+            nodes
+                .iter()
+                .find(|(_, v)| v == vertex)
+                .map(|(node, _)| json!(node))
+                .ok_or(anyhow!("No catanatron node for vertex {}", vertex.index()))
+        };
+
+        let (kind, value) = match action {
+            Action::PlaceInitialSettlement { vertex } => ("BUILD_SETTLEMENT", node_for(vertex)?),
+            Action::UpgradeToCity { vertex } => ("BUILD_CITY", node_for(vertex)?),
+            Action::RollDice => ("ROLL", Value::Null),
+            Action::Discard { cards } => {
+                let cards: Vec<_> = cards
+                    .into_iter()
+                    .flat_map(|(kind, n)| std::iter::repeat_n(resource_name(kind), n))
+                    .collect();
+                ("DISCARD", json!(cards))
+            }
+            Action::MoveRobber {
+                tile,
+                victim,
+                resource,
+            } => {
+                let victim = victim.map(colour_name).transpose()?;
+                let resource = resource.map(resource_name);
+                ("MOVE_ROBBER", json!([cube_for(*tile), victim, resource]))
+            }
+            Action::BuyDevelopmentCard => ("BUY_DEVELOPMENT_CARD", Value::Null),
+            Action::PlayMonopoly { resource } => ("PLAY_MONOPOLY", json!(resource_name(*resource))),
+            Action::EndTurn => ("END_TURN", Value::Null),
+            action => return Err(anyhow!("Catanatron has no single action for {:?}", action)),
+        };
+        Ok(Self(colour_name(player)?.into(), kind.into(), value))
+    }
+
+    /// The player taking this action, and the action itself
+    pub fn to_action(&self, nodes: &[(usize, VertexId)]) -> Result<(PlayerColour, Action)> {
+        let vertex_for = |value: &Value| {
+            let node = value.as_u64().ok_or(anyhow!("Expected a node id"))? as usize;
+            nodes
+                .iter()
+                .find(|(n, _)| *n == node)
+                .map(|(_, vertex)| *vertex)
+                .ok_or(anyhow!("No vertex for catanatron node {}", node))
+        };
+        let name = |value: &Value| {
+            value
+                .as_str()
+                .map(String::from)
+                .ok_or(anyhow!("Expected a name"))
+        };
+
+        let Self(colour, kind, value) = self;
+        let action = match kind.as_str() {
+            "BUILD_SETTLEMENT" => Action::PlaceInitialSettlement {
+                vertex: vertex_for(value)?,
+            },
+            "BUILD_CITY" => Action::UpgradeToCity {
+                vertex: vertex_for(value)?,
+            },
+            "ROLL" => Action::RollDice,
+            "DISCARD" => {
+                let mut cards = Resources::new();
+                for card in value
+                    .as_array()
+                    .ok_or(anyhow!("Expected a list of cards"))?
+                {
+                    cards[resource_for(&name(card)?)?] += 1;
+                }
+                Action::Discard { cards }
+            }
+            "MOVE_ROBBER" => {
+                let cube: [i32; 3] = serde_json::from_value(value[0].clone())?;
+                let victim = match &value[1] {
+                    Value::Null => None,
+                    victim => Some(colour_for(&name(victim)?)?),
+                };
+                let resource = match &value[2] {
+                    Value::Null => None,
+                    resource => Some(resource_for(&name(resource)?)?),
+                };
+                Action::MoveRobber {
+                    tile: tile_for(cube)?,
+                    victim,
+                    resource,
+                }
+            }
+            "BUY_DEVELOPMENT_CARD" => Action::BuyDevelopmentCard,
+            "PLAY_MONOPOLY" => Action::PlayMonopoly {
+                resource: resource_for(&name(value)?)?,
+            },
+            "END_TURN" => Action::EndTurn,
+            kind => return Err(anyhow!("Unsupported catanatron action {}", kind)),
+        };
+        Ok((colour_for(colour)?, action))
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_board_round_trip() {
+        let board = Board::from_tiles(std::array::from_fn(|idx| match idx {
+            4 => Tile::new(TileKind::Desert, 7),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Lumber), idx % 11 + 2),
+        }));
+
+        let exported = CatanatronBoard::from_board(&board);
+        assert_eq!(exported.tiles[0].coordinate, [0, 2, -2]);
+        assert_eq!(exported.tiles[0].tile.resource.as_deref(), Some("WOOD"));
+        assert_eq!(exported.robber_coordinate, Some([0, 1, -1]));
+
+        let json = serde_json::to_string(&exported).unwrap();
+        let imported: CatanatronBoard = serde_json::from_str(&json).unwrap();
+        let imported = imported.to_board().unwrap();
+        for idx in 0..board.tile_count() {
+            assert_eq!(
+                imported.tile(idx).unwrap().kind(),
+                board.tile(idx).unwrap().kind()
+            );
+        }
+        assert_eq!(imported.robber(), Some(4));
+    }
+
+    #[test]
+    fn test_water_is_ignored() {
+        let mut exported = CatanatronBoard::from_board(&Board::new());
+        exported.tiles.push(CatanatronTileEntry {
+            coordinate: [3, -3, 0],
+            tile: CatanatronTile {
+                kind: "WATER".into(),
+                resource: None,
+                number: None,
+            },
+        });
+        assert!(exported.to_board().is_ok());
+
+        exported.tiles.remove(0);
+        assert!(exported.to_board().is_err());
+    }
+
+    #[test]
+    fn test_action_round_trip() {
+        let nodes = [(12, VertexId::new(2))];
+        let actions = [
+            Action::RollDice,
+            Action::UpgradeToCity {
+                vertex: VertexId::new(2),
+            },
+            Action::MoveRobber {
+                tile: 9,
+                victim: Some(PlayerColour::Blue),
+                resource: None,
+            },
+            Action::MoveRobber {
+                tile: 9,
+                victim: Some(PlayerColour::Blue),
+                resource: Some(ResourceKind::Ore),
+            },
+            Action::Discard {
+                cards: Resources::new_explicit(0, 0, 2, 0, 1),
+            },
+            Action::PlayMonopoly {
+                resource: ResourceKind::Grain,
+            },
+            Action::EndTurn,
+        ];
+
+        for action in actions {
+            let exported =
+                CatanatronAction::from_action(PlayerColour::Red, &action, &nodes).unwrap();
+            assert_eq!(
+                exported.to_action(&nodes).unwrap(),
+                (PlayerColour::Red, action)
+            );
+        }
+
+        let json = r#"["BLUE", "MOVE_ROBBER", [[0, 0, 0], null, null]]"#;
+        let action: CatanatronAction = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            action.to_action(&nodes).unwrap(),
+            (
+                PlayerColour::Blue,
+                Action::MoveRobber {
+                    tile: 9,
+                    victim: None,
+                    resource: None,
+                }
+            )
+        );
+
+        let city = Action::UpgradeToCity {
+            vertex: VertexId::new(3),
+        };
+        assert!(CatanatronAction::from_action(PlayerColour::Red, &city, &nodes).is_err());
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::board::{tile_coordinates, Board, HarborKind, Tile, TileKind};
+use crate::events::GameEvent;
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+
+/// Colonist's codes for each resource, which it also uses for the tiles producing them
+const RESOURCE_CODES: [(ResourceKind, u8); ResourceKind::COUNT] = [
+    (ResourceKind::Lumber, 1),
+    (ResourceKind::Brick, 2),
+    (ResourceKind::Wool, 3),
+    (ResourceKind::Grain, 4),
+    (ResourceKind::Ore, 5),
+];
+
+/// Tile type Colonist uses for the desert
+const DESERT_CODE: u8 = 0;
+
+/// Port type Colonist uses for a generic 3:1 harbor, with 2:1 harbors following on from it in
+/// the same order as `RESOURCE_CODES`
+const GENERIC_PORT_CODE: u8 = 1;
+
+/// Colonist's codes for the colours both games have in common
+const COLOUR_CODES: [(PlayerColour, u8); 4] = [
+    (PlayerColour::Red, 1),
+    (PlayerColour::Blue, 2),
+    (PlayerColour::Green, 4),
+    (PlayerColour::Purple, 6),
+];
+
+fn resource_code(kind: ResourceKind) -> u8 {
+    RESOURCE_CODES
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, code)| *code)
+        .expect("every resource has a code")
+}
+
+fn resource_for(code: u8) -> Result<ResourceKind> {
+    RESOURCE_CODES
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(kind, _)| *kind)
+        .ok_or(anyhow!("Unknown resource code {}", code))
+}
+
+fn colour_code(colour: PlayerColour) -> Result<u8> {
+    COLOUR_CODES
+        .iter()
+        .find(|(c, _)| *c == colour)
+        .map(|(_, code)| *code)
+        .ok_or(anyhow!("Colonist has no code for {:?}", colour))
+}
+
+fn colour_for(code: u8) -> Result<PlayerColour> {
+    COLOUR_CODES
+        .iter()
+        .find(|(_, c)| *c == code)
+        .map(|(colour, _)| *colour)
+        .ok_or(anyhow!("Unknown colour code {}", code))
+}
+
+/// Axial coordinates of a hex, with `x` counting across each row and `y` counting rows down
+/// from the centre
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ColonistHex {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColonistTile {
+    pub hex_face: ColonistHex,
+    pub tile_type: u8,
+    pub dice_number: u8,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColonistPort {
+    /// The tile the harbor belongs to
+    pub hex_face: ColonistHex,
+    pub port_type: u8,
+}
+
+/// A board in the layout Colonist exports its games in
+///
+/// Colonist keeps harbors apart from the tiles, while this crate puts them on the tile they sit
+/// next to, so each port has to name a resource tile
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColonistBoard {
+    pub tiles: Vec<ColonistTile>,
+    #[serde(default)]
+    pub ports: Vec<ColonistPort>,
+    #[serde(default)]
+    pub robber: Option<ColonistHex>,
+}
+
+impl ColonistBoard {
+    pub fn from_board(board: &Board) -> Self {
+        let coords = tile_coordinates();
+        let hex = |idx: usize| ColonistHex {
+            x: coords[idx].0,
+            y: coords[idx].1,
+        };
+
+        let mut tiles = Vec::new();
+        let mut ports = Vec::new();
+        for idx in 0..board.tile_count().min(coords.len()) {
+            let tile = board.tile(idx).expect("tile is on the board");
+            let tile_type = tile.kind().resource().map_or(DESERT_CODE, resource_code);
+            tiles.push(ColonistTile {
+                hex_face: hex(idx),
+                tile_type,
+                dice_number: *tile.token() as u8,
+            });
+
+            if let TileKind::ResourceWithHarbor(harbor, _) = tile.kind() {
+                let port_type = match harbor {
+                    HarborKind::Generic => GENERIC_PORT_CODE,
+                    HarborKind::Special(kind) => GENERIC_PORT_CODE + resource_code(*kind),
+                };
+                ports.push(ColonistPort {
+                    hex_face: hex(idx),
+                    port_type,
+                });
+            }
+        }
+
+        Self {
+            tiles,
+            ports,
+            robber: board.robber().map(hex),
+        }
+    }
+
+    /// Build the board this layout describes, which has to cover every tile of the default board
+    pub fn to_board(&self) -> Result<Board> {
+        let coords = tile_coordinates();
+        let index_of = |hex: &ColonistHex| {
+            coords
+                .iter()
+                .position(|c| *c == (hex.x, hex.y))
+                .ok_or(anyhow!("({}, {}) isn't on the board", hex.x, hex.y))
+        };
+
+        let mut kinds = vec![None; coords.len()];
+        for tile in &self.tiles {
+            let kind = match tile.tile_type {
+                DESERT_CODE => TileKind::Desert,
+                code => TileKind::Resource(resource_for(code)?),
+            };
+            kinds[index_of(&tile.hex_face)?] = Some((kind, tile.dice_number as usize));
+        }
+        for port in &self.ports {
+            let harbor = match port.port_type {
+                GENERIC_PORT_CODE => HarborKind::Generic,
+                code => HarborKind::Special(resource_for(code - GENERIC_PORT_CODE)?),
+            };
+            let tile = kinds[index_of(&port.hex_face)?]
+                .as_mut()
+                .ok_or(anyhow!("Port on a missing tile"))?;
+            let resource = tile
+                .0
+                .resource()
+                .ok_or(anyhow!("Ports have to be on a resource tile"))?;
+            tile.0 = TileKind::ResourceWithHarbor(harbor, resource);
+        }
+
+        let tiles: Vec<_> = kinds
+            .into_iter()
+            .map(|tile| tile.map(|(kind, token)| Tile::new(kind, token)))
+            .collect::<Option<_>>()
+            .ok_or(anyhow!("Every tile of the board has to be given"))?;
+        let mut board = Board::from_tiles(tiles.try_into().expect("one tile per coordinate"));
+        if let Some(robber) = &self.robber {
+            board.set_robber(index_of(robber)?)?;
+        }
+        Ok(board)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Entries from a Colonist game log, for the events both games record
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ColonistLogEntry {
+    #[serde(rename_all = "camelCase")]
+    PlayerOrder { player_colors: Vec<u8> },
+    /// Cards handed out, one resource code per card
+    #[serde(rename_all = "camelCase")]
+    GotResources { player_color: u8, cards: Vec<u8> },
+    #[serde(rename_all = "camelCase")]
+    BuiltCity { player_color: u8 },
+    #[serde(rename_all = "camelCase")]
+    PlayedMonopoly {
+        player_color: u8,
+        card: u8,
+        amount: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    Won { player_color: u8 },
+}
+
+impl ColonistLogEntry {
+    /// The log entry for `event`, if Colonist records anything like it
+    pub fn from_event(event: &GameEvent) -> Result<Option<Self>> {
+        let entry = match event {
+            GameEvent::TurnOrder { players } => ColonistLogEntry::PlayerOrder {
+                player_colors: players
+                    .iter()
+                    .map(|p| colour_code(*p))
+                    .collect::<Result<_>>()?,
+            },
+            GameEvent::Production { player, resources } => ColonistLogEntry::GotResources {
+                player_color: colour_code(*player)?,
+                cards: resources
+                    .into_iter()
+                    .flat_map(|(kind, n)| std::iter::repeat_n(resource_code(kind), n))
+                    .collect(),
+            },
+            GameEvent::CityBuilt { player } => ColonistLogEntry::BuiltCity {
+                player_color: colour_code(*player)?,
+            },
+            GameEvent::MonopolyPlayed {
+                player,
+                resource,
+                taken,
+                ..
+            } => ColonistLogEntry::PlayedMonopoly {
+                player_color: colour_code(*player)?,
+                card: resource_code(*resource),
+                amount: *taken,
+            },
+            GameEvent::GameWon { player } => ColonistLogEntry::Won {
+                player_color: colour_code(*player)?,
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(entry))
+    }
+
+    pub fn to_event(&self) -> Result<GameEvent> {
+        Ok(match self {
+            ColonistLogEntry::PlayerOrder { player_colors } => GameEvent::TurnOrder {
+                players: player_colors
+                    .iter()
+                    .map(|c| colour_for(*c))
+                    .collect::<Result<_>>()?,
+            },
+            ColonistLogEntry::GotResources {
+                player_color,
+                cards,
+            } => {
+                let mut resources = Resources::new();
+                for card in cards {
+                    resources[resource_for(*card)?] += 1;
+                }
+                GameEvent::Production {
+                    player: colour_for(*player_color)?,
+                    resources,
+                }
+            }
+            ColonistLogEntry::BuiltCity { player_color } => GameEvent::CityBuilt {
+                player: colour_for(*player_color)?,
+            },
+            ColonistLogEntry::PlayedMonopoly {
+                player_color,
+                card,
+                amount,
+            } => GameEvent::MonopolyPlayed {
+                player: colour_for(*player_color)?,
+                resource: resource_for(*card)?,
+                taken: *amount,
+                from: Vec::new(),
+            },
+            ColonistLogEntry::Won { player_color } => GameEvent::GameWon {
+                player: colour_for(*player_color)?,
+            },
+        })
+    }
+
+    /// Convert a game's events into a Colonist log, leaving out any Colonist doesn't record
+    pub fn export(events: &[GameEvent]) -> Result<Vec<Self>> {
+        events
+            .iter()
+            .filter_map(|event| Self::from_event(event).transpose())
+            .collect()
+    }
+
+    pub fn import(log: &[Self]) -> Result<Vec<GameEvent>> {
+        log.iter().map(Self::to_event).collect()
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_board_round_trip() {
+        let harbor = TileKind::ResourceWithHarbor(
+            HarborKind::Special(ResourceKind::Wool),
+            ResourceKind::Ore,
+        );
+        let board = Board::from_tiles(std::array::from_fn(|idx| match idx {
+            0 => Tile::new(harbor, 5),
+            9 => Tile::new(TileKind::Desert, 7),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Brick), 6),
+        }));
+
+        let colonist = ColonistBoard::from_board(&board);
+        assert_eq!(colonist.tiles.len(), 19);
+        assert_eq!(
+            colonist.ports,
+            vec![ColonistPort {
+                hex_face: ColonistHex { x: 0, y: -2 },
+                port_type: GENERIC_PORT_CODE + 3,
+            }]
+        );
+        assert_eq!(colonist.robber, Some(ColonistHex { x: 0, y: 0 }));
+
+        let json = colonist.to_json().unwrap();
+        assert!(json.contains("\"hexFace\""));
+        let imported = ColonistBoard::from_json(&json).unwrap().to_board().unwrap();
+        for idx in 0..board.tile_count() {
+            assert_eq!(
+                imported.tile(idx).unwrap().kind(),
+                board.tile(idx).unwrap().kind()
+            );
+            assert_eq!(
+                imported.tile(idx).unwrap().token(),
+                board.tile(idx).unwrap().token()
+            );
+        }
+        assert_eq!(imported.robber(), Some(9));
+    }
+
+    #[test]
+    fn test_incomplete_board() {
+        let mut colonist = ColonistBoard::from_board(&Board::new());
+        colonist.tiles.pop();
+        assert!(colonist.to_board().is_err());
+
+        let json = r#"{"tiles": [{"hexFace": {"x": 5, "y": 5}, "tileType": 1, "diceNumber": 6}]}"#;
+        assert!(ColonistBoard::from_json(json).unwrap().to_board().is_err());
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let events = vec![
+            GameEvent::TurnOrder {
+                players: vec![PlayerColour::Blue, PlayerColour::Red],
+            },
+            GameEvent::Production {
+                player: PlayerColour::Red,
+                resources: Resources::new_explicit(1, 0, 2, 0, 0),
+            },
+            GameEvent::DiceOffRoll {
+                player: PlayerColour::Red,
+                dice: (3, 4),
+            },
+            GameEvent::GameWon {
+                player: PlayerColour::Blue,
+            },
+        ];
+
+        let log = ColonistLogEntry::export(&events).unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(
+            log[1],
+            ColonistLogEntry::GotResources {
+                player_color: 1,
+                cards: vec![5, 3, 3],
+            }
+        );
+        let json = serde_json::to_string(&log).unwrap();
+        assert!(json.contains(r#""type":"gotResources","playerColor":1"#));
+
+        let imported = ColonistLogEntry::import(&log).unwrap();
+        assert_eq!(imported, [&events[..2], &events[3..]].concat());
+
+        let custom = GameEvent::GameWon {
+            player: PlayerColour::Custom { r: 0, g: 0, b: 0 },
+        };
+        assert!(ColonistLogEntry::from_event(&custom).is_err());
+    }
+}
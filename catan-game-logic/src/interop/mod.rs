@@ -0,0 +1,5 @@
+pub(crate) mod catanatron;
+pub(crate) mod colonist;
+
+pub use catanatron::{CatanatronAction, CatanatronBoard, CatanatronTile, CatanatronTileEntry};
+pub use colonist::{ColonistBoard, ColonistHex, ColonistLogEntry, ColonistPort, ColonistTile};
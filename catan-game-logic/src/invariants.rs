@@ -0,0 +1,244 @@
+use std::fmt::Display;
+
+use crate::bank::TOTAL_RESOURCES;
+use crate::building::Building;
+use crate::development_cards::DevelopmentCard;
+use crate::game::Game;
+use crate::player::{BuildingStock, PlayerColour};
+use crate::resources::ResourceKind;
+
+/// Something the engine should never let happen, found by `Game::check_invariants`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// The bank and the players' hands don't add up to every resource card in the game
+    ResourcesNotConserved {
+        kind: ResourceKind,
+        expected: usize,
+        found: usize,
+    },
+    /// More development cards of a kind are in the deck, in hands and played than the game began
+    /// with
+    DevelopmentCardsNotConserved {
+        kind: DevelopmentCard,
+        expected: usize,
+        found: usize,
+    },
+    /// A player's pieces in stock and on the board don't add up to what they started with
+    PiecesNotConserved {
+        player: PlayerColour,
+        building: Building,
+        expected: usize,
+        found: usize,
+    },
+    /// The victory points kept on a player differ from what their pieces and awards are worth
+    VictoryPointsMismatch {
+        player: PlayerColour,
+        recorded: usize,
+        derived: usize,
+    },
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvariantViolation::ResourcesNotConserved {
+                kind,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} {} in the game, found {}",
+                expected, kind, found
+            ),
+            InvariantViolation::DevelopmentCardsNotConserved {
+                kind,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} {:?} cards in the game, found {}",
+                expected, kind, found
+            ),
+            InvariantViolation::PiecesNotConserved {
+                player,
+                building,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{:?} should have {} {:?} pieces, found {}",
+                player, expected, building, found
+            ),
+            InvariantViolation::VictoryPointsMismatch {
+                player,
+                recorded,
+                derived,
+            } => write!(
+                f,
+                "{:?} has {} victory points recorded, but their board is worth {}",
+                player, recorded, derived
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+impl Game {
+    /// Check nothing has been created or lost, returning everything which doesn't add up
+    ///
+    /// every resource card is either in the bank or a hand, each player's pieces are either in
+    /// their stock or on the board, and the victory points kept on each player match their
+    /// buildings and awards. Played knights are counted on players, but other played cards
+    /// aren't kept anywhere, so monopoly and road building cards can only be checked not to
+    /// have multiplied. Enable the `debug-invariants` feature to run this after every action
+    pub fn check_invariants(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+
+        for kind in ResourceKind::ALL {
+            let found = self.get_bank().remaining(kind)
+                + self
+                    .players()
+                    .iter()
+                    .map(|p| p.resources()[kind])
+                    .sum::<usize>();
+            if found != TOTAL_RESOURCES {
+                violations.push(InvariantViolation::ResourcesNotConserved {
+                    kind,
+                    expected: TOTAL_RESOURCES,
+                    found,
+                });
+            }
+        }
+
+        for kind in DevelopmentCard::ALL {
+            let expected = self.options().development_cards[kind];
+            let held = self
+                .players()
+                .iter()
+                .flat_map(|p| p.development_cards())
+                .filter(|card| **card == kind)
+                .count();
+            let played = match kind {
+                DevelopmentCard::Knight => self.players().iter().map(|p| p.knights_played()).sum(),
+                _ => 0,
+            };
+            let found = self.get_bank().remaining_dev_cards()[kind] + held + played;
+            let untracked = matches!(
+                kind,
+                DevelopmentCard::Monopoly | DevelopmentCard::RoadBuilding
+            );
+            if found > expected || (found < expected && !untracked) {
+                violations.push(InvariantViolation::DevelopmentCardsNotConserved {
+                    kind,
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        let board = self.get_board();
+        let full = BuildingStock::new();
+        for player in self.players() {
+            let colour = *player.colour();
+            for building in [Building::Settlement, Building::City, Building::Road] {
+                let placed = match building {
+                    Building::Road => board
+                        .edges()
+                        .iter()
+                        .filter(|e| e.road() == Some(&colour))
+                        .count(),
+                    _ => board
+                        .vertices()
+                        .iter()
+                        .filter(|v| v.building() == Some(&(colour, building)))
+                        .count(),
+                };
+                let found = player.stock().remaining(building) + placed;
+                let expected = full.remaining(building);
+                if found != expected {
+                    violations.push(InvariantViolation::PiecesNotConserved {
+                        player: colour,
+                        building,
+                        expected,
+                        found,
+                    });
+                }
+            }
+
+            // hidden cards and chits are worked out when they're needed, so aren't recorded
+            if let Ok(vp) = self.vp_breakdown(&colour) {
+                let derived = vp.total() - vp.hidden_cards - vp.chits;
+                if player.victory_points() != derived {
+                    violations.push(InvariantViolation::VictoryPointsMismatch {
+                        player: colour,
+                        recorded: player.victory_points(),
+                        derived,
+                    });
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::game::running_game;
+    use crate::resources::Resources;
+    use crate::transfer::{Party, Transfer};
+
+    #[test]
+    fn test_conserved() {
+        let mut g = running_game();
+        assert_eq!(g.check_invariants(), Ok(()));
+
+        let hand = Resources::new_with_amount(1);
+        let transfer = Transfer::new().add(Party::Bank, Party::Player(PlayerColour::Red), hand);
+        g.transfer(&transfer).unwrap();
+        assert_eq!(g.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut g = running_game();
+        g.get_player_mut(PlayerColour::Red).unwrap().resources_mut()[ResourceKind::Ore] += 1;
+        *g.get_player_mut(PlayerColour::Blue)
+            .unwrap()
+            .victory_points_mut() += 1;
+        g.get_player_mut(PlayerColour::Blue)
+            .unwrap()
+            .development_cards_mut()
+            .push(DevelopmentCard::Monopoly);
+
+        let violations = g.check_invariants().unwrap_err();
+        assert_eq!(violations.len(), 3);
+        assert!(
+            violations.contains(&InvariantViolation::ResourcesNotConserved {
+                kind: ResourceKind::Ore,
+                expected: TOTAL_RESOURCES,
+                found: TOTAL_RESOURCES + 1,
+            })
+        );
+        assert!(
+            violations.contains(&InvariantViolation::DevelopmentCardsNotConserved {
+                kind: DevelopmentCard::Monopoly,
+                expected: 2,
+                found: 3,
+            })
+        );
+        assert!(matches!(
+            violations[2],
+            InvariantViolation::VictoryPointsMismatch {
+                player: PlayerColour::Blue,
+                ..
+            }
+        ));
+    }
+}
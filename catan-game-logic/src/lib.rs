@@ -1,18 +1,187 @@
-#![feature(hash_drain_filter)]
-#![feature(variant_count)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 
+extern crate alloc;
+
+#[macro_use]
+pub(crate) mod trace;
+
+pub(crate) mod achievements;
+#[cfg(feature = "std")]
+pub(crate) mod actions;
+pub(crate) mod awards;
+#[cfg(feature = "std")]
 pub(crate) mod bank;
+#[cfg(feature = "std")]
 pub(crate) mod board;
 pub(crate) mod building;
+#[cfg(feature = "std")]
+pub(crate) mod chat;
+#[cfg(feature = "std")]
+pub(crate) mod compact;
+#[cfg(feature = "std")]
+pub(crate) mod delta;
 pub(crate) mod development_cards;
+pub(crate) mod dice;
+#[cfg(feature = "std")]
+pub(crate) mod discard;
+#[cfg(feature = "std")]
+pub(crate) mod driver;
+#[cfg(feature = "std")]
+pub(crate) mod encoding;
+#[cfg(feature = "rand")]
+pub(crate) mod env;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub(crate) mod event_log;
+pub(crate) mod events;
+#[cfg(feature = "std")]
+pub(crate) mod extensions;
+#[cfg(feature = "std")]
+pub(crate) mod fish;
+#[cfg(feature = "std")]
 pub(crate) mod game;
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub(crate) mod gym;
+#[cfg(feature = "std")]
+pub(crate) mod hotseat;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub(crate) mod interop;
+#[cfg(feature = "std")]
+pub(crate) mod invariants;
+#[cfg(feature = "rand")]
+pub(crate) mod lobby;
+pub(crate) mod options;
+pub(crate) mod platform;
 pub(crate) mod player;
+#[cfg(feature = "std")]
+pub(crate) mod player_map;
+#[cfg(feature = "std")]
+pub(crate) mod ratings;
+#[cfg(feature = "std")]
+pub(crate) mod report;
 pub(crate) mod resources;
+#[cfg(feature = "rand")]
+pub(crate) mod rollout;
+#[cfg(feature = "std")]
+pub(crate) mod scenarios;
+#[cfg(feature = "schemars")]
+pub(crate) mod schema;
+pub(crate) mod score;
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub(crate) mod selfplay;
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub(crate) mod simulator;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub(crate) mod snapshot;
+#[cfg(feature = "std")]
+pub(crate) mod tournament;
 pub(crate) mod trade;
+#[cfg(feature = "std")]
+pub(crate) mod trade_manager;
+#[cfg(feature = "std")]
+pub(crate) mod transfer;
+#[cfg(feature = "ts-rs")]
+pub(crate) mod typescript;
+#[cfg(feature = "std")]
+pub(crate) mod validation;
+#[cfg(feature = "std")]
+pub(crate) mod view;
+#[cfg(feature = "wasm-bindgen")]
+pub(crate) mod wasm;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub(crate) mod wire;
+pub(crate) mod zobrist;
 
-pub use game::Game;
-pub use player::Player;
+pub use achievements::{Achievement, AchievementTracker};
+#[cfg(feature = "std")]
+pub use actions::{Action, ActionError};
+pub use awards::Award;
+#[cfg(feature = "std")]
+pub use bank::{Bank, BankError, BankSummary};
+#[cfg(feature = "std")]
+pub use board::{Board, Edge, EdgeId, Location, Tile, TileKind, Vertex, VertexId};
+pub use building::Building;
+#[cfg(feature = "std")]
+pub use chat::{Chat, ChatAuthor, ChatMessage, MAX_CHAT_MESSAGE_LEN};
+#[cfg(feature = "std")]
+pub use delta::GameDelta;
+pub use development_cards::{DevelopmentCard, DevelopmentCardCounts};
+pub use dice::{Dice, DiceRoller, FixedDice, Luck, RollHistory, RollRecord};
+#[cfg(feature = "rand")]
+pub use dice::{DiceDeck, FairDice};
+#[cfg(feature = "std")]
+pub use driver::{GameDriver, PlayerController};
+#[cfg(feature = "std")]
+pub use encoding::{ACTION_SPACE, EDGE_COUNT, OBSERVATION_SIZE, SEATS, VERTEX_COUNT};
+#[cfg(feature = "rand")]
+pub use env::{Env, Observation, Reward, RewardShaping};
+#[cfg(all(feature = "std", feature = "serde"))]
+pub use event_log::{import_log, EventLogEntry};
+pub use events::{Decision, GameEvent};
+#[cfg(feature = "std")]
+pub use extensions::{ExtensionAction, ExtensionItem, ExtensionItemId, Extensions, RulesExtension};
+#[cfg(feature = "std")]
+pub use fish::{FishBag, FishPurchase};
+#[cfg(feature = "std")]
+pub use game::{Game, GameState, TurnPhase};
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub use gym::CatanEnv;
+#[cfg(feature = "std")]
+pub use hotseat::{HotSeat, Seat};
+#[cfg(all(feature = "std", feature = "serde"))]
+pub use interop::{
+    CatanatronAction, CatanatronBoard, CatanatronTile, CatanatronTileEntry, ColonistBoard,
+    ColonistHex, ColonistLogEntry, ColonistPort, ColonistTile,
+};
+#[cfg(feature = "std")]
+pub use invariants::InvariantViolation;
+#[cfg(feature = "rand")]
+pub use lobby::{Lobby, LobbySeat, DEFAULT_LOBBY_SEATS};
+pub use options::{ClockExpiry, CostTable, DecisionTimeouts, GameOptions, Resignation, TurnClock};
+pub use player::{BuildingStock, Player, PlayerColour, PlayerId};
+#[cfg(feature = "std")]
+pub use player_map::{PlayerMap, PlayerMut};
+#[cfg(feature = "std")]
+pub use ratings::{Elo, MemoryRatings, Rating, RatingChange, RatingStore};
+#[cfg(feature = "std")]
+pub use report::{
+    AwardChange, GameReport, Income, PlayerReport, PointsChange, RollFrequency, TradeRecord,
+};
+pub use resources::{
+    CompactResources, InsufficientResources, ParseResourcesError, ResourceOverflow, Resources,
+};
+#[cfg(feature = "rand")]
+pub use rollout::{rollout, simulate, RolloutPolicy, SimulationResult, UniformRollout};
+#[cfg(feature = "std")]
+pub use scenarios::{OilSprings, Rivers, Scenario, ScenarioAction};
+#[cfg(feature = "schemars")]
+pub use schema::{export_schemas, schema, SCHEMAS};
+pub use score::{GameSummary, PlayerSummary, VpBreakdown};
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub use selfplay::{
+    read_self_play_log, Agent, AgentController, RandomAgent, SelfPlay, SelfPlayGame, SelfPlayRecord,
+};
+#[cfg(all(feature = "rand", feature = "serde"))]
+pub use simulator::{SimulationReport, Simulator};
+#[cfg(all(feature = "std", feature = "serde"))]
+pub use snapshot::SnapshotError;
+#[cfg(feature = "std")]
+pub use tournament::{Format, Standing, Table, Tournament, DEFAULT_PLACEMENT_POINTS};
+pub use trade::{Trade, TradeState};
+#[cfg(feature = "std")]
+pub use trade_manager::TradeManager;
+#[cfg(feature = "std")]
+pub use transfer::{Party, Transfer};
+#[cfg(feature = "ts-rs")]
+pub use typescript::{export_typescript, TypeScript};
+#[cfg(feature = "std")]
+pub use validation::{BoardViolation, ValidationRules};
+#[cfg(feature = "std")]
+pub use view::{OpponentView, PlayerView};
+#[cfg(feature = "wasm-bindgen")]
+pub use wasm::WasmGame;
+#[cfg(all(feature = "std", feature = "serde"))]
+pub use wire::WireFormat;
 
 pub use development_cards::DevelopmentCard::*;
 pub use resources::ResourceKind::*;
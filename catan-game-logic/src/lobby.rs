@@ -0,0 +1,288 @@
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::dice::FairDice;
+use crate::game::Game;
+use crate::options::GameOptions;
+use crate::player::PlayerColour;
+
+/// Seats at a table unless `with_capacity` says otherwise
+pub const DEFAULT_LOBBY_SEATS: usize = 4;
+
+/// Colours handed to bots filling empty seats, in order
+const BOT_COLOURS: [PlayerColour; 4] = [
+    PlayerColour::Red,
+    PlayerColour::Blue,
+    PlayerColour::Green,
+    PlayerColour::Purple,
+];
+
+/// A seat somebody has claimed in a `Lobby`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct LobbySeat {
+    pub colour: PlayerColour,
+    pub ready: bool,
+}
+
+/// Players gathering for a game before it starts
+///
+/// players claim a colour each, the first to arrive becoming the host, and say when they're
+/// ready. Once everyone is, the host starts the game, with bots taking any empty seats if the
+/// lobby fills them
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Lobby {
+    options: GameOptions,
+    seats: Vec<LobbySeat>,
+    capacity: usize,
+    host: Option<PlayerColour>,
+    fill_with_bots: bool,
+}
+
+impl Lobby {
+    pub fn new(options: GameOptions) -> Self {
+        Self {
+            options,
+            seats: Vec::new(),
+            capacity: DEFAULT_LOBBY_SEATS,
+            host: None,
+            fill_with_bots: false,
+        }
+    }
+
+    /// Seat at most `capacity` players, bots included
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Fill any seats still empty when the game starts with bots
+    pub fn with_bots(mut self, fill_with_bots: bool) -> Self {
+        self.fill_with_bots = fill_with_bots;
+        self
+    }
+
+    pub fn options(&self) -> &GameOptions {
+        &self.options
+    }
+
+    /// Claimed seats, in the order they were claimed, which becomes the turn order
+    pub fn seats(&self) -> &[LobbySeat] {
+        &self.seats
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn host(&self) -> Option<PlayerColour> {
+        self.host
+    }
+
+    pub fn fills_with_bots(&self) -> bool {
+        self.fill_with_bots
+    }
+
+    /// Take the seat for `colour`, becoming the host if nobody else is here
+    pub fn claim(&mut self, colour: PlayerColour) -> Result<()> {
+        if self.seat(&colour).is_some() {
+            return Err(anyhow!("{} is already taken", colour));
+        }
+        if self.seats.len() >= self.capacity {
+            return Err(anyhow!("The lobby is full"));
+        }
+        self.seats.push(LobbySeat {
+            colour,
+            ready: false,
+        });
+        self.host.get_or_insert(colour);
+        Ok(())
+    }
+
+    /// Give up `colour`'s seat, handing the host on to whoever arrived next
+    pub fn release(&mut self, colour: PlayerColour) -> Result<()> {
+        let idx = self
+            .seats
+            .iter()
+            .position(|seat| seat.colour == colour)
+            .ok_or(anyhow!("{} hasn't got a seat", colour))?;
+        self.seats.remove(idx);
+        if self.host == Some(colour) {
+            self.host = self.seats.first().map(|seat| seat.colour);
+        }
+        Ok(())
+    }
+
+    pub fn set_ready(&mut self, colour: PlayerColour, ready: bool) -> Result<()> {
+        let seat = self
+            .seats
+            .iter_mut()
+            .find(|seat| seat.colour == colour)
+            .ok_or(anyhow!("{} hasn't got a seat", colour))?;
+        seat.ready = ready;
+        Ok(())
+    }
+
+    /// Whether the game can start, i.e. everybody seated is ready and there'll be at least two
+    /// players once any bots sit down
+    pub fn is_ready(&self) -> bool {
+        let players = match self.fill_with_bots {
+            true => self
+                .capacity
+                .min(self.seats.len() + self.bot_colours().count()),
+            false => self.seats.len(),
+        };
+        players >= 2 && self.seats.iter().all(|seat| seat.ready)
+    }
+
+    /// Start the game on `player`'s say so, who has to be the host
+    ///
+    /// players take their turns in the order they claimed their seats, followed by any bots
+    pub fn start(&self, player: PlayerColour) -> Result<Game> {
+        self.start_with(player, self.options)
+    }
+
+    /// Start the game like `start`, but with the board, the deck, the dice and everything else
+    /// left to chance reproducible from `seed`
+    ///
+    /// replaying the same actions on a game started from the same lobby and seed always ends
+    /// up in the same place
+    pub fn start_seeded(&self, player: PlayerColour, seed: u64) -> Result<Game> {
+        let mut game = self.start_with(
+            player,
+            GameOptions {
+                deck_seed: self.options.deck_seed.or(Some(seed)),
+                board_seed: self.options.board_seed.or(Some(seed)),
+                ..self.options
+            },
+        )?;
+        game.set_dice_roller(FairDice::seeded(seed));
+        game.seed_rng(StdRng::seed_from_u64(seed).gen());
+        Ok(game)
+    }
+
+    fn start_with(&self, player: PlayerColour, options: GameOptions) -> Result<Game> {
+        if self.host != Some(player) {
+            return Err(anyhow!("Only the host can start the game"));
+        }
+        if !self.is_ready() {
+            return Err(anyhow!("Not everyone is ready"));
+        }
+
+        let mut game = Game::with_options(options);
+        for seat in &self.seats {
+            game.add_player(seat.colour);
+        }
+        if self.fill_with_bots {
+            let empty = self.capacity.saturating_sub(self.seats.len());
+            for colour in self.bot_colours().take(empty) {
+                game.add_player(colour);
+                game.get_player_mut(colour)?.set_bot(true);
+            }
+        }
+        Ok(game)
+    }
+
+    fn seat(&self, colour: &PlayerColour) -> Option<&LobbySeat> {
+        self.seats.iter().find(|seat| seat.colour == *colour)
+    }
+
+    /// Colours nobody has claimed, which bots can have
+    fn bot_colours(&self) -> impl Iterator<Item = PlayerColour> + '_ {
+        BOT_COLOURS
+            .into_iter()
+            .filter(|colour| self.seat(colour).is_none())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::selfplay::{Agent, RandomAgent};
+
+    #[test]
+    fn test_seats() {
+        let mut lobby = Lobby::new(GameOptions::default()).with_capacity(2);
+        lobby.claim(PlayerColour::Red).unwrap();
+        assert!(lobby.claim(PlayerColour::Red).is_err());
+        lobby.claim(PlayerColour::Blue).unwrap();
+        assert!(lobby.claim(PlayerColour::Green).is_err());
+        assert_eq!(lobby.host(), Some(PlayerColour::Red));
+
+        lobby.release(PlayerColour::Red).unwrap();
+        assert_eq!(lobby.host(), Some(PlayerColour::Blue));
+        assert!(lobby.release(PlayerColour::Red).is_err());
+        lobby.claim(PlayerColour::Green).unwrap();
+        assert_eq!(lobby.seats().len(), 2);
+    }
+
+    #[test]
+    fn test_start() {
+        let mut lobby = Lobby::new(GameOptions::default());
+        lobby.claim(PlayerColour::Red).unwrap();
+        lobby.claim(PlayerColour::Blue).unwrap();
+        lobby.set_ready(PlayerColour::Red, true).unwrap();
+        assert!(!lobby.is_ready());
+        assert!(lobby.start(PlayerColour::Red).is_err());
+
+        lobby.set_ready(PlayerColour::Blue, true).unwrap();
+        assert!(lobby.start(PlayerColour::Blue).is_err());
+        let game = lobby.start(PlayerColour::Red).unwrap();
+        let players: Vec<_> = game.players().iter().map(|p| *p.colour()).collect();
+        assert_eq!(players, vec![PlayerColour::Red, PlayerColour::Blue]);
+    }
+
+    #[test]
+    fn test_bots() {
+        let mut lobby = Lobby::new(GameOptions::default()).with_bots(true);
+        lobby.claim(PlayerColour::Blue).unwrap();
+        lobby.set_ready(PlayerColour::Blue, true).unwrap();
+        assert!(lobby.is_ready());
+
+        let game = lobby.start(PlayerColour::Blue).unwrap();
+        assert_eq!(game.players().len(), DEFAULT_LOBBY_SEATS);
+        assert!(!game.players()[0].is_bot());
+        assert!(game.players()[1..].iter().all(|p| p.is_bot()));
+        assert_eq!(*game.players()[1].colour(), PlayerColour::Red);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_start_seeded() {
+        let mut lobby = Lobby::new(GameOptions::default());
+        for colour in [PlayerColour::Red, PlayerColour::Blue] {
+            lobby.claim(colour).unwrap();
+            lobby.set_ready(colour, true).unwrap();
+        }
+        let mut a = lobby.start_seeded(PlayerColour::Red, 4).unwrap();
+        let mut b = lobby.start_seeded(PlayerColour::Red, 4).unwrap();
+        assert!(lobby.start_seeded(PlayerColour::Blue, 4).is_err());
+
+        // the same moves play out the same way, dice and all
+        for game in [&mut a, &mut b] {
+            let mut rng = StdRng::seed_from_u64(9);
+            for _ in 0..300 {
+                let player = game.awaiting_player().unwrap();
+                let view = game.view_for(&player).unwrap();
+                game.apply(player, RandomAgent::default().act(&view, &mut rng))
+                    .unwrap();
+            }
+        }
+        assert_eq!(a.events(), b.events());
+        assert_eq!(a.zobrist(), b.zobrist());
+    }
+}
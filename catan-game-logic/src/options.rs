@@ -0,0 +1,196 @@
+use core::time::Duration;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::building::Building;
+use crate::development_cards::DevelopmentCardCounts;
+use crate::resources::Resources;
+
+/// How long players get to make each decision, unless the game is set up otherwise
+pub const DEFAULT_DECISION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Players holding more than this many cards when a 7 is rolled have to discard half of them,
+/// unless the game is set up otherwise
+pub const DISCARD_THRESHOLD: usize = 7;
+
+/// Rule variants and settings chosen when a game is created
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GameOptions {
+    /// Play with the Fishermen of Catan variant, where the desert becomes a lake and settlers
+    /// next to it haul in fish to spend on special actions
+    pub fishermen: bool,
+    /// Play with the Harbormaster card, held by whoever has the most buildings on harbors
+    pub harbormaster: bool,
+    pub timeouts: DecisionTimeouts,
+    /// How long players have to make their moves, see `Game::time_remaining`
+    pub clock: TurnClock,
+    /// What happens to a player who runs out of time on the clock
+    pub clock_expiry: ClockExpiry,
+    /// What happens to the turns of players who resign, see `Game::resign`
+    pub resignation: Resignation,
+    pub costs: CostTable,
+    /// Players holding more than this many cards when a 7 is rolled have to discard half
+    pub discard_threshold: usize,
+    /// Most cards a player can hold, cards the bank would pay out beyond this stay in the bank
+    pub hand_limit: Option<usize>,
+    /// House rule where the robber names the resource to steal, rather than taking a card at
+    /// random
+    pub choose_stolen_resource: bool,
+    /// Cards in the development card deck, the base game's 25 unless a variant changes them
+    pub development_cards: DevelopmentCardCounts,
+    /// Shuffle the development card deck reproducibly, otherwise it's shuffled from the thread's
+    /// random number generator
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
+    pub deck_seed: Option<u64>,
+    /// Lay out the board reproducibly, otherwise it comes from the thread's random number
+    /// generator
+    #[cfg_attr(feature = "ts-rs", ts(type = "number | null"))]
+    pub board_seed: Option<u64>,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            fishermen: false,
+            harbormaster: false,
+            timeouts: DecisionTimeouts::default(),
+            clock: TurnClock::default(),
+            clock_expiry: ClockExpiry::default(),
+            resignation: Resignation::default(),
+            costs: CostTable::default(),
+            discard_threshold: DISCARD_THRESHOLD,
+            hand_limit: None,
+            choose_stolen_resource: false,
+            development_cards: DevelopmentCardCounts::base_game(),
+            deck_seed: None,
+            board_seed: None,
+        }
+    }
+}
+
+/// How long each player has to make their moves, whenever the game is waiting on them
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TurnClock {
+    /// Players can take as long as they like
+    #[default]
+    Untimed,
+    /// Each move has to be made within the same time limit
+    PerMove(#[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))] Duration),
+    /// Each player has a bank of time which runs down while the game waits on them, topped up
+    /// by `increment` for every move they make
+    ChessClock {
+        #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))]
+        initial: Duration,
+        #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))]
+        increment: Duration,
+    },
+}
+
+/// What the game does when a player runs out of time on the clock
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ClockExpiry {
+    /// Make the least the game needs to carry on, discarding at random, rolling the dice or
+    /// ending the turn
+    #[default]
+    DefaultMove,
+    /// Hand the player's seat to a bot, see `Player::is_bot`
+    ReplaceWithBot,
+}
+
+/// What the game does with the turns of a player who resigns
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Resignation {
+    /// Pass over their turns, making the least the game needs from them when a 7 is rolled
+    #[default]
+    SkipTurns,
+    /// Hand their seat to a bot, see `Player::is_bot`
+    Bot,
+}
+
+/// What everything costs to build or buy
+///
+/// defaults to the prices in the official rules, variants can change them for a game through
+/// `GameOptions::costs`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CostTable {
+    pub settlement: Resources,
+    pub city: Resources,
+    pub road: Resources,
+    pub development_card: Resources,
+}
+
+impl CostTable {
+    /// Price of building `kind`
+    pub fn building(&self, kind: Building) -> Resources {
+        match kind {
+            Building::Settlement => self.settlement,
+            Building::City => self.city,
+            Building::Road => self.road,
+        }
+    }
+}
+
+impl Default for CostTable {
+    fn default() -> Self {
+        Self {
+            settlement: Resources::new_explicit(0, 1, 1, 1, 1),
+            city: Resources::new_explicit(3, 2, 0, 0, 0),
+            road: Resources::new_explicit(0, 0, 0, 1, 1),
+            development_card: Resources::new_explicit(1, 1, 1, 0, 0),
+        }
+    }
+}
+
+/// How long players have to make decisions outside of their own turn, before the game makes a
+/// default choice for them
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DecisionTimeouts {
+    /// Choosing which cards to discard after a 7, otherwise they're discarded at random
+    #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))]
+    pub discard: Duration,
+    /// Moving the robber after a 7, otherwise it's moved to a random tile without stealing
+    #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))]
+    pub move_robber: Duration,
+    /// Responding to a trade offer, otherwise the offer is declined
+    #[cfg_attr(feature = "ts-rs", ts(as = "crate::typescript::DurationJson"))]
+    pub trade_response: Duration,
+}
+
+impl Default for DecisionTimeouts {
+    fn default() -> Self {
+        Self {
+            discard: DEFAULT_DECISION_TIMEOUT,
+            move_robber: DEFAULT_DECISION_TIMEOUT,
+            trade_response: DEFAULT_DECISION_TIMEOUT,
+        }
+    }
+}
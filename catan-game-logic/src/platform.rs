@@ -0,0 +1,48 @@
+//! What the engine needs from whatever it's running on, which is the time and a source of
+//! entropy, so that both work in the browser too
+
+#[cfg(feature = "rand")]
+use rand::{rngs::StdRng, SeedableRng};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+/// A generator for anything left to chance which wasn't given a seed, drawing on the operating
+/// system, or the browser's crypto API under wasm
+///
+/// this is the only place the engine gets entropy from, so a game built entirely from seeds, like
+/// one from `Lobby::start_seeded`, plays out the same everywhere
+#[cfg(feature = "rand")]
+pub(crate) fn entropy_rng() -> StdRng {
+    StdRng::from_entropy()
+}
+
+/// The current time, for deadlines, clocks and timestamps
+///
+/// `SystemTime::now` panics in the browser, so under wasm this asks JavaScript instead
+#[cfg(feature = "std")]
+pub(crate) fn now() -> SystemTime {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm-bindgen"))]
+    return std::time::UNIX_EPOCH + std::time::Duration::from_millis(js_sys::Date::now() as u64);
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm-bindgen")))]
+    SystemTime::now()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    #[cfg(feature = "rand")]
+    use rand::Rng;
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_entropy_rng() {
+        let (a, b): (u64, u64) = (entropy_rng().gen(), entropy_rng().gen());
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_now() {
+        let before = SystemTime::now();
+        assert!(now() >= before);
+    }
+}
@@ -1,9 +1,29 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::str::FromStr;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::{
+    achievements::Achievement, building::Building, development_cards::DevelopmentCard,
+    resources::Resources,
+};
 
-use crate::{development_cards::DevelopmentCard, resources::Resources};
+pub const STARTING_SETTLEMENTS: usize = 5;
+pub const STARTING_CITIES: usize = 4;
+pub const STARTING_ROADS: usize = 15;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PlayerColour {
     Red,
     Green,
@@ -12,12 +32,134 @@ pub enum PlayerColour {
     Custom { r: u8, g: u8, b: u8 },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+/// Written as its name in lower case, or as a hex code like `#ff8800` for custom colours
+impl Display for PlayerColour {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PlayerColour::Custom { r, g, b } => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            colour => f.write_str(colour.name()),
+        }
+    }
+}
+
+/// Reads back what `Display` writes, ignoring case
+impl FromStr for PlayerColour {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or(anyhow!("Invalid colour code {}", s))
+            };
+            if hex.len() != 6 {
+                return Err(anyhow!("Invalid colour code {}", s));
+            }
+
+            return Ok(PlayerColour::Custom {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            });
+        }
+        [
+            PlayerColour::Red,
+            PlayerColour::Green,
+            PlayerColour::Blue,
+            PlayerColour::Purple,
+        ]
+        .into_iter()
+        .find(|colour| s.eq_ignore_ascii_case(colour.name()))
+        .ok_or(anyhow!("Unknown colour {}", s))
+    }
+}
+
+impl PlayerColour {
+    /// Lower case name of a standard colour
+    fn name(&self) -> &'static str {
+        match self {
+            PlayerColour::Red => "red",
+            PlayerColour::Green => "green",
+            PlayerColour::Blue => "blue",
+            PlayerColour::Purple => "purple",
+            PlayerColour::Custom { .. } => "custom",
+        }
+    }
+}
+
+/// Identifies a player within a game, where every player has their own colour
+pub type PlayerId = PlayerColour;
+
+/// Buildings a player has yet to place on the board
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BuildingStock {
+    settlements: usize,
+    cities: usize,
+    roads: usize,
+}
+
+impl BuildingStock {
+    pub fn new() -> Self {
+        Self {
+            settlements: STARTING_SETTLEMENTS,
+            cities: STARTING_CITIES,
+            roads: STARTING_ROADS,
+        }
+    }
+
+    pub fn remaining(&self, kind: Building) -> usize {
+        match kind {
+            Building::Settlement => self.settlements,
+            Building::City => self.cities,
+            Building::Road => self.roads,
+        }
+    }
+
+    fn count_mut(&mut self, kind: Building) -> &mut usize {
+        match kind {
+            Building::Settlement => &mut self.settlements,
+            Building::City => &mut self.cities,
+            Building::Road => &mut self.roads,
+        }
+    }
+
+    /// Take a piece out of the stock to place on the board, fails if there are none left
+    pub fn take(&mut self, kind: Building) -> Result<()> {
+        let count = self.count_mut(kind);
+        if *count == 0 {
+            return Err(anyhow!("No pieces of that kind left to place"));
+        }
+        *count -= 1;
+        Ok(())
+    }
+
+    /// Return a piece removed from the board to the stock
+    pub fn put_back(&mut self, kind: Building) {
+        *self.count_mut(kind) += 1;
+    }
+}
+
+impl Default for BuildingStock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Player {
     colour: PlayerColour,
     resources: Resources,
     development_cards: Vec<DevelopmentCard>,
     victory_points: usize,
+    stock: BuildingStock,
+    knights_played: usize,
+    chits: usize,
+    bot: bool,
+    fish: usize,
+    achievements: Vec<Achievement>,
 }
 
 impl Player {
@@ -27,6 +169,12 @@ impl Player {
             resources: Resources::new(),
             development_cards: Vec::new(),
             victory_points: 0,
+            stock: BuildingStock::new(),
+            knights_played: 0,
+            chits: 0,
+            bot: false,
+            fish: 0,
+            achievements: Vec::new(),
         }
     }
 
@@ -38,7 +186,118 @@ impl Player {
         &mut self.resources
     }
 
+    /// Number of resource cards in the player's hand, which everyone at the table can see
+    pub fn hand_size(&self) -> usize {
+        self.resources.total()
+    }
+
     pub fn colour(&self) -> &PlayerColour {
         &self.colour
     }
+
+    pub fn development_cards(&self) -> &[DevelopmentCard] {
+        &self.development_cards
+    }
+
+    pub fn development_cards_mut(&mut self) -> &mut Vec<DevelopmentCard> {
+        &mut self.development_cards
+    }
+
+    pub fn victory_points(&self) -> usize {
+        self.victory_points
+    }
+
+    pub fn victory_points_mut(&mut self) -> &mut usize {
+        &mut self.victory_points
+    }
+
+    pub fn stock(&self) -> &BuildingStock {
+        &self.stock
+    }
+
+    pub fn stock_mut(&mut self) -> &mut BuildingStock {
+        &mut self.stock
+    }
+
+    /// Number of knights this player has played so far
+    pub fn knights_played(&self) -> usize {
+        self.knights_played
+    }
+
+    pub fn knights_played_mut(&mut self) -> &mut usize {
+        &mut self.knights_played
+    }
+
+    /// Victory point chits this player has been given by a scenario
+    pub fn chits(&self) -> usize {
+        self.chits
+    }
+
+    pub fn chits_mut(&mut self) -> &mut usize {
+        &mut self.chits
+    }
+
+    /// Whether this seat is played by a bot rather than a person
+    pub fn is_bot(&self) -> bool {
+        self.bot
+    }
+
+    pub fn set_bot(&mut self, bot: bool) {
+        self.bot = bot;
+    }
+
+    /// Fish this player has caught and not yet spent, in the Fishermen variant
+    pub fn fish(&self) -> usize {
+        self.fish
+    }
+
+    pub fn fish_mut(&mut self) -> &mut usize {
+        &mut self.fish
+    }
+
+    /// Badges this player has earned so far, in the order they earned them
+    pub fn achievements(&self) -> &[Achievement] {
+        &self.achievements
+    }
+
+    pub fn achievements_mut(&mut self) -> &mut Vec<Achievement> {
+        &mut self.achievements
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_colour_text() {
+        for colour in [
+            PlayerColour::Red,
+            PlayerColour::Purple,
+            PlayerColour::Custom {
+                r: 255,
+                g: 136,
+                b: 0,
+            },
+        ] {
+            assert_eq!(colour.to_string().parse::<PlayerColour>().unwrap(), colour);
+        }
+        assert_eq!(
+            PlayerColour::Custom { r: 1, g: 2, b: 3 }.to_string(),
+            "#010203"
+        );
+        assert_eq!("Blue".parse::<PlayerColour>().unwrap(), PlayerColour::Blue);
+        assert_eq!(
+            "#FF8800".parse::<PlayerColour>().unwrap(),
+            PlayerColour::Custom {
+                r: 255,
+                g: 136,
+                b: 0
+            }
+        );
+        for bad in ["custom", "orange", "#ff88", "#gg8800", "#ff88001"] {
+            assert!(bad.parse::<PlayerColour>().is_err());
+        }
+    }
 }
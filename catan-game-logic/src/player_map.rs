@@ -0,0 +1,272 @@
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::player::{Player, PlayerColour, PlayerId};
+use crate::zobrist::player_key;
+
+/// How many of the standard colours there are, each of which gets a slot in a `PlayerMap`'s
+/// index
+const STANDARD_COLOURS: usize = 4;
+
+/// A game's players in turn order, which can also be looked up by id
+///
+/// reading the players goes through the turn order slice, while anything which adds, removes or
+/// reorders them goes through the map. Players in the standard colours are found in constant time
+/// through a fixed array of their places in the turn order, which is cheap to copy along with
+/// the game, while the odd custom colour is found by scanning the list. Serialises as the plain
+/// list of players
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<Player>", into = "Vec<Player>"))]
+pub struct PlayerMap {
+    players: Vec<Player>,
+    /// Where each of the standard colours sits in `players`, rebuilt whenever the turn order
+    /// changes
+    index: [Option<u8>; STANDARD_COLOURS],
+    /// Zobrist hash of every player's cards, kept up to date as they change
+    hash: u64,
+}
+
+impl PlayerMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `player` to the end of the turn order, unless a player with the same id is already
+    /// here, returning whether they were added
+    pub fn push(&mut self, player: Player) -> bool {
+        if self.position(player.colour()).is_some() {
+            return false;
+        }
+        if let Some(slot) = slot(player.colour()) {
+            self.index[slot] = Some(self.players.len() as u8);
+        }
+        self.hash ^= player_key(&player);
+        self.players.push(player);
+        true
+    }
+
+    pub fn get(&self, id: &PlayerId) -> Option<&Player> {
+        self.players.get(self.position(id)?)
+    }
+
+    pub fn get_mut(&mut self, id: &PlayerId) -> Option<PlayerMut<'_>> {
+        let idx = self.position(id)?;
+        Some(PlayerMut::new(&mut self.players[idx], &mut self.hash))
+    }
+
+    /// Where `id` sits in the turn order
+    pub fn position(&self, id: &PlayerId) -> Option<usize> {
+        match slot(id) {
+            Some(slot) => self.index[slot].map(usize::from),
+            None => self.players.iter().position(|player| player.colour() == id),
+        }
+    }
+
+    /// Take `id` out of the game, closing up the turn order behind them
+    pub fn remove(&mut self, id: &PlayerId) -> Option<Player> {
+        let idx = self.position(id)?;
+        let player = self.players.remove(idx);
+        self.hash ^= player_key(&player);
+        self.reindex();
+        Some(player)
+    }
+
+    /// Change the turn order with `f`, e.g. by shuffling or sorting the players
+    pub fn reorder(&mut self, f: impl FnOnce(&mut [Player])) {
+        f(&mut self.players);
+        self.reindex();
+    }
+
+    /// Point the index back at where everyone is after the turn order has changed
+    fn reindex(&mut self) {
+        self.index = Default::default();
+        for (idx, player) in self.players.iter().enumerate() {
+            if let Some(slot) = slot(player.colour()) {
+                self.index[slot] = Some(idx as u8);
+            }
+        }
+    }
+
+    /// Zobrist hash of the players' resource and development cards and the knights they've
+    /// played, whatever order they're in
+    ///
+    /// it's updated whenever a player is added, removed or let go of by `get_mut`, so reading it
+    /// is free
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    /// Work the players' hash out from scratch
+    pub(crate) fn full_hash(&self) -> u64 {
+        self.players
+            .iter()
+            .fold(0, |hash, player| hash ^ player_key(player))
+    }
+}
+
+/// Which slot of a `PlayerMap`'s index `id` has, if it's one of the standard colours
+fn slot(id: &PlayerId) -> Option<usize> {
+    match id {
+        PlayerColour::Red => Some(0),
+        PlayerColour::Green => Some(1),
+        PlayerColour::Blue => Some(2),
+        PlayerColour::Purple => Some(3),
+        PlayerColour::Custom { .. } => None,
+    }
+}
+
+/// A player borrowed from a `PlayerMap`, which folds whatever changed about them into the map's
+/// hash when it's let go of
+pub struct PlayerMut<'a> {
+    player: &'a mut Player,
+    hash: &'a mut u64,
+    before: u64,
+}
+
+impl<'a> PlayerMut<'a> {
+    fn new(player: &'a mut Player, hash: &'a mut u64) -> Self {
+        let before = player_key(player);
+        Self {
+            player,
+            hash,
+            before,
+        }
+    }
+}
+
+impl Deref for PlayerMut<'_> {
+    type Target = Player;
+
+    fn deref(&self) -> &Self::Target {
+        self.player
+    }
+}
+
+impl DerefMut for PlayerMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.player
+    }
+}
+
+impl Drop for PlayerMut<'_> {
+    fn drop(&mut self) {
+        *self.hash ^= self.before ^ player_key(self.player);
+    }
+}
+
+impl Deref for PlayerMap {
+    type Target = [Player];
+
+    fn deref(&self) -> &Self::Target {
+        &self.players
+    }
+}
+
+impl From<Vec<Player>> for PlayerMap {
+    fn from(players: Vec<Player>) -> Self {
+        let mut map = Self::new();
+        for player in players {
+            map.push(player);
+        }
+        map
+    }
+}
+
+impl From<PlayerMap> for Vec<Player> {
+    fn from(map: PlayerMap) -> Self {
+        map.players
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resources::ResourceKind;
+
+    fn map() -> PlayerMap {
+        [PlayerColour::Red, PlayerColour::Blue, PlayerColour::Green]
+            .into_iter()
+            .map(Player::new)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut players = map();
+        assert!(!players.push(Player::new(PlayerColour::Red)));
+        assert_eq!(players.len(), 3);
+        assert_eq!(players.position(&PlayerColour::Blue), Some(1));
+        *players
+            .get_mut(&PlayerColour::Green)
+            .unwrap()
+            .victory_points_mut() = 2;
+        assert_eq!(players[2].victory_points(), 2);
+        assert!(players.get(&PlayerColour::Purple).is_none());
+    }
+
+    #[test]
+    fn test_zobrist() {
+        let mut players = map();
+        let start = players.zobrist();
+        assert_eq!(start, players.full_hash());
+
+        players
+            .get_mut(&PlayerColour::Blue)
+            .unwrap()
+            .resources_mut()[ResourceKind::Ore] += 2;
+        assert_ne!(players.zobrist(), start);
+        assert_eq!(players.zobrist(), players.full_hash());
+
+        // turn order doesn't come into it
+        players.reorder(|p| p.reverse());
+        assert_eq!(players.zobrist(), players.full_hash());
+        players.remove(&PlayerColour::Red);
+        assert_eq!(players.zobrist(), players.full_hash());
+    }
+
+    #[test]
+    fn test_reorder_and_remove() {
+        let mut players = map();
+        players.reorder(|p| p.reverse());
+        assert_eq!(*players[0].colour(), PlayerColour::Green);
+        assert_eq!(players.position(&PlayerColour::Red), Some(2));
+
+        let blue = players.remove(&PlayerColour::Blue).unwrap();
+        assert_eq!(*blue.colour(), PlayerColour::Blue);
+        assert_eq!(players.position(&PlayerColour::Red), Some(1));
+        assert!(players.remove(&PlayerColour::Blue).is_none());
+        assert_eq!(
+            *players.get(&PlayerColour::Green).unwrap().colour(),
+            PlayerColour::Green
+        );
+    }
+
+    #[test]
+    fn test_custom_colour() {
+        let mut players = map();
+        let custom = PlayerColour::Custom { r: 1, g: 2, b: 3 };
+        assert!(players.push(Player::new(custom)));
+        assert!(!players.push(Player::new(custom)));
+        players.reorder(|p| p.rotate_left(1));
+        assert_eq!(players.position(&custom), Some(2));
+        assert_eq!(players.position(&PlayerColour::Red), Some(3));
+        players.remove(&PlayerColour::Green);
+        assert_eq!(*players.get(&custom).unwrap().colour(), custom);
+        assert_eq!(players.position(&PlayerColour::Blue), Some(0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let players = map();
+        let json = serde_json::to_string(&players).unwrap();
+        assert_eq!(json, serde_json::to_string(&*players).unwrap());
+        let back: PlayerMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, players);
+        assert_eq!(back.position(&PlayerColour::Green), Some(2));
+    }
+}
@@ -0,0 +1,271 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::player::PlayerColour;
+use crate::report::GameReport;
+
+/// A player's standing on the ladder
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rating {
+    pub rating: f64,
+    /// Rated games the player has finished
+    pub games: usize,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: Elo::STARTING_RATING,
+            games: 0,
+        }
+    }
+}
+
+/// How one player's rating moved after a game
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RatingChange {
+    pub player: String,
+    pub colour: PlayerColour,
+    pub before: f64,
+    pub after: f64,
+}
+
+/// Somewhere to keep ratings between games, keyed by whatever a server uses to recognise a
+/// player from one game to the next, such as their account name
+pub trait RatingStore: Debug + Send + Sync {
+    /// The rating for `player`, if they've been rated before
+    fn get(&self, player: &str) -> Result<Option<Rating>>;
+
+    /// Write `rating` for `player`, replacing whatever was there
+    fn set(&self, player: &str, rating: Rating) -> Result<()>;
+
+    /// Every rated player, best first
+    fn standings(&self) -> Result<Vec<(String, Rating)>>;
+}
+
+/// Ratings kept in memory, which are lost when the process exits
+#[derive(Debug, Default)]
+pub struct MemoryRatings {
+    ratings: Mutex<HashMap<String, Rating>>,
+}
+
+impl MemoryRatings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn ratings(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, Rating>>> {
+        self.ratings
+            .lock()
+            .map_err(|_| anyhow!("The ratings lock was poisoned"))
+    }
+}
+
+impl RatingStore for MemoryRatings {
+    fn get(&self, player: &str) -> Result<Option<Rating>> {
+        Ok(self.ratings()?.get(player).copied())
+    }
+
+    fn set(&self, player: &str, rating: Rating) -> Result<()> {
+        self.ratings()?.insert(player.to_string(), rating);
+        Ok(())
+    }
+
+    fn standings(&self) -> Result<Vec<(String, Rating)>> {
+        let mut standings: Vec<_> = self
+            .ratings()?
+            .iter()
+            .map(|(player, rating)| (player.clone(), *rating))
+            .collect();
+        standings.sort_by(|(_, a), (_, b)| b.rating.total_cmp(&a.rating));
+        Ok(standings)
+    }
+}
+
+/// Multiplayer Elo, scoring a game as a round of head-to-head matches between every pair of
+/// players
+///
+/// the winner beats everyone, and the rest are placed by their victory points, drawing with
+/// anyone on the same score
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Elo {
+    /// Most a player's rating can move in one game
+    pub k: f64,
+}
+
+impl Default for Elo {
+    fn default() -> Self {
+        Self { k: 32.0 }
+    }
+}
+
+impl Elo {
+    pub const STARTING_RATING: f64 = 1500.0;
+
+    pub fn new(k: f64) -> Self {
+        Self { k }
+    }
+
+    /// Chance a player rated `rating` beats one rated `opponent`
+    pub fn expected(rating: f64, opponent: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+    }
+
+    /// Rate a finished game, where `players` says who sat in each seat, saving the new ratings
+    /// to `store`
+    ///
+    /// players missing from `players`, such as bots, aren't rated and don't count against
+    /// anyone. Fails without changing anything if the game has no winner, or fewer than two
+    /// players would be rated
+    pub fn rate(
+        &self,
+        store: &dyn RatingStore,
+        report: &GameReport,
+        players: &[(PlayerColour, &str)],
+    ) -> Result<Vec<RatingChange>> {
+        let winner = report
+            .winner
+            .ok_or(anyhow!("Only games with a winner can be rated"))?;
+        let mut seated = Vec::new();
+        for (colour, player) in players {
+            let points = report
+                .player(colour)
+                .ok_or(anyhow!("{} didn't play in this game", colour))?
+                .victory_points;
+            let rating = store.get(player)?.unwrap_or_default();
+            seated.push((*colour, *player, points, rating));
+        }
+        if seated.len() < 2 {
+            return Err(anyhow!("At least two players are needed for a rated game"));
+        }
+
+        let placing = |colour: PlayerColour, points: usize| match colour == winner {
+            true => usize::MAX,
+            false => points,
+        };
+        let opponents = (seated.len() - 1) as f64;
+        let mut changes = Vec::new();
+        for &(colour, player, points, rating) in &seated {
+            let delta: f64 = seated
+                .iter()
+                .filter(|(other, ..)| *other != colour)
+                .map(|&(other, _, other_points, other_rating)| {
+                    let score = match placing(colour, points).cmp(&placing(other, other_points)) {
+                        Ordering::Greater => 1.0,
+                        Ordering::Equal => 0.5,
+                        Ordering::Less => 0.0,
+                    };
+                    score - Self::expected(rating.rating, other_rating.rating)
+                })
+                .sum();
+            changes.push(RatingChange {
+                player: player.to_string(),
+                colour,
+                before: rating.rating,
+                after: rating.rating + self.k * delta / opponents,
+            });
+        }
+
+        for (change, (.., rating)) in changes.iter().zip(&seated) {
+            store.set(
+                &change.player,
+                Rating {
+                    rating: change.after,
+                    games: rating.games + 1,
+                },
+            )?;
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dice::Luck;
+    use crate::report::PlayerReport;
+
+    fn report(winner: Option<PlayerColour>, points: &[(PlayerColour, usize)]) -> GameReport {
+        GameReport {
+            winner,
+            turns: 40,
+            players: points
+                .iter()
+                .map(|&(colour, victory_points)| PlayerReport {
+                    colour,
+                    income: Default::default(),
+                    trades: Default::default(),
+                    luck: Luck::new(colour),
+                    victory_points,
+                })
+                .collect(),
+            dice: Vec::new(),
+            victory_points: Vec::new(),
+            awards: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_expected() {
+        assert_eq!(Elo::expected(1500.0, 1500.0), 0.5);
+        assert!((Elo::expected(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!(Elo::expected(1400.0, 1600.0) < 0.5);
+    }
+
+    #[test]
+    fn test_rate() {
+        let store = MemoryRatings::new();
+        let report = report(
+            Some(PlayerColour::Red),
+            &[
+                (PlayerColour::Red, 10),
+                (PlayerColour::Blue, 6),
+                (PlayerColour::Green, 6),
+                (PlayerColour::Purple, 3),
+            ],
+        );
+        let players = [
+            (PlayerColour::Red, "alice"),
+            (PlayerColour::Blue, "bob"),
+            (PlayerColour::Green, "carol"),
+            (PlayerColour::Purple, "dave"),
+        ];
+        let changes = Elo::default().rate(&store, &report, &players).unwrap();
+
+        // evenly rated players gain for everyone they beat, and lose for everyone who beat them
+        let after: Vec<_> = changes.iter().map(|c| c.after - c.before).collect();
+        assert!((after[0] - 16.0).abs() < 1e-9);
+        assert!(after[1].abs() < 1e-9 && after[2].abs() < 1e-9);
+        assert!((after[3] + 16.0).abs() < 1e-9);
+        assert!(after.iter().sum::<f64>().abs() < 1e-9);
+
+        let standings = store.standings().unwrap();
+        assert_eq!(standings[0].0, "alice");
+        assert_eq!(standings[0].1.games, 1);
+        assert_eq!(standings.last().unwrap().0, "dave");
+    }
+
+    #[test]
+    fn test_unrateable() {
+        let store = MemoryRatings::new();
+        let points = [(PlayerColour::Red, 8), (PlayerColour::Blue, 5)];
+        let players = [(PlayerColour::Red, "alice"), (PlayerColour::Blue, "bob")];
+        let elo = Elo::default();
+
+        assert!(elo.rate(&store, &report(None, &points), &players).is_err());
+        let won = report(Some(PlayerColour::Red), &points);
+        assert!(elo.rate(&store, &won, &players[..1]).is_err());
+        assert!(elo
+            .rate(&store, &won, &[(PlayerColour::Green, "carol"), players[0]])
+            .is_err());
+        assert!(store.standings().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::awards::Award;
+use crate::dice::{combinations_for, Luck};
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+
+/// Where a player's cards came from over a game
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Income {
+    /// Collected from their second settlement during the setup
+    pub setup: Resources,
+    /// Produced by dice rolls
+    pub rolls: Resources,
+    /// Taken from the other players with monopolies
+    pub monopolies: Resources,
+    /// Received from other players in trades
+    pub trades: Resources,
+    /// Cards stolen with the robber, without saying which
+    pub stolen: usize,
+    /// Fish hauled in from the lake
+    pub fish: usize,
+}
+
+impl Income {
+    /// Every resource card the player got, not counting stolen cards or fish
+    pub fn resources(&self) -> Resources {
+        self.setup + self.rolls + self.monopolies + self.trades
+    }
+}
+
+/// A player's trades with the other players over a game
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TradeRecord {
+    /// How many trades the player was part of, on either side
+    pub trades: usize,
+    pub given: Resources,
+    pub received: Resources,
+}
+
+impl TradeRecord {
+    /// How many more cards the player got out of their trades than they gave away
+    pub fn net(&self) -> isize {
+        self.received.total() as isize - self.given.total() as isize
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerReport {
+    pub colour: PlayerColour,
+    pub income: Income,
+    pub trades: TradeRecord,
+    /// Production from rolls against what the player's buildings should have made
+    pub luck: Luck,
+    /// Points at the end of the game, including hidden victory point cards
+    pub victory_points: usize,
+}
+
+/// How often a total came up, next to how often fair dice would roll it
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RollFrequency {
+    pub total: u8,
+    pub rolled: usize,
+    pub expected: f64,
+}
+
+/// A player's visible victory points changing to `points`, during `turn`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PointsChange {
+    pub turn: usize,
+    pub player: PlayerColour,
+    pub points: usize,
+}
+
+/// An award changing hands during `turn`, or going back to the supply
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AwardChange {
+    pub turn: usize,
+    pub award: Award,
+    pub holder: Option<PlayerColour>,
+}
+
+/// Everything a summary screen needs to show how a game went, see `Game::report`
+///
+/// turns are counted by dice rolls, so the setup is turn 0 and the first roll starts turn 1
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameReport {
+    pub winner: Option<PlayerColour>,
+    pub turns: usize,
+    /// Every player in turn order
+    pub players: Vec<PlayerReport>,
+    /// One entry for each total from `MIN_ROLL` to `MAX_ROLL`
+    pub dice: Vec<RollFrequency>,
+    pub victory_points: Vec<PointsChange>,
+    pub awards: Vec<AwardChange>,
+}
+
+impl GameReport {
+    pub fn player(&self, colour: &PlayerColour) -> Option<&PlayerReport> {
+        self.players.iter().find(|p| &p.colour == colour)
+    }
+}
+
+impl Game {
+    /// A breakdown of the game so far, usually asked for once it's over
+    pub fn report(&self) -> GameReport {
+        let summary = self.summary();
+        let mut players: Vec<PlayerReport> = summary
+            .players
+            .iter()
+            .map(|p| PlayerReport {
+                colour: p.colour,
+                income: Income::default(),
+                trades: TradeRecord::default(),
+                luck: self
+                    .luck()
+                    .iter()
+                    .find(|luck| *luck.player() == p.colour)
+                    .copied()
+                    .unwrap_or(Luck::new(p.colour)),
+                victory_points: p.victory_points.total(),
+            })
+            .collect();
+        let mut turn = 0;
+        let mut victory_points = Vec::new();
+        let mut awards = Vec::new();
+        let mut offers: HashMap<Uuid, (Resources, Resources)> = HashMap::new();
+
+        for event in self.events() {
+            match *event {
+                GameEvent::DiceRolled { .. } => turn += 1,
+                GameEvent::Production { player, resources } => {
+                    if let Some(p) = players.iter_mut().find(|p| p.colour == player) {
+                        match turn {
+                            0 => p.income.setup += resources,
+                            _ => p.income.rolls += resources,
+                        }
+                    }
+                }
+                GameEvent::FishHaul { player, fish } => {
+                    if let Some(p) = players.iter_mut().find(|p| p.colour == player) {
+                        p.income.fish += fish;
+                    }
+                }
+                GameEvent::RobberMoved {
+                    player,
+                    stole_card: true,
+                    ..
+                } => {
+                    if let Some(p) = players.iter_mut().find(|p| p.colour == player) {
+                        p.income.stolen += 1;
+                    }
+                }
+                GameEvent::MonopolyPlayed {
+                    player,
+                    resource,
+                    taken,
+                    ..
+                } => {
+                    if let Some(p) = players.iter_mut().find(|p| p.colour == player) {
+                        p.income.monopolies[resource] += taken;
+                    }
+                }
+                GameEvent::TradeProposed {
+                    trade,
+                    offering,
+                    wants,
+                    ..
+                } => {
+                    offers.insert(trade, (offering, wants));
+                }
+                GameEvent::TradeCompleted { trade, from, to } => {
+                    let Some(&(offering, wants)) = offers.get(&trade) else {
+                        continue;
+                    };
+                    for p in players.iter_mut() {
+                        let (given, received) = match p.colour {
+                            colour if colour == from => (offering, wants),
+                            colour if colour == to => (wants, offering),
+                            _ => continue,
+                        };
+                        p.trades.trades += 1;
+                        p.trades.given += given;
+                        p.trades.received += received;
+                        p.income.trades += received;
+                    }
+                }
+                GameEvent::VictoryPointsChanged { player, points } => {
+                    victory_points.push(PointsChange {
+                        turn,
+                        player,
+                        points,
+                    });
+                }
+                GameEvent::AwardChanged { award, holder } => {
+                    awards.push(AwardChange {
+                        turn,
+                        award,
+                        holder,
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        let rolls = self.roll_history().len();
+        let dice = self
+            .dice_histogram()
+            .into_iter()
+            .map(|(total, rolled)| RollFrequency {
+                total,
+                rolled,
+                expected: (rolls * combinations_for(total)) as f64 / 36.0,
+            })
+            .collect();
+
+        GameReport {
+            winner: summary.winner,
+            turns: turn,
+            players,
+            dice,
+            victory_points,
+            awards,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::dice::FixedDice;
+    use crate::game::{GameState, TurnPhase};
+    use crate::options::GameOptions;
+    use crate::rollout::{rollout, UniformRollout};
+
+    /// A game between red, blue and green played out at random
+    fn played_game() -> Game {
+        let mut g = Game::with_options(GameOptions {
+            board_seed: Some(3),
+            deck_seed: Some(3),
+            ..GameOptions::default()
+        });
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.add_player(PlayerColour::Green);
+        let mut rng = StdRng::seed_from_u64(3);
+        rollout(&mut g, &mut UniformRollout, &mut rng, 3000).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_report() {
+        let g = played_game();
+        let report = g.report();
+
+        assert_eq!(report.winner, g.winner());
+        assert_eq!(report.turns, g.roll_history().len());
+        assert_eq!(report.players.len(), 3);
+        // everyone collects from their second settlement during the setup
+        assert!(report.players.iter().all(|p| !p.income.setup.is_empty()));
+
+        assert_eq!(report.dice.len(), 11);
+        let rolled: usize = report.dice.iter().map(|d| d.rolled).sum();
+        let expected: f64 = report.dice.iter().map(|d| d.expected).sum();
+        assert_eq!(rolled, report.turns);
+        assert!((expected - report.turns as f64).abs() < 1e-6);
+        // everyone's luck covers every roll
+        assert!(report
+            .players
+            .iter()
+            .all(|p| p.luck.rolls() == report.turns));
+        let produced: usize = report.players.iter().map(|p| p.luck.produced()).sum();
+        let rolls: usize = report.players.iter().map(|p| p.income.rolls.total()).sum();
+        assert_eq!(produced, rolls);
+
+        // the timeline ends on each player's visible points
+        for p in g.players() {
+            let last = report
+                .victory_points
+                .iter()
+                .rev()
+                .find(|change| &change.player == p.colour());
+            let visible = g.vp_breakdown(p.colour()).unwrap().public();
+            assert_eq!(last.map_or(0, |change| change.points), visible);
+        }
+    }
+
+    #[test]
+    fn test_trades() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.set_dice_roller(FixedDice::new([(3, 5)]));
+        // play through the setup and the first roll
+        while g.state() != GameState::Running || g.phase() != TurnPhase::Main {
+            let player = g.awaiting_player().unwrap();
+            let action = g.legal_actions(&player)[0].clone();
+            g.apply(player, action).unwrap();
+        }
+        let from = *g.current_player().unwrap().colour();
+        let to = match from {
+            PlayerColour::Red => PlayerColour::Blue,
+            _ => PlayerColour::Red,
+        };
+        let offering = Resources::new_explicit(0, 2, 0, 0, 0);
+        let wants = Resources::new_explicit(1, 0, 0, 0, 0);
+        *g.get_player_mut(from).unwrap().resources_mut() += offering;
+        *g.get_player_mut(to).unwrap().resources_mut() += wants;
+
+        let trade = g.propose_trade(from, offering, wants).unwrap();
+        g.accept_trade(trade, to).unwrap();
+        g.get_trades_mut().finalize_trade(trade, to).unwrap();
+        g.finalize_trade(trade).unwrap();
+
+        let report = g.report();
+        assert_eq!(report.turns, 1);
+        let proposer = report.player(&from).unwrap();
+        assert_eq!(proposer.trades.trades, 1);
+        assert_eq!(proposer.trades.net(), -1);
+        assert_eq!(proposer.income.trades, wants);
+        let partner = report.player(&to).unwrap();
+        assert_eq!(partner.trades.net(), 1);
+        assert_eq!(partner.trades.given, wants);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialization() {
+        let report = played_game().report();
+        let json = serde_json::to_string(&report).unwrap();
+        let back: GameReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.players, report.players);
+        assert_eq!(back.victory_points, report.victory_points);
+        // expected counts may come back a rounding error out
+        for (back, dice) in back.dice.iter().zip(&report.dice) {
+            assert_eq!(back.rolled, dice.rolled);
+            assert!((back.expected - dice.expected).abs() < 1e-9);
+        }
+    }
+}
@@ -1,15 +1,30 @@
-use rand::{thread_rng, Rng};
+use alloc::string::{String, ToString};
+use core::fmt::Display;
+use core::ops::{Add, AddAssign};
+use core::ops::{Index, IndexMut};
+use core::ops::{Mul, MulAssign};
+use core::ops::{Sub, SubAssign};
+use core::str::FromStr;
+#[cfg(feature = "rand")]
+use rand::distributions::{Distribution, Standard};
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::mem::variant_count;
-use std::ops::{Add, AddAssign};
-use std::ops::{Index, IndexMut};
-use std::ops::{Mul, MulAssign};
-use std::ops::{Sub, SubAssign};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 
 use crate::building::Building;
-
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Copy, Clone)]
-#[serde(rename_all = "snake_case")]
+#[cfg(feature = "rand")]
+use crate::platform::entropy_rng;
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ResourceKind {
     Ore,
     Grain,
@@ -21,37 +36,148 @@ pub enum ResourceKind {
 use ResourceKind::*;
 
 impl ResourceKind {
+    /// Number of distinct resource kinds
+    pub const COUNT: usize = 5;
+
+    /// Every kind of resource
+    pub const ALL: [ResourceKind; ResourceKind::COUNT] = [Ore, Grain, Wool, Brick, Lumber];
+
+    /// Number of tiles producing this resource in the base game, 18 in all
+    pub fn base_game_tiles(&self) -> usize {
+        match self {
+            Ore | Brick => 3,
+            Grain | Wool | Lumber => 4,
+        }
+    }
+
+    /// A resource picked as if from the base game's tiles, see the `Standard` distribution
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        match rng.gen_range(0..=variant_count::<ResourceKind>() - 1) {
-            0 => Ore,
-            1 => Grain,
-            2 => Wool,
-            3 => Brick,
-            4 => Lumber,
-            n => panic!("Invalid index, i: {}", n),
+        entropy_rng().gen()
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Ore => "ore",
+            Grain => "grain",
+            Wool => "wool",
+            Brick => "brick",
+            Lumber => "lumber",
+        }
+    }
+}
+
+/// Taking more cards of a resource out of a hand, or the bank, than it holds
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InsufficientResources {
+    pub kind: ResourceKind,
+    pub needed: usize,
+    pub available: usize,
+}
+
+impl Display for InsufficientResources {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Not enough {}, needed {} but only {} available",
+            self.kind.name(),
+            self.needed,
+            self.available
+        )
+    }
+}
+
+impl core::error::Error for InsufficientResources {}
+
+/// More cards of a resource than fit in a `CompactResources`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ResourceOverflow {
+    pub kind: ResourceKind,
+    pub count: usize,
+}
+
+impl Display for ResourceOverflow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Too much {} to store compactly, {} is more than {}",
+            self.kind.name(),
+            self.count,
+            u8::MAX
+        )
+    }
+}
+
+impl core::error::Error for ResourceOverflow {}
+
+/// Picks each resource as often as it turns up on the base game's tiles
+#[cfg(feature = "rand")]
+impl Distribution<ResourceKind> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ResourceKind {
+        let total: usize = ResourceKind::ALL.iter().map(|k| k.base_game_tiles()).sum();
+        let mut pick = rng.gen_range(0..total);
+        for kind in ResourceKind::ALL {
+            if pick < kind.base_game_tiles() {
+                return kind;
+            }
+            pick -= kind.base_game_tiles();
         }
+        unreachable!("pick is less than the total")
     }
 }
 
-impl<S> From<S> for ResourceKind
-where
-    S: AsRef<str>,
-{
-    fn from(value: S) -> Self {
-        let value = value.as_ref().to_lowercase();
-        match value.as_ref() {
-            "ore" => Self::Ore,
-            "grain" => Self::Grain,
-            "wool" => Self::Wool,
-            "brick" => Self::Brick,
-            "lumber" => Self::Lumber,
-            _ => panic!("Unrecognized resource"),
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Text which couldn't be read as a resource, or a list of them
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseResourcesError {
+    UnknownResource(String),
+    InvalidCount(String),
+}
+
+impl Display for ParseResourcesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseResourcesError::UnknownResource(s) => write!(f, "Unrecognized resource {}", s),
+            ParseResourcesError::InvalidCount(s) => write!(f, "Invalid resource count {}", s),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+impl core::error::Error for ParseResourcesError {}
+
+/// Reads a resource by name, ignoring case
+impl FromStr for ResourceKind {
+    type Err = ParseResourcesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResourceKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseResourcesError::UnknownResource(s.to_string()))
+    }
+}
+
+impl TryFrom<&str> for ResourceKind {
+    type Error = ParseResourcesError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// A number of cards of each resource, like a hand, a price or what's left in the bank
+///
+/// the ordering compares one kind after another, which is only useful for sorting. It says
+/// nothing about whether one set of resources covers another, which is what `contains` is for
+#[derive(Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Resources {
     ore: usize,
     grain: usize,
@@ -97,16 +223,85 @@ impl Resources {
         }
     }
 
-    pub fn to_json(self) -> String {
+    #[cfg(feature = "serde")]
+    pub fn to_json(self) -> alloc::string::String {
         serde_json::to_string(&self).unwrap()
     }
 
+    /// Number of cards of every kind put together
+    pub fn total(&self) -> usize {
+        self.iter().map(|(_, n)| n).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Whether there are at least as many of every kind here as in `other`, e.g. whether a hand
+    /// can pay a cost
+    pub fn contains(&self, other: &Resources) -> bool {
+        other.iter().all(|(kind, n)| self[kind] >= n)
+    }
+
+    /// How many of each kind there are, in the order of `ResourceKind::ALL`
+    pub fn iter(&self) -> core::array::IntoIter<(ResourceKind, usize), 5> {
+        (*self).into_iter()
+    }
+
+    /// These resources less `rhs`, or `None` if any kind would go below zero
+    pub fn checked_sub(self, rhs: Resources) -> Option<Resources> {
+        Some(Resources {
+            ore: self.ore.checked_sub(rhs.ore)?,
+            grain: self.grain.checked_sub(rhs.grain)?,
+            wool: self.wool.checked_sub(rhs.wool)?,
+            brick: self.brick.checked_sub(rhs.brick)?,
+            lumber: self.lumber.checked_sub(rhs.lumber)?,
+        })
+    }
+
+    /// These resources less `rhs`, stopping at zero for any kind there isn't enough of
+    pub fn saturating_sub(self, rhs: Resources) -> Resources {
+        Resources {
+            ore: self.ore.saturating_sub(rhs.ore),
+            grain: self.grain.saturating_sub(rhs.grain),
+            wool: self.wool.saturating_sub(rhs.wool),
+            brick: self.brick.saturating_sub(rhs.brick),
+            lumber: self.lumber.saturating_sub(rhs.lumber),
+        }
+    }
+
+    /// Take `rhs` away, changing nothing if any kind would go below zero
+    pub fn try_sub(&mut self, rhs: Resources) -> Result<(), InsufficientResources> {
+        for (kind, needed) in rhs {
+            if self[kind] < needed {
+                return Err(InsufficientResources {
+                    kind,
+                    needed,
+                    available: self[kind],
+                });
+            }
+        }
+        *self -= rhs;
+        Ok(())
+    }
+
+    /// Take `n` cards of `kind` away, changing nothing if there aren't that many
+    pub fn try_remove(
+        &mut self,
+        kind: ResourceKind,
+        n: usize,
+    ) -> Result<(), InsufficientResources> {
+        let available = self[kind];
+        self[kind] = available.checked_sub(n).ok_or(InsufficientResources {
+            kind,
+            needed: n,
+            available,
+        })?;
+        Ok(())
+    }
+
     pub fn can_build(&self, infrastructure: Building) -> bool {
-        let resource_requirements = infrastructure.get_resource_cost();
-        resource_requirements
-            .into_iter()
-            .filter(|(_, count)| *count == 0)
-            .all(|(kind, count)| self[kind] >= count)
+        self.contains(&infrastructure.get_resource_cost())
     }
 }
 
@@ -144,7 +339,7 @@ impl Add<Resources> for Resources {
             grain: self.grain + rhs.grain,
             wool: self.wool + rhs.wool,
             brick: self.brick + rhs.brick,
-            lumber: self.ore + rhs.ore,
+            lumber: self.lumber + rhs.lumber,
         }
     }
 }
@@ -167,7 +362,7 @@ impl Sub<Resources> for Resources {
             grain: self.grain - rhs.grain,
             wool: self.wool - rhs.wool,
             brick: self.brick - rhs.brick,
-            lumber: self.ore - rhs.ore,
+            lumber: self.lumber - rhs.lumber,
         }
     }
 }
@@ -208,7 +403,7 @@ impl MulAssign<usize> for Resources {
 
 impl IntoIterator for Resources {
     type Item = (ResourceKind, usize);
-    type IntoIter = std::array::IntoIter<Self::Item, 5>;
+    type IntoIter = core::array::IntoIter<Self::Item, 5>;
 
     fn into_iter(self) -> Self::IntoIter {
         [
@@ -222,17 +417,121 @@ impl IntoIterator for Resources {
     }
 }
 
+impl IntoIterator for &Resources {
+    type Item = (ResourceKind, usize);
+    type IntoIter = core::array::IntoIter<Self::Item, 5>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Reads a comma separated list of counts and resources, like `"2 ore, 1 wool"`
+///
+/// kinds which are left out count as none, and kinds listed more than once are added together,
+/// so an empty string is no resources at all
+impl FromStr for Resources {
+    type Err = ParseResourcesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut resources = Resources::new();
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (count, kind) = item
+                .split_once(char::is_whitespace)
+                .ok_or(ParseResourcesError::InvalidCount(item.to_string()))?;
+            let count: usize = count
+                .parse()
+                .map_err(|_| ParseResourcesError::InvalidCount(count.to_string()))?;
+            resources[kind.trim().parse()?] += count;
+        }
+        Ok(resources)
+    }
+}
+
 impl Default for Resources {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// `Resources` packed into a byte for each kind, for keeping lots of hands in memory, e.g. in
+/// rollouts which copy thousands of positions
+///
+/// counts are capped at 255, so converting from `Resources` fails for anything larger. Kinds are
+/// stored in the order of `ResourceKind::ALL`
+#[derive(Debug, Default, Eq, PartialEq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompactResources([u8; ResourceKind::COUNT]);
+
+impl CompactResources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().map(|&n| n as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&n| n == 0)
+    }
+
+    /// Whether there are at least as many of every kind here as in `other`
+    pub fn contains(&self, other: &CompactResources) -> bool {
+        self.0.iter().zip(other.0).all(|(&n, m)| n >= m)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ResourceKind, usize)> + '_ {
+        ResourceKind::ALL
+            .into_iter()
+            .zip(self.0.iter().map(|&n| n as usize))
+    }
+}
+
+impl Index<ResourceKind> for CompactResources {
+    type Output = u8;
+    fn index(&self, index: ResourceKind) -> &Self::Output {
+        &self.0[index as usize]
+    }
+}
+
+impl IndexMut<ResourceKind> for CompactResources {
+    fn index_mut(&mut self, index: ResourceKind) -> &mut Self::Output {
+        &mut self.0[index as usize]
+    }
+}
+
+impl TryFrom<Resources> for CompactResources {
+    type Error = ResourceOverflow;
+
+    /// Fails, naming the first kind which doesn't fit, if any count is over 255
+    fn try_from(resources: Resources) -> Result<Self, Self::Error> {
+        let mut compact = CompactResources::new();
+        for (kind, n) in resources {
+            compact[kind] = u8::try_from(n).map_err(|_| ResourceOverflow { kind, count: n })?;
+        }
+        Ok(compact)
+    }
+}
+
+impl From<CompactResources> for Resources {
+    fn from(compact: CompactResources) -> Self {
+        let mut resources = Resources::new();
+        for (kind, n) in compact.iter() {
+            resources[kind] = n;
+        }
+        resources
+    }
+}
+
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "rand")]
     use std::panic::catch_unwind;
 
     use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
 
     #[test]
     fn test_init() {
@@ -277,9 +576,136 @@ mod test {
     fn test_index() {
         let r = Resources::new_with_amount(20);
         assert_eq!(r[Ore], 20);
-        // checks that indexing with an invalid key panics
-        let result = std::panic::catch_unwind(|| ResourceKind::from("foo"));
-        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("Wool".parse::<ResourceKind>(), Ok(Wool));
+        assert_eq!(ResourceKind::try_from("lumber"), Ok(Lumber));
+        assert_eq!(
+            ResourceKind::try_from("foo"),
+            Err(ParseResourcesError::UnknownResource("foo".into()))
+        );
+        for kind in ResourceKind::ALL {
+            assert_eq!(kind.to_string().parse::<ResourceKind>(), Ok(kind));
+        }
+
+        assert_eq!(
+            "2 ore, 1 wool".parse::<Resources>(),
+            Ok(Resources::new_explicit(2, 0, 1, 0, 0))
+        );
+        assert_eq!(
+            "1 brick,1 Brick , 3 grain".parse::<Resources>(),
+            Ok(Resources::new_explicit(0, 3, 0, 2, 0))
+        );
+        assert_eq!("".parse::<Resources>(), Ok(Resources::new()));
+        assert_eq!(
+            "two ore".parse::<Resources>(),
+            Err(ParseResourcesError::InvalidCount("two".into()))
+        );
+        assert!("2 gold".parse::<Resources>().is_err());
+        assert!("ore".parse::<Resources>().is_err());
+    }
+
+    #[test]
+    fn test_operators() {
+        let a = Resources::new_explicit(5, 1, 2, 3, 9);
+        let b = Resources::new_explicit(1, 1, 0, 2, 4);
+        assert_eq!(a + b, Resources::new_explicit(6, 2, 2, 5, 13));
+        assert_eq!(a - b, Resources::new_explicit(4, 0, 2, 1, 5));
+
+        let mut r = a;
+        r += b;
+        assert_eq!(r, a + b);
+        r -= b;
+        assert_eq!(r, a);
+        assert_eq!(a * 2, Resources::new_explicit(10, 2, 4, 6, 18));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let hand = Resources::new_explicit(2, 1, 0, 0, 3);
+        let cost = Resources::new_explicit(1, 1, 0, 0, 1);
+        assert_eq!(
+            hand.checked_sub(cost),
+            Some(Resources::new_explicit(1, 0, 0, 0, 2))
+        );
+        assert_eq!(cost.checked_sub(hand), None);
+        assert_eq!(
+            cost.saturating_sub(hand),
+            Resources::new_explicit(0, 0, 0, 0, 0)
+        );
+
+        let mut r = hand;
+        let err = r
+            .try_sub(Resources::new_explicit(0, 0, 1, 0, 0))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            InsufficientResources {
+                kind: Wool,
+                needed: 1,
+                available: 0
+            }
+        );
+        assert_eq!(r, hand);
+        assert!(r.try_remove(Lumber, 4).is_err());
+        r.try_remove(Lumber, 3).unwrap();
+        assert_eq!(r[Lumber], 0);
+        assert_eq!(
+            err.to_string(),
+            "Not enough wool, needed 1 but only 0 available"
+        );
+    }
+
+    #[test]
+    fn test_totals() {
+        let hand = Resources::new_explicit(3, 0, 1, 0, 2);
+        assert_eq!(hand.total(), 6);
+        assert!(!hand.is_empty());
+        assert!(Resources::new().is_empty());
+
+        // wool is missing, even though the derived ordering puts the hand first
+        let cost = Resources::new_explicit(1, 0, 2, 0, 0);
+        assert!(hand > cost);
+        assert!(!hand.contains(&cost));
+        assert!(hand.contains(&Resources::new_explicit(3, 0, 1, 0, 0)));
+        assert!(hand.contains(&Resources::new()));
+
+        let kinds: Vec<_> = (&hand).into_iter().filter(|(_, n)| *n > 0).collect();
+        assert_eq!(kinds, vec![(Ore, 3), (Wool, 1), (Lumber, 2)]);
+        assert_eq!(
+            hand.iter().map(|(kind, _)| kind).collect::<Vec<_>>(),
+            ResourceKind::ALL
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let hand = Resources::new_explicit(3, 0, 1, 0, 2);
+        let compact = CompactResources::try_from(hand).unwrap();
+        assert_eq!(core::mem::size_of::<CompactResources>(), 5);
+        assert_eq!(compact[Ore], 3);
+        assert_eq!(compact.total(), hand.total());
+        assert_eq!(Resources::from(compact), hand);
+
+        let cost = CompactResources::try_from(Resources::new_explicit(1, 0, 1, 0, 0)).unwrap();
+        assert!(compact.contains(&cost));
+        assert!(!cost.contains(&compact));
+        assert!(CompactResources::new().is_empty());
+
+        let err = CompactResources::try_from(Resources::new_explicit(0, 256, 0, 0, 0));
+        assert_eq!(
+            err,
+            Err(ResourceOverflow {
+                kind: Grain,
+                count: 256
+            })
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "Too much grain to store compactly, 256 is more than 255"
+        );
     }
 
     #[test]
@@ -292,8 +718,12 @@ mod test {
 
         let r = Building::City.get_resource_cost();
         assert!(r.can_build(Building::City));
+
+        let r = Resources::new_explicit(2, 2, 0, 0, 0);
+        assert!(!r.can_build(Building::City));
     }
     #[test]
+    #[cfg(feature = "rand")]
     fn test_random() {
         let resources = catch_unwind(|| {
             (0..10).for_each(|_| {
@@ -302,4 +732,21 @@ mod test {
         });
         assert!(resources.is_ok());
     }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_distribution() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = Resources::new();
+        for _ in 0..18_000 {
+            counts[rng.gen::<ResourceKind>()] += 1;
+        }
+        // about 3000 ore and brick, and 4000 of the rest
+        for kind in ResourceKind::ALL {
+            let expected = kind.base_game_tiles() * 1000;
+            assert!(counts[kind].abs_diff(expected) < 300, "{:?}", counts);
+        }
+    }
 }
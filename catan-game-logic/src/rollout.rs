@@ -0,0 +1,218 @@
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::actions::Action;
+use crate::dice::FairDice;
+use crate::game::{Game, GameState};
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+
+/// Picks the moves made while playing a game out to the end in a simulation
+///
+/// unlike an `Agent`, a policy sees the whole game, hidden cards and all, since it's only ever
+/// playing against itself
+pub trait RolloutPolicy: Debug {
+    /// Choose one of `actions` for `player`
+    fn choose(
+        &mut self,
+        game: &Game,
+        player: PlayerColour,
+        actions: &[Action],
+        rng: &mut StdRng,
+    ) -> Action;
+
+    /// Choose `count` cards for `player` to discard after a 7, at random unless overridden
+    fn discard(
+        &mut self,
+        game: &Game,
+        player: PlayerColour,
+        count: usize,
+        rng: &mut StdRng,
+    ) -> Resources {
+        let hand = game
+            .get_player(&player)
+            .map_or(Resources::new(), |p| *p.resources());
+        random_discard(hand, count, rng)
+    }
+}
+
+/// Picks every move uniformly at random
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct UniformRollout;
+
+impl RolloutPolicy for UniformRollout {
+    fn choose(
+        &mut self,
+        _game: &Game,
+        _player: PlayerColour,
+        actions: &[Action],
+        rng: &mut StdRng,
+    ) -> Action {
+        actions[rng.gen_range(0..actions.len())].clone()
+    }
+}
+
+/// `count` cards picked at random from `hand`
+pub(crate) fn random_discard(mut hand: Resources, count: usize, rng: &mut impl Rng) -> Resources {
+    let mut cards = Resources::new();
+    for _ in 0..count {
+        let total = hand.total();
+        if total == 0 {
+            break;
+        }
+        let mut pick = rng.gen_range(0..total);
+        for (kind, n) in hand {
+            if pick < n {
+                hand[kind] -= 1;
+                cards[kind] += 1;
+                break;
+            }
+            pick -= n;
+        }
+    }
+    cards
+}
+
+/// Play `game` out following `policy` for at most `max_steps` moves, returning the winner if
+/// anyone won in time
+///
+/// the game's dice are swapped for fair ones seeded from `rng`, and the rest of its chance is
+/// reseeded from `rng` too, so every rollout plays out differently
+pub fn rollout(
+    game: &mut Game,
+    policy: &mut dyn RolloutPolicy,
+    rng: &mut StdRng,
+    max_steps: usize,
+) -> Result<Option<PlayerColour>> {
+    game.set_dice_roller(FairDice::seeded(rng.gen()));
+    game.seed_rng(rng.gen());
+    for _ in 0..max_steps {
+        if game.state() == GameState::Complete {
+            break;
+        }
+        let player = game
+            .awaiting_player()
+            .ok_or(anyhow!("The game isn't waiting on anyone"))?;
+        let action = match game.players_awaiting_discard().first() {
+            Some((_, count)) => Action::Discard {
+                cards: policy.discard(game, player, *count, rng),
+            },
+            None => {
+                let actions = game.legal_actions(&player);
+                if actions.is_empty() {
+                    return Err(anyhow!("{:?} has nothing to do", player));
+                }
+                policy.choose(game, player, &actions, rng)
+            }
+        };
+        game.apply(player, action)?;
+    }
+    Ok(game.winner())
+}
+
+/// How often each player won over a batch of rollouts
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SimulationResult {
+    /// Wins for each player, in turn order
+    pub wins: Vec<(PlayerColour, usize)>,
+    /// Rollouts which ran out of steps before anyone won
+    pub unfinished: usize,
+}
+
+impl SimulationResult {
+    pub fn wins(&self, player: &PlayerColour) -> usize {
+        self.wins
+            .iter()
+            .find(|(p, _)| p == player)
+            .map_or(0, |(_, n)| *n)
+    }
+}
+
+/// Play `playouts` copies of `game` out to the end with `policy`, seeded from `seed`
+pub fn simulate(
+    game: &Game,
+    policy: &mut dyn RolloutPolicy,
+    playouts: usize,
+    seed: u64,
+    max_steps: usize,
+) -> Result<SimulationResult> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut result = SimulationResult {
+        wins: game.players().iter().map(|p| (*p.colour(), 0)).collect(),
+        unfinished: 0,
+    };
+    for _ in 0..playouts {
+        match rollout(&mut game.fork(), policy, &mut rng, max_steps)? {
+            Some(winner) => {
+                if let Some((_, n)) = result.wins.iter_mut().find(|(p, _)| *p == winner) {
+                    *n += 1;
+                }
+            }
+            None => result.unfinished += 1,
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::running_game;
+
+    /// Upgrades to a city whenever it can, and otherwise ends its turn as soon as possible
+    #[derive(Debug)]
+    struct CityBuilder;
+
+    impl RolloutPolicy for CityBuilder {
+        fn choose(
+            &mut self,
+            _game: &Game,
+            _player: PlayerColour,
+            actions: &[Action],
+            rng: &mut StdRng,
+        ) -> Action {
+            actions
+                .iter()
+                .find(|a| matches!(a, Action::UpgradeToCity { .. }))
+                .or(actions.iter().find(|a| **a == Action::EndTurn))
+                .cloned()
+                .unwrap_or_else(|| actions[rng.gen_range(0..actions.len())].clone())
+        }
+    }
+
+    #[test]
+    fn test_random_discard() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let hand = Resources::new_explicit(2, 0, 1, 0, 3);
+        let cards = random_discard(hand, 4, &mut rng);
+        assert_eq!(cards.into_iter().map(|(_, n)| n).sum::<usize>(), 4);
+        assert!(cards.into_iter().all(|(kind, n)| n <= hand[kind]));
+    }
+
+    #[test]
+    fn test_rollout() {
+        let g = running_game();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut played = g.clone();
+        rollout(&mut played, &mut CityBuilder, &mut rng, 500).unwrap();
+        assert!(!played.roll_history().is_empty());
+
+        // the original game is left alone by simulations
+        let result = simulate(&g, &mut UniformRollout, 3, 1, 200).unwrap();
+        assert_eq!(
+            result.wins(&PlayerColour::Red) + result.wins(&PlayerColour::Blue) + result.unfinished,
+            3
+        );
+        assert!(g.roll_history().is_empty());
+    }
+
+    #[test]
+    fn test_simulate_is_seeded() {
+        let g = running_game();
+        let first = simulate(&g, &mut CityBuilder, 2, 9, 1000).unwrap();
+        assert_eq!(simulate(&g, &mut CityBuilder, 2, 9, 1000).unwrap(), first);
+    }
+}
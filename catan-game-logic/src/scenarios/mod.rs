@@ -0,0 +1,118 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+use anyhow::Result;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::board::{EdgeId, VertexId};
+use crate::game::Game;
+use crate::player::PlayerColour;
+use crate::resources::ResourceKind;
+
+pub(crate) mod oil_springs;
+pub(crate) mod rivers;
+
+pub use oil_springs::OilSprings;
+pub use rivers::Rivers;
+
+/// Extra rules layered on top of the base game, which `Game` calls into at fixed points
+///
+/// every hook has a default which does nothing, so scenarios only implement the ones they need
+pub trait Scenario: Debug + Send + Sync {
+    /// Human readable name of the scenario
+    fn name(&self) -> &str;
+
+    /// Called once, when the scenario is added to a game
+    fn on_setup(&mut self, _game: &mut Game) {}
+
+    /// Called after every roll of the dice, once the base game has handed out production
+    fn on_roll(&mut self, _game: &mut Game, _roll: u8) {}
+
+    /// Called whenever a settlement is placed on the board
+    fn on_settlement_built(&mut self, _game: &mut Game, _player: PlayerColour, _vertex: VertexId) {}
+
+    /// Called as `player` ends their turn, before play passes on
+    fn on_turn_end(&mut self, _game: &mut Game, _player: PlayerColour) {}
+
+    /// Actions this scenario adds to those `player` could take right now
+    fn extra_actions(&self, _game: &Game, _player: &PlayerColour) -> Vec<ScenarioAction> {
+        Vec::new()
+    }
+
+    /// Carry out one of the actions this scenario adds
+    fn apply(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        action: ScenarioAction,
+    ) -> Result<()>;
+
+    fn clone_box(&self) -> Box<dyn Scenario>;
+
+    /// Lets callers get back to the concrete scenario, e.g. to read its state
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Actions only available while playing one of the scenarios
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ScenarioAction {
+    /// Build a bridge over a river, in Rivers of Catan
+    BuildBridge { edge: EdgeId },
+    /// Swap gold for a resource, in Rivers of Catan
+    BuyWithGold { resource: ResourceKind },
+    /// Burn a barrel of oil for resources, in Oil Springs
+    ConvertOil { resource: ResourceKind },
+    /// Take a barrel of oil out of circulation, in Oil Springs
+    SequesterOil,
+}
+
+/// Owned handle to the scenario a game is playing, if any
+///
+/// scenarios can't be compared directly, so two handles are equal when they're playing the same
+/// scenario
+#[derive(Debug, Default)]
+pub struct ScenarioHandle(pub(crate) Option<Box<dyn Scenario>>);
+
+impl Clone for ScenarioHandle {
+    fn clone(&self) -> Self {
+        Self(self.0.as_ref().map(|scenario| scenario.clone_box()))
+    }
+}
+
+impl PartialEq for ScenarioHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ref().map(|s| s.name()) == other.0.as_ref().map(|s| s.name())
+    }
+}
+
+impl Eq for ScenarioHandle {}
+
+/// Amount of some scenario-specific token `player` holds, out of a list of per-player counts
+fn count_of(counts: &[(PlayerColour, usize)], player: &PlayerColour) -> usize {
+    counts
+        .iter()
+        .find(|(p, _)| p == player)
+        .map(|(_, count)| *count)
+        .unwrap_or(0)
+}
+
+/// Mutable access to `player`'s count, starting them on zero if they don't have one yet
+fn count_mut(counts: &mut Vec<(PlayerColour, usize)>, player: PlayerColour) -> &mut usize {
+    let idx = match counts.iter().position(|(p, _)| *p == player) {
+        Some(idx) => idx,
+        None => {
+            counts.push((player, 0));
+            counts.len() - 1
+        }
+    };
+    &mut counts[idx].1
+}
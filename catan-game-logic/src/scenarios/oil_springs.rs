@@ -0,0 +1,274 @@
+use std::any::Any;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, TurnPhase, ROBBER_ROLL};
+use crate::player::PlayerColour;
+use crate::resources::ResourceKind;
+use crate::scenarios::{count_mut, count_of, Scenario, ScenarioAction};
+
+/// Resources the bank hands over for each barrel of oil
+pub const RESOURCES_PER_OIL: usize = 2;
+
+/// Barrels of oil which have to be sequestered for each victory point
+pub const OIL_PER_SEQUESTRATION_POINT: usize = 3;
+
+/// A disaster strikes every time this many more barrels of oil have been used
+pub const DISASTER_THRESHOLD: usize = 5;
+
+/// Oil Springs, where some tiles also produce oil. Oil can be swapped for resources, but burning
+/// too much of it brings on disasters, so players can instead sequester it for victory points
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OilSprings {
+    /// Tiles with an oil spring on them
+    springs: Vec<usize>,
+    oil: Vec<(PlayerColour, usize)>,
+    sequestered: Vec<(PlayerColour, usize)>,
+    /// Barrels of oil used by every player so far
+    oil_used: usize,
+    disasters: usize,
+}
+
+impl OilSprings {
+    /// Tiles which get an oil spring in the default layout
+    pub const DEFAULT_SPRINGS: [usize; 4] = [1, 7, 11, 17];
+
+    pub fn new(springs: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            springs: springs.into_iter().collect(),
+            oil: Vec::new(),
+            sequestered: Vec::new(),
+            oil_used: 0,
+            disasters: 0,
+        }
+    }
+
+    pub fn springs(&self) -> &[usize] {
+        &self.springs
+    }
+
+    pub fn oil(&self, player: &PlayerColour) -> usize {
+        count_of(&self.oil, player)
+    }
+
+    /// Barrels of oil `player` has taken out of circulation
+    pub fn sequestered(&self, player: &PlayerColour) -> usize {
+        count_of(&self.sequestered, player)
+    }
+
+    pub fn oil_used(&self) -> usize {
+        self.oil_used
+    }
+
+    /// Number of disasters brought on by burning oil so far
+    pub fn disasters(&self) -> usize {
+        self.disasters
+    }
+
+    /// Hand out oil for `roll`, one barrel for each settlement and two for each city next to a
+    /// spring with that number, unless the robber is on it. Springs in the desert never produce
+    pub fn produce(&mut self, game: &Game, roll: u8) {
+        let board = game.get_board();
+        for &spring in &self.springs {
+            let producing = matches!(
+                board.tile(spring),
+                Some(tile) if *tile.token() == roll as usize && tile.kind().resource().is_some()
+            );
+            if !producing || board.robber() == Some(spring) {
+                continue;
+            }
+
+            for vertex in board.vertices_of_tile(spring) {
+                if let Some((owner, building)) = vertex.building() {
+                    *count_mut(&mut self.oil, *owner) += building.production();
+                }
+            }
+        }
+    }
+
+    /// Burn a barrel of `player`'s oil in exchange for resources from the bank, returning whether
+    /// it brought on a disaster
+    pub fn convert(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        resource: ResourceKind,
+    ) -> Result<bool> {
+        game.ensure_turn(&player, TurnPhase::Main)?;
+        if self.oil(&player) == 0 {
+            return Err(anyhow!("No oil to convert"));
+        }
+
+        let bought = game
+            .get_bank_mut()
+            .distribute_resource(resource, RESOURCES_PER_OIL)?;
+        *game.get_player_mut(player)?.resources_mut() += bought;
+        *count_mut(&mut self.oil, player) -= 1;
+
+        self.oil_used += 1;
+        let disaster = self.oil_used.is_multiple_of(DISASTER_THRESHOLD);
+        if disaster {
+            self.disasters += 1;
+        }
+        Ok(disaster)
+    }
+
+    /// Take `amount` of `player`'s oil out of circulation for good
+    pub fn sequester(&mut self, player: PlayerColour, amount: usize) -> Result<()> {
+        if self.oil(&player) < amount {
+            return Err(anyhow!("Not enough oil to sequester"));
+        }
+        *count_mut(&mut self.oil, player) -= amount;
+        *count_mut(&mut self.sequestered, player) += amount;
+        Ok(())
+    }
+
+    /// Victory points `player` has earned by sequestering oil
+    pub fn victory_points(&self, player: &PlayerColour) -> usize {
+        self.sequestered(player) / OIL_PER_SEQUESTRATION_POINT
+    }
+}
+
+impl Default for OilSprings {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SPRINGS)
+    }
+}
+
+impl Scenario for OilSprings {
+    fn name(&self) -> &str {
+        "Oil Springs"
+    }
+
+    fn on_roll(&mut self, game: &mut Game, roll: u8) {
+        if roll != ROBBER_ROLL {
+            self.produce(game, roll);
+        }
+    }
+
+    fn extra_actions(&self, game: &Game, player: &PlayerColour) -> Vec<ScenarioAction> {
+        if self.oil(player) == 0 {
+            return Vec::new();
+        }
+
+        let mut actions: Vec<_> = ResourceKind::ALL
+            .into_iter()
+            .filter(|kind| game.get_bank().remaining(*kind) >= RESOURCES_PER_OIL)
+            .map(|resource| ScenarioAction::ConvertOil { resource })
+            .collect();
+        actions.push(ScenarioAction::SequesterOil);
+        actions
+    }
+
+    fn apply(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        action: ScenarioAction,
+    ) -> Result<()> {
+        match action {
+            ScenarioAction::ConvertOil { resource } => {
+                self.convert(game, player, resource).map(|_| ())
+            }
+            ScenarioAction::SequesterOil => {
+                game.ensure_turn(&player, TurnPhase::Main)?;
+                self.sequester(player, 1)
+            }
+            _ => Err(anyhow!("That action isn't part of this scenario")),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Scenario> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::board::VertexId;
+    use crate::dice::FixedDice;
+
+    /// A game just after red's first roll, with a spring on each tile next to red's first
+    /// settlement, between tiles 0, 1 and 4
+    fn oil_game() -> (Game, OilSprings) {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        for (player, vertex) in [
+            (PlayerColour::Red, 2),
+            (PlayerColour::Blue, 30),
+            (PlayerColour::Blue, 40),
+            (PlayerColour::Red, 10),
+        ] {
+            g.place_initial_settlement(player, VertexId::new(vertex))
+                .unwrap();
+        }
+        g.roll_dice().unwrap();
+
+        (g, OilSprings::new([0, 1, 4]))
+    }
+
+    #[test]
+    fn test_produce() {
+        let (g, mut oil) = oil_game();
+        let board = g.get_board();
+        let producing: Vec<_> = oil
+            .springs()
+            .iter()
+            .filter(|&&t| board.robber() != Some(t))
+            .map(|&t| board.tile(t).unwrap())
+            .filter(|tile| tile.kind().resource().is_some() && *tile.token() != 7)
+            .collect();
+
+        for roll in (2..=12).filter(|roll| *roll != 7) {
+            oil.produce(&g, roll);
+        }
+        assert_eq!(oil.oil(&PlayerColour::Red), producing.len());
+        assert_eq!(oil.oil(&PlayerColour::Blue), 0);
+    }
+
+    #[test]
+    fn test_convert() {
+        let (mut g, mut oil) = oil_game();
+        assert!(oil
+            .convert(&mut g, PlayerColour::Red, ResourceKind::Ore)
+            .is_err());
+
+        *count_mut(&mut oil.oil, PlayerColour::Red) = 5;
+        let before = g.get_player(&PlayerColour::Red).unwrap().resources()[ResourceKind::Ore];
+        for _ in 0..4 {
+            assert!(!oil
+                .convert(&mut g, PlayerColour::Red, ResourceKind::Ore)
+                .unwrap());
+        }
+        assert!(oil
+            .convert(&mut g, PlayerColour::Red, ResourceKind::Ore)
+            .unwrap());
+        assert_eq!(oil.disasters(), 1);
+        assert_eq!(
+            g.get_player(&PlayerColour::Red).unwrap().resources()[ResourceKind::Ore],
+            before + 5 * RESOURCES_PER_OIL
+        );
+    }
+
+    #[test]
+    fn test_sequester() {
+        let (_, mut oil) = oil_game();
+        *count_mut(&mut oil.oil, PlayerColour::Red) = 7;
+        assert!(oil.sequester(PlayerColour::Red, 8).is_err());
+
+        oil.sequester(PlayerColour::Red, 7).unwrap();
+        assert_eq!(oil.oil(&PlayerColour::Red), 0);
+        assert_eq!(oil.sequestered(&PlayerColour::Red), 7);
+        assert_eq!(oil.victory_points(&PlayerColour::Red), 2);
+    }
+}
@@ -0,0 +1,419 @@
+use std::any::Any;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, EdgeId, VertexId};
+use crate::building::Building;
+use crate::game::{Game, TurnPhase};
+use crate::player::PlayerColour;
+use crate::resources::{ResourceKind, Resources};
+use crate::scenarios::{count_mut, count_of, Scenario, ScenarioAction};
+
+/// Gold earned for building a bridge over a river
+pub const BRIDGE_GOLD: usize = 3;
+
+/// Gold earned for building a settlement on a riverbank
+pub const RIVERBANK_GOLD: usize = 1;
+
+/// Gold the bank takes in exchange for a resource of the player's choice
+pub const GOLD_PER_RESOURCE: usize = 2;
+
+/// Victory points the wealthiest settler earns
+pub const WEALTHIEST_SETTLER_POINTS: i32 = 1;
+
+/// Victory points the poorest settlers lose
+pub const POOREST_SETTLER_PENALTY: i32 = -2;
+
+/// Rivers of Catan, where rivers wind between tiles and can only be crossed by building bridges.
+/// Building on a river earns gold, which buys resources from the bank, and the richest and
+/// poorest players gain or lose victory points for it
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rivers {
+    /// Edges along the rivers, where bridges are built instead of roads
+    rivers: Vec<EdgeId>,
+    gold: Vec<(PlayerColour, usize)>,
+}
+
+impl Rivers {
+    /// Tiles each of the default rivers flows between, from source to mouth
+    pub const DEFAULT_RIVER_TILES: [&'static [usize]; 2] = [&[3, 4, 8, 9], &[10, 14, 15]];
+
+    /// Lay out rivers on `board`, each flowing along the borders between consecutive tiles in one
+    /// of `river_tiles`
+    pub fn new<'a>(board: &Board, river_tiles: impl IntoIterator<Item = &'a [usize]>) -> Self {
+        let mut rivers = Vec::new();
+        for tiles in river_tiles {
+            for pair in tiles.windows(2) {
+                let border = board
+                    .edges_of_tile(pair[0])
+                    .find(|edge| board.edges_of_tile(pair[1]).any(|e| e.id() == edge.id()));
+                if let Some(edge) = border {
+                    rivers.push(*edge.id());
+                }
+            }
+        }
+
+        Self {
+            rivers,
+            gold: Vec::new(),
+        }
+    }
+
+    /// Lay out the default rivers on `board`
+    pub fn with_default_rivers(board: &Board) -> Self {
+        Self::new(board, Self::DEFAULT_RIVER_TILES)
+    }
+
+    /// Resources needed to build a bridge
+    pub fn bridge_cost() -> Resources {
+        Resources::new_explicit(0, 0, 0, 2, 1)
+    }
+
+    pub fn rivers(&self) -> &[EdgeId] {
+        &self.rivers
+    }
+
+    pub fn is_river(&self, edge: EdgeId) -> bool {
+        self.rivers.contains(&edge)
+    }
+
+    /// Whether `vertex` sits on the bank of a river
+    pub fn is_riverbank(&self, board: &Board, vertex: VertexId) -> bool {
+        self.rivers.iter().any(
+            |edge| matches!(board.edge(*edge), Some(edge) if edge.vertices().contains(&vertex)),
+        )
+    }
+
+    pub fn gold(&self, player: &PlayerColour) -> usize {
+        count_of(&self.gold, player)
+    }
+
+    fn gold_mut(&mut self, player: PlayerColour) -> &mut usize {
+        count_mut(&mut self.gold, player)
+    }
+
+    /// Build a bridge for `player` over a river, paying for it from their hand
+    pub fn build_bridge(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        edge: EdgeId,
+    ) -> Result<()> {
+        if !self.is_river(edge) {
+            return Err(anyhow!("Bridges can only be built over rivers"));
+        }
+        game.ensure_turn(&player, TurnPhase::Main)?;
+        if !game
+            .get_player(&player)?
+            .resources()
+            .contains(&Self::bridge_cost())
+        {
+            return Err(anyhow!("Not enough resources to build a bridge"));
+        }
+
+        game.place_road(player, edge)?;
+        game.pay_to_bank(player, Self::bridge_cost())?;
+        *self.gold_mut(player) += BRIDGE_GOLD;
+        Ok(())
+    }
+
+    /// Pay out gold for a settlement `player` has just built at `vertex`, if it's on a riverbank
+    pub fn settlement_built(&mut self, board: &Board, player: PlayerColour, vertex: VertexId) {
+        if self.is_riverbank(board, vertex) {
+            *self.gold_mut(player) += RIVERBANK_GOLD;
+        }
+    }
+
+    /// Swap some of `player`'s gold for a resource from the bank
+    pub fn buy_resource(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        resource: ResourceKind,
+    ) -> Result<()> {
+        game.ensure_turn(&player, TurnPhase::Main)?;
+        if self.gold(&player) < GOLD_PER_RESOURCE {
+            return Err(anyhow!("Not enough gold"));
+        }
+
+        let bought = game.get_bank_mut().distribute_resource(resource, 1)?;
+        *game.get_player_mut(player)?.resources_mut() += bought;
+        *self.gold_mut(player) -= GOLD_PER_RESOURCE;
+        Ok(())
+    }
+
+    /// The single player holding the most gold, if nobody is tied with them
+    pub fn wealthiest(&self, players: &[PlayerColour]) -> Option<PlayerColour> {
+        let most = players.iter().map(|p| self.gold(p)).max()?;
+        let mut richest = players.iter().filter(|p| self.gold(p) == most);
+        match (richest.next(), richest.next()) {
+            (Some(player), None) => Some(*player),
+            _ => None,
+        }
+    }
+
+    /// Every player tied for the least gold, who all take the poor settler penalty
+    pub fn poorest(&self, players: &[PlayerColour]) -> Vec<PlayerColour> {
+        let least = players.iter().map(|p| self.gold(p)).min().unwrap_or(0);
+        players
+            .iter()
+            .filter(|p| self.gold(p) == least)
+            .copied()
+            .collect()
+    }
+
+    /// Victory points `player` gains or loses from how much gold they hold compared to `players`
+    pub fn victory_points(&self, player: &PlayerColour, players: &[PlayerColour]) -> i32 {
+        let mut points = 0;
+        if self.wealthiest(players) == Some(*player) {
+            points += WEALTHIEST_SETTLER_POINTS;
+        }
+        if self.poorest(players).contains(player) {
+            points += POOREST_SETTLER_PENALTY;
+        }
+        points
+    }
+}
+
+impl Default for Rivers {
+    /// No rivers until the scenario is added to a game, which lays out the default ones
+    fn default() -> Self {
+        Self {
+            rivers: Vec::new(),
+            gold: Vec::new(),
+        }
+    }
+}
+
+impl Scenario for Rivers {
+    fn name(&self) -> &str {
+        "Rivers of Catan"
+    }
+
+    fn on_setup(&mut self, game: &mut Game) {
+        if self.rivers.is_empty() {
+            self.rivers = Self::with_default_rivers(game.get_board()).rivers;
+        }
+    }
+
+    fn on_settlement_built(&mut self, game: &mut Game, player: PlayerColour, vertex: VertexId) {
+        self.settlement_built(game.get_board(), player, vertex);
+    }
+
+    fn extra_actions(&self, game: &Game, player: &PlayerColour) -> Vec<ScenarioAction> {
+        let Ok(p) = game.get_player(player) else {
+            return Vec::new();
+        };
+
+        let mut actions = Vec::new();
+        if p.resources().contains(&Self::bridge_cost()) && p.stock().remaining(Building::Road) > 0 {
+            actions.extend(
+                self.rivers
+                    .iter()
+                    .filter(|edge| game.check_road(player, **edge).is_ok())
+                    .map(|edge| ScenarioAction::BuildBridge { edge: *edge }),
+            );
+        }
+        if self.gold(player) >= GOLD_PER_RESOURCE {
+            actions.extend(
+                ResourceKind::ALL
+                    .into_iter()
+                    .filter(|kind| game.get_bank().remaining(*kind) > 0)
+                    .map(|resource| ScenarioAction::BuyWithGold { resource }),
+            );
+        }
+        actions
+    }
+
+    fn apply(
+        &mut self,
+        game: &mut Game,
+        player: PlayerColour,
+        action: ScenarioAction,
+    ) -> Result<()> {
+        match action {
+            ScenarioAction::BuildBridge { edge } => self.build_bridge(game, player, edge),
+            ScenarioAction::BuyWithGold { resource } => self.buy_resource(game, player, resource),
+            _ => Err(anyhow!("That action isn't part of this scenario")),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Scenario> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::actions::Action;
+    use crate::dice::FixedDice;
+
+    /// A game just after red's first roll, with settlements on vertex 2, between tiles 0, 1 and 4,
+    /// and a river flowing from tile 0 to tile 1 beside it
+    fn river_game() -> (Game, Rivers) {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        for (player, vertex) in [
+            (PlayerColour::Red, 2),
+            (PlayerColour::Blue, 30),
+            (PlayerColour::Blue, 40),
+            (PlayerColour::Red, 10),
+        ] {
+            g.place_initial_settlement(player, VertexId::new(vertex))
+                .unwrap();
+        }
+        g.roll_dice().unwrap();
+
+        let rivers = Rivers::new(g.get_board(), [&[0, 1][..]]);
+        (g, rivers)
+    }
+
+    #[test]
+    fn test_layout() {
+        let b = Board::new();
+        let rivers = Rivers::with_default_rivers(&b);
+        assert_eq!(rivers.rivers().len(), 5);
+
+        let rivers = Rivers::new(&b, [&[0, 1][..]]);
+        let edge = b.edge_between(VertexId::new(1), VertexId::new(2)).unwrap();
+        assert_eq!(rivers.rivers(), &[edge]);
+        assert!(rivers.is_riverbank(&b, VertexId::new(2)));
+        assert!(!rivers.is_riverbank(&b, VertexId::new(3)));
+    }
+
+    #[test]
+    fn test_build_bridge() {
+        let (mut g, mut rivers) = river_game();
+        let bridge = rivers.rivers()[0];
+        let land = g
+            .get_board()
+            .edge_between(VertexId::new(2), VertexId::new(3))
+            .unwrap();
+
+        g.set_hand(PlayerColour::Red, Rivers::bridge_cost());
+        assert!(rivers
+            .build_bridge(&mut g, PlayerColour::Red, land)
+            .is_err());
+        assert!(rivers
+            .build_bridge(&mut g, PlayerColour::Blue, bridge)
+            .is_err());
+
+        rivers
+            .build_bridge(&mut g, PlayerColour::Red, bridge)
+            .unwrap();
+        assert_eq!(rivers.gold(&PlayerColour::Red), BRIDGE_GOLD);
+        assert_eq!(
+            *g.get_player(&PlayerColour::Red).unwrap().resources(),
+            Resources::new()
+        );
+        assert_eq!(
+            g.get_board().edge(bridge).unwrap().road(),
+            Some(&PlayerColour::Red)
+        );
+
+        rivers
+            .buy_resource(&mut g, PlayerColour::Red, ResourceKind::Ore)
+            .unwrap();
+        assert_eq!(rivers.gold(&PlayerColour::Red), 1);
+        assert!(rivers
+            .buy_resource(&mut g, PlayerColour::Red, ResourceKind::Ore)
+            .is_err());
+    }
+
+    #[test]
+    fn test_wealth() {
+        let (g, mut rivers) = river_game();
+        let players = [PlayerColour::Red, PlayerColour::Blue, PlayerColour::Green];
+        assert_eq!(rivers.wealthiest(&players), None);
+        assert_eq!(rivers.poorest(&players), players.to_vec());
+
+        rivers.settlement_built(g.get_board(), PlayerColour::Red, VertexId::new(2));
+        rivers.settlement_built(g.get_board(), PlayerColour::Blue, VertexId::new(2));
+        rivers.settlement_built(g.get_board(), PlayerColour::Red, VertexId::new(1));
+        assert_eq!(rivers.gold(&PlayerColour::Red), 2);
+        assert_eq!(rivers.wealthiest(&players), Some(PlayerColour::Red));
+        assert_eq!(rivers.poorest(&players), vec![PlayerColour::Green]);
+        assert_eq!(rivers.victory_points(&PlayerColour::Red, &players), 1);
+        assert_eq!(rivers.victory_points(&PlayerColour::Blue, &players), 0);
+        assert_eq!(rivers.victory_points(&PlayerColour::Green, &players), -2);
+    }
+
+    #[test]
+    fn test_scenario_hooks() {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        g.set_dice_roller(FixedDice::new([(1, 2)]));
+        g.set_scenario(Rivers::new(g.get_board(), [&[0, 1][..]]));
+        assert_eq!(g.scenario().unwrap().name(), "Rivers of Catan");
+
+        // red's first settlement is on the bank of the river
+        for (player, vertex) in [
+            (PlayerColour::Red, 2),
+            (PlayerColour::Blue, 30),
+            (PlayerColour::Blue, 40),
+            (PlayerColour::Red, 10),
+        ] {
+            g.place_initial_settlement(player, VertexId::new(vertex))
+                .unwrap();
+        }
+        g.roll_dice().unwrap();
+        g.set_hand(PlayerColour::Red, Rivers::bridge_cost());
+
+        let rivers = g
+            .scenario()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Rivers>()
+            .unwrap();
+        assert_eq!(rivers.gold(&PlayerColour::Red), RIVERBANK_GOLD);
+        let bridge = Action::Scenario {
+            action: ScenarioAction::BuildBridge {
+                edge: rivers.rivers()[0],
+            },
+        };
+        assert!(g.legal_actions(&PlayerColour::Red).contains(&bridge));
+
+        g.apply(PlayerColour::Red, bridge).unwrap();
+        let rivers = g
+            .scenario()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Rivers>()
+            .unwrap();
+        assert_eq!(
+            rivers.gold(&PlayerColour::Red),
+            RIVERBANK_GOLD + BRIDGE_GOLD
+        );
+        assert!(g
+            .legal_actions(&PlayerColour::Red)
+            .contains(&Action::Scenario {
+                action: ScenarioAction::BuyWithGold {
+                    resource: ResourceKind::Wool
+                }
+            }));
+    }
+
+    #[test]
+    fn test_default_rivers() {
+        let mut g = Game::new();
+        g.set_scenario(Rivers::default());
+        let rivers = g
+            .scenario()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Rivers>()
+            .unwrap();
+        assert_eq!(rivers.rivers().len(), 5);
+    }
+}
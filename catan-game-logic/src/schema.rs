@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use schemars::{schema_for, Schema};
+
+use crate::actions::Action;
+use crate::events::GameEvent;
+use crate::options::GameOptions;
+use crate::view::PlayerView;
+
+/// Every payload with a schema, by the name its schema goes by
+pub const SCHEMAS: [&str; 4] = ["Action", "GameEvent", "PlayerView", "GameOptions"];
+
+/// The JSON Schema for the payload called `name`, one of `SCHEMAS`
+///
+/// schemas follow the serde attributes, so they describe exactly what `serde_json` reads and
+/// writes, with everything they refer to under `$defs`
+pub fn schema(name: &str) -> Option<Schema> {
+    match name {
+        "Action" => Some(schema_for!(Action)),
+        "GameEvent" => Some(schema_for!(GameEvent)),
+        "PlayerView" => Some(schema_for!(PlayerView)),
+        "GameOptions" => Some(schema_for!(GameOptions)),
+        _ => None,
+    }
+}
+
+/// Write every one of `SCHEMAS` into `dir` as `<name>.schema.json`, returning the paths written
+pub fn export_schemas(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for name in SCHEMAS {
+        let path = dir.join(format!("{}.schema.json", name));
+        let schema = schema(name).expect("every name in SCHEMAS has a schema");
+        fs::write(&path, serde_json::to_string_pretty(&schema)?)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        for name in SCHEMAS {
+            let schema = schema(name).unwrap();
+            assert_eq!(schema.get("title"), Some(&Value::from(name)));
+        }
+        assert!(schema("GameConfig").is_none());
+
+        // tagged enums follow their serde attributes
+        let action = serde_json::to_string(&schema("Action").unwrap()).unwrap();
+        assert!(action.contains(r#""const":"roll_dice""#));
+        // and whatever a view refers to is defined alongside it
+        let view = schema("PlayerView").unwrap();
+        let defs = view.get("$defs").unwrap().as_object().unwrap();
+        for def in ["Board", "Resources", "OpponentView", "Action"] {
+            assert!(defs.contains_key(def), "{}", def);
+        }
+    }
+
+    #[test]
+    fn test_export_schemas() {
+        let dir = std::env::temp_dir().join(format!("catan-schemas-{}", std::process::id()));
+        let written = export_schemas(&dir).unwrap();
+        assert_eq!(written.len(), SCHEMAS.len());
+
+        let json = fs::read_to_string(dir.join("GameOptions.schema.json")).unwrap();
+        let schema: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(schema["title"], "GameOptions");
+        assert!(schema["properties"]["timeouts"].is_object());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::achievements::Achievement;
+use crate::player::PlayerColour;
+
+/// Where each of a player's victory points came from
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct VpBreakdown {
+    /// One point for each settlement on the board
+    pub settlements: usize,
+    /// Two points for each city on the board
+    pub cities: usize,
+    pub longest_road: usize,
+    pub largest_army: usize,
+    pub harbormaster: usize,
+    /// Victory point development cards, which only their owner knows about until the game ends
+    pub hidden_cards: usize,
+    /// Victory point chits handed out by scenarios
+    pub chits: usize,
+}
+
+impl VpBreakdown {
+    pub fn total(&self) -> usize {
+        self.settlements
+            + self.cities
+            + self.longest_road
+            + self.largest_army
+            + self.harbormaster
+            + self.hidden_cards
+            + self.chits
+    }
+
+    /// Points other players can see, i.e. everything except hidden victory point cards
+    pub fn public(&self) -> usize {
+        self.total() - self.hidden_cards
+    }
+}
+
+/// How a game turned out, or how it stands so far
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameSummary {
+    pub winner: Option<PlayerColour>,
+    /// Every player in turn order
+    pub players: Vec<PlayerSummary>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerSummary {
+    pub colour: PlayerColour,
+    pub victory_points: VpBreakdown,
+    pub achievements: Vec<Achievement>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_total() {
+        let vp = VpBreakdown {
+            settlements: 2,
+            cities: 4,
+            longest_road: 2,
+            largest_army: 0,
+            harbormaster: 2,
+            hidden_cards: 1,
+            chits: 1,
+        };
+        assert_eq!(vp.total(), 12);
+        assert_eq!(vp.public(), 11);
+        assert_eq!(VpBreakdown::default().total(), 0);
+    }
+}
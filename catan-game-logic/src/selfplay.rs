@@ -0,0 +1,432 @@
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::actions::Action;
+use crate::driver::{PlayerController, DEFAULT_MAX_STEPS};
+use crate::env::{Env, Observation, Reward};
+use crate::game::Game;
+use crate::options::GameOptions;
+use crate::platform::entropy_rng;
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+use crate::rollout::random_discard;
+use crate::view::PlayerView;
+
+/// First bytes of every self-play log, see `SelfPlay::run`
+pub const SELF_PLAY_MAGIC: &[u8; 8] = b"CATANSP1";
+
+/// Something which picks actions for one seat of a self-play game
+pub trait Agent: Debug {
+    /// Choose what to do next, given what the agent can see
+    ///
+    /// when the observation asks for a `discard`, the action has to be an `Action::Discard`
+    fn act(&mut self, observation: &Observation, rng: &mut StdRng) -> Action;
+}
+
+/// Plays uniformly at random, apart from ending its turn with probability `end_turn` whenever
+/// it's allowed to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RandomAgent {
+    pub end_turn: f64,
+}
+
+impl Default for RandomAgent {
+    fn default() -> Self {
+        Self { end_turn: 0.5 }
+    }
+}
+
+impl RandomAgent {
+    fn pick(&self, actions: &[Action], rng: &mut impl Rng) -> Action {
+        if actions.contains(&Action::EndTurn) && rng.gen_bool(self.end_turn) {
+            return Action::EndTurn;
+        }
+        actions[rng.gen_range(0..actions.len())].clone()
+    }
+}
+
+impl Agent for RandomAgent {
+    fn act(&mut self, observation: &Observation, rng: &mut StdRng) -> Action {
+        if let Some(owed) = observation.discard {
+            return Action::Discard {
+                cards: random_discard(observation.hand, owed, rng),
+            };
+        }
+        self.pick(&observation.legal_actions, rng)
+    }
+}
+
+/// Plays from the thread's random number generator, use an `AgentController` for games which
+/// can be replayed from a seed
+impl PlayerController for RandomAgent {
+    fn choose_action(&mut self, _view: &PlayerView, legal: &[Action]) -> Action {
+        self.pick(legal, &mut entropy_rng())
+    }
+
+    fn choose_discard(&mut self, view: &PlayerView, count: usize) -> Resources {
+        random_discard(view.hand, count, &mut entropy_rng())
+    }
+}
+
+/// Seats an `Agent` at a `GameDriver`, with a random number generator of its own
+#[derive(Debug)]
+pub struct AgentController<A> {
+    agent: A,
+    rng: StdRng,
+}
+
+impl<A: Agent> AgentController<A> {
+    pub fn new(agent: A, seed: u64) -> Self {
+        Self {
+            agent,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<A: Agent> PlayerController for AgentController<A> {
+    fn choose_action(&mut self, view: &PlayerView, _legal: &[Action]) -> Action {
+        // agents choose from the observation, which already lists the legal actions
+        self.agent.act(view, &mut self.rng)
+    }
+
+    fn choose_discard(&mut self, view: &PlayerView, count: usize) -> Resources {
+        match self.agent.act(view, &mut self.rng) {
+            Action::Discard { cards } => cards,
+            _ => random_discard(view.hand, count, &mut self.rng),
+        }
+    }
+}
+
+/// One decision from a self-play game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfPlayRecord {
+    pub observation: Observation,
+    pub action: Action,
+    /// From the deciding player's point of view, 1 if they went on to win, -1 if somebody else
+    /// did, or 0 if the game was abandoned
+    pub outcome: f64,
+}
+
+/// A game's worth of records, as stored in a self-play log
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfPlayGame {
+    /// Which game of the run this was, which also decides its seed
+    pub index: u64,
+    pub records: Vec<SelfPlayRecord>,
+}
+
+/// Plays seeded games between agents, logging every decision to disk for training
+#[derive(Debug)]
+pub struct SelfPlay {
+    seats: Vec<(PlayerColour, Box<dyn Agent>)>,
+    options: GameOptions,
+    seed: u64,
+    max_steps: usize,
+}
+
+impl SelfPlay {
+    /// Self-play between `seats`, with game `n` of the run seeded by `seed + n`
+    pub fn new(seats: Vec<(PlayerColour, Box<dyn Agent>)>, seed: u64) -> Self {
+        Self {
+            seats,
+            options: GameOptions::default(),
+            seed,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    pub fn with_options(mut self, options: GameOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Abandon games which haven't finished after `max_steps` steps
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Play game `index` of the run
+    pub fn play(&mut self, index: u64) -> Result<SelfPlayGame> {
+        let seed = self.seed.wrapping_add(index);
+        let mut decisions = Vec::new();
+        let game = play_game(
+            &mut self.seats,
+            self.options,
+            seed,
+            self.max_steps,
+            |observation, action| decisions.push((observation.clone(), action.clone())),
+        )?;
+
+        let winner = game.winner();
+        let records = decisions
+            .into_iter()
+            .map(|(observation, action)| SelfPlayRecord {
+                outcome: match winner {
+                    Some(winner) if winner == observation.viewer => 1.0,
+                    Some(_) => -1.0,
+                    None => 0.0,
+                },
+                observation,
+                action,
+            })
+            .collect();
+        Ok(SelfPlayGame { index, records })
+    }
+
+    /// Play until the log at `path` holds `games` games, returning how many were played
+    ///
+    /// games already in the log are kept, so an interrupted run picks up where it left off, and
+    /// a game left half written is thrown away and played again. The log is a binary file:
+    ///
+    /// - the 8 bytes of `SELF_PLAY_MAGIC`
+    /// - then for each game, its index as a little endian `u64` and its number of records as a
+    ///   little endian `u32`
+    /// - followed by each record as a little endian `u32` length and that many bytes of the
+    ///   record's JSON
+    pub fn run(&mut self, path: impl AsRef<Path>, games: u64) -> Result<u64> {
+        let path = path.as_ref();
+        let logged = match File::open(path) {
+            Ok(file) => scan_log(file)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => (0, SELF_PLAY_MAGIC.len() as u64),
+            Err(err) => return Err(err.into()),
+        };
+        let (done, valid_len) = logged;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(path)?;
+        if done == 0 {
+            file.set_len(0)?;
+            file.write_all(SELF_PLAY_MAGIC)?;
+        }
+        file.set_len(valid_len)?;
+        file.seek(SeekFrom::End(0))?;
+
+        let mut writer = BufWriter::new(file);
+        for index in done..games {
+            let game = self.play(index)?;
+            write_game(&mut writer, &game)?;
+            writer.flush()?;
+        }
+        Ok(games.saturating_sub(done))
+    }
+}
+
+/// Play a game between `seats` seeded by `seed`, showing each decision to `record` as it's made,
+/// and returning the game once it's won or `max_steps` steps have been taken
+pub(crate) fn play_game(
+    seats: &mut [(PlayerColour, Box<dyn Agent>)],
+    options: GameOptions,
+    seed: u64,
+    max_steps: usize,
+    mut record: impl FnMut(&Observation, &Action),
+) -> Result<Game> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let players = seats.iter().map(|(colour, _)| *colour).collect();
+    let mut env = Env::new(players, options, Reward::Win);
+    let mut observation = env.reset(seed)?;
+
+    for _ in 0..max_steps {
+        let agent = seats
+            .iter_mut()
+            .find(|(colour, _)| *colour == observation.viewer)
+            .map(|(_, agent)| agent)
+            .ok_or(anyhow!("No agent for {:?}", observation.viewer))?;
+        let action = agent.act(&observation, &mut rng);
+        let (next, _, done) = env.step(action.clone())?;
+        record(&observation, &action);
+        observation = next;
+        if done {
+            break;
+        }
+    }
+    Ok(env.into_game())
+}
+
+fn write_game(writer: &mut impl Write, game: &SelfPlayGame) -> Result<()> {
+    writer.write_all(&game.index.to_le_bytes())?;
+    writer.write_all(&(game.records.len() as u32).to_le_bytes())?;
+    for record in &game.records {
+        let json = serde_json::to_vec(record)?;
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(&json)?;
+    }
+    Ok(())
+}
+
+/// Read a whole game from `reader`, or nothing if the log ends before the game does
+fn read_game(reader: &mut impl Read) -> Result<Option<SelfPlayGame>> {
+    fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool> {
+        match reader.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    let mut index = [0; 8];
+    let mut count = [0; 4];
+    if !read_exact(reader, &mut index)? || !read_exact(reader, &mut count)? {
+        return Ok(None);
+    }
+    let mut records = Vec::new();
+    for _ in 0..u32::from_le_bytes(count) {
+        let mut len = [0; 4];
+        if !read_exact(reader, &mut len)? {
+            return Ok(None);
+        }
+        let mut json = vec![0; u32::from_le_bytes(len) as usize];
+        if !read_exact(reader, &mut json)? {
+            return Ok(None);
+        }
+        records.push(serde_json::from_slice(&json)?);
+    }
+    Ok(Some(SelfPlayGame {
+        index: u64::from_le_bytes(index),
+        records,
+    }))
+}
+
+/// Every complete game in a self-play log, see `SelfPlay::run`
+pub fn read_self_play_log(path: impl AsRef<Path>) -> Result<Vec<SelfPlayGame>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_magic(&mut reader)?;
+    let mut games = Vec::new();
+    while let Some(game) = read_game(&mut reader)? {
+        games.push(game);
+    }
+    Ok(games)
+}
+
+fn read_magic(reader: &mut impl Read) -> Result<()> {
+    let mut magic = [0; 8];
+    match reader.read_exact(&mut magic) {
+        Ok(()) if &magic == SELF_PLAY_MAGIC => Ok(()),
+        Ok(()) => Err(anyhow!("Not a self-play log")),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Number of complete games in a log, and the length of the log up to the end of the last one
+fn scan_log(file: File) -> Result<(u64, u64)> {
+    let mut reader = BufReader::new(file);
+    if reader.get_ref().metadata()?.len() < SELF_PLAY_MAGIC.len() as u64 {
+        return Ok((0, SELF_PLAY_MAGIC.len() as u64));
+    }
+    read_magic(&mut reader)?;
+    let mut games = 0;
+    let mut valid_len = reader.stream_position()?;
+    while read_game(&mut reader)?.is_some() {
+        games += 1;
+        valid_len = reader.stream_position()?;
+    }
+    Ok((games, valid_len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dice::FairDice;
+    use crate::driver::GameDriver;
+    use crate::events::GameEvent;
+    use crate::game::GameState;
+
+    fn self_play() -> SelfPlay {
+        SelfPlay::new(
+            vec![
+                (PlayerColour::Red, Box::new(RandomAgent::default())),
+                (PlayerColour::Blue, Box::new(RandomAgent { end_turn: 0.2 })),
+            ],
+            11,
+        )
+        .with_max_steps(300)
+    }
+
+    fn log_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.bin", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_driven_game() {
+        let mut game = Game::new();
+        game.add_player(PlayerColour::Red);
+        game.add_player(PlayerColour::Blue);
+        game.set_dice_roller(FairDice::seeded(3));
+        let mut driver = GameDriver::new(game)
+            .with_controller(PlayerColour::Red, RandomAgent::default())
+            .with_controller(
+                PlayerColour::Blue,
+                AgentController::new(RandomAgent { end_turn: 0.2 }, 3),
+            )
+            .with_max_steps(500);
+
+        let summary = driver.run().unwrap();
+        assert_eq!(summary.players.len(), 2);
+        assert_ne!(driver.game().state(), GameState::Setup);
+        assert!(driver
+            .game()
+            .events()
+            .iter()
+            .any(|event| matches!(event, GameEvent::DiceRolled { .. })));
+    }
+
+    #[test]
+    fn test_play() {
+        let game = self_play().play(0).unwrap();
+        assert!(!game.records.is_empty());
+        let first = &game.records[0];
+        assert!(matches!(
+            first.action,
+            Action::PlaceInitialSettlement { .. }
+        ));
+        assert!(game
+            .records
+            .iter()
+            .all(|r| [1.0, 0.0, -1.0].contains(&r.outcome)));
+    }
+
+    #[test]
+    fn test_run_and_resume() {
+        let path = log_path("test_run_and_resume");
+        let mut sp = self_play();
+        assert_eq!(sp.run(&path, 2).unwrap(), 2);
+
+        // chop the last game in half, as if the run had been interrupted
+        let len = std::fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 10).unwrap();
+        assert_eq!(read_self_play_log(&path).unwrap().len(), 1);
+
+        assert_eq!(sp.run(&path, 3).unwrap(), 2);
+        let games = read_self_play_log(&path).unwrap();
+        assert_eq!(
+            games.iter().map(|g| g.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(sp.run(&path, 3).unwrap(), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_not_a_log() {
+        let path = log_path("test_not_a_log");
+        std::fs::write(&path, b"definitely not a log").unwrap();
+        assert!(read_self_play_log(&path).is_err());
+        assert!(self_play().run(&path, 1).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
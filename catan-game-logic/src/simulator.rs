@@ -0,0 +1,273 @@
+use std::fmt::Debug;
+
+use anyhow::Result;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::dice::{MAX_ROLL, MIN_ROLL};
+use crate::driver::DEFAULT_MAX_STEPS;
+use crate::events::GameEvent;
+use crate::game::Game;
+use crate::options::GameOptions;
+use crate::player::PlayerColour;
+use crate::selfplay::{play_game, Agent};
+
+/// Number of different totals two dice can roll
+const ROLL_TOTALS: usize = (MAX_ROLL - MIN_ROLL + 1) as usize;
+
+/// Statistics gathered over a batch of simulated games, see `Simulator::run`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub games: usize,
+    /// Wins for each seat
+    pub wins: Vec<(PlayerColour, usize)>,
+    /// Games which ran out of steps before anyone won
+    pub unfinished: usize,
+    /// Turns each game lasted, in the order they were played
+    pub game_lengths: Vec<usize>,
+    /// Resource cards each seat collected on each turn, summed over every game, where turn 0 is
+    /// the setup
+    ///
+    /// later turns are only reached by longer games, so see `game_lengths` before averaging them
+    pub income: Vec<(PlayerColour, Vec<usize>)>,
+    /// Number of times each total was rolled over every game, where index 0 is for `MIN_ROLL`
+    pub dice: [usize; ROLL_TOTALS],
+}
+
+impl SimulationReport {
+    /// Fraction of the games `player` won
+    pub fn win_rate(&self, player: &PlayerColour) -> f64 {
+        let wins = self
+            .wins
+            .iter()
+            .find(|(p, _)| p == player)
+            .map_or(0, |(_, n)| *n);
+        match self.games {
+            0 => 0.0,
+            games => wins as f64 / games as f64,
+        }
+    }
+
+    /// Average number of turns a game lasted
+    pub fn mean_length(&self) -> f64 {
+        match self.game_lengths.len() {
+            0 => 0.0,
+            n => self.game_lengths.iter().sum::<usize>() as f64 / n as f64,
+        }
+    }
+
+    /// Add everything in `other` to this report, as if its games were played after these
+    pub fn merge(&mut self, other: &SimulationReport) {
+        self.games += other.games;
+        self.unfinished += other.unfinished;
+        self.game_lengths.extend(&other.game_lengths);
+        for &(player, wins) in &other.wins {
+            match self.wins.iter_mut().find(|(p, _)| *p == player) {
+                Some((_, n)) => *n += wins,
+                None => self.wins.push((player, wins)),
+            }
+        }
+        for (player, income) in &other.income {
+            let total = match self.income.iter_mut().find(|(p, _)| p == player) {
+                Some((_, total)) => total,
+                None => {
+                    self.income.push((*player, Vec::new()));
+                    &mut self.income.last_mut().unwrap().1
+                }
+            };
+            if total.len() < income.len() {
+                total.resize(income.len(), 0);
+            }
+            for (total, n) in total.iter_mut().zip(income) {
+                *total += n;
+            }
+        }
+        for (total, n) in self.dice.iter_mut().zip(other.dice) {
+            *total += n;
+        }
+    }
+
+    /// Add a finished, or abandoned, game to the report
+    pub fn record(&mut self, game: &Game) {
+        self.games += 1;
+        for player in game.players() {
+            if !self.wins.iter().any(|(p, _)| p == player.colour()) {
+                self.wins.push((*player.colour(), 0));
+                self.income.push((*player.colour(), Vec::new()));
+            }
+        }
+        match game.winner() {
+            Some(winner) => {
+                if let Some((_, n)) = self.wins.iter_mut().find(|(p, _)| *p == winner) {
+                    *n += 1;
+                }
+            }
+            None => self.unfinished += 1,
+        }
+
+        let mut turn = 0;
+        for event in game.events() {
+            match event {
+                GameEvent::DiceRolled { .. } => turn += 1,
+                GameEvent::Production { player, resources } => {
+                    if let Some((_, income)) = self.income.iter_mut().find(|(p, _)| p == player) {
+                        if income.len() <= turn {
+                            income.resize(turn + 1, 0);
+                        }
+                        income[turn] += resources.total();
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.game_lengths.push(turn);
+
+        for (total, n) in self.dice.iter_mut().zip(game.roll_history().counts()) {
+            *total += n;
+        }
+    }
+}
+
+/// Plays batches of seeded games between agents without anyone watching, reporting how they went
+///
+/// for balance testing and comparing agents, unlike `SelfPlay` nothing is kept of the games
+/// themselves. Every game is played by fresh copies of the agents and decided entirely by its
+/// own seed, so a run reports the same however its games are spread over threads
+#[derive(Debug)]
+pub struct Simulator {
+    seats: Vec<(PlayerColour, Box<dyn Seat>)>,
+    options: GameOptions,
+    seed: u64,
+    max_steps: usize,
+}
+
+/// Hands out a fresh agent for each game
+trait Seat: Debug + Send + Sync {
+    fn agent(&self) -> Box<dyn Agent>;
+}
+
+impl<A: Agent + Clone + Send + Sync + 'static> Seat for A {
+    fn agent(&self) -> Box<dyn Agent> {
+        Box::new(self.clone())
+    }
+}
+
+impl Simulator {
+    /// Simulate games with game `n` of the run seeded by `seed + n`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seats: Vec::new(),
+            options: GameOptions::default(),
+            seed,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    /// Seat a copy of `agent` as `player` at the start of every game
+    pub fn with_seat(
+        mut self,
+        player: PlayerColour,
+        agent: impl Agent + Clone + Send + Sync + 'static,
+    ) -> Self {
+        self.seats.retain(|(colour, _)| *colour != player);
+        self.seats.push((player, Box::new(agent)));
+        self
+    }
+
+    pub fn with_options(mut self, options: GameOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Abandon games which haven't finished after `max_steps` steps
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Play game `index` of the run, returning it once it's over
+    pub fn play(&self, index: u64) -> Result<Game> {
+        let mut seats: Vec<_> = self
+            .seats
+            .iter()
+            .map(|(colour, seat)| (*colour, seat.agent()))
+            .collect();
+        let seed = self.seed.wrapping_add(index);
+        play_game(&mut seats, self.options, seed, self.max_steps, |_, _| ())
+    }
+
+    /// Play `games` games, reporting on all of them together
+    ///
+    /// with the `parallel` feature, games are spread over rayon's thread pool
+    pub fn run(&self, games: u64) -> Result<SimulationReport> {
+        let report_on = |index| {
+            let mut report = SimulationReport::default();
+            report.record(&self.play(index)?);
+            Ok(report)
+        };
+        #[cfg(feature = "parallel")]
+        let reports: Vec<Result<SimulationReport>> =
+            (0..games).into_par_iter().map(report_on).collect();
+        #[cfg(not(feature = "parallel"))]
+        let reports: Vec<Result<SimulationReport>> = (0..games).map(report_on).collect();
+
+        let mut report = SimulationReport::default();
+        for game in reports {
+            report.merge(&game?);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::selfplay::RandomAgent;
+
+    fn simulator() -> Simulator {
+        Simulator::new(4)
+            .with_seat(PlayerColour::Red, RandomAgent::default())
+            .with_seat(PlayerColour::Blue, RandomAgent { end_turn: 0.2 })
+            .with_max_steps(300)
+    }
+
+    #[test]
+    fn test_run() {
+        let report = simulator().run(3).unwrap();
+        assert_eq!(report.games, 3);
+        assert_eq!(report.game_lengths.len(), 3);
+        let wins: usize = report.wins.iter().map(|(_, n)| n).sum();
+        assert_eq!(wins + report.unfinished, 3);
+        assert_eq!(
+            report.dice.iter().sum::<usize>(),
+            report.game_lengths.iter().sum::<usize>()
+        );
+        // everyone collects from their second settlement during the setup
+        assert!(report.income.iter().all(|(_, income)| income[0] > 0));
+        assert!(report.mean_length() > 0.0);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let report = simulator().run(4).unwrap();
+        assert_eq!(simulator().run(4).unwrap(), report);
+
+        // the same games played one at a time add up to the same report
+        let mut merged = SimulationReport::default();
+        for index in 0..4 {
+            let mut game = SimulationReport::default();
+            game.record(&simulator().play(index).unwrap());
+            merged.merge(&game);
+        }
+        assert_eq!(merged, report);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let report = simulator().run(2).unwrap();
+        let json = serde_json::to_string(&report).unwrap();
+        let back: SimulationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, report);
+    }
+}
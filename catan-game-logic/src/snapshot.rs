@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+use crate::game::Game;
+use crate::invariants::InvariantViolation;
+use crate::validation::BoardViolation;
+
+/// Why `Game::from_snapshot_validated` turned a snapshot down
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SnapshotError {
+    /// The snapshot isn't a game at all, with the reason it couldn't be read
+    Malformed(String),
+    /// The snapshot reads as a game, but not one the engine could have got to by playing, so it
+    /// was tampered with or corrupted along the way
+    Invalid {
+        board: Vec<BoardViolation>,
+        invariants: Vec<InvariantViolation>,
+    },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Malformed(reason) => write!(f, "Couldn't read the snapshot: {}", reason),
+            SnapshotError::Invalid { board, invariants } => {
+                let reasons: Vec<_> = board
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(invariants.iter().map(ToString::to_string))
+                    .collect();
+                write!(f, "The snapshot isn't a valid game: {}", reasons.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Game {
+    /// Pick up a game saved as JSON, as long as it's one the engine could have got to
+    ///
+    /// on top of reading it, the board can't have anything `Board::validate` finds which
+    /// `BoardViolation::is_corruption`, and the game has to pass `Game::check_invariants`, with
+    /// everything wrong with either returned at once. Use this rather than deserializing
+    /// directly for saves which could have been edited, like those sent by clients or read back
+    /// from storage the server doesn't trust
+    pub fn from_snapshot_validated(json: &str) -> Result<Game, SnapshotError> {
+        let game: Game =
+            serde_json::from_str(json).map_err(|err| SnapshotError::Malformed(err.to_string()))?;
+        let mut board = game.get_board().validate().err().unwrap_or_default();
+        board.retain(BoardViolation::is_corruption);
+        let invariants = game.check_invariants().err().unwrap_or_default();
+        if board.is_empty() && invariants.is_empty() {
+            Ok(game)
+        } else {
+            Err(SnapshotError::Invalid { board, invariants })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::player::PlayerColour;
+    use crate::resources::{ResourceKind, Resources};
+
+    fn snapshot() -> (Game, Value) {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        let action = g.legal_actions(&PlayerColour::Red)[0].clone();
+        g.apply(PlayerColour::Red, action).unwrap();
+        let json = serde_json::to_value(&g).unwrap();
+        (g, json)
+    }
+
+    #[test]
+    fn test_valid_snapshot() {
+        let (g, json) = snapshot();
+        let restored = Game::from_snapshot_validated(&json.to_string()).unwrap();
+        assert_eq!(restored, g);
+    }
+
+    #[test]
+    fn test_malformed_snapshot() {
+        let err = Game::from_snapshot_validated("{\"players\": 3").unwrap_err();
+        assert!(matches!(err, SnapshotError::Malformed(_)));
+        assert!(err.to_string().starts_with("Couldn't read the snapshot"));
+    }
+
+    #[test]
+    fn test_bad_trade_id() {
+        let (mut g, _) = snapshot();
+        let wants = Resources::new_explicit(1, 0, 0, 0, 0);
+        let trade_id = g
+            .get_trades_mut()
+            .propose_trade(PlayerColour::Red, Resources::new(), wants);
+        let mut json = serde_json::to_value(&g).unwrap();
+        let trades = json["trades"]["trades"].as_object_mut().unwrap();
+        let trade = trades.remove(&trade_id.to_string()).unwrap();
+        trades.insert("not-a-uuid".into(), trade);
+
+        let err = Game::from_snapshot_validated(&json.to_string()).unwrap_err();
+        let SnapshotError::Malformed(reason) = &err else {
+            panic!("expected the snapshot to be malformed, got {:?}", err);
+        };
+        assert!(reason.contains("uuid"), "{}", reason);
+    }
+
+    #[test]
+    fn test_hashes_recomputed() {
+        let (g, json) = snapshot();
+        let mut missing = json.clone();
+        missing.as_object_mut().unwrap().remove("hash");
+        missing["board"].as_object_mut().unwrap().remove("hash");
+        let mut edited = json;
+        edited["hash"] = json!(1);
+        edited["board"]["hash"] = json!(2);
+
+        for json in [missing, edited] {
+            let restored = Game::from_snapshot_validated(&json.to_string()).unwrap();
+            assert_eq!(restored, g);
+            assert_eq!(restored.zobrist(), g.zobrist());
+        }
+    }
+
+    #[test]
+    fn test_tampered_snapshot() {
+        let (g, mut json) = snapshot();
+        let tile = (0..g.get_board().tile_count())
+            .find(|&idx| g.get_board()[idx].kind().resource().is_some())
+            .unwrap();
+        json["board"]["tiles"][tile]["token"] = json!(13);
+        json["board"]["robber"] = json!(40);
+        let hand = json["players"][0]["resources"].as_object_mut().unwrap();
+        hand.insert("ore".into(), json!(5));
+
+        let err = Game::from_snapshot_validated(&json.to_string()).unwrap_err();
+        let SnapshotError::Invalid { board, invariants } = &err else {
+            panic!("expected the snapshot to be invalid, got {:?}", err);
+        };
+        assert_eq!(
+            board,
+            &vec![
+                BoardViolation::InvalidToken { tile, token: 13 },
+                BoardViolation::RobberOffBoard { tile: 40 },
+            ]
+        );
+        assert!(matches!(
+            invariants[..],
+            [InvariantViolation::ResourcesNotConserved {
+                kind: ResourceKind::Ore,
+                ..
+            }]
+        ));
+        assert!(err.to_string().contains("The robber is on tile 40"));
+    }
+}
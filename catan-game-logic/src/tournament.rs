@@ -0,0 +1,417 @@
+use std::cmp::Reverse;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::player::PlayerColour;
+use crate::report::GameReport;
+
+/// Colours handed out around each table, in seat order
+const SEAT_COLOURS: [PlayerColour; 4] = [
+    PlayerColour::Red,
+    PlayerColour::Blue,
+    PlayerColour::Green,
+    PlayerColour::Purple,
+];
+
+/// Points for finishing first, second, third and fourth at a table, unless
+/// `with_placement_points` says otherwise
+pub const DEFAULT_PLACEMENT_POINTS: [usize; 4] = [3, 2, 1, 0];
+
+/// How players are drawn together from one round to the next
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Format {
+    /// Enough rounds for everyone to have had the chance to play everyone else, sitting with
+    /// the players they've met least
+    RoundRobin,
+    /// A fixed number of rounds, seating players with others near them in the standings
+    Swiss { rounds: usize },
+}
+
+/// One game in a round, and who sits where
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Table {
+    pub round: usize,
+    pub seats: Vec<(PlayerColour, String)>,
+    /// The game being played at this table, once the organizer has set one up
+    pub game: Option<Uuid>,
+    /// Where each player finished, from 0 for the winner, once the game is over. Tied players
+    /// share the better place
+    pub placings: Option<Vec<(String, usize)>>,
+}
+
+impl Table {
+    pub fn is_finished(&self) -> bool {
+        self.placings.is_some()
+    }
+}
+
+/// A player's record over the tournament so far
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Standing {
+    pub player: String,
+    pub points: usize,
+    pub games: usize,
+    pub wins: usize,
+}
+
+/// A bracket of games played over several rounds, with standings by placement points
+///
+/// the organizer starts each round with `next_round`, links every table to the game set up for
+/// it with `assign`, and hands finished games back with `record`. The next round can only start
+/// once every table in the last one has finished
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tournament {
+    format: Format,
+    players: Vec<String>,
+    table_size: usize,
+    placement_points: Vec<usize>,
+    tables: Vec<Table>,
+}
+
+impl Tournament {
+    /// Fails unless there are enough players for a table, and tables seat between 2 and 4
+    pub fn new(
+        format: Format,
+        players: impl IntoIterator<Item = impl Into<String>>,
+        table_size: usize,
+    ) -> Result<Self> {
+        let players: Vec<String> = players.into_iter().map(Into::into).collect();
+        if !(2..=SEAT_COLOURS.len()).contains(&table_size) {
+            return Err(anyhow!("Tables seat between 2 and {}", SEAT_COLOURS.len()));
+        }
+        if players.len() < table_size {
+            return Err(anyhow!("Need at least {} players", table_size));
+        }
+        if (1..players.len()).any(|idx| players[..idx].contains(&players[idx])) {
+            return Err(anyhow!("Every player needs a different name"));
+        }
+        Ok(Self {
+            format,
+            players,
+            table_size,
+            placement_points: DEFAULT_PLACEMENT_POINTS.to_vec(),
+            tables: Vec::new(),
+        })
+    }
+
+    /// Award `points[place]` for each place at a table, and nothing for places past the end
+    pub fn with_placement_points(mut self, points: Vec<usize>) -> Self {
+        self.placement_points = points;
+        self
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    /// How many rounds the tournament lasts
+    pub fn rounds(&self) -> usize {
+        match self.format {
+            Format::RoundRobin => (self.players.len() - 1).div_ceil(self.table_size - 1),
+            Format::Swiss { rounds } => rounds,
+        }
+    }
+
+    /// Rounds started so far
+    pub fn round(&self) -> usize {
+        self.tables.last().map_or(0, |table| table.round + 1)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.round() == self.rounds() && self.tables.iter().all(Table::is_finished)
+    }
+
+    /// Seat everyone for the next round, returning its tables
+    pub fn next_round(&mut self) -> Result<&[Table]> {
+        if self.round() >= self.rounds() {
+            return Err(anyhow!("Every round has been played"));
+        }
+        if !self.tables.iter().all(Table::is_finished) {
+            return Err(anyhow!("The last round hasn't finished yet"));
+        }
+
+        let mut remaining: Vec<String> = match self.format {
+            Format::RoundRobin => self.players.clone(),
+            Format::Swiss { .. } => self.standings().into_iter().map(|s| s.player).collect(),
+        };
+        let round = self.round();
+        let first = self.tables.len();
+        for size in self.table_sizes() {
+            let mut seated = vec![remaining.remove(0)];
+            while seated.len() < size {
+                // the first of the players who've met this table least, so Swiss tables stay
+                // close in the standings
+                let idx = (0..remaining.len())
+                    .min_by_key(|&idx| {
+                        seated
+                            .iter()
+                            .map(|player| self.meetings(player, &remaining[idx]))
+                            .sum::<usize>()
+                    })
+                    .unwrap_or(0);
+                seated.push(remaining.remove(idx));
+            }
+            self.tables.push(Table {
+                round,
+                seats: SEAT_COLOURS.into_iter().zip(seated).collect(),
+                game: None,
+                placings: None,
+            });
+        }
+        Ok(&self.tables[first..])
+    }
+
+    /// Tables for every player, as even in size as they can be without going over `table_size`
+    fn table_sizes(&self) -> Vec<usize> {
+        let tables = self.players.len().div_ceil(self.table_size);
+        let (size, larger) = (self.players.len() / tables, self.players.len() % tables);
+        (0..tables)
+            .map(|idx| size + usize::from(idx < larger))
+            .collect()
+    }
+
+    /// Games `a` and `b` have sat at together
+    pub fn meetings(&self, a: &str, b: &str) -> usize {
+        self.tables
+            .iter()
+            .filter(|table| {
+                table.seats.iter().any(|(_, p)| p == a) && table.seats.iter().any(|(_, p)| p == b)
+            })
+            .count()
+    }
+
+    /// Link table `table`, counting from the first table of the first round, to `game`
+    pub fn assign(&mut self, table: usize, game: Uuid) -> Result<()> {
+        if self.tables.iter().any(|t| t.game == Some(game)) {
+            return Err(anyhow!(
+                "That game is already being played at another table"
+            ));
+        }
+        let table = self
+            .tables
+            .get_mut(table)
+            .ok_or(anyhow!("There's no table {}", table))?;
+        if table.is_finished() {
+            return Err(anyhow!("That table has already finished"));
+        }
+        table.game = Some(game);
+        Ok(())
+    }
+
+    /// Games assigned to tables which haven't finished yet
+    pub fn pending_games(&self) -> Vec<Uuid> {
+        self.tables
+            .iter()
+            .filter(|table| !table.is_finished())
+            .filter_map(|table| table.game)
+            .collect()
+    }
+
+    /// Record how `game` went, placing the winner first and everyone else by their points
+    pub fn record(&mut self, game: Uuid, report: &GameReport) -> Result<()> {
+        let table = self
+            .tables
+            .iter_mut()
+            .find(|table| table.game == Some(game))
+            .ok_or(anyhow!("No table is playing that game"))?;
+        if table.is_finished() {
+            return Err(anyhow!("That table has already finished"));
+        }
+
+        let mut scores = Vec::new();
+        for (colour, player) in &table.seats {
+            let points = report
+                .player(colour)
+                .ok_or(anyhow!("{} didn't play in that game", colour))?
+                .victory_points;
+            scores.push((player.clone(), (report.winner == Some(*colour), points)));
+        }
+        scores.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let placings = scores
+            .iter()
+            .map(|(player, score)| {
+                let place = scores.iter().position(|(_, other)| other == score);
+                (player.clone(), place.unwrap_or(0))
+            })
+            .collect();
+        table.placings = Some(placings);
+        Ok(())
+    }
+
+    /// Every player's record, best first, by placement points and then wins
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|player| Standing {
+                player: player.clone(),
+                points: 0,
+                games: 0,
+                wins: 0,
+            })
+            .collect();
+        for placings in self
+            .tables
+            .iter()
+            .filter_map(|table| table.placings.as_ref())
+        {
+            for (player, place) in placings {
+                if let Some(standing) = standings.iter_mut().find(|s| &s.player == player) {
+                    standing.games += 1;
+                    standing.wins += usize::from(*place == 0);
+                    standing.points += self.placement_points.get(*place).copied().unwrap_or(0);
+                }
+            }
+        }
+        // sorting is stable, so players level on everything stay in the order they entered
+        standings.sort_by_key(|s| Reverse((s.points, s.wins)));
+        standings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dice::Luck;
+    use crate::report::PlayerReport;
+
+    fn players(count: usize) -> Vec<String> {
+        (0..count).map(|idx| format!("player {}", idx)).collect()
+    }
+
+    /// A report where the first seat wins, and each seat after scores a point less
+    fn report(table: &Table) -> GameReport {
+        GameReport {
+            winner: Some(table.seats[0].0),
+            turns: 50,
+            players: table
+                .seats
+                .iter()
+                .enumerate()
+                .map(|(idx, &(colour, _))| PlayerReport {
+                    colour,
+                    income: Default::default(),
+                    trades: Default::default(),
+                    luck: Luck::new(colour),
+                    victory_points: 10 - idx,
+                })
+                .collect(),
+            dice: Vec::new(),
+            victory_points: Vec::new(),
+            awards: Vec::new(),
+        }
+    }
+
+    /// Give every table in the current round a game and finish it
+    fn play_round(t: &mut Tournament) {
+        let first = t.tables().len() - t.tables().iter().filter(|t| !t.is_finished()).count();
+        for idx in first..t.tables().len() {
+            let game = Uuid::new_v4();
+            t.assign(idx, game).unwrap();
+            let report = report(&t.tables()[idx]);
+            t.record(game, &report).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Tournament::new(Format::RoundRobin, players(8), 5).is_err());
+        assert!(Tournament::new(Format::RoundRobin, players(3), 4).is_err());
+        assert!(Tournament::new(Format::RoundRobin, ["a", "b", "a"], 3).is_err());
+        let t = Tournament::new(Format::RoundRobin, players(9), 4).unwrap();
+        assert_eq!(t.rounds(), 3);
+        assert_eq!(t.table_sizes(), vec![3, 3, 3]);
+        assert_eq!(t.round(), 0);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut t = Tournament::new(Format::RoundRobin, players(4), 2).unwrap();
+        assert_eq!(t.rounds(), 3);
+        assert!(t.next_round().is_ok());
+        assert!(t.next_round().is_err());
+        play_round(&mut t);
+        for _ in 1..3 {
+            t.next_round().unwrap();
+            play_round(&mut t);
+        }
+        assert!(t.is_finished());
+        assert!(t.next_round().is_err());
+
+        // everyone met everyone else exactly once
+        let names = players(4);
+        for a in &names {
+            for b in names.iter().filter(|b| *b != a) {
+                assert_eq!(t.meetings(a, b), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_swiss() {
+        let mut t = Tournament::new(Format::Swiss { rounds: 2 }, players(8), 4).unwrap();
+        let tables = t.next_round().unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(
+            tables[0].seats[0],
+            (PlayerColour::Red, "player 0".to_string())
+        );
+        play_round(&mut t);
+
+        // both winners sit together in the next round
+        let leaders: Vec<_> = t
+            .standings()
+            .into_iter()
+            .take(2)
+            .map(|s| s.player)
+            .collect();
+        assert_eq!(t.standings()[0].points, 3);
+        let tables = t.next_round().unwrap();
+        let top: Vec<_> = tables[0].seats.iter().map(|(_, p)| p.clone()).collect();
+        assert!(leaders.iter().all(|leader| top.contains(leader)));
+        assert_eq!(t.meetings(&leaders[0], &leaders[1]), 1);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut t = Tournament::new(Format::Swiss { rounds: 1 }, players(3), 3).unwrap();
+        t.next_round().unwrap();
+        let game = Uuid::new_v4();
+        assert!(t.record(game, &report(&t.tables()[0])).is_err());
+        t.assign(0, game).unwrap();
+        assert_eq!(t.pending_games(), vec![game]);
+
+        // the two players behind the winner tie, sharing second place
+        let mut tied = report(&t.tables()[0]);
+        tied.players[2].victory_points = tied.players[1].victory_points;
+        t.record(game, &tied).unwrap();
+        assert!(t.record(game, &tied).is_err());
+        assert!(t.pending_games().is_empty());
+        assert!(t.is_finished());
+
+        let placings = t.tables()[0].placings.clone().unwrap();
+        assert_eq!(
+            placings.iter().map(|(_, p)| *p).collect::<Vec<_>>(),
+            [0, 1, 1]
+        );
+        let standings = t.standings();
+        assert_eq!(standings[0].wins, 1);
+        assert_eq!((standings[1].points, standings[2].points), (2, 2));
+    }
+}
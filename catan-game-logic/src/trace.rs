@@ -0,0 +1,77 @@
+//! Instrumentation with `tracing`, which compiles to nothing without the `tracing` feature
+
+/// Enter a span at `level` for the rest of the enclosing block
+#[allow(unused_macros)]
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)*).entered();
+    };
+}
+
+/// Record an event at `level`, inside whichever span is entered
+#[allow(unused_macros)]
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($arg)*);
+    };
+}
+
+#[cfg(all(test, feature = "tracing", feature = "rand"))]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::game::Game;
+    use crate::{Action, PlayerColour};
+
+    /// Counts the spans opened and events recorded, throwing everything else away
+    #[derive(Clone, Default)]
+    struct Counter {
+        spans: Arc<AtomicUsize>,
+        events: Arc<AtomicUsize>,
+    }
+
+    impl Subscriber for Counter {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(self.spans.fetch_add(1, Ordering::SeqCst) as u64 + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {
+            self.events.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_tracing() {
+        let counter = Counter::default();
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        let settle = g.legal_actions(&PlayerColour::Red)[0].clone();
+
+        tracing::subscriber::with_default(counter.clone(), || {
+            g.apply(PlayerColour::Red, settle).unwrap();
+            assert!(g.apply(PlayerColour::Blue, Action::EndTurn).is_err());
+        });
+        // a span for each action, the settlement's events and the rejected action
+        assert_eq!(counter.spans.load(Ordering::SeqCst), 2);
+        assert!(counter.events.load(Ordering::SeqCst) >= 3);
+    }
+}
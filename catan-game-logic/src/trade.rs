@@ -1,18 +1,23 @@
 use crate::{player::PlayerColour, resources::Resources};
 
+use alloc::vec::Vec;
+
 use anyhow::{anyhow, Result};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TradeState {
     Proposed,
     LockedIn,
     Accepted,
 }
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use TradeState::*;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Trade {
     from: PlayerColour,
     accepted_by: Vec<PlayerColour>,
@@ -82,6 +87,11 @@ impl Trade {
         }
     }
 
+    /// Players who have said they're willing to make this trade
+    pub fn accepted_by(&self) -> &[PlayerColour] {
+        &self.accepted_by
+    }
+
     pub fn offering(&self) -> &Resources {
         &self.offering
     }
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+use crate::trade::Trade;
+
+/// Keeps track of trades offered between players, so the bank only has to look after its cards
+///
+/// its map is ordered, so the trades always serialise to the same bytes
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TradeManager {
+    #[cfg_attr(feature = "serde", serde(with = "uuid_map"))]
+    trades: BTreeMap<Uuid, Trade>,
+}
+
+#[cfg(feature = "serde")]
+mod uuid_map {
+    use crate::trade::Trade;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+    use uuid::Uuid;
+
+    pub fn serialize<S>(map: &BTreeMap<Uuid, Trade>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let new_map: BTreeMap<String, &Trade> =
+            map.iter().map(|(k, v)| (k.to_string(), v)).collect();
+        new_map.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<Uuid, Trade>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map: BTreeMap<String, Trade> = BTreeMap::deserialize(deserializer)?;
+        map.into_iter()
+            .map(|(k, v)| Ok((Uuid::parse_str(&k).map_err(D::Error::custom)?, v)))
+            .collect()
+    }
+}
+
+impl TradeManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_trade(&self, trade_id: Uuid) -> Option<&Trade> {
+        self.trades.get(&trade_id)
+    }
+
+    pub fn get_trade_mut(&mut self, trade_id: Uuid) -> Option<&mut Trade> {
+        self.trades.get_mut(&trade_id)
+    }
+
+    /// Number of trades which haven't been withdrawn
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Every trade which hasn't been withdrawn, in order of id
+    pub fn iter(&self) -> impl Iterator<Item = (&Uuid, &Trade)> {
+        self.trades.iter()
+    }
+
+    /// Propose a new trade to the other players
+    ///
+    /// creates a new instance of a `Trade` object, and insert it into the `trades` map
+    pub fn propose_trade(
+        &mut self,
+        from: PlayerColour,
+        offering: Resources,
+        wants: Resources,
+    ) -> Uuid {
+        let t = Trade::new(from, offering, wants);
+        let uuid = Uuid::new_v4();
+        self.trades.insert(uuid, t);
+        uuid
+    }
+
+    /// Withdraw a trade, returning it if it was still open
+    pub fn cancel_trade(&mut self, trade_id: Uuid) -> Option<Trade> {
+        self.trades.remove(&trade_id)
+    }
+
+    /// Indicate a player is willing to make a trade
+    pub fn accept_trade(&mut self, trade_id: Uuid, accepted_by: PlayerColour) -> Result<()> {
+        self.trades
+            .get_mut(&trade_id)
+            .ok_or(anyhow!("Trade not found"))?
+            .accept(accepted_by)
+    }
+
+    /// Indicate that the player offering the trade is willing to finalize the player
+    pub fn finalize_trade(&mut self, trade_id: Uuid, player: PlayerColour) -> Result<()> {
+        self.trades
+            .get_mut(&trade_id)
+            .ok_or(anyhow!("Trade not found"))?
+            .confirm_recipient(player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::trade::TradeState;
+
+    #[test]
+    fn test_propose_trade() {
+        let mut t = TradeManager::new();
+        let trade_id = t.propose_trade(
+            PlayerColour::Red,
+            Resources::new_explicit(0, 0, 1, 0, 1),
+            Resources::new_explicit(2, 0, 0, 0, 0),
+        );
+        assert_eq!(t.len(), 1);
+        assert!(t.get_trade(trade_id).is_some());
+        assert!(t.cancel_trade(trade_id).is_some());
+        assert!(t.is_empty());
+    }
+
+    #[test]
+    fn test_accept_trade() {
+        let mut t = TradeManager::new();
+        let trade_id = t.propose_trade(
+            PlayerColour::Red,
+            Resources::new_explicit(0, 0, 1, 0, 1),
+            Resources::new_explicit(2, 0, 0, 0, 0),
+        );
+        assert!(t.accept_trade(trade_id, PlayerColour::Blue).is_ok());
+        assert_eq!(
+            *t.get_trade(trade_id).unwrap().state(),
+            TradeState::Proposed
+        );
+        assert!(t.accept_trade(Uuid::new_v4(), PlayerColour::Blue).is_err());
+    }
+
+    #[test]
+    fn test_finalize_trade() {
+        let mut t = TradeManager::new();
+        let trade_id = t.propose_trade(
+            PlayerColour::Red,
+            Resources::new_explicit(0, 0, 1, 0, 1),
+            Resources::new_explicit(2, 0, 0, 0, 0),
+        );
+        t.accept_trade(trade_id, PlayerColour::Blue).unwrap();
+        t.finalize_trade(trade_id, PlayerColour::Blue).unwrap();
+
+        assert_eq!(
+            *t.get_trade(trade_id).unwrap().state(),
+            TradeState::LockedIn
+        )
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialization_is_stable() {
+        let mut t = TradeManager::new();
+        for _ in 0..5 {
+            t.propose_trade(
+                PlayerColour::Red,
+                Resources::new_explicit(1, 0, 0, 0, 0),
+                Resources::new_explicit(0, 1, 0, 0, 0),
+            );
+        }
+        let json = serde_json::to_string(&t).unwrap();
+        assert_eq!(serde_json::to_string(&t.clone()).unwrap(), json);
+        let back: TradeManager = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+
+use crate::game::Game;
+use crate::player::PlayerId;
+use crate::resources::Resources;
+
+/// Somewhere resources can move to or from in a `Transfer`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Party {
+    Bank,
+    Player(PlayerId),
+}
+
+/// A set of resource movements between the bank and players, which happen all together or not
+/// at all
+///
+/// every party's debits are checked against what it holds before anything moves, see
+/// `Game::transfer`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Transfer {
+    moves: Vec<(Party, Party, Resources)>,
+}
+
+impl Transfer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move `resources` from `from` to `to`
+    pub fn add(mut self, from: Party, to: Party, resources: Resources) -> Self {
+        self.moves.push((from, to, resources));
+        self
+    }
+
+    /// Move `resources` from one player to another
+    pub fn between(self, from: PlayerId, to: PlayerId, resources: Resources) -> Self {
+        self.add(Party::Player(from), Party::Player(to), resources)
+    }
+
+    /// Have `player` pay `cost` to the bank
+    pub fn pay_bank(self, player: PlayerId, cost: Resources) -> Self {
+        self.add(Party::Player(player), Party::Bank, cost)
+    }
+
+    pub fn moves(&self) -> &[(Party, Party, Resources)] {
+        &self.moves
+    }
+
+    /// Everything `party` gives up over the whole transfer
+    ///
+    /// incoming resources don't count towards it, so no party can pay with cards it's only
+    /// receiving in the same transfer
+    pub fn debits(&self, party: Party) -> Resources {
+        let mut total = Resources::new();
+        for (from, _, resources) in &self.moves {
+            if *from == party {
+                total += *resources;
+            }
+        }
+        total
+    }
+}
+
+impl Game {
+    /// Carry out `transfer`, failing without moving anything if a player can't be found or any
+    /// party can't cover its debits
+    pub fn transfer(&mut self, transfer: &Transfer) -> Result<()> {
+        let mut parties: Vec<Party> = Vec::new();
+        for (from, to, _) in transfer.moves() {
+            for party in [*from, *to] {
+                if !parties.contains(&party) {
+                    parties.push(party);
+                }
+            }
+        }
+
+        for party in &parties {
+            let debits = transfer.debits(*party);
+            let covered = match party {
+                Party::Bank => self.get_bank().resources().contains(&debits),
+                Party::Player(player) => self.get_player(player)?.resources().contains(&debits),
+            };
+            if !covered {
+                return Err(match party {
+                    Party::Bank => anyhow!("The bank doesn't have enough resources"),
+                    Party::Player(_) => anyhow!("Not enough resources"),
+                });
+            }
+        }
+
+        for (from, to, resources) in transfer.moves() {
+            match *from {
+                Party::Bank => self.get_bank_mut().take_resources(*resources)?,
+                Party::Player(player) => self
+                    .get_player_mut(player)?
+                    .resources_mut()
+                    .try_sub(*resources)?,
+            }
+            match *to {
+                Party::Bank => self.get_bank_mut().return_resources(*resources),
+                Party::Player(player) => {
+                    *self.get_player_mut(player)?.resources_mut() += *resources
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::player::PlayerColour::*;
+    use crate::resources::ResourceKind;
+
+    fn game() -> Game {
+        let mut g = Game::new();
+        g.add_player(Red);
+        g.add_player(Blue);
+        *g.get_player_mut(Red).unwrap().resources_mut() = Resources::new_explicit(2, 0, 0, 0, 1);
+        *g.get_player_mut(Blue).unwrap().resources_mut() = Resources::new_explicit(0, 3, 0, 0, 0);
+        g
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut g = game();
+        let transfer = Transfer::new()
+            .between(Red, Blue, Resources::new_explicit(1, 0, 0, 0, 0))
+            .between(Blue, Red, Resources::new_explicit(0, 2, 0, 0, 0))
+            .pay_bank(Red, Resources::new_explicit(0, 0, 0, 0, 1));
+        g.transfer(&transfer).unwrap();
+
+        let red = g.get_player(&Red).unwrap().resources();
+        assert_eq!(*red, Resources::new_explicit(1, 2, 0, 0, 0));
+        let blue = g.get_player(&Blue).unwrap().resources();
+        assert_eq!(*blue, Resources::new_explicit(1, 1, 0, 0, 0));
+        assert_eq!(g.get_bank().remaining(ResourceKind::Lumber), 20);
+    }
+
+    #[test]
+    fn test_failed_transfer_moves_nothing() {
+        let mut g = game();
+        let before = g.clone();
+
+        // blue can't pay, so red mustn't be charged either
+        let transfer = Transfer::new()
+            .between(Red, Blue, Resources::new_explicit(2, 0, 0, 0, 0))
+            .between(Blue, Red, Resources::new_explicit(0, 4, 0, 0, 0));
+        assert!(g.transfer(&transfer).is_err());
+        assert_eq!(g, before);
+
+        // cards coming in can't pay for cards going out
+        let transfer = Transfer::new()
+            .between(Blue, Red, Resources::new_explicit(0, 3, 0, 0, 0))
+            .pay_bank(Red, Resources::new_explicit(0, 1, 0, 0, 0));
+        assert!(g.transfer(&transfer).is_err());
+        assert_eq!(g, before);
+
+        let transfer = Transfer::new().pay_bank(Purple, Resources::new());
+        assert!(g.transfer(&transfer).is_err());
+
+        let transfer = Transfer::new().add(
+            Party::Bank,
+            Party::Player(Red),
+            Resources::new_explicit(20, 0, 0, 0, 0),
+        );
+        assert!(g.transfer(&transfer).is_err());
+        assert_eq!(g, before);
+    }
+}
@@ -0,0 +1,147 @@
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+use ts_rs::{TypeVisitor, TS};
+
+use crate::actions::Action;
+use crate::chat::ChatMessage;
+use crate::events::GameEvent;
+use crate::lobby::Lobby;
+use crate::options::GameOptions;
+use crate::view::PlayerView;
+
+/// TypeScript declarations for a set of types and every type they refer to, all in one `.d.ts`
+/// file so a frontend can check the JSON it trades with the server as it's compiled
+///
+/// the declarations follow the serde attributes, so they describe exactly what `serde_json`
+/// writes. See `TypeScript::api` for the engine's own payloads
+#[derive(Debug, Default)]
+pub struct TypeScript {
+    declared: HashSet<TypeId>,
+    declarations: Vec<String>,
+}
+
+impl TypeScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declarations for the views, actions, events, options, lobbies and chat messages a server
+    /// passes back and forth
+    pub fn api() -> Self {
+        Self::new()
+            .with::<PlayerView>()
+            .with::<Action>()
+            .with::<GameEvent>()
+            .with::<GameOptions>()
+            .with::<Lobby>()
+            .with::<ChatMessage>()
+    }
+
+    /// Declare `T` along with everything it refers to, skipping anything already declared
+    pub fn with<T: TS + 'static + ?Sized>(mut self) -> Self {
+        self.visit::<T>();
+        self
+    }
+
+    /// Number of types declared
+    pub fn len(&self) -> usize {
+        self.declarations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    /// Write every declaration to `writer`, each one exported, returning how many were written
+    pub fn write(&self, mut writer: impl Write) -> Result<usize> {
+        writeln!(
+            writer,
+            "// Generated by catan-game-logic, do not edit by hand"
+        )?;
+        for declaration in &self.declarations {
+            writeln!(writer)?;
+            writeln!(writer, "{}", declaration)?;
+        }
+        writer.flush()?;
+        Ok(self.len())
+    }
+}
+
+impl TypeVisitor for TypeScript {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        // only types with a declaration of their own, rather than `Vec` and the like
+        if T::output_path().is_none() || !self.declared.insert(TypeId::of::<T>()) {
+            return;
+        }
+        let docs = T::DOCS.unwrap_or_default();
+        self.declarations
+            .push(format!("{}export {}", docs, T::decl()));
+        T::visit_dependencies(self);
+    }
+}
+
+/// How serde writes a `std::time::Duration`, for declaring fields which hold one
+#[derive(TS)]
+#[ts(rename = "Duration")]
+pub(crate) struct DurationJson {
+    #[ts(type = "number")]
+    secs: u64,
+    nanos: u32,
+}
+
+/// How serde writes a `std::time::SystemTime`
+#[derive(TS)]
+#[ts(rename = "SystemTime")]
+pub(crate) struct SystemTimeJson {
+    #[ts(type = "number")]
+    secs_since_epoch: u64,
+    nanos_since_epoch: u32,
+}
+
+/// Write declarations for `TypeScript::api` to `writer`, returning how many types were declared
+pub fn export_typescript(writer: impl Write) -> Result<usize> {
+    TypeScript::api().write(writer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resources::Resources;
+
+    #[test]
+    fn test_api() {
+        let ts = TypeScript::api();
+        let mut out = Vec::new();
+        assert_eq!(export_typescript(&mut out).unwrap(), ts.len());
+        let out = String::from_utf8(out).unwrap();
+
+        // everything the payloads refer to is declared, once
+        for name in [
+            "PlayerView",
+            "Action",
+            "GameEvent",
+            "Resources",
+            "Board",
+            "Duration",
+        ] {
+            let declaration = format!("export type {} =", name);
+            assert_eq!(out.matches(&declaration).count(), 1, "{}", name);
+        }
+        // seeds and hashes come out of `serde_json` as plain numbers
+        assert!(!out.contains("bigint"));
+        // tagged enums follow their serde attributes
+        assert!(out.contains(r#"{ "type": "roll_dice" }"#));
+    }
+
+    #[test]
+    fn test_with() {
+        let ts = TypeScript::new().with::<Resources>();
+        assert_eq!(ts.len(), 1);
+        assert_eq!(ts.with::<Resources>().len(), 1);
+        assert!(TypeScript::new().with::<Vec<u8>>().is_empty());
+        assert!(TypeScript::api().len() > TypeScript::new().with::<Action>().len());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::Display;
+
+use crate::board::{Board, TileKind, DEFAULT_TILE_COUNT};
+
+/// Something wrong with a board, found by `Board::validate`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BoardViolation {
+    /// The board doesn't have a tile for every space on the default layout
+    TileCount { expected: usize, found: usize },
+    /// A resource tile's token isn't one of 2 to 12, or is the robber's 7
+    InvalidToken { tile: usize, token: usize },
+    /// Two tiles next to each other both have a 6 or an 8
+    AdjacentRedNumbers { tiles: (usize, usize) },
+    /// The robber is on a tile the board doesn't have
+    RobberOffBoard { tile: usize },
+    /// A harbor on a tile which doesn't touch the sea
+    InlandHarbor { tile: usize },
+    /// A tile without six corners, or a vertex referring to a tile which isn't there
+    BrokenTile { tile: usize },
+    /// Some vertices can't be reached from the others along edges
+    Disconnected,
+}
+
+impl Display for BoardViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardViolation::TileCount { expected, found } => {
+                write!(f, "Expected {} tiles, found {}", expected, found)
+            }
+            BoardViolation::InvalidToken { tile, token } => {
+                write!(f, "Tile {} has an invalid token, {}", tile, token)
+            }
+            BoardViolation::AdjacentRedNumbers { tiles: (a, b) } => {
+                write!(f, "Tiles {} and {} both have a 6 or 8", a, b)
+            }
+            BoardViolation::RobberOffBoard { tile } => {
+                write!(
+                    f,
+                    "The robber is on tile {}, which isn't on the board",
+                    tile
+                )
+            }
+            BoardViolation::InlandHarbor { tile } => {
+                write!(f, "Tile {} has a harbor but isn't on the coast", tile)
+            }
+            BoardViolation::BrokenTile { tile } => write!(f, "Tile {} is malformed", tile),
+            BoardViolation::Disconnected => write!(f, "The board isn't connected"),
+        }
+    }
+}
+
+impl std::error::Error for BoardViolation {}
+
+impl BoardViolation {
+    /// Whether no board the engine lays out itself could have this, so the board must have been
+    /// corrupted or tampered with, rather than just being one a person wouldn't lay out
+    ///
+    /// boards laid out by hand can put a 7 on a resource tile or a harbor inland, and the game
+    /// plays on them fine, but they always have every tile, rolls of 2 to 12 and the robber on
+    /// the board
+    pub fn is_corruption(&self) -> bool {
+        match self {
+            BoardViolation::InvalidToken { token, .. } => !(2..=12).contains(token),
+            BoardViolation::AdjacentRedNumbers { .. } | BoardViolation::InlandHarbor { .. } => {
+                false
+            }
+            BoardViolation::TileCount { .. }
+            | BoardViolation::RobberOffBoard { .. }
+            | BoardViolation::BrokenTile { .. }
+            | BoardViolation::Disconnected => true,
+        }
+    }
+}
+
+/// Optional checks for `Board::validate_with`
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ValidationRules {
+    /// Don't allow 6s and 8s on neighbouring tiles, as recommended for a balanced board
+    pub separate_red_numbers: bool,
+}
+
+impl Board {
+    /// Check the board is one the game can be played on, returning everything wrong with it
+    ///
+    /// useful for scenario authors, and for catching boards which were corrupted on their way
+    /// in from a save. See `validate_with` for stricter checks
+    pub fn validate(&self) -> Result<(), Vec<BoardViolation>> {
+        self.validate_with(&ValidationRules::default())
+    }
+
+    /// Check the board, along with any of the optional checks in `rules`
+    pub fn validate_with(&self, rules: &ValidationRules) -> Result<(), Vec<BoardViolation>> {
+        let mut violations = Vec::new();
+        if self.tile_count() != DEFAULT_TILE_COUNT {
+            violations.push(BoardViolation::TileCount {
+                expected: DEFAULT_TILE_COUNT,
+                found: self.tile_count(),
+            });
+        }
+
+        let is_red = |idx: usize| self.tile(idx).is_some_and(|t| matches!(t.token(), 6 | 8));
+        for idx in 0..self.tile_count() {
+            let tile = &self[idx];
+            let token = *tile.token();
+            let producing = tile.kind().resource().is_some();
+            if producing && (token == 7 || !(2..=12).contains(&token)) {
+                violations.push(BoardViolation::InvalidToken { tile: idx, token });
+            }
+            if rules.separate_red_numbers && is_red(idx) {
+                for other in self.adjacent_tiles(idx).filter(|&o| o > idx && is_red(o)) {
+                    violations.push(BoardViolation::AdjacentRedNumbers {
+                        tiles: (idx, other),
+                    });
+                }
+            }
+            if matches!(tile.kind(), TileKind::ResourceWithHarbor(..))
+                && self.adjacent_tiles(idx).count() == 6
+            {
+                violations.push(BoardViolation::InlandHarbor { tile: idx });
+            }
+            if self.vertices_of_tile(idx).count() != 6 {
+                violations.push(BoardViolation::BrokenTile { tile: idx });
+            }
+        }
+        for vertex in self.vertices() {
+            for &tile in vertex.tiles().iter().filter(|&&t| t >= self.tile_count()) {
+                violations.push(BoardViolation::BrokenTile { tile });
+            }
+        }
+
+        if let Some(tile) = self.robber().filter(|&tile| tile >= self.tile_count()) {
+            violations.push(BoardViolation::RobberOffBoard { tile });
+        }
+        if !self.is_connected() {
+            violations.push(BoardViolation::Disconnected);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Whether every vertex can be reached from the first along edges
+    fn is_connected(&self) -> bool {
+        let Some(start) = self.vertices().first() else {
+            return true;
+        };
+        let mut seen = vec![false; self.vertices().len()];
+        let mut stack = vec![*start.id()];
+        seen[start.id().index()] = true;
+        while let Some(vertex) = stack.pop() {
+            for next in self.adjacent_vertices(vertex) {
+                if let Some(seen) = seen.get_mut(next.index()).filter(|seen| !**seen) {
+                    *seen = true;
+                    stack.push(next);
+                }
+            }
+        }
+        seen.into_iter().all(|seen| seen)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{HarborKind, Tile};
+    use crate::resources::ResourceKind;
+
+    /// A board where every token is fine, with the desert in the middle and 6s and 8s on
+    /// neighbouring tiles 0 and 1
+    fn board() -> Board {
+        Board::from_tiles(std::array::from_fn(|idx| match idx {
+            9 => Tile::new(TileKind::Desert, 7),
+            0 => Tile::new(TileKind::Resource(ResourceKind::Ore), 6),
+            1 => Tile::new(TileKind::Resource(ResourceKind::Grain), 8),
+            2 => Tile::new(
+                TileKind::ResourceWithHarbor(HarborKind::Generic, ResourceKind::Wool),
+                5,
+            ),
+            _ => Tile::new(TileKind::Resource(ResourceKind::Brick), 2 + idx % 4),
+        }))
+    }
+
+    #[test]
+    fn test_valid_board() {
+        let b = board();
+        assert_eq!(b.validate(), Ok(()));
+        assert_eq!(
+            b.validate_with(&ValidationRules {
+                separate_red_numbers: true
+            }),
+            Err(vec![BoardViolation::AdjacentRedNumbers { tiles: (0, 1) }])
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut b = board();
+        b.set_tile(3, Tile::new(TileKind::Resource(ResourceKind::Lumber), 7))
+            .unwrap();
+        b.set_tile(
+            9,
+            Tile::new(
+                TileKind::ResourceWithHarbor(HarborKind::Generic, ResourceKind::Ore),
+                4,
+            ),
+        )
+        .unwrap();
+        let violations = b.validate().unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                BoardViolation::InvalidToken { tile: 3, token: 7 },
+                BoardViolation::InlandHarbor { tile: 9 },
+            ]
+        );
+        assert_eq!(violations[0].to_string(), "Tile 3 has an invalid token, 7");
+    }
+
+    #[test]
+    fn test_empty_board() {
+        let violations = Board::default().validate().unwrap_err();
+        assert_eq!(
+            violations,
+            vec![BoardViolation::TileCount {
+                expected: DEFAULT_TILE_COUNT,
+                found: 0
+            }]
+        );
+    }
+}
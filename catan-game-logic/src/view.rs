@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use crate::actions::Action;
+use crate::bank::BankSummary;
+use crate::board::Board;
+use crate::development_cards::DevelopmentCard;
+use crate::game::{GameState, TurnPhase};
+use crate::player::PlayerColour;
+use crate::resources::Resources;
+use crate::score::VpBreakdown;
+
+/// The game as one player sees it, leaving out what's hidden from them, from `Game::view_for`
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct PlayerView {
+    pub viewer: PlayerColour,
+    pub state: GameState,
+    pub phase: TurnPhase,
+    pub current_player: Option<PlayerColour>,
+    pub board: Board,
+    pub bank: BankSummary,
+    pub hand: Resources,
+    /// How many cards the viewer has to discard, while they owe a discard after a 7
+    pub discard: Option<usize>,
+    pub development_cards: Vec<DevelopmentCard>,
+    /// The viewer's own points, including their hidden victory point cards
+    pub victory_points: VpBreakdown,
+    /// Everyone else, in turn order
+    pub opponents: Vec<OpponentView>,
+    pub legal_actions: Vec<Action>,
+    /// Time the viewer has left on the clock, if the game is timed
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<crate::typescript::DurationJson>"))]
+    pub time_remaining: Option<Duration>,
+}
+
+/// What a player can see of one of their opponents
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct OpponentView {
+    pub colour: PlayerColour,
+    /// Number of resource cards in their hand, but not which ones
+    pub cards: usize,
+    pub development_cards: usize,
+    /// Points everyone can see, i.e. without hidden victory point cards
+    pub victory_points: usize,
+    pub knights_played: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "ts-rs", ts(as = "Option<crate::typescript::DurationJson>"))]
+    pub time_remaining: Option<Duration>,
+    /// Whether they've resigned, see `Game::resign`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub resigned: bool,
+}
@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Result};
+use wasm_bindgen::prelude::*;
+
+use crate::actions::Action;
+use crate::game::Game;
+use crate::lobby::Lobby;
+use crate::options::GameOptions;
+use crate::platform::now;
+use crate::player::PlayerColour;
+
+/// A game for JavaScript to play, for checking moves in the browser before sending them and for
+/// hotseat games with no server at all
+///
+/// colours go back and forth as their names, like `"red"`, and everything else as JSON in the
+/// same shape the server uses, so `JSON.parse` is all a frontend needs
+#[wasm_bindgen(js_name = Game)]
+pub struct WasmGame(Game);
+
+#[wasm_bindgen(js_class = Game)]
+impl WasmGame {
+    /// Start a game between `players`, who take their turns in the order given, with the board,
+    /// the dice and everything else left to chance decided by `seed`
+    ///
+    /// `options` is a JSON `GameOptions`, or the official rules if it's left out
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        players: Vec<String>,
+        seed: u32,
+        options: Option<String>,
+    ) -> Result<WasmGame, JsError> {
+        Self::start(&players, seed, options.as_deref()).map_err(js_error)
+    }
+
+    /// Pick up a game saved with `toJson`
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmGame, JsError> {
+        Ok(Self(serde_json::from_str(json)?))
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.0)?)
+    }
+
+    /// `"setup"`, `"running"`, `"paused"` or `"complete"`
+    pub fn state(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.0.state())?)
+    }
+
+    /// The player the game is waiting on next, if anyone
+    #[wasm_bindgen(js_name = awaitingPlayer)]
+    pub fn awaiting_player(&self) -> Option<String> {
+        self.0.awaiting_player().map(|player| player.to_string())
+    }
+
+    pub fn winner(&self) -> Option<String> {
+        self.0.winner().map(|player| player.to_string())
+    }
+
+    /// Every action `player` could take right now, as a JSON array
+    #[wasm_bindgen(js_name = legalActions)]
+    pub fn legal_actions(&self, player: &str) -> Result<String, JsError> {
+        self.try_legal_actions(player).map_err(js_error)
+    }
+
+    /// Take the JSON `action` for `player`, throwing with the reason if it isn't allowed
+    pub fn apply(&mut self, player: &str, action: &str) -> Result<(), JsError> {
+        self.try_apply(player, action).map_err(js_error)
+    }
+
+    /// The game as `player` sees it, as a JSON `PlayerView`
+    pub fn view(&self, player: &str) -> Result<String, JsError> {
+        self.try_view(player).map_err(js_error)
+    }
+
+    /// Events from the `since`th on, as a JSON array, so a frontend can keep up by passing how
+    /// many it's seen
+    pub fn events(&self, since: usize) -> Result<String, JsError> {
+        let events = self.0.events().get(since..).unwrap_or_default();
+        Ok(serde_json::to_string(events)?)
+    }
+
+    /// Make the default choice for anyone who's run out of time, as of now
+    #[wasm_bindgen(js_name = resolveTimeouts)]
+    pub fn resolve_timeouts(&mut self) -> Result<(), JsError> {
+        self.0.resolve_timeouts(now()).map_err(js_error)
+    }
+}
+
+impl WasmGame {
+    fn start(players: &[String], seed: u32, options: Option<&str>) -> Result<Self> {
+        let options: GameOptions = match options {
+            Some(json) => serde_json::from_str(json)?,
+            None => GameOptions::default(),
+        };
+        let colours = players
+            .iter()
+            .map(|player| player.parse())
+            .collect::<Result<Vec<PlayerColour>>>()?;
+        let host = *colours.first().ok_or(anyhow!("A game needs players"))?;
+        let mut lobby = Lobby::new(options).with_capacity(colours.len());
+        for colour in colours {
+            lobby.claim(colour)?;
+            lobby.set_ready(colour, true)?;
+        }
+        Ok(Self(lobby.start_seeded(host, seed.into())?))
+    }
+
+    fn try_legal_actions(&self, player: &str) -> Result<String> {
+        Ok(serde_json::to_string(
+            &self.0.legal_actions(&player.parse()?),
+        )?)
+    }
+
+    fn try_apply(&mut self, player: &str, action: &str) -> Result<()> {
+        let action: Action = serde_json::from_str(action)?;
+        Ok(self.0.apply(player.parse()?, action)?)
+    }
+
+    fn try_view(&self, player: &str) -> Result<String> {
+        Ok(serde_json::to_string(&self.0.view_for(&player.parse()?)?)?)
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.0
+    }
+}
+
+fn js_error(err: anyhow::Error) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::GameState;
+    use crate::view::PlayerView;
+
+    fn players() -> Vec<String> {
+        vec!["red".to_string(), "blue".to_string()]
+    }
+
+    #[test]
+    fn test_start() {
+        let game = WasmGame::start(&players(), 7, None).unwrap();
+        assert_eq!(game.awaiting_player().as_deref(), Some("red"));
+        assert_eq!(game.game().players().len(), 2);
+
+        // the same seed sets up the same game
+        let again = WasmGame::start(&players(), 7, None).unwrap();
+        assert_eq!(again.game().get_board(), game.game().get_board());
+
+        assert!(WasmGame::start(&[], 7, None).is_err());
+        assert!(WasmGame::start(&["mauve".to_string()], 7, None).is_err());
+        assert!(WasmGame::start(&players(), 7, Some("{")).is_err());
+    }
+
+    #[test]
+    fn test_play() {
+        let mut game = WasmGame::start(&players(), 7, None).unwrap();
+        let actions: Vec<Action> =
+            serde_json::from_str(&game.try_legal_actions("red").unwrap()).unwrap();
+        let action = serde_json::to_string(&actions[0]).unwrap();
+
+        assert!(game.try_apply("blue", &action).is_err());
+        game.try_apply("red", &action).unwrap();
+        assert_eq!(game.awaiting_player().as_deref(), Some("blue"));
+        assert_eq!(game.game().state(), GameState::Setup);
+
+        let view: PlayerView = serde_json::from_str(&game.try_view("blue").unwrap()).unwrap();
+        assert_eq!(view, game.game().view_for(&PlayerColour::Blue).unwrap());
+        assert!(game.try_view("purple").is_err());
+
+        let json = game.to_json().unwrap();
+        let restored = WasmGame::from_json(&json).unwrap();
+        assert_eq!(restored.game(), game.game());
+        assert!(game.events(0).unwrap().starts_with('['));
+        assert_eq!(game.events(usize::MAX).unwrap(), "[]");
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// How payloads are encoded between a server and its clients
+///
+/// JSON is always there. The binary formats come with their features, and carry everything
+/// JSON does, tags and all, so anything serde can write one way it can read back the other
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    /// CBOR, which comes to around two thirds the size of the same JSON
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// MessagePack, with structs written as maps so they keep their field names, the way
+    /// JavaScript libraries like msgpack-lite write objects
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+}
+
+impl WireFormat {
+    /// Every format this build can read and write, JSON first
+    pub const ALL: &'static [WireFormat] = &[
+        WireFormat::Json,
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor,
+        #[cfg(feature = "msgpack")]
+        WireFormat::Msgpack,
+    ];
+
+    /// The media type for a `Content-Type` or `Accept` header
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            WireFormat::Json => "application/json",
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => "application/cbor",
+            #[cfg(feature = "msgpack")]
+            WireFormat::Msgpack => "application/msgpack",
+        }
+    }
+
+    /// The format a `Content-Type` or `Accept` header asks for, taking the first one this
+    /// build knows about and ignoring any parameters
+    pub fn from_content_type(header: &str) -> Option<Self> {
+        header.split(',').find_map(|media| {
+            let media = media.split(';').next().unwrap_or_default().trim();
+            match media.to_ascii_lowercase().as_str() {
+                "application/json" => Some(WireFormat::Json),
+                #[cfg(feature = "cbor")]
+                "application/cbor" => Some(WireFormat::Cbor),
+                #[cfg(feature = "msgpack")]
+                "application/msgpack" | "application/x-msgpack" => Some(WireFormat::Msgpack),
+                _ => None,
+            }
+        })
+    }
+
+    /// Whether the format is text, which is what WebSocket text frames are for
+    pub fn is_text(&self) -> bool {
+        *self == WireFormat::Json
+    }
+
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            WireFormat::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+            #[cfg(feature = "msgpack")]
+            WireFormat::Msgpack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            WireFormat::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => Ok(ciborium::from_reader(bytes)?),
+            #[cfg(feature = "msgpack")]
+            WireFormat::Msgpack => Ok(rmp_serde::from_slice(bytes)?),
+        }
+    }
+
+    /// Re-encode a payload from `from` into this format, without knowing its type
+    pub fn transcode(&self, from: WireFormat, bytes: &[u8]) -> Result<Vec<u8>> {
+        if *self == from {
+            return Ok(bytes.to_vec());
+        }
+        let value: serde_json::Value = from.decode(bytes)?;
+        self.encode(&value)
+    }
+}
+
+impl Display for WireFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            WireFormat::Json => "json",
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => "cbor",
+            #[cfg(feature = "msgpack")]
+            WireFormat::Msgpack => "msgpack",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(WireFormat::Json),
+            #[cfg(feature = "cbor")]
+            "cbor" => Ok(WireFormat::Cbor),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => Ok(WireFormat::Msgpack),
+            _ => Err(anyhow!("Unknown wire format {}", s)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod test {
+    use super::*;
+    use crate::game::Game;
+    use crate::player::PlayerColour;
+
+    fn game() -> Game {
+        let mut g = Game::new();
+        g.add_player(PlayerColour::Red);
+        g.add_player(PlayerColour::Blue);
+        let action = g.legal_actions(&PlayerColour::Red)[0].clone();
+        g.apply(PlayerColour::Red, action).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_content_type() {
+        let json = WireFormat::Json;
+        assert_eq!(
+            WireFormat::from_content_type(json.content_type()),
+            Some(json)
+        );
+        let header = "text/html, application/json; charset=utf-8";
+        assert_eq!(WireFormat::from_content_type(header), Some(json));
+        assert_eq!(WireFormat::from_content_type("text/plain"), None);
+        assert_eq!("json".parse::<WireFormat>().unwrap(), json);
+        assert!("xml".parse::<WireFormat>().is_err());
+        assert_eq!(WireFormat::ALL[0], WireFormat::default());
+    }
+
+    #[test]
+    fn test_json() {
+        let g = game();
+        let bytes = WireFormat::Json.encode(&g).unwrap();
+        assert_eq!(bytes, serde_json::to_vec(&g).unwrap());
+        assert_eq!(WireFormat::Json.decode::<Game>(&bytes).unwrap(), g);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor() {
+        use crate::delta::GameDelta;
+        use crate::events::GameEvent;
+        use crate::view::PlayerView;
+
+        let cbor = WireFormat::Cbor;
+        assert_eq!(
+            WireFormat::from_content_type("application/cbor"),
+            Some(cbor)
+        );
+        assert_eq!(cbor.to_string().parse::<WireFormat>().unwrap(), cbor);
+        assert!(WireFormat::ALL.contains(&cbor));
+
+        // games, views, deltas and events all come back as they went in
+        let before = game();
+        let mut g = before.clone();
+        let action = g.legal_actions(&PlayerColour::Blue)[0].clone();
+        g.apply(PlayerColour::Blue, action).unwrap();
+        assert_eq!(cbor.decode::<Game>(&cbor.encode(&g).unwrap()).unwrap(), g);
+        let view = g.view_for(&PlayerColour::Red).unwrap();
+        let bytes = cbor.encode(&view).unwrap();
+        assert_eq!(cbor.decode::<PlayerView>(&bytes).unwrap(), view);
+        let delta = g.diff(&before);
+        assert_eq!(
+            cbor.decode::<GameDelta>(&cbor.encode(&delta).unwrap())
+                .unwrap(),
+            delta
+        );
+        let events = g.events().to_vec();
+        let decoded: Vec<GameEvent> = cbor.decode(&cbor.encode(&events).unwrap()).unwrap();
+        assert_eq!(decoded, events);
+
+        // and take up much less room than JSON
+        assert!(bytes.len() < WireFormat::Json.encode(&view).unwrap().len() * 3 / 4);
+        let json = WireFormat::Json.encode(&view).unwrap();
+        let transcoded = cbor.transcode(WireFormat::Json, &json).unwrap();
+        assert_eq!(cbor.decode::<PlayerView>(&transcoded).unwrap(), view);
+        assert!(cbor.transcode(WireFormat::Json, b"{").is_err());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack() {
+        use crate::events::GameEvent;
+        use crate::view::PlayerView;
+
+        let msgpack = WireFormat::Msgpack;
+        assert_eq!(
+            WireFormat::from_content_type("application/x-msgpack"),
+            Some(msgpack)
+        );
+        assert_eq!(msgpack.to_string().parse::<WireFormat>().unwrap(), msgpack);
+
+        let g = game();
+        let bytes = msgpack.encode(&g).unwrap();
+        assert_eq!(msgpack.decode::<Game>(&bytes).unwrap(), g);
+        let events = g.events().to_vec();
+        let decoded: Vec<GameEvent> = msgpack.decode(&msgpack.encode(&events).unwrap()).unwrap();
+        assert_eq!(decoded, events);
+
+        // fields keep their names, so schemaless clients can read them
+        let view = g.view_for(&PlayerColour::Red).unwrap();
+        let bytes = msgpack.encode(&view).unwrap();
+        let value: serde_json::Value = msgpack.decode(&bytes).unwrap();
+        assert_eq!(value["viewer"], "red");
+        assert!(bytes.len() < WireFormat::Json.encode(&view).unwrap().len());
+        let json = WireFormat::Json.transcode(msgpack, &bytes).unwrap();
+        assert_eq!(WireFormat::Json.decode::<PlayerView>(&json).unwrap(), view);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::development_cards::DevelopmentCard;
+use crate::player::{Player, PlayerColour};
+
+/// The kinds of feature making up a position's hash, keeping the keys of each kind apart
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Feature {
+    Tile = 1,
+    Robber,
+    Building,
+    Road,
+    State,
+    Phase,
+    Turn,
+    Cards,
+    DevelopmentCards,
+    Knights,
+    Discard,
+    CardPlayed,
+    CardBought,
+    Winner,
+    FreeRoads,
+}
+
+/// Key for a feature, made from its kind and whatever identifies it, e.g. the vertex, owner and
+/// building for a building
+///
+/// keys are worked out rather than looked up in a table, so they're the same in every game and
+/// there's no limit on vertices or custom colours
+pub(crate) fn key(feature: Feature, parts: &[u64]) -> u64 {
+    parts.iter().fold(splitmix64(feature as u64), |hash, part| {
+        splitmix64(hash ^ part)
+    })
+}
+
+/// A number standing for `colour`, different for every colour
+pub(crate) fn colour_code(colour: &PlayerColour) -> u64 {
+    match *colour {
+        PlayerColour::Red => 1,
+        PlayerColour::Green => 2,
+        PlayerColour::Blue => 3,
+        PlayerColour::Purple => 4,
+        PlayerColour::Custom { r, g, b } => {
+            (5 << 24) | ((r as u64) << 16) | ((g as u64) << 8) | b as u64
+        }
+    }
+}
+
+/// Key for everything about `player` that's part of the position: their resource cards,
+/// development cards and the knights they've played
+pub(crate) fn player_key(player: &Player) -> u64 {
+    let colour = colour_code(player.colour());
+    let mut hash = key(Feature::Knights, &[colour, player.knights_played() as u64]);
+    for (kind, n) in *player.resources() {
+        hash ^= key(Feature::Cards, &[colour, kind as u64, n as u64]);
+    }
+    for card in DevelopmentCard::ALL {
+        let n = player
+            .development_cards()
+            .iter()
+            .filter(|c| **c == card)
+            .count();
+        hash ^= key(Feature::DevelopmentCards, &[colour, card as u64, n as u64]);
+    }
+    hash
+}
+
+/// One round of the splitmix64 generator, which scatters nearby inputs across the whole range
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keys_differ() {
+        let red = colour_code(&PlayerColour::Red);
+        let keys = [
+            key(Feature::Building, &[0, red, 0]),
+            key(Feature::Building, &[1, red, 0]),
+            key(Feature::Building, &[0, red, 1]),
+            key(Feature::Road, &[0, red, 0]),
+            key(Feature::Building, &[0, colour_code(&PlayerColour::Blue), 0]),
+        ];
+        for (i, a) in keys.iter().enumerate() {
+            assert!(keys[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(key(Feature::Road, &[3, red]), key(Feature::Road, &[3, red]));
+    }
+}
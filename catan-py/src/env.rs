@@ -0,0 +1,119 @@
+use pyo3::prelude::*;
+
+use catan_game_logic::{CatanEnv, GameOptions, RandomAgent, Reward};
+
+use crate::game::{colour, PyGame};
+use crate::value_error;
+
+/// A training environment in the usual reset and step style, speaking in encoded observations
+/// and action indices
+///
+/// `opponents` are seats played by random agents in between steps, so observations are only
+/// ever for the other seats. `reward` is `"win"`, for 1 on the step which wins the game and -1
+/// if someone else wins it, or `"victory_points"` for the points each step gains
+#[pyclass(name = "Env", unsendable)]
+#[derive(Debug)]
+pub struct PyEnv(CatanEnv);
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (players, opponents = Vec::new(), reward = "win", options = None))]
+    pub fn new(
+        players: Vec<String>,
+        opponents: Vec<String>,
+        reward: &str,
+        options: Option<&str>,
+    ) -> PyResult<Self> {
+        let reward = match reward {
+            "win" => Reward::Win,
+            "victory_points" => Reward::VictoryPoints,
+            other => return Err(value_error(format!("Unknown reward {:?}", other))),
+        };
+        let options: GameOptions = match options {
+            Some(json) => serde_json::from_str(json).map_err(value_error)?,
+            None => GameOptions::default(),
+        };
+        let players = players
+            .iter()
+            .map(|player| colour(player))
+            .collect::<PyResult<Vec<_>>>()?;
+        let mut env = CatanEnv::new(players.clone(), options, reward);
+        for opponent in &opponents {
+            let opponent = colour(opponent)?;
+            if !players.contains(&opponent) {
+                return Err(value_error(format!("{} isn't playing", opponent)));
+            }
+            env = env.with_agent(opponent, RandomAgent::default());
+        }
+        Ok(Self(env))
+    }
+
+    /// Start a fresh game from `seed`, returning the first observation
+    pub fn reset(&mut self, seed: u64) -> PyResult<Vec<f32>> {
+        self.0.reset(seed).map_err(value_error)
+    }
+
+    /// Take the action at index `action`, returning the next observation, the reward and
+    /// whether the game is over
+    pub fn step(&mut self, action: usize) -> PyResult<(Vec<f32>, f64, bool)> {
+        self.0.step(action).map_err(value_error)
+    }
+
+    /// Which action indices are legal for the seat being observed
+    pub fn action_mask(&self) -> PyResult<Vec<bool>> {
+        self.0.action_mask().map_err(value_error)
+    }
+
+    /// The seat the latest observation is for
+    #[getter]
+    pub fn player(&self) -> PyResult<String> {
+        let view = self.0.observation().map_err(value_error)?;
+        Ok(view.viewer.to_string())
+    }
+
+    /// A copy of the game being played
+    #[getter]
+    pub fn game(&self) -> PyGame {
+        PyGame(self.0.game().clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use catan_game_logic::OBSERVATION_SIZE;
+
+    use super::*;
+
+    fn env() -> PyEnv {
+        let players = vec!["red".into(), "blue".into()];
+        PyEnv::new(players, vec!["blue".into()], "win", None).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        let players = || vec!["red".to_string(), "blue".to_string()];
+        assert!(PyEnv::new(players(), Vec::new(), "points", None).is_err());
+        assert!(PyEnv::new(players(), vec!["green".into()], "win", None).is_err());
+        assert!(PyEnv::new(players(), Vec::new(), "victory_points", None).is_ok());
+    }
+
+    #[test]
+    fn test_episode() {
+        let mut env = env();
+        let observation = env.reset(3).unwrap();
+        assert_eq!(observation.len(), OBSERVATION_SIZE);
+        for _ in 0..200 {
+            assert_eq!(env.player().unwrap(), "red");
+            let mask = env.action_mask().unwrap();
+            let action = mask.iter().position(|legal| *legal).unwrap();
+            let (observation, _, done) = env.step(action).unwrap();
+            assert_eq!(observation.len(), OBSERVATION_SIZE);
+            if done {
+                break;
+            }
+        }
+        assert!(env.step(usize::MAX).is_err());
+        assert!(!env.game().0.events().is_empty());
+    }
+}
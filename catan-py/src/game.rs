@@ -0,0 +1,209 @@
+use pyo3::prelude::*;
+
+use catan_game_logic::{Action, Game, GameOptions, Lobby, PlayerColour};
+
+use crate::value_error;
+
+/// A single move, which can be compared, printed, and passed back to `Game.apply`
+#[pyclass(name = "Action", eq, frozen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyAction(pub Action);
+
+#[pymethods]
+impl PyAction {
+    /// Read an action written by `to_json`, or sent to the server's API
+    #[staticmethod]
+    pub fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str(json).map(Self).map_err(value_error)
+    }
+
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(value_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Action({})", self.to_json().unwrap_or_default())
+    }
+}
+
+/// A game of Catan, played one action at a time
+///
+/// players are colours given by name, like `"red"`. Views and events come back as JSON, in the
+/// same shape the server sends them
+#[pyclass(name = "Game")]
+#[derive(Debug, Clone)]
+pub struct PyGame(pub Game);
+
+#[pymethods]
+impl PyGame {
+    /// Start a game between `players`, who take their turns in the order given, with the board,
+    /// the dice and everything else left to chance decided by `seed`
+    ///
+    /// `options` is a JSON `GameOptions`, or the official rules if it's left out
+    #[new]
+    #[pyo3(signature = (players, seed, options = None))]
+    pub fn new(players: Vec<String>, seed: u64, options: Option<&str>) -> PyResult<Self> {
+        let options: GameOptions = match options {
+            Some(json) => serde_json::from_str(json).map_err(value_error)?,
+            None => GameOptions::default(),
+        };
+        let colours = players
+            .iter()
+            .map(|player| colour(player))
+            .collect::<PyResult<Vec<_>>>()?;
+        let host = *colours.first().ok_or(value_error("A game needs players"))?;
+        let mut lobby = Lobby::new(options).with_capacity(colours.len());
+        for colour in colours {
+            lobby.claim(colour).map_err(value_error)?;
+            lobby.set_ready(colour, true).map_err(value_error)?;
+        }
+        lobby
+            .start_seeded(host, seed)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    /// Pick up a game saved with `to_json`
+    #[staticmethod]
+    pub fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str(json).map(Self).map_err(value_error)
+    }
+
+    pub fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(value_error)
+    }
+
+    /// `"setup"`, `"running"`, `"paused"` or `"complete"`
+    #[getter]
+    pub fn state(&self) -> PyResult<String> {
+        serde_json::to_value(self.0.state())
+            .map(|state| state.as_str().unwrap_or_default().to_string())
+            .map_err(value_error)
+    }
+
+    /// The player the game is waiting on next, if anyone
+    #[getter]
+    pub fn awaiting_player(&self) -> Option<String> {
+        self.0.awaiting_player().map(|player| player.to_string())
+    }
+
+    #[getter]
+    pub fn winner(&self) -> Option<String> {
+        self.0.winner().map(|player| player.to_string())
+    }
+
+    /// Every action `player` could take right now
+    pub fn legal_actions(&self, player: &str) -> PyResult<Vec<PyAction>> {
+        Ok(self
+            .0
+            .legal_actions(&colour(player)?)
+            .into_iter()
+            .map(PyAction)
+            .collect())
+    }
+
+    /// Take `action` for `player`, raising a `ValueError` with the reason if it isn't allowed
+    pub fn apply(&mut self, player: &str, action: PyAction) -> PyResult<()> {
+        self.0.apply(colour(player)?, action.0).map_err(value_error)
+    }
+
+    /// The game as `player` sees it, as a JSON `PlayerView`
+    pub fn view(&self, player: &str) -> PyResult<String> {
+        let view = self.0.view_for(&colour(player)?).map_err(value_error)?;
+        serde_json::to_string(&view).map_err(value_error)
+    }
+
+    /// What `player` sees, encoded as `OBSERVATION_SIZE` floats
+    pub fn observation(&self, player: &str) -> PyResult<Vec<f32>> {
+        let view = self.0.view_for(&colour(player)?).map_err(value_error)?;
+        view.encode().map_err(value_error)
+    }
+
+    /// Which of the `ACTION_SPACE` action indices are legal for `player`
+    pub fn action_mask(&self, player: &str) -> PyResult<Vec<bool>> {
+        let view = self.0.view_for(&colour(player)?).map_err(value_error)?;
+        Ok(view.action_mask())
+    }
+
+    /// Where `action` sits in the action space, as `player` sees it, if it's in there at all
+    pub fn action_index(&self, player: &str, action: PyAction) -> PyResult<Option<usize>> {
+        let view = self.0.view_for(&colour(player)?).map_err(value_error)?;
+        Ok(view.action_index(&action.0))
+    }
+
+    /// The action at `index` in the action space, as `player` sees it
+    pub fn action_at(&self, player: &str, index: usize) -> PyResult<Option<PyAction>> {
+        let view = self.0.view_for(&colour(player)?).map_err(value_error)?;
+        Ok(view.action_at(index).map(PyAction))
+    }
+
+    /// Events from the `since`th on, as a JSON list
+    #[pyo3(signature = (since = 0))]
+    pub fn events(&self, since: usize) -> PyResult<String> {
+        let events = self.0.events().get(since..).unwrap_or_default();
+        serde_json::to_string(events).map_err(value_error)
+    }
+
+    fn __repr__(&self) -> String {
+        let players: Vec<_> = self
+            .0
+            .players()
+            .iter()
+            .map(|p| p.colour().to_string())
+            .collect();
+        let state = self.state().unwrap_or_default();
+        format!("Game(players={:?}, state={:?})", players, state)
+    }
+}
+
+/// Read a colour Python gave by name
+pub(crate) fn colour(player: &str) -> PyResult<PlayerColour> {
+    player.parse().map_err(value_error)
+}
+
+#[cfg(test)]
+mod test {
+    use catan_game_logic::{GameState, ACTION_SPACE, OBSERVATION_SIZE};
+
+    use super::*;
+
+    fn game() -> PyGame {
+        PyGame::new(vec!["red".into(), "blue".into()], 7, None).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        let g = game();
+        assert_eq!(g.awaiting_player().as_deref(), Some("red"));
+        assert_eq!(g.state().unwrap(), "setup");
+        // the same seed sets up the same board
+        assert_eq!(g.0.get_board(), game().0.get_board());
+
+        assert!(PyGame::new(Vec::new(), 7, None).is_err());
+        assert!(PyGame::new(vec!["mauve".into()], 7, None).is_err());
+        assert!(PyGame::new(vec!["red".into()], 7, Some("{")).is_err());
+    }
+
+    #[test]
+    fn test_play() {
+        let mut g = game();
+        let action = g.legal_actions("red").unwrap().remove(0);
+        let index = g.action_index("red", action.clone()).unwrap().unwrap();
+        assert!(g.action_mask("red").unwrap()[index]);
+        assert_eq!(g.action_mask("red").unwrap().len(), ACTION_SPACE);
+        assert_eq!(g.action_at("red", index).unwrap(), Some(action.clone()));
+        assert_eq!(g.observation("blue").unwrap().len(), OBSERVATION_SIZE);
+
+        assert!(g.apply("blue", action.clone()).is_err());
+        g.apply("red", action.clone()).unwrap();
+        assert_eq!(g.awaiting_player().as_deref(), Some("blue"));
+        assert_eq!(g.0.state(), GameState::Setup);
+        assert!(g.events(0).unwrap().contains("settlement_built"));
+
+        let restored = PyGame::from_json(&g.to_json().unwrap()).unwrap();
+        assert_eq!(restored.0, g.0);
+        let json = action.to_json().unwrap();
+        assert_eq!(PyAction::from_json(&json).unwrap(), action);
+        assert!(PyAction::from_json("{}").is_err());
+    }
+}
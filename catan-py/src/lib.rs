@@ -0,0 +1,30 @@
+// pyo3's macros convert every `PyResult` error into itself
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use catan_game_logic::{ACTION_SPACE, OBSERVATION_SIZE};
+
+pub(crate) mod env;
+pub(crate) mod game;
+
+pub use env::PyEnv;
+pub use game::{PyAction, PyGame};
+
+/// The Settlers of Catan rules engine, with `Game` to play a move at a time and `Env` to train
+/// agents on encoded observations and action indices
+#[pymodule]
+fn catan(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyAction>()?;
+    m.add_class::<PyEnv>()?;
+    m.add("OBSERVATION_SIZE", OBSERVATION_SIZE)?;
+    m.add("ACTION_SPACE", ACTION_SPACE)?;
+    Ok(())
+}
+
+/// Raise `err` in Python as a `ValueError`
+pub(crate) fn value_error(err: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
@@ -0,0 +1,52 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+/// Write out the server and client for `proto/catan.proto`, whose messages are in `grpc.rs`
+///
+/// the service is described here rather than compiled from the `.proto`, so building doesn't
+/// need `protoc`
+#[cfg(feature = "grpc")]
+fn grpc() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("super::{}", input))
+            .output_type(format!("super::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+    };
+    let service = Service::builder()
+        .name("Catan")
+        .package("catan")
+        .method(
+            method(
+                "create_game",
+                "CreateGame",
+                "CreateGameRequest",
+                "CreatedGame",
+            )
+            .build(),
+        )
+        .method(method("join_game", "JoinGame", "JoinRequest", "Seat").build())
+        .method(method("set_ready", "SetReady", "ReadyRequest", "Lobby").build())
+        .method(method("start_game", "StartGame", "GameRequest", "View").build())
+        .method(method("get_view", "GetView", "GameRequest", "View").build())
+        .method(method("submit_action", "SubmitAction", "ActionRequest", "View").build())
+        .method(
+            method(
+                "stream_events",
+                "StreamEvents",
+                "StreamRequest",
+                "StreamMessage",
+            )
+            .server_streaming()
+            .build(),
+        )
+        .build();
+    println!("cargo:rerun-if-changed=build.rs");
+    Builder::new().compile(&[service]);
+}
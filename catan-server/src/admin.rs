@@ -0,0 +1,303 @@
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+use uuid::Uuid;
+
+use catan_game_logic::{Game, GameState, Lobby, PlayerColour};
+
+use crate::error::ApiError;
+use crate::manager::GameManager;
+use crate::room::Room;
+use crate::routes::bearer;
+
+/// Endpoints for whoever runs the server, which all need the admin token as a bearer token
+///
+/// - `GET /admin/games?state=&player=` lists the games in memory, see `GamesQuery`
+/// - `GET /admin/games/{id}` is everything about a game, hands and all, see `GameDetails`
+/// - `POST /admin/games/{id}/end` ends a game on the spot, see `EndRequest`
+/// - `POST /admin/games/{id}/rollback` winds a game back, see `RollbackRequest`
+/// - `DELETE /admin/games/{id}/players/{colour}` kicks a player out of the lobby or the game
+pub(crate) fn admin_routes() -> Router<GameManager> {
+    Router::new()
+        .route("/admin/games", get(list_games))
+        .route("/admin/games/{id}", get(inspect_game))
+        .route("/admin/games/{id}/end", post(end_game))
+        .route("/admin/games/{id}/rollback", post(rollback_game))
+        .route("/admin/games/{id}/players/{colour}", delete(kick_player))
+}
+
+/// Proof a request came from whoever runs the server, by sending the token the server was
+/// given with `GameManager::with_admin_token`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Admin;
+
+impl FromRequestParts<GameManager> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        games: &GameManager,
+    ) -> Result<Self, Self::Rejection> {
+        match bearer(parts) {
+            Some(token) if games.is_admin(token) => Ok(Admin),
+            _ => Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "The admin token is needed, as a bearer token",
+            )),
+        }
+    }
+}
+
+/// How far along a room is, from its lobby to the end of its game
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[serde(rename_all = "lowercase")]
+pub enum RoomState {
+    Lobby,
+    Setup,
+    Running,
+    Paused,
+    Complete,
+}
+
+impl RoomState {
+    fn of(room: &Room) -> Self {
+        match room.game().map(Game::state) {
+            Err(_) => RoomState::Lobby,
+            Ok(GameState::Setup) => RoomState::Setup,
+            Ok(GameState::Running) => RoomState::Running,
+            Ok(GameState::Paused) => RoomState::Paused,
+            Ok(GameState::Complete) => RoomState::Complete,
+        }
+    }
+}
+
+/// Query of `GET /admin/games`, where each filter left out matches every game
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+pub struct GamesQuery {
+    pub state: Option<RoomState>,
+    /// Only games this colour is playing in
+    pub player: Option<PlayerColour>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct GameListing {
+    pub id: Uuid,
+    pub state: RoomState,
+    /// Everyone at the table, bots included once the game has started
+    pub players: Vec<PlayerColour>,
+    /// Seconds since anything last touched the game
+    pub idle_secs: u64,
+}
+
+/// Everything about a game, apart from the tokens handed out for it
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct GameDetails {
+    pub id: Uuid,
+    pub state: RoomState,
+    pub lobby: Lobby,
+    /// Players holding seat tokens
+    pub seated: Vec<PlayerColour>,
+    pub spectators: usize,
+    /// Actions the game can be rolled back through, if they were all recorded
+    pub actions: Option<usize>,
+    /// The whole game, which is only for debugging so isn't typed any further
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-rs", ts(type = "unknown", optional))]
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub game: Option<Game>,
+}
+
+impl GameDetails {
+    fn of(id: Uuid, room: &Room) -> Self {
+        Self {
+            id,
+            state: RoomState::of(room),
+            lobby: room.lobby().clone(),
+            seated: room.seated(),
+            spectators: room.spectator_count(),
+            actions: room.action_count(),
+            game: room.game().ok().cloned(),
+        }
+    }
+}
+
+/// Body of `POST /admin/games/{id}/end`, which can be left out
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[serde(default)]
+pub struct EndRequest {
+    /// Declare this player the winner, rather than nobody
+    pub winner: Option<PlayerColour>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct RollbackRequest {
+    /// How many of the game's actions to keep, from the start
+    pub actions: usize,
+}
+
+/// The games matching the query, most recently active first
+async fn list_games(
+    State(games): State<GameManager>,
+    _: Admin,
+    Query(query): Query<GamesQuery>,
+) -> Json<Vec<GameListing>> {
+    let mut listings: Vec<_> = games
+        .map_rooms(|id, room| GameListing {
+            id: *id,
+            state: RoomState::of(room),
+            players: match room.game() {
+                Ok(game) => game.players().iter().map(|p| *p.colour()).collect(),
+                Err(_) => room.seated(),
+            },
+            idle_secs: room.last_active.elapsed().as_secs(),
+        })
+        .into_iter()
+        .filter(|listing| query.state.is_none_or(|state| state == listing.state))
+        .filter(|listing| {
+            query
+                .player
+                .is_none_or(|player| listing.players.contains(&player))
+        })
+        .collect();
+    listings.sort_by_key(|listing| (listing.idle_secs, listing.id));
+    Json(listings)
+}
+
+async fn inspect_game(
+    State(games): State<GameManager>,
+    _: Admin,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GameDetails>, ApiError> {
+    games.with_room(&id, |room| Ok(Json(GameDetails::of(id, room))))
+}
+
+async fn end_game(
+    State(games): State<GameManager>,
+    _: Admin,
+    Path(id): Path<Uuid>,
+    request: Option<Json<EndRequest>>,
+) -> Result<Json<GameDetails>, ApiError> {
+    let request = request.map_or(EndRequest::default(), |Json(request)| request);
+    games.with_room(&id, |room| {
+        room.end(request.winner)?;
+        Ok(Json(GameDetails::of(id, room)))
+    })
+}
+
+async fn rollback_game(
+    State(games): State<GameManager>,
+    _: Admin,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RollbackRequest>,
+) -> Result<Json<GameDetails>, ApiError> {
+    games.with_room(&id, |room| {
+        room.rollback(request.actions)?;
+        Ok(Json(GameDetails::of(id, room)))
+    })
+}
+
+async fn kick_player(
+    State(games): State<GameManager>,
+    _: Admin,
+    Path((id, colour)): Path<(Uuid, PlayerColour)>,
+) -> Result<StatusCode, ApiError> {
+    games.with_room(&id, |room| {
+        room.kick(colour)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::routes::router;
+    use crate::routes::test::{join, send, start, token};
+
+    #[tokio::test]
+    async fn test_admin() {
+        let admin = Uuid::new_v4();
+        let app = router(GameManager::new().with_admin_token(admin.to_string()));
+        let (_, created) = send(&app, "POST", "/games", None, None).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+        start(&app, &game, &[&red, &blue]).await;
+
+        // seat tokens don't open the admin endpoints, and nor does anything on a server
+        // without an admin token
+        let (status, _) = send(&app, "GET", "/admin/games", token(&red), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let closed = router(GameManager::new());
+        let (status, _) = send(&closed, "GET", "/admin/games", Some(admin), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, games) = send(&app, "GET", "/admin/games", Some(admin), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(games[0]["id"], game);
+        assert_eq!(games[0]["state"], "setup");
+        for filter in ["state=lobby", "player=purple"] {
+            let uri = format!("/admin/games?{}", filter);
+            let (_, games) = send(&app, "GET", &uri, Some(admin), None).await;
+            assert_eq!(games, json!([]));
+        }
+
+        // red settles, then the game is rolled back to before they did
+        let actions = format!("/games/{}/actions", game);
+        let settle = json!({ "type": "place_initial_settlement", "vertex": 2 });
+        send(&app, "POST", &actions, token(&red), Some(settle)).await;
+        let uri = format!("/admin/games/{}", game);
+        let (_, details) = send(&app, "GET", &uri, Some(admin), None).await;
+        assert_eq!(details["actions"], 1);
+        let rollback = format!("/admin/games/{}/rollback", game);
+        let (status, _) = send(
+            &app,
+            "POST",
+            &rollback,
+            Some(admin),
+            Some(json!({ "actions": 5 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, details) = send(
+            &app,
+            "POST",
+            &rollback,
+            Some(admin),
+            Some(json!({ "actions": 0 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(details["actions"], 0);
+        assert_eq!(details["game"]["setup_placements"], 0);
+
+        // kicking blue revokes their token
+        let kick = format!("/admin/games/{}/players/blue", game);
+        let (status, _) = send(&app, "DELETE", &kick, Some(admin), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let view = format!("/games/{}/view", game);
+        let (status, _) = send(&app, "GET", &view, token(&blue), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let end = format!("/admin/games/{}/end", game);
+        let winner = Some(json!({ "winner": "red" }));
+        let (status, details) = send(&app, "POST", &end, Some(admin), winner).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(details["state"], "complete");
+        assert_eq!(details["game"]["winner"], "red");
+        let (status, _) = send(&app, "POST", &end, Some(admin), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+}
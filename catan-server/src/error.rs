@@ -0,0 +1,70 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+use catan_game_logic::ActionError;
+
+/// A request the server turned down, sent back as its status code and a JSON body giving the
+/// reason
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub reason: String,
+}
+
+/// The JSON body of every error response
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS), ts(rename = "ApiError"))]
+#[schemars(rename = "ApiError")]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, reason: impl Into<String>) -> Self {
+        Self {
+            status,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn not_found(what: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("No such {}", what))
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "A seat token is needed, as a bearer token",
+        )
+    }
+}
+
+/// Actions which can't be taken yet are a conflict with the game's state, while actions which
+/// break the rules can never be processed as sent
+impl From<ActionError> for ApiError {
+    fn from(err: ActionError) -> Self {
+        let status = match err {
+            ActionError::NotAllowedNow(_) => StatusCode::CONFLICT,
+            ActionError::Illegal(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        Self::new(status, err.reason())
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody { error: self.reason };
+        (self.status, Json(body)).into_response()
+    }
+}
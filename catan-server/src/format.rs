@@ -0,0 +1,205 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use catan_game_logic::WireFormat;
+
+use crate::error::ApiError;
+
+/// The most a request body can hold, the same limit axum puts on JSON bodies
+const MAX_BODY: usize = 2 * 1024 * 1024;
+
+/// Let each client pick the `WireFormat` it talks in: `Content-Type` says what a request body
+/// is in, and `Accept` what the response should be in
+///
+/// handlers only ever read and write JSON, so bodies are re-encoded on the way in and out.
+/// Anything asking for a format this build doesn't have gets JSON, as does a response which
+/// isn't JSON to begin with, like a WebSocket upgrade
+pub(crate) async fn negotiate(request: Request, next: Next) -> Response {
+    let accept = format(request.headers(), ACCEPT).unwrap_or_default();
+    let response = match decode(request).await {
+        Ok(request) => next.run(request).await,
+        Err(err) => err.into_response(),
+    };
+    encode(response, accept)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+/// Re-encode a request body as JSON, if it's in some other format
+async fn decode(request: Request) -> Result<Request, ApiError> {
+    let from = match format(request.headers(), CONTENT_TYPE) {
+        Some(from) if from != WireFormat::Json => from,
+        _ => return Ok(request),
+    };
+    let (mut parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_BODY)
+        .await
+        .map_err(|err| ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, err.to_string()))?;
+    let json = WireFormat::Json.transcode(from, &bytes)?;
+    set_format(&mut parts.headers, WireFormat::Json);
+    Ok(Request::from_parts(parts, Body::from(json)))
+}
+
+/// Re-encode a JSON response body in the format `accept`ed
+async fn encode(response: Response, accept: WireFormat) -> Result<Response, ApiError> {
+    if accept == WireFormat::Json || format(response.headers(), CONTENT_TYPE).is_none() {
+        return Ok(response);
+    }
+    let internal = |err: String| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err);
+    let (mut parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|err| internal(err.to_string()))?;
+    let encoded = accept
+        .transcode(WireFormat::Json, &bytes)
+        .map_err(|err| internal(err.to_string()))?;
+    set_format(&mut parts.headers, accept);
+    Ok(Response::from_parts(parts, Body::from(encoded)))
+}
+
+fn format(headers: &HeaderMap, name: HeaderName) -> Option<WireFormat> {
+    let header = headers.get(name)?.to_str().ok()?;
+    WireFormat::from_content_type(header)
+}
+
+/// Mark a re-encoded body as being in `format`, dropping a length which no longer holds
+fn set_format(headers: &mut HeaderMap, format: WireFormat) {
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    headers.remove(CONTENT_LENGTH);
+}
+
+#[cfg(test)]
+mod test {
+    use axum::Router;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::manager::GameManager;
+    use crate::routes::router;
+
+    /// Send `body` in `format`, asking for `accept` back, returning the status, content type
+    /// and body of the response
+    async fn send_as(
+        app: &Router,
+        uri: &str,
+        format: WireFormat,
+        accept: &str,
+        body: Value,
+    ) -> (StatusCode, Option<WireFormat>, Vec<u8>) {
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(CONTENT_TYPE, format.content_type())
+            .header(ACCEPT, accept)
+            .body(Body::from(format.encode(&body).unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let content_type = super::format(response.headers(), CONTENT_TYPE);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, content_type, bytes.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_json() {
+        let app = router(GameManager::new());
+        let options = json!({ "seats": 3 });
+        let (status, format, bytes) =
+            send_as(&app, "/games", WireFormat::Json, "text/html", options).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(format, Some(WireFormat::Json));
+        let created: Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(created["id"].is_string());
+
+        // unknown formats are left for the handlers to turn down
+        let request = Request::builder()
+            .method("POST")
+            .uri("/games")
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_cbor() {
+        use axum::http::header::AUTHORIZATION;
+        use catan_game_logic::PlayerView;
+
+        use crate::routes::test::{send, start};
+
+        let app = router(GameManager::new());
+        let cbor = WireFormat::Cbor;
+        let (status, format, bytes) =
+            send_as(&app, "/games", cbor, "application/cbor", json!({})).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(format, Some(cbor));
+        let created: Value = cbor.decode(&bytes).unwrap();
+        let game = created["id"].as_str().unwrap().to_string();
+
+        // requests can be in one format and responses in the other
+        let uri = format!("/games/{}/players", game);
+        let colour = json!({ "colour": "red" });
+        let (status, format, bytes) = send_as(&app, &uri, cbor, "*/*", colour).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(format, Some(WireFormat::Json));
+        let red: Value = serde_json::from_slice(&bytes).unwrap();
+        let uri = format!("/games/{}/players", game);
+        let blue = send(&app, "POST", &uri, None, Some(json!({ "colour": "blue" }))).await;
+        start(&app, &game, &[&red, &blue.1]).await;
+
+        let request = Request::builder()
+            .uri(format!("/games/{}/view", game))
+            .header(
+                AUTHORIZATION,
+                format!("Bearer {}", red["token"].as_str().unwrap()),
+            )
+            .header(ACCEPT, "application/cbor")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let view: PlayerView = cbor.decode(&bytes).unwrap();
+        assert_eq!(view.viewer.to_string(), "red");
+
+        // errors come back in the format asked for, including for bodies which don't decode
+        let request = Request::builder()
+            .method("POST")
+            .uri("/games")
+            .header(CONTENT_TYPE, "application/cbor")
+            .header(ACCEPT, "application/cbor")
+            .body(Body::from(vec![0xff, 0x00]))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let error: Value = cbor.decode(&bytes).unwrap();
+        assert!(error["error"].is_string());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_msgpack() {
+        let app = router(GameManager::new());
+        let msgpack = WireFormat::Msgpack;
+        let options = json!({ "seats": 3 });
+        let (status, format, bytes) =
+            send_as(&app, "/games", msgpack, "application/x-msgpack", options).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(format, Some(msgpack));
+        let created: Value = msgpack.decode(&bytes).unwrap();
+        assert!(created["id"].is_string());
+    }
+}
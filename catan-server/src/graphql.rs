@@ -0,0 +1,768 @@
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use async_graphql::http::{WebSocket as GraphQLWebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::{
+    Context, Data, EmptyMutation, ErrorExtensions, Json as GraphQLJson, Object, Schema,
+    SimpleObject, Subscription, ID,
+};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::stream::{self, Stream, StreamExt};
+use futures_util::SinkExt;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use uuid::Uuid;
+
+use catan_game_logic::{
+    Action, Board, Brick, Building, ChatMessage, GameEvent, GameOptions, GameState, Grain, Lobby,
+    Lumber, OpponentView, Ore, PlayerColour, PlayerView, Resources, TileKind, Trade, TradeState,
+    TurnPhase, Wool,
+};
+
+use crate::error::ApiError;
+use crate::manager::GameManager;
+use crate::room::Room;
+
+/// Everything `/graphql` answers, with no mutations since actions still go through the REST API
+pub type CatanSchema = Schema<Query, EmptyMutation, Subscription>;
+
+/// The GraphQL schema, which is only put together the first time it's asked for
+///
+/// it holds no games itself, each request brings the `GameManager` along with it
+pub fn graphql_schema() -> &'static CatanSchema {
+    static SCHEMA: OnceLock<CatanSchema> = OnceLock::new();
+    SCHEMA.get_or_init(|| Schema::new(Query, EmptyMutation, Subscription))
+}
+
+/// The GraphQL endpoints
+///
+/// - `POST /graphql` answers a query, with the seat or spectator token as a bearer token
+/// - `GET /graphql/ws` runs subscriptions over a WebSocket, in either the `graphql-transport-ws`
+///   or the older `graphql-ws` protocol, with the token as `token` in the `connection_init`
+///   payload since browsers can't set headers on a WebSocket
+pub(crate) fn graphql_routes() -> Router<GameManager> {
+    Router::new()
+        .route("/graphql", post(graphql))
+        .route("/graphql/ws", get(graphql_ws))
+}
+
+/// The token a request was made with, if any
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Token(Uuid);
+
+async fn graphql(
+    State(games): State<GameManager>,
+    headers: HeaderMap,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let mut request = request.data(games);
+    if let Some(token) = bearer(&headers) {
+        request = request.data(Token(token));
+    }
+    Json(graphql_schema().execute(request).await)
+}
+
+async fn graphql_ws(
+    State(games): State<GameManager>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let protocol = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|protocols| {
+            protocols
+                .split(',')
+                .find_map(|protocol| protocol.trim().parse().ok())
+        })
+        .unwrap_or(WebSocketProtocols::GraphQLWS);
+    ws.protocols(["graphql-transport-ws", "graphql-ws"])
+        .on_upgrade(move |socket| subscribe(socket, games, protocol))
+}
+
+async fn subscribe(socket: WebSocket, games: GameManager, protocol: WebSocketProtocols) {
+    let (mut sink, stream) = socket.split();
+    let input = stream
+        .take_while(|message| std::future::ready(message.is_ok()))
+        .filter_map(|message| {
+            std::future::ready(match message {
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => Some(message.into_data()),
+                _ => None,
+            })
+        });
+    let mut data = Data::default();
+    data.insert(games);
+    let mut messages = GraphQLWebSocket::new(graphql_schema().clone(), input, protocol)
+        .connection_data(data)
+        .on_connection_init(|payload| async move {
+            let mut data = Data::default();
+            let token = payload.get("token").and_then(|token| token.as_str());
+            if let Some(token) = token.and_then(|token| Uuid::parse_str(token).ok()) {
+                data.insert(Token(token));
+            }
+            Ok(data)
+        });
+    while let Some(message) = messages.next().await {
+        let message = match message {
+            WsMessage::Text(text) => Message::Text(text.into()),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        };
+        if sink.send(message).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn bearer(headers: &HeaderMap) -> Option<Uuid> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| Uuid::parse_str(token.trim()).ok())
+}
+
+/// The REST API's reason for turning a request down, with its status code as `status` in the
+/// error's extensions
+fn error(err: ApiError) -> async_graphql::Error {
+    let status = err.status.as_u16();
+    async_graphql::Error::new(err.reason).extend_with(|_, e| e.set("status", status))
+}
+
+fn game_id(id: &ID) -> async_graphql::Result<Uuid> {
+    Uuid::parse_str(id).map_err(|_| error(ApiError::not_found("game")))
+}
+
+fn token(ctx: &Context<'_>) -> async_graphql::Result<Uuid> {
+    match ctx.data_opt::<Token>() {
+        Some(Token(token)) => Ok(*token),
+        None => Err(error(ApiError::unauthorized())),
+    }
+}
+
+/// Run `f` on the room `id`, for whoever the request's token lets watch it
+fn with_viewer<T>(
+    ctx: &Context<'_>,
+    id: &Uuid,
+    f: impl FnOnce(&Room, Option<PlayerColour>) -> Result<T, ApiError>,
+) -> async_graphql::Result<T> {
+    let token = token(ctx)?;
+    ctx.data::<GameManager>()?
+        .with_room(id, |room| {
+            let viewer = room.viewer(&token)?;
+            f(room, viewer)
+        })
+        .map_err(error)
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The game `id`, as the request's token sees it
+    async fn game(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<GameNode> {
+        let id = game_id(&id)?;
+        ctx.data::<GameManager>()?
+            .with_room(&id, |_| Ok(()))
+            .map_err(error)?;
+        Ok(GameNode { id })
+    }
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// The game `id` as it is now, and again every time anything in it changes, for picking out
+    /// the fields a screen needs to redraw
+    async fn game(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> async_graphql::Result<impl Stream<Item = GameNode>> {
+        let id = game_id(&id)?;
+        Ok(changes(ctx, id)?.map(move |()| GameNode { id }))
+    }
+
+    /// The game's events as they happen, starting from event `since`
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        #[graphql(default)] since: usize,
+    ) -> async_graphql::Result<impl Stream<Item = EventNode>> {
+        let id = game_id(&id)?;
+        let games = ctx.data::<GameManager>()?.clone();
+        let mut cursor = since;
+        Ok(changes(ctx, id)?.flat_map(move |()| {
+            let events = games
+                .with_room(&id, |room| {
+                    let game = room.game()?;
+                    let events: Vec<_> = game.subscribe(cursor).map(EventNode::new).collect();
+                    cursor = game.next_event().max(cursor);
+                    Ok(events)
+                })
+                .unwrap_or_default();
+            stream::iter(events)
+        }))
+    }
+}
+
+/// Once straight away, then every time the room `id` changes, until the request's token stops
+/// working or the room is evicted
+fn changes(ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<impl Stream<Item = ()>> {
+    let token = token(ctx)?;
+    let games = ctx.data::<GameManager>()?.clone();
+    let updates = games
+        .with_room(&id, |room| {
+            room.viewer(&token)?;
+            Ok(room.subscribe())
+        })
+        .map_err(error)?;
+    let changes = stream::unfold(
+        (games, updates, true),
+        move |(games, mut updates, first): (GameManager, Receiver<()>, bool)| async move {
+            // missed updates don't matter, since everything is read from the room
+            if !first && matches!(updates.recv().await, Err(RecvError::Closed)) {
+                return None;
+            }
+            games.with_room(&id, |room| room.viewer(&token)).ok()?;
+            Some(((), (games, updates, false)))
+        },
+    );
+    Ok(changes)
+}
+
+/// A game, or its lobby until it starts
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GameNode {
+    id: Uuid,
+}
+
+#[Object(name = "Game")]
+impl GameNode {
+    async fn id(&self) -> ID {
+        ID(self.id.to_string())
+    }
+
+    /// Whether the host has started the game
+    async fn started(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        with_viewer(ctx, &self.id, |room, _| Ok(room.game().is_ok()))
+    }
+
+    async fn lobby(&self, ctx: &Context<'_>) -> async_graphql::Result<LobbyNode> {
+        with_viewer(ctx, &self.id, |room, _| Ok(LobbyNode::new(room.lobby())))
+    }
+
+    /// The colour of the request's seat, which spectators don't have
+    async fn viewer(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<String>> {
+        with_viewer(ctx, &self.id, |_, viewer| {
+            Ok(viewer.map(|viewer| viewer.to_string()))
+        })
+    }
+
+    /// The game as the seat's player sees it, which spectators don't get
+    async fn view(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ViewNode>> {
+        with_viewer(ctx, &self.id, |room, viewer| match viewer {
+            Some(viewer) => Ok(Some(ViewNode(room.game()?.view_for(&viewer)?))),
+            None => Ok(None),
+        })
+    }
+
+    /// Everyone at the table, in turn order, with only what the whole table can see
+    async fn players(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<PlayerNode>> {
+        with_viewer(ctx, &self.id, |room, _| {
+            let game = room.game()?;
+            let now = SystemTime::now();
+            let players = game
+                .players()
+                .iter()
+                .map(|p| {
+                    PlayerNode(OpponentView {
+                        colour: *p.colour(),
+                        cards: p.hand_size(),
+                        development_cards: p.development_cards().len(),
+                        victory_points: game.vp_breakdown(p.colour()).map_or(0, |vp| vp.public()),
+                        knights_played: p.knights_played(),
+                        time_remaining: game.time_remaining(p.colour(), now),
+                        resigned: game.is_resigned(p.colour()),
+                    })
+                })
+                .collect();
+            Ok(players)
+        })
+    }
+
+    async fn board(&self, ctx: &Context<'_>) -> async_graphql::Result<BoardNode> {
+        with_viewer(ctx, &self.id, |room, _| {
+            Ok(BoardNode(room.game()?.get_board().clone()))
+        })
+    }
+
+    /// Trades offered between players which haven't been withdrawn
+    async fn trades(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TradeNode>> {
+        with_viewer(ctx, &self.id, |room, _| {
+            let trades = room.game()?.get_trades().iter();
+            Ok(trades
+                .map(|(id, trade)| TradeNode::new(id, trade))
+                .collect())
+        })
+    }
+
+    /// The game's events from event `since` on
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] since: usize,
+    ) -> async_graphql::Result<Vec<EventNode>> {
+        with_viewer(ctx, &self.id, |room, _| {
+            Ok(room.game()?.subscribe(since).map(EventNode::new).collect())
+        })
+    }
+
+    /// The chat from message `since` on
+    async fn chat(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] since: usize,
+    ) -> async_graphql::Result<Vec<GraphQLJson<ChatMessage>>> {
+        with_viewer(ctx, &self.id, |room, _| {
+            Ok(room.chat().since(since).cloned().map(GraphQLJson).collect())
+        })
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Lobby")]
+pub struct LobbyNode {
+    host: Option<String>,
+    /// Number of seats at the table, bots included
+    capacity: usize,
+    /// Whether bots take any seats still empty when the game starts
+    fills_with_bots: bool,
+    seats: Vec<LobbySeatNode>,
+    options: GraphQLJson<GameOptions>,
+}
+
+impl LobbyNode {
+    fn new(lobby: &Lobby) -> Self {
+        let seats = lobby.seats().iter().map(|seat| LobbySeatNode {
+            colour: seat.colour.to_string(),
+            ready: seat.ready,
+        });
+        Self {
+            host: lobby.host().map(|host| host.to_string()),
+            capacity: lobby.capacity(),
+            fills_with_bots: lobby.fills_with_bots(),
+            seats: seats.collect(),
+            options: GraphQLJson(*lobby.options()),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "LobbySeat")]
+pub struct LobbySeatNode {
+    colour: String,
+    ready: bool,
+}
+
+pub struct ViewNode(PlayerView);
+
+#[Object(name = "View")]
+impl ViewNode {
+    async fn viewer(&self) -> String {
+        self.0.viewer.to_string()
+    }
+
+    async fn state(&self) -> GraphQLJson<GameState> {
+        GraphQLJson(self.0.state)
+    }
+
+    async fn phase(&self) -> GraphQLJson<TurnPhase> {
+        GraphQLJson(self.0.phase)
+    }
+
+    async fn current_player(&self) -> Option<String> {
+        self.0.current_player.map(|player| player.to_string())
+    }
+
+    async fn board(&self) -> BoardNode {
+        BoardNode(self.0.board.clone())
+    }
+
+    async fn hand(&self) -> ResourcesNode {
+        ResourcesNode(self.0.hand)
+    }
+
+    /// How many cards the viewer has to discard, while they owe a discard after a 7
+    async fn discard(&self) -> Option<usize> {
+        self.0.discard
+    }
+
+    async fn development_cards(&self) -> Vec<String> {
+        let cards = self.0.development_cards.iter();
+        cards.map(|card| card.to_string()).collect()
+    }
+
+    /// The viewer's own points, including their hidden victory point cards
+    async fn victory_points(&self) -> usize {
+        self.0.victory_points.total()
+    }
+
+    /// Everyone else, in turn order
+    async fn opponents(&self) -> Vec<PlayerNode> {
+        self.0.opponents.iter().copied().map(PlayerNode).collect()
+    }
+
+    async fn legal_actions(&self) -> Vec<GraphQLJson<Action>> {
+        self.0
+            .legal_actions
+            .iter()
+            .cloned()
+            .map(GraphQLJson)
+            .collect()
+    }
+
+    /// Milliseconds the viewer has left on the clock, if the game is timed
+    async fn time_remaining(&self) -> Option<u64> {
+        millis(self.0.time_remaining)
+    }
+}
+
+/// What everyone can see of a player
+pub struct PlayerNode(OpponentView);
+
+#[Object(name = "Player")]
+impl PlayerNode {
+    async fn colour(&self) -> String {
+        self.0.colour.to_string()
+    }
+
+    /// Number of resource cards in their hand, but not which ones
+    async fn cards(&self) -> usize {
+        self.0.cards
+    }
+
+    async fn development_cards(&self) -> usize {
+        self.0.development_cards
+    }
+
+    /// Points everyone can see, i.e. without hidden victory point cards
+    async fn victory_points(&self) -> usize {
+        self.0.victory_points
+    }
+
+    async fn knights_played(&self) -> usize {
+        self.0.knights_played
+    }
+
+    /// Milliseconds they have left on the clock, if the game is timed
+    async fn time_remaining(&self) -> Option<u64> {
+        millis(self.0.time_remaining)
+    }
+
+    async fn resigned(&self) -> bool {
+        self.0.resigned
+    }
+}
+
+fn millis(duration: Option<std::time::Duration>) -> Option<u64> {
+    duration.map(|duration| duration.as_millis() as u64)
+}
+
+pub struct BoardNode(Board);
+
+#[Object(name = "Board")]
+impl BoardNode {
+    async fn tiles(&self) -> Vec<TileNode> {
+        (0..self.0.tile_count())
+            .filter_map(|index| {
+                let tile = self.0.tile(index)?;
+                Some(TileNode {
+                    index,
+                    kind: GraphQLJson(*tile.kind()),
+                    token: *tile.token(),
+                })
+            })
+            .collect()
+    }
+
+    /// The tile the robber is on
+    async fn robber(&self) -> Option<usize> {
+        self.0.robber()
+    }
+
+    async fn buildings(&self) -> Vec<BuildingNode> {
+        let vertices = self.0.vertices().iter();
+        vertices
+            .filter_map(|vertex| {
+                let (player, kind) = vertex.building()?;
+                Some(BuildingNode {
+                    vertex: vertex.id().index(),
+                    player: player.to_string(),
+                    kind: GraphQLJson(*kind),
+                })
+            })
+            .collect()
+    }
+
+    async fn roads(&self) -> Vec<RoadNode> {
+        let edges = self.0.edges().iter();
+        edges
+            .filter_map(|edge| {
+                Some(RoadNode {
+                    edge: edge.id().index(),
+                    player: edge.road()?.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Tile")]
+pub struct TileNode {
+    index: usize,
+    kind: GraphQLJson<TileKind>,
+    /// The number rolled to produce from the tile
+    token: usize,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Building")]
+pub struct BuildingNode {
+    vertex: usize,
+    player: String,
+    kind: GraphQLJson<Building>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Road")]
+pub struct RoadNode {
+    edge: usize,
+    player: String,
+}
+
+pub struct ResourcesNode(Resources);
+
+#[Object(name = "Resources")]
+impl ResourcesNode {
+    async fn ore(&self) -> usize {
+        self.0[Ore]
+    }
+
+    async fn grain(&self) -> usize {
+        self.0[Grain]
+    }
+
+    async fn wool(&self) -> usize {
+        self.0[Wool]
+    }
+
+    async fn brick(&self) -> usize {
+        self.0[Brick]
+    }
+
+    async fn lumber(&self) -> usize {
+        self.0[Lumber]
+    }
+
+    async fn total(&self) -> usize {
+        self.0.total()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Trade")]
+pub struct TradeNode {
+    id: ID,
+    from: String,
+    /// The player the offer was locked in with, once there is one
+    to: Option<String>,
+    accepted_by: Vec<String>,
+    offering: ResourcesNode,
+    wants: ResourcesNode,
+    state: GraphQLJson<TradeState>,
+}
+
+impl TradeNode {
+    fn new(id: &Uuid, trade: &Trade) -> Self {
+        let accepted_by = trade.accepted_by().iter();
+        Self {
+            id: ID(id.to_string()),
+            from: trade.get_offering_player().to_string(),
+            to: trade.get_trade_partner().ok().map(|to| to.to_string()),
+            accepted_by: accepted_by.map(ToString::to_string).collect(),
+            offering: ResourcesNode(*trade.offering()),
+            wants: ResourcesNode(*trade.wants()),
+            state: GraphQLJson(*trade.state()),
+        }
+    }
+}
+
+/// An event along with its sequence number, like the REST API's `SequencedEvent`
+#[derive(SimpleObject)]
+#[graphql(name = "Event")]
+pub struct EventNode {
+    seq: usize,
+    event: GraphQLJson<GameEvent>,
+}
+
+impl EventNode {
+    fn new((seq, event): (usize, &GameEvent)) -> Self {
+        Self {
+            seq,
+            event: GraphQLJson(event.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    use super::*;
+    use crate::routes::router;
+    use crate::routes::test::{join, send, start, token};
+
+    const QUERY: &str = "query($id: ID!) { game(id: $id) {
+        started viewer lobby { host seats { colour ready } }
+        view { currentPlayer hand { total } legalActions }
+        players { colour victoryPoints }
+        board { tiles { kind token } robber }
+        trades { id } events { seq }
+    } }";
+
+    /// Run `query` against `app` with `token`, returning the response
+    async fn query(app: &Router, seat: Option<&Value>, query: &str, id: &str) -> Value {
+        let body = json!({ "query": query, "variables": { "id": id } });
+        send(app, "POST", "/graphql", seat.and_then(token), Some(body))
+            .await
+            .1
+    }
+
+    #[tokio::test]
+    async fn test_query() {
+        let app = router(GameManager::new());
+        let (_, created) = send(&app, "POST", "/games", None, Some(json!({}))).await;
+        let id = created["id"].as_str().unwrap();
+        let red = join(&app, id, "red").await;
+        let blue = join(&app, id, "blue").await;
+
+        // the lobby is there before the game starts, but nothing from the game is
+        let lobby = "query($id: ID!) { game(id: $id) { started lobby { host } } }";
+        let response = query(&app, Some(&red.1), lobby, id).await;
+        assert_eq!(response["data"]["game"]["started"], false);
+        assert_eq!(response["data"]["game"]["lobby"]["host"], "red");
+        let response = query(&app, Some(&red.1), QUERY, id).await;
+        assert_eq!(response["data"]["game"]["lobby"]["host"], "red");
+        assert!(response["data"]["game"]["board"].is_null());
+        assert_eq!(response["errors"][0]["extensions"]["status"], 409);
+
+        start(&app, id, &[&red.1, &blue.1]).await;
+        let response = query(&app, Some(&red.1), QUERY, id).await;
+        assert!(response["errors"].is_null(), "{}", response);
+        let game = &response["data"]["game"];
+        assert_eq!(game["viewer"], "red");
+        assert_eq!(game["view"]["currentPlayer"], "red");
+        assert!(!game["view"]["legalActions"].as_array().unwrap().is_empty());
+        assert_eq!(game["players"].as_array().unwrap().len(), 2);
+        assert_eq!(game["board"]["tiles"].as_array().unwrap().len(), 19);
+        assert_eq!(game["trades"], json!([]));
+
+        // spectators see the table but not a seat's view, and tokens are needed to see either
+        let uri = format!("/games/{}/spectators", id);
+        let (_, spectator) = send(&app, "POST", &uri, token(&red.1), None).await;
+        let response = query(&app, Some(&spectator), QUERY, id).await;
+        assert!(response["data"]["game"]["view"].is_null());
+        assert_eq!(response["data"]["game"]["players"][0]["colour"], "red");
+        let response = query(&app, None, QUERY, id).await;
+        assert_eq!(response["errors"][0]["extensions"]["status"], 401);
+        let missing = Uuid::new_v4().to_string();
+        let response = query(&app, Some(&red.1), QUERY, &missing).await;
+        assert_eq!(response["errors"][0]["extensions"]["status"], 404);
+    }
+
+    #[tokio::test]
+    async fn test_subscription() {
+        let games = GameManager::new();
+        let id = games.create(Lobby::new(GameOptions::default())).unwrap();
+        let blue = games
+            .with_room(&id, |room| {
+                room.join(PlayerColour::Red)?;
+                let blue = room.join(PlayerColour::Blue)?;
+                room.set_ready(PlayerColour::Red, true)?;
+                room.set_ready(PlayerColour::Blue, true)?;
+                room.start(PlayerColour::Red)?;
+                Ok(blue)
+            })
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(games.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut request = format!("ws://{}/graphql/ws", addr)
+            .into_client_request()
+            .unwrap();
+        let protocol = "graphql-transport-ws".parse().unwrap();
+        request
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, protocol);
+        let (mut socket, _) = connect_async(request).await.unwrap();
+        let text = |message: Value| tungstenite::Message::Text(message.to_string().into());
+        let init = json!({ "type": "connection_init", "payload": { "token": blue } });
+        socket.send(text(init)).await.unwrap();
+        let query = "subscription($id: ID!) { events(id: $id) { seq event } }";
+        let subscribe = json!({
+            "id": "1",
+            "type": "subscribe",
+            "payload": { "query": query, "variables": { "id": id } },
+        });
+        socket.send(text(subscribe)).await.unwrap();
+
+        // events arrive as they happen, after those already in the game
+        let action = games
+            .with_room(&id, |room| {
+                Ok(room.game()?.legal_actions(&PlayerColour::Red)[0].clone())
+            })
+            .unwrap();
+        games
+            .with_room(&id, |room| room.apply(PlayerColour::Red, action))
+            .unwrap();
+        let next_event = games
+            .with_room(&id, |room| Ok(room.game()?.next_event()))
+            .unwrap();
+        let mut seqs = Vec::new();
+        while seqs.len() < next_event {
+            let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            if message["type"] == "next" {
+                seqs.push(
+                    message["payload"]["data"]["events"]["seq"]
+                        .as_u64()
+                        .unwrap(),
+                );
+            }
+        }
+        assert_eq!(seqs, (0..next_event as u64).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,502 @@
+use std::pin::Pin;
+
+use axum::http::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use catan_game_logic::{Action, GameOptions, Lobby, PlayerColour, DEFAULT_LOBBY_SEATS};
+
+use crate::error::ApiError;
+use crate::manager::GameManager;
+use crate::stream::{catch_up, StreamMessage, StreamQuery};
+
+use proto::catan_server::{Catan, CatanServer};
+
+/// The messages of `proto/catan.proto`, along with the generated `catan_server` and
+/// `catan_client`
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CreateGameRequest {
+        /// A `GameOptions` as JSON, left empty for the defaults
+        #[prost(string, tag = "1")]
+        pub options: String,
+        /// Number of seats at the table, bots included
+        #[prost(uint32, optional, tag = "2")]
+        pub seats: Option<u32>,
+        /// Whether bots take any seats still empty when the game starts
+        #[prost(bool, tag = "3")]
+        pub bots: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct CreatedGame {
+        #[prost(string, tag = "1")]
+        pub id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct JoinRequest {
+        #[prost(string, tag = "1")]
+        pub game_id: String,
+        /// A colour as `PlayerColour` writes it
+        #[prost(string, tag = "2")]
+        pub colour: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Seat {
+        #[prost(string, tag = "1")]
+        pub player: String,
+        #[prost(string, tag = "2")]
+        pub token: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ReadyRequest {
+        #[prost(string, tag = "1")]
+        pub game_id: String,
+        #[prost(bool, tag = "2")]
+        pub ready: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GameRequest {
+        #[prost(string, tag = "1")]
+        pub game_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ActionRequest {
+        #[prost(string, tag = "1")]
+        pub game_id: String,
+        /// An `Action` as JSON
+        #[prost(string, tag = "2")]
+        pub action: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Lobby {
+        /// A `Lobby` as JSON
+        #[prost(string, tag = "1")]
+        pub lobby: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct View {
+        /// A `PlayerView` as JSON
+        #[prost(string, tag = "1")]
+        pub view: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamRequest {
+        #[prost(string, tag = "1")]
+        pub game_id: String,
+        /// Sequence number of the first event to send
+        #[prost(uint64, tag = "2")]
+        pub since: u64,
+        /// Sequence number of the first chat message to send
+        #[prost(uint64, tag = "3")]
+        pub chat_since: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Event {
+        #[prost(uint64, tag = "1")]
+        pub seq: u64,
+        /// A `GameEvent` as JSON
+        #[prost(string, tag = "2")]
+        pub event: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamMessage {
+        #[prost(oneof = "stream_message::Message", tags = "1, 2, 3, 4")]
+        pub message: Option<stream_message::Message>,
+    }
+
+    pub mod stream_message {
+        /// The same as the REST API's `StreamMessage`, with everything but the sequence
+        /// numbers as JSON
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Message {
+            #[prost(message, tag = "1")]
+            Event(super::Event),
+            #[prost(string, tag = "2")]
+            View(String),
+            #[prost(string, tag = "3")]
+            Lobby(String),
+            #[prost(string, tag = "4")]
+            Chat(String),
+        }
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/catan.Catan.rs"));
+}
+
+/// The gRPC service in `proto/catan.proto`, serving the same games as the REST API
+///
+/// it covers what a client needs to play, from creating a game through to streaming its
+/// events. Errors are the REST API's, with their status codes mapped onto gRPC's
+pub fn grpc_service(games: GameManager) -> CatanServer<GrpcService> {
+    CatanServer::new(GrpcService { games })
+}
+
+#[derive(Clone)]
+pub struct GrpcService {
+    games: GameManager,
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::StreamMessage, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Catan for GrpcService {
+    async fn create_game(
+        &self,
+        request: Request<proto::CreateGameRequest>,
+    ) -> Result<Response<proto::CreatedGame>, Status> {
+        let request = request.into_inner();
+        let options = match request.options.as_str() {
+            "" => GameOptions::default(),
+            options => json(options)?,
+        };
+        let seats = request
+            .seats
+            .map_or(DEFAULT_LOBBY_SEATS, |seats| seats as usize);
+        let lobby = Lobby::new(options)
+            .with_capacity(seats)
+            .with_bots(request.bots);
+        let id = self.games.create(lobby)?;
+        Ok(Response::new(proto::CreatedGame { id: id.to_string() }))
+    }
+
+    async fn join_game(
+        &self,
+        request: Request<proto::JoinRequest>,
+    ) -> Result<Response<proto::Seat>, Status> {
+        let request = request.into_inner();
+        let id = uuid(&request.game_id)?;
+        let colour: PlayerColour = request.colour.parse().map_err(ApiError::from)?;
+        let token = self.games.with_room(&id, |room| room.join(colour))?;
+        Ok(Response::new(proto::Seat {
+            player: colour.to_string(),
+            token: token.to_string(),
+        }))
+    }
+
+    async fn set_ready(
+        &self,
+        request: Request<proto::ReadyRequest>,
+    ) -> Result<Response<proto::Lobby>, Status> {
+        let token = token(request.metadata())?;
+        let request = request.into_inner();
+        let lobby = self.games.with_room(&uuid(&request.game_id)?, |room| {
+            let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+            room.set_ready(player, request.ready)?;
+            Ok(room.lobby().clone())
+        })?;
+        Ok(Response::new(proto::Lobby {
+            lobby: to_json(&lobby)?,
+        }))
+    }
+
+    async fn start_game(
+        &self,
+        request: Request<proto::GameRequest>,
+    ) -> Result<Response<proto::View>, Status> {
+        let token = token(request.metadata())?;
+        let view = self
+            .games
+            .with_room(&uuid(&request.get_ref().game_id)?, |room| {
+                let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+                room.start(player)?;
+                Ok(room.game()?.view_for(&player)?)
+            })?;
+        Ok(Response::new(proto::View {
+            view: to_json(&view)?,
+        }))
+    }
+
+    async fn get_view(
+        &self,
+        request: Request<proto::GameRequest>,
+    ) -> Result<Response<proto::View>, Status> {
+        let token = token(request.metadata())?;
+        let view = self
+            .games
+            .with_room(&uuid(&request.get_ref().game_id)?, |room| {
+                let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+                Ok(room.game()?.view_for(&player)?)
+            })?;
+        Ok(Response::new(proto::View {
+            view: to_json(&view)?,
+        }))
+    }
+
+    async fn submit_action(
+        &self,
+        request: Request<proto::ActionRequest>,
+    ) -> Result<Response<proto::View>, Status> {
+        let token = token(request.metadata())?;
+        let request = request.into_inner();
+        let action: Action = json(&request.action)?;
+        let view = self.games.with_room(&uuid(&request.game_id)?, |room| {
+            let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+            room.apply(player, action)?;
+            Ok(room.game()?.view_for(&player)?)
+        })?;
+        Ok(Response::new(proto::View {
+            view: to_json(&view)?,
+        }))
+    }
+
+    type StreamEventsStream = EventStream;
+
+    /// Stream the same messages as `stream_events` sends down a WebSocket, ending the stream
+    /// with `UNAUTHENTICATED` once the token stops working
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamRequest>,
+    ) -> Result<Response<EventStream>, Status> {
+        let token = token(request.metadata())?;
+        let request = request.into_inner();
+        let id = uuid(&request.game_id)?;
+        let mut updates = self.games.with_room(&id, |room| {
+            room.viewer(&token)?;
+            Ok(room.subscribe())
+        })?;
+        let mut cursor = StreamQuery {
+            token: Some(token),
+            since: request.since as usize,
+            chat_since: request.chat_since as usize,
+            ..StreamQuery::default()
+        };
+        let games = self.games.clone();
+        let (sender, receiver) = mpsc::channel(16);
+        tokio::spawn(async move {
+            loop {
+                let messages = games.with_room(&id, |room| {
+                    let viewer = room.viewer(&token)?;
+                    Ok(catch_up(room, viewer, &mut cursor))
+                });
+                let messages = match messages {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        let _ = sender.send(Err(err.into())).await;
+                        return;
+                    }
+                };
+                for message in messages {
+                    if sender.send(stream_message(&message)).await.is_err() {
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    update = updates.recv() => match update {
+                        Ok(()) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = sender.closed() => return,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+fn stream_message(message: &StreamMessage) -> Result<proto::StreamMessage, Status> {
+    use proto::stream_message::Message;
+
+    let message = match message {
+        StreamMessage::Event { seq, event } => Message::Event(proto::Event {
+            seq: *seq as u64,
+            event: to_json(event)?,
+        }),
+        StreamMessage::View { view } => Message::View(to_json(view)?),
+        StreamMessage::Lobby { lobby } => Message::Lobby(to_json(lobby)?),
+        StreamMessage::Chat { message } => Message::Chat(to_json(message)?),
+    };
+    Ok(proto::StreamMessage {
+        message: Some(message),
+    })
+}
+
+/// The seat or spectator token sent as `authorization: Bearer <token>`
+fn token(metadata: &MetadataMap) -> Result<Uuid, Status> {
+    metadata
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| Uuid::parse_str(token.trim()).ok())
+        .ok_or(ApiError::unauthorized().into())
+}
+
+fn uuid(id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(id).map_err(|_| ApiError::not_found("game").into())
+}
+
+fn json<T: DeserializeOwned>(json: &str) -> Result<T, Status> {
+    serde_json::from_str(json).map_err(|err| Status::invalid_argument(err.to_string()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, Status> {
+    serde_json::to_string(value).map_err(|err| Status::internal(err.to_string()))
+}
+
+/// The nearest gRPC code to each status the REST API turns requests down with
+impl From<ApiError> for Status {
+    fn from(err: ApiError) -> Self {
+        let code = match err.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                tonic::Code::InvalidArgument
+            }
+            StatusCode::UNAUTHORIZED => tonic::Code::Unauthenticated,
+            StatusCode::FORBIDDEN => tonic::Code::PermissionDenied,
+            StatusCode::NOT_FOUND => tonic::Code::NotFound,
+            StatusCode::CONFLICT => tonic::Code::FailedPrecondition,
+            StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS => {
+                tonic::Code::ResourceExhausted
+            }
+            StatusCode::SERVICE_UNAVAILABLE => tonic::Code::Unavailable,
+            _ => tonic::Code::Internal,
+        };
+        Status::new(code, err.reason)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tokio_stream::StreamExt;
+    use tonic::transport::{Channel, Server};
+    use tonic::Code;
+
+    use super::proto::catan_client::CatanClient;
+    use super::proto::stream_message::Message;
+    use super::*;
+
+    async fn serve(games: GameManager) -> CatanClient<Channel> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpListenerStream::new(listener);
+        tokio::spawn(
+            Server::builder()
+                .add_service(grpc_service(games))
+                .serve_with_incoming(incoming),
+        );
+        CatanClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap()
+    }
+
+    fn authorized<T>(message: T, token: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        let bearer = format!("Bearer {}", token).parse().unwrap();
+        request.metadata_mut().insert("authorization", bearer);
+        request
+    }
+
+    #[tokio::test]
+    async fn test_game() {
+        let mut client = serve(GameManager::new()).await;
+        let request = proto::CreateGameRequest {
+            seats: Some(2),
+            ..Default::default()
+        };
+        let id = client.create_game(request).await.unwrap().into_inner().id;
+
+        let mut seats = vec![];
+        for colour in ["red", "blue"] {
+            let request = proto::JoinRequest {
+                game_id: id.clone(),
+                colour: colour.into(),
+            };
+            let seat = client.join_game(request).await.unwrap().into_inner();
+            assert_eq!(seat.player, colour);
+            seats.push(seat.token);
+        }
+        let game = proto::GameRequest {
+            game_id: id.clone(),
+        };
+
+        // acting for a seat needs its token
+        let err = client.start_game(game.clone()).await.unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        let err = client
+            .start_game(authorized(game.clone(), &seats[0]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::FailedPrecondition);
+
+        for token in &seats {
+            let ready = proto::ReadyRequest {
+                game_id: id.clone(),
+                ready: true,
+            };
+            client.set_ready(authorized(ready, token)).await.unwrap();
+        }
+        let mut events = client
+            .stream_events(authorized(
+                proto::StreamRequest {
+                    game_id: id.clone(),
+                    ..Default::default()
+                },
+                &seats[1],
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let message = events.next().await.unwrap().unwrap();
+        assert!(matches!(message.message, Some(Message::Lobby(_))));
+
+        client
+            .start_game(authorized(game.clone(), &seats[0]))
+            .await
+            .unwrap();
+        let view = client
+            .get_view(authorized(game, &seats[0]))
+            .await
+            .unwrap()
+            .into_inner();
+        let view: catan_game_logic::PlayerView = serde_json::from_str(&view.view).unwrap();
+        let action = view.legal_actions[0].clone();
+        let request = proto::ActionRequest {
+            game_id: id.clone(),
+            action: serde_json::to_string(&action).unwrap(),
+        };
+        client
+            .submit_action(authorized(request, &seats[0]))
+            .await
+            .unwrap();
+
+        // the stream catches up with the game's events
+        let mut seq = None;
+        while seq.is_none() {
+            let message = events.next().await.unwrap().unwrap();
+            if let Some(Message::Event(event)) = message.message {
+                seq = Some(event.seq);
+            }
+        }
+        assert_eq!(seq, Some(0));
+
+        // actions which don't parse are turned down
+        let request = proto::ActionRequest {
+            game_id: id,
+            action: "{}".into(),
+        };
+        let err = client
+            .submit_action(authorized(request, &seats[0]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,36 @@
+pub(crate) mod admin;
+pub(crate) mod error;
+pub(crate) mod format;
+#[cfg(feature = "graphql")]
+pub(crate) mod graphql;
+#[cfg(feature = "grpc")]
+pub(crate) mod grpc;
+pub(crate) mod manager;
+pub(crate) mod openapi;
+pub(crate) mod room;
+pub(crate) mod routes;
+#[cfg(feature = "sqlite")]
+pub(crate) mod sqlite;
+pub(crate) mod storage;
+pub(crate) mod stream;
+#[cfg(feature = "ts-rs")]
+pub(crate) mod typescript;
+
+pub use admin::{
+    Admin, EndRequest, GameDetails, GameListing, GamesQuery, RollbackRequest, RoomState,
+};
+pub use error::{ApiError, ErrorBody};
+#[cfg(feature = "graphql")]
+pub use graphql::{graphql_schema, CatanSchema};
+#[cfg(feature = "grpc")]
+pub use grpc::{grpc_service, proto, GrpcService};
+pub use manager::{GameManager, DEFAULT_IDLE_TTL};
+pub use openapi::openapi;
+pub use room::Room;
+pub use routes::router;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+pub use storage::{MemoryStorage, Persistence, SavedGame, SavedRoom, Storage};
+pub use stream::StreamMessage;
+#[cfg(feature = "ts-rs")]
+pub use typescript::typescript;
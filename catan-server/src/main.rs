@@ -0,0 +1,85 @@
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+use catan_server::{router, GameManager, Persistence};
+
+/// Address to listen on, unless `CATAN_ADDR` says otherwise
+const DEFAULT_ADDR: &str = "0.0.0.0:3000";
+
+/// Address to serve gRPC on, unless `CATAN_GRPC_ADDR` says otherwise
+#[cfg(feature = "grpc")]
+const DEFAULT_GRPC_ADDR: &str = "0.0.0.0:50051";
+
+/// How often to look for idle games to evict
+const EVICTION_PERIOD: Duration = Duration::from_secs(60);
+
+/// How often to look for players who've run out of time
+const TIMEOUT_PERIOD: Duration = Duration::from_secs(1);
+
+/// Serves the API on `CATAN_ADDR`, with the admin endpoints open to `CATAN_ADMIN_TOKEN` if it's
+/// set
+///
+/// built with the `grpc` feature, the gRPC service is served on `CATAN_GRPC_ADDR` as well.
+/// Built with the `ts-rs` feature, `catan-server typescript` prints the TypeScript declarations
+/// for the API instead
+#[tokio::main]
+async fn main() -> Result<()> {
+    #[cfg(feature = "ts-rs")]
+    if std::env::args().nth(1).as_deref() == Some("typescript") {
+        catan_server::typescript().write(std::io::stdout().lock())?;
+        return Ok(());
+    }
+    let addr = std::env::var("CATAN_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.into());
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let mut games = storage(GameManager::new())?;
+    if let Ok(token) = std::env::var("CATAN_ADMIN_TOKEN") {
+        games = games.with_admin_token(token);
+    }
+    tokio::spawn(games.clone().evict_periodically(EVICTION_PERIOD));
+    tokio::spawn(games.clone().resolve_timeouts_periodically(TIMEOUT_PERIOD));
+    let rest = axum::serve(listener, router(games.clone())).into_future();
+    #[cfg(feature = "grpc")]
+    tokio::try_join!(async { Ok(rest.await?) }, serve_grpc(games))?;
+    #[cfg(not(feature = "grpc"))]
+    rest.await?;
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+async fn serve_grpc(games: GameManager) -> Result<()> {
+    let addr = std::env::var("CATAN_GRPC_ADDR").unwrap_or_else(|_| DEFAULT_GRPC_ADDR.into());
+    tonic::transport::Server::builder()
+        .add_service(catan_server::grpc_service(games))
+        .serve(addr.parse()?)
+        .await?;
+    Ok(())
+}
+
+/// Keep games in the SQLite database at `CATAN_DATABASE`, if it's set, saving them as
+/// `CATAN_PERSISTENCE` says, either `snapshot` or `event_log`
+fn storage(games: GameManager) -> Result<GameManager> {
+    let Ok(path) = std::env::var("CATAN_DATABASE") else {
+        return Ok(games);
+    };
+    let persistence = match std::env::var("CATAN_PERSISTENCE").as_deref() {
+        Ok("snapshot") | Err(_) => Persistence::Snapshot,
+        Ok("event_log") => Persistence::EventLog,
+        Ok(other) => return Err(anyhow!("Unknown persistence {}", other)),
+    };
+    open(games, &path, persistence)
+}
+
+#[cfg(feature = "sqlite")]
+fn open(games: GameManager, path: &str, persistence: Persistence) -> Result<GameManager> {
+    let storage = catan_server::SqliteStorage::open(path)?;
+    Ok(games.with_storage(storage, persistence))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open(_: GameManager, _: &str, _: Persistence) -> Result<GameManager> {
+    Err(anyhow!(
+        "CATAN_DATABASE needs the server built with the sqlite feature"
+    ))
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use catan_game_logic::{GameState, Lobby, Tournament};
+
+use crate::error::ApiError;
+use crate::room::Room;
+use crate::storage::{Persistence, Storage};
+
+/// How long a game can go untouched before it's evicted, unless `with_ttl` says otherwise
+pub const DEFAULT_IDLE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Every game on the server, keyed by id and shared between the request handlers
+///
+/// each room has its own lock, so actions in one game are applied one at a time without holding
+/// up any other game, while the registry itself is only locked long enough to find a room.
+///
+/// with storage, every change to a room is saved before its lock is let go, and games which
+/// aren't in memory, because they were evicted or the server restarted, are loaded back the
+/// next time they're asked for
+#[derive(Debug, Clone)]
+pub struct GameManager {
+    rooms: Arc<RwLock<HashMap<Uuid, Arc<Mutex<Room>>>>>,
+    ttl: Duration,
+    storage: Option<Arc<dyn Storage>>,
+    persistence: Persistence,
+    /// The credential the admin endpoints need, which are closed without one
+    admin_token: Option<Arc<str>>,
+}
+
+impl Default for GameManager {
+    fn default() -> Self {
+        Self {
+            rooms: Arc::default(),
+            ttl: DEFAULT_IDLE_TTL,
+            storage: None,
+            persistence: Persistence::default(),
+            admin_token: None,
+        }
+    }
+}
+
+impl GameManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evict games once they've gone `ttl` without anything touching them
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Keep every game in `storage` too, written down as `persistence` says
+    pub fn with_storage(
+        mut self,
+        storage: impl Storage + 'static,
+        persistence: Persistence,
+    ) -> Self {
+        self.storage = Some(Arc::new(storage));
+        self.persistence = persistence;
+        self
+    }
+
+    /// Open the admin endpoints to anyone sending `token` as a bearer token
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into().into());
+        self
+    }
+
+    /// Whether `token` is the admin credential
+    pub fn is_admin(&self, token: &str) -> bool {
+        self.admin_token.as_deref() == Some(token)
+    }
+
+    /// Open a new room around `lobby`, returning its id
+    pub fn create(&self, lobby: Lobby) -> Result<Uuid, ApiError> {
+        let id = Uuid::new_v4();
+        let mut room = Room::new(lobby);
+        self.save(&id, &mut room)?;
+        self.rooms
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(id, Arc::new(Mutex::new(room)));
+        Ok(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Run `f` on room `id`, with the room to itself while it runs
+    pub fn with_room<T>(
+        &self,
+        id: &Uuid,
+        f: impl FnOnce(&mut Room) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        let room = self.room(id)?;
+        let mut room = lock(&room);
+        room.last_active = Instant::now();
+        let result = f(&mut room);
+        self.save(id, &mut room)?;
+        result
+    }
+
+    /// Record every finished game `tournament` is waiting on, returning how many there were
+    ///
+    /// this doesn't count as touching the games, so finished ones can still be evicted, and games
+    /// which can't be found are left pending
+    pub fn update_tournament(&self, tournament: &mut Tournament) -> usize {
+        let mut recorded = 0;
+        for id in tournament.pending_games() {
+            let Ok(room) = self.room(&id) else {
+                continue;
+            };
+            let report = match lock(&room).game() {
+                Ok(game) if game.state() == GameState::Complete => game.report(),
+                _ => continue,
+            };
+            if tournament.record(id, &report).is_ok() {
+                recorded += 1;
+            }
+        }
+        recorded
+    }
+
+    /// Run `f` on every room in memory, one at a time, collecting what it returns
+    ///
+    /// unlike `with_room`, this doesn't count as touching the rooms
+    pub fn map_rooms<T>(&self, mut f: impl FnMut(&Uuid, &Room) -> T) -> Vec<T> {
+        let rooms: Vec<_> = self
+            .read()
+            .iter()
+            .map(|(id, room)| (*id, room.clone()))
+            .collect();
+        rooms.iter().map(|(id, room)| f(id, &lock(room))).collect()
+    }
+
+    /// Drop every game nobody has touched for the TTL, returning how many went
+    ///
+    /// games in use right now are never idle, so they're skipped rather than waited for. Games
+    /// in storage can still be loaded back
+    pub fn evict_idle(&self) -> usize {
+        let mut rooms = self
+            .rooms
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = rooms.len();
+        rooms.retain(|_, room| match room.try_lock() {
+            Ok(room) => room.last_active.elapsed() < self.ttl,
+            Err(_) => true,
+        });
+        before - rooms.len()
+    }
+
+    /// Evict idle games every `period`, forever
+    pub async fn evict_periodically(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            self.evict_idle();
+        }
+    }
+
+    /// Deal with everyone who's run out of time in every game, as of now
+    ///
+    /// this doesn't count as touching the games, so they can still be evicted for being idle
+    pub fn resolve_timeouts(&self) {
+        let rooms: Vec<_> = self
+            .read()
+            .iter()
+            .map(|(id, room)| (*id, room.clone()))
+            .collect();
+        for (id, room) in rooms {
+            let mut room = lock(&room);
+            // a room which can't be saved now will be the next time it changes
+            if room.resolve_timeouts(SystemTime::now()).is_ok() {
+                let _ = self.save(&id, &mut room);
+            }
+        }
+    }
+
+    /// Resolve timeouts every `period`, forever, which is as precise as deadlines get
+    pub async fn resolve_timeouts_periodically(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            self.resolve_timeouts();
+        }
+    }
+
+    /// Write `room` to storage, if there's storage and anything has changed
+    fn save(&self, id: &Uuid, room: &mut Room) -> Result<(), ApiError> {
+        if let (Some(storage), true) = (&self.storage, room.unsaved) {
+            storage
+                .save(id, &room.save(self.persistence))
+                .map_err(storage_error)?;
+        }
+        room.unsaved = false;
+        Ok(())
+    }
+
+    /// Room `id`, from memory or else from storage
+    fn room(&self, id: &Uuid) -> Result<Arc<Mutex<Room>>, ApiError> {
+        let room = self.read().get(id).cloned();
+        match room {
+            Some(room) => Ok(room),
+            None => self.load(id),
+        }
+    }
+
+    /// Bring room `id` back from storage, unless another request already has
+    fn load(&self, id: &Uuid) -> Result<Arc<Mutex<Room>>, ApiError> {
+        let storage = self.storage.as_ref().ok_or(ApiError::not_found("game"))?;
+        let saved = storage
+            .load(id)
+            .map_err(storage_error)?
+            .ok_or(ApiError::not_found("game"))?;
+        let room = Room::restore(saved).map_err(storage_error)?;
+        let mut rooms = self
+            .rooms
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(rooms
+            .entry(*id)
+            .or_insert_with(|| Arc::new(Mutex::new(room)))
+            .clone())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<Uuid, Arc<Mutex<Room>>>> {
+        self.rooms
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The room, carrying on even if a handler panicked while holding its lock, rather than taking
+/// the game down with it
+fn lock(room: &Mutex<Room>) -> MutexGuard<'_, Room> {
+    room.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn storage_error(err: anyhow::Error) -> ApiError {
+    ApiError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Couldn't reach the game's storage: {}", err),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use catan_game_logic::{Format, GameEvent, GameOptions, PlayerColour, TurnClock};
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn lobby() -> Lobby {
+        Lobby::new(GameOptions::default())
+    }
+
+    #[test]
+    fn test_rooms_are_separate() {
+        let games = GameManager::new();
+        let a = games.create(lobby()).unwrap();
+        let b = games.create(lobby()).unwrap();
+        assert_eq!(games.len(), 2);
+
+        games
+            .with_room(&a, |room| room.join(PlayerColour::Red))
+            .unwrap();
+        let players = |id| games.with_room(&id, |room| Ok(room.lobby().seats().len()));
+        assert_eq!(players(a), Ok(1));
+        assert_eq!(players(b), Ok(0));
+        assert!(games.with_room(&Uuid::new_v4(), |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn test_update_tournament() {
+        let games = GameManager::new();
+        let mut tournament =
+            Tournament::new(Format::Swiss { rounds: 1 }, ["alice", "bob"], 2).unwrap();
+        tournament.next_round().unwrap();
+        let id = games.create(lobby()).unwrap();
+        tournament.assign(0, id).unwrap();
+        games
+            .with_room(&id, |room| {
+                for colour in [PlayerColour::Red, PlayerColour::Blue] {
+                    room.join(colour)?;
+                    room.set_ready(colour, true)?;
+                }
+                room.start(PlayerColour::Red)
+            })
+            .unwrap();
+
+        // nothing to record until the game is over
+        assert_eq!(games.update_tournament(&mut tournament), 0);
+        games
+            .with_room(&id, |room| room.end(Some(PlayerColour::Red)))
+            .unwrap();
+        assert_eq!(games.update_tournament(&mut tournament), 1);
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.standings()[0].player, "alice");
+        assert_eq!(games.update_tournament(&mut tournament), 0);
+    }
+
+    #[test]
+    fn test_evict_idle() {
+        let games = GameManager::new();
+        games.create(lobby()).unwrap();
+        assert_eq!(games.evict_idle(), 0);
+
+        let games = games.with_ttl(Duration::ZERO);
+        let id = games.create(lobby()).unwrap();
+        assert_eq!(games.evict_idle(), 2);
+        assert!(games.is_empty());
+        assert_eq!(
+            games.with_room(&id, |_| Ok(())),
+            Err(ApiError::not_found("game"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_timeouts() {
+        let games = GameManager::new();
+        let clock = TurnClock::PerMove(Duration::ZERO);
+        let options = GameOptions {
+            clock,
+            ..GameOptions::default()
+        };
+        let id = games.create(Lobby::new(options)).unwrap();
+        games
+            .with_room(&id, |room| {
+                for colour in [PlayerColour::Red, PlayerColour::Blue] {
+                    room.join(colour)?;
+                    room.set_ready(colour, true)?;
+                }
+                room.start(PlayerColour::Red)
+            })
+            .unwrap();
+
+        // red's clock starts, and then runs out straight away
+        games.resolve_timeouts();
+        games.resolve_timeouts();
+        let events = games
+            .with_room(&id, |room| Ok(room.game()?.events().to_vec()))
+            .unwrap();
+        assert!(events.contains(&GameEvent::SettlementBuilt {
+            player: PlayerColour::Red
+        }));
+    }
+
+    #[test]
+    fn test_storage() {
+        for persistence in [Persistence::Snapshot, Persistence::EventLog] {
+            let games = GameManager::new()
+                .with_ttl(Duration::ZERO)
+                .with_storage(MemoryStorage::new(), persistence);
+            let id = games.create(lobby().with_bots(true)).unwrap();
+            let token = games
+                .with_room(&id, |room| {
+                    let token = room.join(PlayerColour::Blue)?;
+                    room.set_ready(PlayerColour::Blue, true)?;
+                    room.start(PlayerColour::Blue)?;
+                    // a settlement and a road, after which the bots place theirs
+                    for _ in 0..2 {
+                        let action = room.game()?.legal_actions(&PlayerColour::Blue)[0].clone();
+                        room.apply(PlayerColour::Blue, action)?;
+                    }
+                    Ok(token)
+                })
+                .unwrap();
+            let game = games
+                .with_room(&id, |room| Ok(room.game()?.clone()))
+                .unwrap();
+
+            // evicted games come back from storage as they were
+            assert_eq!(games.evict_idle(), 1);
+            games
+                .with_room(&id, |room| {
+                    assert_eq!(room.player(&token), Some(PlayerColour::Blue));
+                    assert_eq!(room.game()?.events(), game.events());
+                    assert_eq!(room.game()?.zobrist(), game.zobrist());
+                    Ok(())
+                })
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use std::sync::OnceLock;
+
+use axum::http::StatusCode;
+use axum::Json;
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::Schema;
+use serde_json::{json, Map, Value};
+
+use catan_game_logic::{Action, ChatMessage, Lobby, PlayerColour, PlayerView, WireFormat};
+
+use crate::admin::{EndRequest, GameDetails, GameListing, RollbackRequest, RoomState};
+use crate::error::ErrorBody;
+use crate::routes::{
+    ChatRequest, CreateGame, CreatedGame, JoinRequest, ReadyRequest, Resume, Seat, Spectator,
+};
+use crate::stream::StreamMessage;
+
+/// Who a request has to come from, by the bearer token it sends
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Auth {
+    Anyone,
+    /// A seat token, or a spectator token where the endpoint allows one
+    Seat,
+    Admin,
+}
+
+/// One endpoint, as the document describes it
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    auth: Auth,
+    /// Query string parameters, each with its schema and what it's for
+    query: Vec<(&'static str, Schema, &'static str)>,
+    /// The JSON body, and whether it can be left out
+    body: Option<(Schema, bool)>,
+    /// The status of a successful response, with its JSON body if it has one
+    status: StatusCode,
+    response: Option<Schema>,
+    /// Statuses of the errors the endpoint can respond with, each with an `ErrorBody`
+    errors: &'static [StatusCode],
+}
+
+impl Operation {
+    fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            summary,
+            auth: Auth::Anyone,
+            query: Vec::new(),
+            body: None,
+            status: StatusCode::OK,
+            response: None,
+            errors: &[],
+        }
+    }
+
+    fn auth(self, auth: Auth) -> Self {
+        Self { auth, ..self }
+    }
+
+    fn query(mut self, name: &'static str, schema: Schema, description: &'static str) -> Self {
+        self.query.push((name, schema, description));
+        self
+    }
+
+    fn body(self, schema: Schema) -> Self {
+        Self {
+            body: Some((schema, true)),
+            ..self
+        }
+    }
+
+    fn optional_body(self, schema: Schema) -> Self {
+        Self {
+            body: Some((schema, false)),
+            ..self
+        }
+    }
+
+    fn responds(self, status: StatusCode, response: Option<Schema>) -> Self {
+        Self {
+            status,
+            response,
+            ..self
+        }
+    }
+
+    fn errors(self, errors: &'static [StatusCode]) -> Self {
+        Self { errors, ..self }
+    }
+
+    fn to_json(&self, error: &Schema) -> Value {
+        let mut parameters: Vec<Value> = Vec::new();
+        for segment in self.path.split('/') {
+            let Some(name) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                continue;
+            };
+            let schema = match name {
+                "id" => json!({ "type": "string", "format": "uuid" }),
+                _ => json!({ "$ref": "#/components/schemas/PlayerColour" }),
+            };
+            parameters.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": schema,
+            }));
+        }
+        for (name, schema, description) in &self.query {
+            parameters.push(json!({
+                "name": name,
+                "in": "query",
+                "description": description,
+                "schema": schema,
+            }));
+        }
+
+        let mut responses = Map::new();
+        let reason = |status: &StatusCode| status.canonical_reason().unwrap_or_default();
+        let mut success = json!({ "description": reason(&self.status) });
+        if let Some(schema) = &self.response {
+            success["content"] = content(schema);
+        }
+        responses.insert(self.status.as_u16().to_string(), success);
+        for status in self.errors {
+            responses.insert(
+                status.as_u16().to_string(),
+                json!({
+                    "description": reason(status),
+                    "content": content(error),
+                }),
+            );
+        }
+
+        let mut operation = json!({
+            "summary": self.summary,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if let Some((schema, required)) = &self.body {
+            operation["requestBody"] = json!({
+                "required": required,
+                "content": content(schema),
+            });
+        }
+        match self.auth {
+            Auth::Anyone => (),
+            Auth::Seat => operation["security"] = json!([{ "seat": [] }]),
+            Auth::Admin => operation["security"] = json!([{ "admin": [] }]),
+        }
+        operation
+    }
+}
+
+/// A body with `schema`, in any `WireFormat` the server can read and write
+fn content(schema: &Schema) -> Value {
+    WireFormat::ALL
+        .iter()
+        .map(|format| {
+            (
+                format.content_type().to_string(),
+                json!({ "schema": schema }),
+            )
+        })
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+fn schema(value: Value) -> Schema {
+    value.try_into().expect("only objects are passed in")
+}
+
+/// Every endpoint the server has, in the order `router` lists them
+fn operations(gen: &mut SchemaGenerator) -> Vec<Operation> {
+    use StatusCode as S;
+
+    let since = schema(json!({ "type": "integer", "minimum": 0, "default": 0 }));
+    let formats: Vec<_> = WireFormat::ALL.iter().map(ToString::to_string).collect();
+    let format = schema(json!({ "type": "string", "enum": formats, "default": "json" }));
+    let token = schema(json!({ "type": "string", "format": "uuid" }));
+    #[allow(unused_mut)]
+    let mut operations = vec![
+        Operation::new("post", "/games", "Open a lobby for a game")
+            .optional_body(gen.subschema_for::<CreateGame>())
+            .responds(S::CREATED, Some(gen.subschema_for::<CreatedGame>()))
+            .errors(&[S::BAD_REQUEST, S::INTERNAL_SERVER_ERROR]),
+        Operation::new(
+            "get",
+            "/games/{id}/lobby",
+            "The lobby's seats and who's ready",
+        )
+        .responds(S::OK, Some(gen.subschema_for::<Lobby>()))
+        .errors(&[S::NOT_FOUND]),
+        Operation::new("post", "/games/{id}/players", "Claim a seat")
+            .body(gen.subschema_for::<JoinRequest>())
+            .responds(S::CREATED, Some(gen.subschema_for::<Seat>()))
+            .errors(&[S::BAD_REQUEST, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "delete",
+            "/games/{id}/players",
+            "Give the seat up before the start",
+        )
+        .auth(Auth::Seat)
+        .responds(S::NO_CONTENT, None)
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "post",
+            "/games/{id}/ready",
+            "Say whether the seat's player is ready",
+        )
+        .auth(Auth::Seat)
+        .body(gen.subschema_for::<ReadyRequest>())
+        .responds(S::OK, Some(gen.subschema_for::<Lobby>()))
+        .errors(&[S::BAD_REQUEST, S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new("post", "/games/{id}/start", "Start the game, for the host")
+            .auth(Auth::Seat)
+            .responds(S::OK, Some(gen.subschema_for::<PlayerView>()))
+            .errors(&[S::UNAUTHORIZED, S::FORBIDDEN, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "post",
+            "/games/{id}/token",
+            "Swap the seat's token for a new one",
+        )
+        .auth(Auth::Seat)
+        .responds(S::OK, Some(gen.subschema_for::<Seat>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new(
+            "post",
+            "/games/{id}/spectators",
+            "Hand out a token to watch with",
+        )
+        .auth(Auth::Seat)
+        .responds(S::CREATED, Some(gen.subschema_for::<Spectator>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new(
+            "get",
+            "/games/{id}/view",
+            "The game as the seat's player sees it",
+        )
+        .auth(Auth::Seat)
+        .responds(S::OK, Some(gen.subschema_for::<PlayerView>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "get",
+            "/games/{id}/resume",
+            "Catch a reconnecting client up",
+        )
+        .auth(Auth::Seat)
+        .query(
+            "since",
+            since.clone(),
+            "The first event the client hasn't seen",
+        )
+        .query(
+            "chat_since",
+            since.clone(),
+            "The first chat message it hasn't seen",
+        )
+        .responds(S::OK, Some(gen.subschema_for::<Resume>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "get",
+            "/games/{id}/chat",
+            "The chat, for seats and spectators alike",
+        )
+        .auth(Auth::Seat)
+        .query("since", since.clone(), "The first message to send")
+        .responds(S::OK, Some(gen.subschema_for::<Vec<ChatMessage>>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new(
+            "post",
+            "/games/{id}/chat",
+            "Send a message from the seat's player",
+        )
+        .auth(Auth::Seat)
+        .body(gen.subschema_for::<ChatRequest>())
+        .responds(S::CREATED, Some(gen.subschema_for::<ChatMessage>()))
+        .errors(&[S::BAD_REQUEST, S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new(
+            "post",
+            "/games/{id}/actions",
+            "Take an action for the seat's player",
+        )
+        .auth(Auth::Seat)
+        .body(gen.subschema_for::<Action>())
+        .responds(S::OK, Some(gen.subschema_for::<PlayerView>()))
+        .errors(&[
+            S::UNAUTHORIZED,
+            S::NOT_FOUND,
+            S::CONFLICT,
+            S::UNPROCESSABLE_ENTITY,
+        ]),
+        Operation::new(
+            "post",
+            "/games/{id}/resign",
+            "Give the game up for the seat's player",
+        )
+        .auth(Auth::Seat)
+        .responds(S::OK, Some(gen.subschema_for::<PlayerView>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        // browsers can't set headers on a WebSocket, so the token goes in the query instead
+        Operation::new(
+            "get",
+            "/games/{id}/events",
+            "Stream events over a WebSocket, each a StreamMessage in its own frame",
+        )
+        .query("token", token, "A seat or spectator token")
+        .query("since", since.clone(), "The first event to send")
+        .query("chat_since", since, "The first chat message to send")
+        .query("format", format, "JSON for text frames, or a binary format")
+        .responds(S::SWITCHING_PROTOCOLS, None)
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new("get", "/openapi.json", "This document")
+            .responds(S::OK, Some(Schema::from(true))),
+        Operation::new(
+            "get",
+            "/admin/games",
+            "Games in memory, most recently active first",
+        )
+        .auth(Auth::Admin)
+        .query(
+            "state",
+            gen.subschema_for::<RoomState>(),
+            "Only games in this state",
+        )
+        .query(
+            "player",
+            gen.subschema_for::<PlayerColour>(),
+            "Only games this colour is playing in",
+        )
+        .responds(S::OK, Some(gen.subschema_for::<Vec<GameListing>>()))
+        .errors(&[S::UNAUTHORIZED]),
+        Operation::new(
+            "get",
+            "/admin/games/{id}",
+            "Everything about a game, hands and all",
+        )
+        .auth(Auth::Admin)
+        .responds(S::OK, Some(gen.subschema_for::<GameDetails>()))
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND]),
+        Operation::new("post", "/admin/games/{id}/end", "End a game on the spot")
+            .auth(Auth::Admin)
+            .optional_body(gen.subschema_for::<EndRequest>())
+            .responds(S::OK, Some(gen.subschema_for::<GameDetails>()))
+            .errors(&[S::BAD_REQUEST, S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new("post", "/admin/games/{id}/rollback", "Wind a game back")
+            .auth(Auth::Admin)
+            .body(gen.subschema_for::<RollbackRequest>())
+            .responds(S::OK, Some(gen.subschema_for::<GameDetails>()))
+            .errors(&[S::BAD_REQUEST, S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+        Operation::new(
+            "delete",
+            "/admin/games/{id}/players/{colour}",
+            "Kick a player out of the lobby or the game",
+        )
+        .auth(Auth::Admin)
+        .responds(S::NO_CONTENT, None)
+        .errors(&[S::UNAUTHORIZED, S::NOT_FOUND, S::CONFLICT]),
+    ];
+    // the schema behind them is GraphQL's to describe, through introspection
+    #[cfg(feature = "graphql")]
+    operations.extend([
+        Operation::new("post", "/graphql", "Answer a GraphQL query")
+            .auth(Auth::Seat)
+            .body(schema(json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "operationName": { "type": "string" },
+                    "variables": { "type": "object" },
+                },
+                "required": ["query"],
+            })))
+            .responds(S::OK, Some(schema(json!({ "type": "object" })))),
+        Operation::new(
+            "get",
+            "/graphql/ws",
+            "Run GraphQL subscriptions over a WebSocket",
+        )
+        .responds(S::SWITCHING_PROTOCOLS, None),
+    ]);
+    operations
+}
+
+/// The OpenAPI 3.1 document for every endpoint, with their bodies, errors and tokens
+///
+/// bodies are described by the same JSON Schemas as `catan_game_logic::schema`, under
+/// `components`, along with `StreamMessage` for what event streams send
+pub fn openapi() -> Value {
+    let mut settings = SchemaSettings::draft2020_12();
+    settings.definitions_path = "/components/schemas".into();
+    settings.meta_schema = None;
+    let mut gen = settings.into_generator();
+
+    let error = gen.subschema_for::<ErrorBody>();
+    let mut paths = Map::new();
+    for operation in operations(&mut gen) {
+        let path = paths
+            .entry(operation.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path[operation.method] = operation.to_json(&error);
+    }
+    gen.subschema_for::<StreamMessage>();
+    gen.subschema_for::<PlayerColour>();
+
+    let bearer = |description: &str| {
+        let mut scheme = json!({ "type": "http", "scheme": "bearer" });
+        scheme["description"] = description.into();
+        scheme
+    };
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Settlers of Catan",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(true),
+            "securitySchemes": {
+                "seat": bearer("The token handed out for a seat, or to a spectator"),
+                "admin": bearer("The token the server was started with"),
+            },
+        },
+    })
+}
+
+/// `GET /openapi.json`, which is only put together the first time it's asked for
+pub(crate) async fn openapi_json() -> Json<Value> {
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    Json(DOCUMENT.get_or_init(openapi).clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::manager::GameManager;
+    use crate::routes::router;
+    use crate::routes::test::send;
+
+    /// Every `$ref` anywhere in `value`
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(target)) = map.get("$ref") {
+                    found.push(target);
+                }
+                map.values().for_each(|v| refs(v, found));
+            }
+            Value::Array(values) => values.iter().for_each(|v| refs(v, found)),
+            _ => (),
+        }
+    }
+
+    #[test]
+    fn test_openapi() {
+        let doc = openapi();
+        let graphql = if cfg!(feature = "graphql") { 2 } else { 0 };
+        assert_eq!(doc["paths"].as_object().unwrap().len(), 19 + graphql);
+        let action = &doc["paths"]["/games/{id}/actions"]["post"];
+        assert_eq!(action["security"][0]["seat"], json!([]));
+        assert!(action["responses"]["422"].is_object());
+        assert_eq!(
+            action["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Action"
+        );
+        // bodies come in every format the server has
+        let content = action["responses"]["200"]["content"].as_object().unwrap();
+        assert_eq!(content.len(), WireFormat::ALL.len());
+
+        // everything referred to is in the document
+        let schemas = doc["components"]["schemas"].as_object().unwrap();
+        let mut found = Vec::new();
+        refs(&doc, &mut found);
+        for target in found {
+            let name = target.strip_prefix("#/components/schemas/").unwrap();
+            assert!(schemas.contains_key(name), "{}", target);
+        }
+        for name in ["ApiError", "StreamMessage", "GameEvent", "PlayerView"] {
+            assert!(schemas.contains_key(name), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openapi_json() {
+        let app = router(GameManager::new());
+        let (status, doc) = send(&app, "GET", "/openapi.json", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(doc, openapi());
+    }
+}
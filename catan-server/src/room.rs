@@ -0,0 +1,405 @@
+use std::time::{Instant, SystemTime};
+
+use anyhow::{anyhow, Result};
+use axum::http::StatusCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use catan_game_logic::{Action, ActionError, Chat, ChatMessage, Game, Lobby, PlayerColour};
+
+use crate::error::ApiError;
+use crate::storage::{Persistence, SavedGame, SavedRoom};
+
+/// Most moves the bots can make in a row before handing back, in case they never stop
+const MAX_BOT_MOVES: usize = 5000;
+
+/// A table on the server, starting out as a lobby and then holding the game, along with its
+/// chat, the tokens handed out for its seats and spectators and a channel telling anyone
+/// watching when it changes
+#[derive(Debug)]
+pub struct Room {
+    lobby: Lobby,
+    game: Option<Game>,
+    chat: Chat,
+    seats: Vec<(Uuid, PlayerColour)>,
+    /// Tokens which can watch the game but not act in it
+    spectators: Vec<Uuid>,
+    /// What the game is started from, so it can be replayed from its actions
+    seed: u64,
+    /// Every action taken since the game started, unless it was loaded from a snapshot
+    actions: Option<Vec<(PlayerColour, Action)>>,
+    updates: broadcast::Sender<()>,
+    /// Chooses the bots' moves
+    rng: StdRng,
+    /// When anything last touched the room, for evicting idle games
+    pub(crate) last_active: Instant,
+    /// Whether the room has changed since it was last saved
+    pub(crate) unsaved: bool,
+}
+
+impl Room {
+    pub fn new(lobby: Lobby) -> Self {
+        let (updates, _) = broadcast::channel(16);
+        let mut rng = StdRng::from_entropy();
+        Self {
+            lobby,
+            game: None,
+            chat: Chat::new(),
+            seats: Vec::new(),
+            spectators: Vec::new(),
+            seed: rng.gen(),
+            actions: Some(Vec::new()),
+            updates,
+            rng,
+            last_active: Instant::now(),
+            unsaved: true,
+        }
+    }
+
+    /// Bring back a room from storage, replaying its actions if it was saved as an event log
+    pub fn restore(saved: SavedRoom) -> Result<Self> {
+        let mut room = Self::new(saved.lobby);
+        room.seats = saved.seats;
+        room.chat = saved.chat;
+        room.spectators = saved.spectators;
+        room.seed = saved.seed;
+        room.unsaved = false;
+        match saved.game {
+            SavedGame::NotStarted => (),
+            SavedGame::Snapshot { game } => {
+                room.game = Some(*game);
+                room.actions = None;
+            }
+            SavedGame::EventLog { actions } => {
+                room.game = Some(room.replay(&actions)?);
+                room.actions = Some(actions);
+            }
+        }
+        Ok(room)
+    }
+
+    /// The room as it should be written to storage
+    ///
+    /// games loaded from a snapshot have no actions to replay, so they're always saved as
+    /// snapshots
+    pub fn save(&self, persistence: Persistence) -> SavedRoom {
+        let game = match (&self.game, &self.actions, persistence) {
+            (None, _, _) => SavedGame::NotStarted,
+            (Some(_), Some(actions), Persistence::EventLog) => SavedGame::EventLog {
+                actions: actions.clone(),
+            },
+            (Some(game), _, _) => SavedGame::Snapshot {
+                game: Box::new(game.clone()),
+            },
+        };
+        SavedRoom {
+            lobby: self.lobby.clone(),
+            seats: self.seats.clone(),
+            spectators: self.spectators.clone(),
+            chat: self.chat.clone(),
+            seed: self.seed,
+            game,
+        }
+    }
+
+    pub fn lobby(&self) -> &Lobby {
+        &self.lobby
+    }
+
+    /// Everyone who's been handed a seat token, in the order they sat down
+    pub fn seated(&self) -> Vec<PlayerColour> {
+        self.seats.iter().map(|(_, colour)| *colour).collect()
+    }
+
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// How many actions have been taken since the game started, if they were all recorded
+    pub fn action_count(&self) -> Option<usize> {
+        self.game.as_ref().and(self.actions.as_ref()).map(Vec::len)
+    }
+
+    pub fn chat(&self) -> &Chat {
+        &self.chat
+    }
+
+    /// Send `text` to the table from `player`, who can be in the lobby or the game
+    pub fn say(&mut self, player: PlayerColour, text: &str) -> Result<ChatMessage, ApiError> {
+        let message = self.chat.say(player, text)?.clone();
+        self.notify();
+        Ok(message)
+    }
+
+    /// The game, once the host has started it
+    pub fn game(&self) -> Result<&Game, ApiError> {
+        self.game.as_ref().ok_or(not_started())
+    }
+
+    /// Claim the seat for `colour`, returning the token to act with from now on
+    pub fn join(&mut self, colour: PlayerColour) -> Result<Uuid, ApiError> {
+        self.ensure_lobby()?;
+        self.lobby
+            .claim(colour)
+            .map_err(|err| ApiError::new(StatusCode::CONFLICT, err.to_string()))?;
+        let token = Uuid::new_v4();
+        self.seats.push((token, colour));
+        self.notify();
+        Ok(token)
+    }
+
+    /// Give up `player`'s seat before the game starts
+    pub fn leave(&mut self, player: PlayerColour) -> Result<(), ApiError> {
+        self.ensure_lobby()?;
+        self.lobby.release(player)?;
+        self.seats.retain(|(_, colour)| *colour != player);
+        self.notify();
+        Ok(())
+    }
+
+    pub fn set_ready(&mut self, player: PlayerColour, ready: bool) -> Result<(), ApiError> {
+        self.ensure_lobby()?;
+        self.lobby.set_ready(player, ready)?;
+        self.notify();
+        Ok(())
+    }
+
+    /// Start the game on the host's say so, letting any bots make their moves
+    pub fn start(&mut self, player: PlayerColour) -> Result<(), ApiError> {
+        self.ensure_lobby()?;
+        if self.lobby.host() != Some(player) {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "Only the host can start the game",
+            ));
+        }
+        let game = self
+            .lobby
+            .start_seeded(player, self.seed)
+            .map_err(|err| ApiError::new(StatusCode::CONFLICT, err.to_string()))?;
+        self.game = Some(game);
+        self.play_bots();
+        self.announce_since(0);
+        self.notify();
+        Ok(())
+    }
+
+    /// Apply `action` for `player`, then let any bots whose turn it is move, letting everyone
+    /// watching know
+    pub fn apply(&mut self, player: PlayerColour, action: Action) -> Result<(), ApiError> {
+        let game = self.game.as_mut().ok_or(not_started())?;
+        let before = game.next_event();
+        game.apply(player, action.clone())?;
+        self.record(player, action);
+        self.play_bots();
+        self.announce_since(before);
+        self.notify();
+        Ok(())
+    }
+
+    /// Give up the game for `player`, then let any bots whose turn it is move
+    ///
+    /// resigning isn't an action, so the game is saved as snapshots from then on
+    pub fn resign(&mut self, player: PlayerColour) -> Result<(), ApiError> {
+        let game = self.game.as_mut().ok_or(not_started())?;
+        let before = game.next_event();
+        game.resign(player).map_err(game_error)?;
+        self.actions = None;
+        self.play_bots();
+        self.announce_since(before);
+        self.notify();
+        Ok(())
+    }
+
+    /// End the game on the spot, declaring `winner` the winner if there is one
+    pub fn end(&mut self, winner: Option<PlayerColour>) -> Result<(), ApiError> {
+        let game = self.game.as_mut().ok_or(not_started())?;
+        let before = game.next_event();
+        game.end(winner).map_err(game_error)?;
+        self.actions = None;
+        self.announce_since(before);
+        self.notify();
+        Ok(())
+    }
+
+    /// Take `player` out of the room, giving up their seat in the lobby or removing them from the
+    /// game, and revoking their seat token either way
+    pub fn kick(&mut self, player: PlayerColour) -> Result<(), ApiError> {
+        match &mut self.game {
+            None => self.lobby.release(player)?,
+            Some(game) => {
+                game.remove_player(player).map_err(game_error)?;
+                self.actions = None;
+            }
+        }
+        self.seats.retain(|(_, colour)| *colour != player);
+        self.play_bots();
+        self.notify();
+        Ok(())
+    }
+
+    /// Wind the game back to how it was after its first `count` actions, by replaying them
+    ///
+    /// only games with every action recorded can be rolled back, which rules out games loaded
+    /// from a snapshot, and games where anything happened besides players' actions
+    pub fn rollback(&mut self, count: usize) -> Result<(), ApiError> {
+        let (Some(_), Some(actions)) = (&self.game, &self.actions) else {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "Only games with every action recorded can be rolled back",
+            ));
+        };
+        let actions = actions
+            .get(..count)
+            .ok_or(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "The game hasn't had that many actions",
+            ))?
+            .to_vec();
+        let game = self
+            .replay(&actions)
+            .map_err(|err| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        self.game = Some(game);
+        self.actions = Some(actions);
+        self.chat
+            .announce(format!("The game was rolled back to move {}", count));
+        self.play_bots();
+        self.notify();
+        Ok(())
+    }
+
+    /// Make the default choice for every decision whose time ran out before `now`, then let
+    /// any bots whose turn it is move
+    pub fn resolve_timeouts(&mut self, now: SystemTime) -> Result<(), ApiError> {
+        let Some(game) = &mut self.game else {
+            return Ok(());
+        };
+        let before = game.next_event();
+        game.resolve_timeouts(now)?;
+        if game.next_event() == before {
+            return Ok(());
+        }
+        // the game moved on without an action, so there's no replaying it from the actions
+        self.actions = None;
+        self.play_bots();
+        self.announce_since(before);
+        self.notify();
+        Ok(())
+    }
+
+    /// Hear about every change to the room from now on
+    ///
+    /// the messages don't say what changed, only that the room is worth catching up on
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.updates.subscribe()
+    }
+
+    /// Swap the seat token `token` for a new one, which is all the seat answers to from then on,
+    /// closing any event stream opened with the old one
+    pub fn rotate(&mut self, token: &Uuid) -> Result<Uuid, ApiError> {
+        let seat = self
+            .seats
+            .iter_mut()
+            .find(|(seat, _)| seat == token)
+            .ok_or(ApiError::unauthorized())?;
+        seat.0 = Uuid::new_v4();
+        let token = seat.0;
+        self.notify();
+        Ok(token)
+    }
+
+    /// Hand out a token which can watch the room but not act in it
+    pub fn spectate(&mut self) -> Uuid {
+        let token = Uuid::new_v4();
+        self.spectators.push(token);
+        self.unsaved = true;
+        token
+    }
+
+    /// The player sitting in the seat `token` was handed out for
+    pub fn player(&self, token: &Uuid) -> Option<PlayerColour> {
+        self.seats
+            .iter()
+            .find(|(seat, _)| seat == token)
+            .map(|(_, colour)| *colour)
+    }
+
+    /// Whether `token` was handed out to watch the room
+    pub fn is_spectator(&self, token: &Uuid) -> bool {
+        self.spectators.contains(token)
+    }
+
+    /// Whose seat `token` watches the room from, which is nobody's for spectators
+    pub fn viewer(&self, token: &Uuid) -> Result<Option<PlayerColour>, ApiError> {
+        match self.player(token) {
+            Some(player) => Ok(Some(player)),
+            None if self.is_spectator(token) => Ok(None),
+            None => Err(ApiError::unauthorized()),
+        }
+    }
+
+    fn ensure_lobby(&self) -> Result<(), ApiError> {
+        match self.game {
+            Some(_) => Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "The game has already started",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Let the bots move until the game waits on somebody else
+    fn play_bots(&mut self) {
+        let Some(game) = &mut self.game else {
+            return;
+        };
+        let moves = game.play_bots(&mut self.rng, MAX_BOT_MOVES);
+        if let Some(actions) = &mut self.actions {
+            actions.extend(moves);
+        }
+    }
+
+    /// The game started from the room's seed, with `actions` applied in order
+    fn replay(&self, actions: &[(PlayerColour, Action)]) -> Result<Game> {
+        let host = self.lobby.host().ok_or(anyhow!("The game has no host"))?;
+        let mut game = self.lobby.start_seeded(host, self.seed)?;
+        for (player, action) in actions {
+            game.apply(*player, action.clone())
+                .map_err(|err| anyhow!("The event log doesn't replay: {}", err.reason()))?;
+        }
+        Ok(game)
+    }
+
+    /// Tell the chat about the events from sequence number `since` on
+    fn announce_since(&mut self, since: usize) {
+        if let Some(game) = &self.game {
+            self.chat.announce_events(&game.events()[since..]);
+        }
+    }
+
+    fn record(&mut self, player: PlayerColour, action: Action) {
+        if let Some(actions) = &mut self.actions {
+            actions.push((player, action));
+        }
+    }
+
+    fn notify(&mut self) {
+        self.unsaved = true;
+        // this only fails when nobody is listening
+        let _ = self.updates.send(());
+    }
+}
+
+fn not_started() -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "The game hasn't started yet")
+}
+
+/// An error from the game, keeping the status of any `ActionError` it's carrying
+fn game_error(err: anyhow::Error) -> ApiError {
+    match err.downcast::<ActionError>() {
+        Ok(err) => err.into(),
+        Err(err) => err.into(),
+    }
+}
@@ -0,0 +1,684 @@
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::middleware::from_fn;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+use uuid::Uuid;
+
+use catan_game_logic::{
+    Action, ChatMessage, GameEvent, GameOptions, Lobby, PlayerColour, PlayerView,
+    DEFAULT_LOBBY_SEATS,
+};
+
+use crate::admin::admin_routes;
+use crate::error::ApiError;
+use crate::format::negotiate;
+use crate::manager::GameManager;
+use crate::openapi::openapi_json;
+use crate::stream::stream_events;
+
+/// Every endpoint of the API
+///
+/// - `POST /games` opens a lobby for a game, see `CreateGame`
+/// - `GET /games/{id}/lobby` is the lobby's seats, who's ready and who's hosting
+/// - `POST /games/{id}/players` claims a seat, returning the token to act with
+/// - `DELETE /games/{id}/players` gives the seat up again before the game starts
+/// - `POST /games/{id}/ready` says whether the seat's player is ready
+/// - `POST /games/{id}/start` starts the game, once everyone's ready, for the host
+/// - `POST /games/{id}/token` swaps the seat's token for a new one, e.g. if it leaked
+/// - `POST /games/{id}/spectators` hands out a token which can only watch the game
+/// - `GET /games/{id}/view` is the game as the seat's player sees it
+/// - `GET /games/{id}/resume?since=` catches a reconnecting client up, see `Resume`
+/// - `GET /games/{id}/chat?since=` is the chat from message `since` on
+/// - `POST /games/{id}/chat` sends a chat message from the seat's player
+/// - `POST /games/{id}/actions` applies an `Action` for the seat's player
+/// - `POST /games/{id}/resign` gives the game up for the seat's player, see `Game::resign`
+/// - `GET /games/{id}/events` streams the game's events over a WebSocket, see `stream_events`
+/// - `GET /openapi.json` describes all of the above, see `openapi`
+/// - `POST /graphql` and `GET /graphql/ws` serve GraphQL when the server is built with the
+///   `graphql` feature, see `graphql_routes`
+///
+/// everything acting for a seat needs its token as a bearer token, and so does handing out
+/// spectator tokens, so only the people at the table decide who watches. The admin endpoints
+/// under `/admin` are listed in `admin_routes`. Bodies are JSON unless `Content-Type` and
+/// `Accept` ask for another `WireFormat`, see `negotiate`
+pub fn router(games: GameManager) -> Router {
+    let router = Router::new()
+        .route("/games", post(create_game))
+        .route("/games/{id}/lobby", get(lobby))
+        .route("/games/{id}/players", post(join_game).delete(leave_game))
+        .route("/games/{id}/ready", post(set_ready))
+        .route("/games/{id}/start", post(start_game))
+        .route("/games/{id}/token", post(rotate_token))
+        .route("/games/{id}/spectators", post(add_spectator))
+        .route("/games/{id}/view", get(view))
+        .route("/games/{id}/resume", get(resume))
+        .route("/games/{id}/chat", get(chat).post(say))
+        .route("/games/{id}/actions", post(submit_action))
+        .route("/games/{id}/resign", post(resign))
+        .route("/games/{id}/events", get(stream_events))
+        .route("/openapi.json", get(openapi_json))
+        .merge(admin_routes());
+    #[cfg(feature = "graphql")]
+    let router = router.merge(crate::graphql::graphql_routes());
+    router.layer(from_fn(negotiate)).with_state(games)
+}
+
+/// Body of `POST /games`, all of which can be left out
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[serde(default)]
+pub struct CreateGame {
+    pub options: GameOptions,
+    /// Number of seats at the table, bots included
+    pub seats: Option<usize>,
+    /// Whether bots take any seats still empty when the game starts
+    pub bots: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct CreatedGame {
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct JoinRequest {
+    pub colour: PlayerColour,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct ReadyRequest {
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct Seat {
+    pub player: PlayerColour,
+    pub token: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct Spectator {
+    pub token: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+pub struct ResumeQuery {
+    /// Sequence number of the first event the client hasn't seen
+    #[serde(default)]
+    pub since: usize,
+    /// Sequence number of the first chat message the client hasn't seen
+    #[serde(default)]
+    pub chat_since: usize,
+}
+
+/// What a client reconnecting to a game has missed, for seat or spectator tokens
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct Resume {
+    /// The game as the seat's player sees it now, which spectators don't get
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-rs", ts(optional))]
+    pub view: Option<PlayerView>,
+    pub events: Vec<SequencedEvent>,
+    /// Sequence number to resume from next time
+    pub next: usize,
+    pub chat: Vec<ChatMessage>,
+    /// Sequence number to resume the chat from next time
+    pub next_chat: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+pub struct ChatQuery {
+    /// Sequence number of the first message to send
+    #[serde(default)]
+    pub since: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct ChatRequest {
+    pub text: String,
+}
+
+/// An event along with its sequence number, which counts up from 0 in the order events happen
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+pub struct SequencedEvent {
+    pub seq: usize,
+    pub event: GameEvent,
+}
+
+/// The seat token sent as `Authorization: Bearer <token>`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SeatToken(pub Uuid);
+
+impl<S: Send + Sync> FromRequestParts<S> for SeatToken {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        bearer(parts)
+            .and_then(|token| Uuid::parse_str(token).ok())
+            .map(SeatToken)
+            .ok_or(ApiError::unauthorized())
+    }
+}
+
+/// The token sent as `Authorization: Bearer <token>`, if there is one
+pub(crate) fn bearer(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+async fn create_game(
+    State(games): State<GameManager>,
+    request: Option<Json<CreateGame>>,
+) -> Result<(StatusCode, Json<CreatedGame>), ApiError> {
+    let request = request.map_or(CreateGame::default(), |Json(request)| request);
+    let lobby = Lobby::new(request.options)
+        .with_capacity(request.seats.unwrap_or(DEFAULT_LOBBY_SEATS))
+        .with_bots(request.bots);
+    let id = games.create(lobby)?;
+    Ok((StatusCode::CREATED, Json(CreatedGame { id })))
+}
+
+async fn lobby(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Lobby>, ApiError> {
+    games.with_room(&id, |room| Ok(Json(room.lobby().clone())))
+}
+
+async fn join_game(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<JoinRequest>,
+) -> Result<(StatusCode, Json<Seat>), ApiError> {
+    let token = games.with_room(&id, |room| room.join(request.colour))?;
+    let seat = Seat {
+        player: request.colour,
+        token,
+    };
+    Ok((StatusCode::CREATED, Json(seat)))
+}
+
+async fn leave_game(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<StatusCode, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        room.leave(player)?;
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+async fn set_ready(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+    Json(request): Json<ReadyRequest>,
+) -> Result<Json<Lobby>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        room.set_ready(player, request.ready)?;
+        Ok(Json(room.lobby().clone()))
+    })
+}
+
+/// Start the game, returning the host's view of it
+async fn start_game(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<PlayerView>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        room.start(player)?;
+        Ok(Json(room.game()?.view_for(&player)?))
+    })
+}
+
+async fn rotate_token(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<Seat>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        let token = room.rotate(&token)?;
+        Ok(Json(Seat { player, token }))
+    })
+}
+
+async fn add_spectator(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<(StatusCode, Json<Spectator>), ApiError> {
+    games.with_room(&id, |room| {
+        room.player(&token).ok_or(ApiError::unauthorized())?;
+        let token = room.spectate();
+        Ok((StatusCode::CREATED, Json(Spectator { token })))
+    })
+}
+
+async fn view(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<PlayerView>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        Ok(Json(room.game()?.view_for(&player)?))
+    })
+}
+
+async fn resume(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ResumeQuery>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<Resume>, ApiError> {
+    games.with_room(&id, |room| {
+        let viewer = room.viewer(&token)?;
+        let game = room.game()?;
+        if query.since > game.next_event() {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                format!("There are only {} events so far", game.next_event()),
+            ));
+        }
+        let view = match viewer {
+            Some(player) => Some(game.view_for(&player)?),
+            None => None,
+        };
+        let events = game
+            .subscribe(query.since)
+            .map(|(seq, event)| SequencedEvent {
+                seq,
+                event: event.clone(),
+            })
+            .collect();
+        Ok(Json(Resume {
+            view,
+            events,
+            next: game.next_event(),
+            chat: room.chat().since(query.chat_since).cloned().collect(),
+            next_chat: room.chat().next_message(),
+        }))
+    })
+}
+
+/// The chat, for seats and spectators alike
+async fn chat(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ChatQuery>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<Vec<ChatMessage>>, ApiError> {
+    games.with_room(&id, |room| {
+        room.viewer(&token)?;
+        Ok(Json(room.chat().since(query.since).cloned().collect()))
+    })
+}
+
+async fn say(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+    Json(request): Json<ChatRequest>,
+) -> Result<(StatusCode, Json<ChatMessage>), ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        let message = room.say(player, &request.text)?;
+        Ok((StatusCode::CREATED, Json(message)))
+    })
+}
+
+/// Apply the action, returning the player's view of the game afterwards
+async fn submit_action(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+    Json(action): Json<Action>,
+) -> Result<Json<PlayerView>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        room.apply(player, action)?;
+        Ok(Json(room.game()?.view_for(&player)?))
+    })
+}
+
+/// Resign, returning the player's view of the game afterwards
+async fn resign(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    SeatToken(token): SeatToken,
+) -> Result<Json<PlayerView>, ApiError> {
+    games.with_room(&id, |room| {
+        let player = room.player(&token).ok_or(ApiError::unauthorized())?;
+        room.resign(player)?;
+        Ok(Json(room.game()?.view_for(&player)?))
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    pub(crate) async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        token: Option<Uuid>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header("content-type", "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = app
+            .clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    pub(crate) async fn join(app: &Router, game: &str, colour: &str) -> (StatusCode, Value) {
+        let uri = format!("/games/{}/players", game);
+        send(app, "POST", &uri, None, Some(json!({ "colour": colour }))).await
+    }
+
+    pub(crate) fn token(seat: &Value) -> Option<Uuid> {
+        seat["token"].as_str().and_then(|t| Uuid::parse_str(t).ok())
+    }
+
+    /// Ready every seat, then start the game as the first of them
+    pub(crate) async fn start(app: &Router, game: &str, seats: &[&Value]) -> (StatusCode, Value) {
+        let uri = format!("/games/{}/ready", game);
+        for seat in seats {
+            let ready = Some(json!({ "ready": true }));
+            assert_eq!(
+                send(app, "POST", &uri, token(seat), ready).await.0,
+                StatusCode::OK
+            );
+        }
+        let uri = format!("/games/{}/start", game);
+        send(app, "POST", &uri, token(seats[0]), None).await
+    }
+
+    #[tokio::test]
+    async fn test_join_and_view() {
+        let app = router(GameManager::new());
+        let (status, created) = send(&app, "POST", "/games", None, None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let game = created["id"].as_str().unwrap().to_string();
+
+        let (status, red) = join(&app, &game, "red").await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(red["player"], "red");
+        assert_eq!(join(&app, &game, "red").await.0, StatusCode::CONFLICT);
+        let (_, blue) = join(&app, &game, "blue").await;
+
+        let uri = format!("/games/{}/view", game);
+        assert_eq!(
+            send(&app, "GET", &uri, token(&red), None).await.0,
+            StatusCode::CONFLICT
+        );
+        assert_eq!(start(&app, &game, &[&red, &blue]).await.0, StatusCode::OK);
+        let (status, view) = send(&app, "GET", &uri, token(&red), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(view["viewer"], "red");
+        assert_eq!(view["opponents"][0]["colour"], "blue");
+
+        assert_eq!(
+            send(&app, "GET", &uri, None, None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, "GET", &uri, Some(Uuid::new_v4()), None).await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        let missing = format!("/games/{}/view", Uuid::new_v4());
+        assert_eq!(
+            send(&app, "GET", &missing, token(&red), None).await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_actions() {
+        let app = router(GameManager::new());
+        let options = json!({ "options": { "discard_threshold": 9 } });
+        let (_, created) = send(&app, "POST", "/games", None, Some(options)).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+        start(&app, &game, &[&red, &blue]).await;
+
+        // blue can't go first, and red can't settle somewhere that doesn't exist
+        let uri = format!("/games/{}/actions", game);
+        let settle =
+            |vertex: usize| json!({ "type": "place_initial_settlement", "vertex": vertex });
+        let (status, _) = send(&app, "POST", &uri, token(&blue), Some(settle(2))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(&app, "POST", &uri, token(&red), Some(settle(999))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, view) = send(&app, "POST", &uri, token(&red), Some(settle(2))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(view["victory_points"]["settlements"], 1);
+
+        // nobody else can sit down once the game has started
+        assert_eq!(join(&app, &game, "green").await.0, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_tokens() {
+        let app = router(GameManager::new());
+        let (_, created) = send(&app, "POST", "/games", None, None).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+        start(&app, &game, &[&red, &blue]).await;
+
+        // once rotated, only the new token works
+        let uri = format!("/games/{}/token", game);
+        let (status, rotated) = send(&app, "POST", &uri, token(&red), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(rotated["player"], "red");
+        assert_ne!(token(&rotated), token(&red));
+        let view = format!("/games/{}/view", game);
+        let (status, _) = send(&app, "GET", &view, token(&red), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, "GET", &view, token(&rotated), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // spectators are handed out by the table, and can't act or see anybody's hand
+        let uri = format!("/games/{}/spectators", game);
+        let (status, _) = send(&app, "POST", &uri, None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, spectator) = send(&app, "POST", &uri, token(&blue), None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = send(&app, "GET", &view, token(&spectator), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let settle = json!({ "type": "place_initial_settlement", "vertex": 2 });
+        let actions = format!("/games/{}/actions", game);
+        let (status, _) = send(&app, "POST", &actions, token(&spectator), Some(settle)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_chat() {
+        let app = router(GameManager::new());
+        let (_, created) = send(&app, "POST", "/games", None, None).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+
+        // players can talk in the lobby, but spectators can only listen
+        let uri = format!("/games/{}/chat", game);
+        let hello = Some(json!({ "text": "hello" }));
+        let (status, message) = send(&app, "POST", &uri, token(&red), hello.clone()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(message["author"]["player"], "red");
+        let empty = Some(json!({ "text": " " }));
+        let (status, _) = send(&app, "POST", &uri, token(&blue), empty).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let spectators = format!("/games/{}/spectators", game);
+        let (_, spectator) = send(&app, "POST", &spectators, token(&blue), None).await;
+        let (status, _) = send(&app, "POST", &uri, token(&spectator), hello).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, chat) = send(&app, "GET", &uri, token(&spectator), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(chat[0]["text"], "hello");
+
+        let since = format!("{}?since=1", uri);
+        let (_, chat) = send(&app, "GET", &since, token(&red), None).await;
+        assert_eq!(chat, json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_resume() {
+        let app = router(GameManager::new());
+        let (_, created) = send(&app, "POST", "/games", None, None).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+        start(&app, &game, &[&red, &blue]).await;
+
+        let uri = format!("/games/{}/resume", game);
+        let (status, resumed) = send(&app, "GET", &uri, token(&red), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let seen = resumed["next"].as_u64().unwrap();
+
+        // red drops off, blue moves on, and red comes back for just what it missed
+        let actions = format!("/games/{}/actions", game);
+        let settle = json!({ "type": "place_initial_settlement", "vertex": 2 });
+        send(&app, "POST", &actions, token(&red), Some(settle)).await;
+        let since = format!("{}?since={}", uri, seen);
+        let (status, resumed) = send(&app, "GET", &since, token(&red), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resumed["view"]["victory_points"]["settlements"], 1);
+        let events = resumed["events"].as_array().unwrap();
+        assert_eq!(events[0]["seq"], seen);
+        assert!(events
+            .iter()
+            .any(|event| event["event"]["type"] == "settlement_built"));
+        assert_eq!(resumed["next"], seen + events.len() as u64);
+
+        // spectators catch up on the events, but get no view
+        let spectators = format!("/games/{}/spectators", game);
+        let (_, spectator) = send(&app, "POST", &spectators, token(&blue), None).await;
+        let (status, resumed) = send(&app, "GET", &since, token(&spectator), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resumed["view"], Value::Null);
+        assert_eq!(resumed["events"].as_array().unwrap().len(), events.len());
+
+        let (_, resumed) = send(&app, "GET", &uri, token(&red), None).await;
+        assert_eq!(resumed["next_chat"], 0);
+
+        let ahead = format!("{}?since=1000", uri);
+        let (status, _) = send(&app, "GET", &ahead, token(&red), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_resign() {
+        let app = router(GameManager::new());
+        let (_, created) = send(&app, "POST", "/games", None, None).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+
+        let uri = format!("/games/{}/resign", game);
+        let (status, _) = send(&app, "POST", &uri, token(&red), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        start(&app, &game, &[&red, &blue]).await;
+
+        // with red gone, blue is the last one standing
+        let (status, view) = send(&app, "POST", &uri, token(&red), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(view["state"], "complete");
+        assert_eq!(view["opponents"][0]["resigned"], false);
+        let (status, _) = send(&app, "POST", &uri, token(&blue), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let chat = format!("/games/{}/chat", game);
+        let (_, chat) = send(&app, "GET", &chat, token(&blue), None).await;
+        let texts: Vec<_> = chat
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| &m["text"])
+            .collect();
+        assert_eq!(texts, vec!["Red resigned", "Blue won the game"]);
+    }
+
+    #[tokio::test]
+    async fn test_lobby() {
+        let app = router(GameManager::new());
+        let body = Some(json!({ "seats": 3, "bots": true }));
+        let (_, created) = send(&app, "POST", "/games", None, body).await;
+        let game = created["id"].as_str().unwrap().to_string();
+        let (_, red) = join(&app, &game, "red").await;
+        let (_, blue) = join(&app, &game, "blue").await;
+
+        // blue leaves again, and isn't the host anyway
+        let uri = format!("/games/{}/players", game);
+        let (status, _) = send(&app, "DELETE", &uri, token(&blue), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, blue) = join(&app, &game, "blue").await;
+        assert_eq!(
+            start(&app, &game, &[&blue, &red]).await.0,
+            StatusCode::FORBIDDEN
+        );
+
+        let uri = format!("/games/{}/lobby", game);
+        let (_, lobby) = send(&app, "GET", &uri, None, None).await;
+        assert_eq!(lobby["host"], "red");
+        assert_eq!(lobby["seats"][1]["ready"], true);
+
+        // the empty seat goes to a bot
+        let (status, view) = start(&app, &game, &[&red, &blue]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(view["opponents"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+use crate::storage::{SavedRoom, Storage};
+
+/// Every change to the schema, in order, each run once against a database when it's opened
+///
+/// the database's `user_version` is how many have been run, so new migrations go on the end and
+/// old ones are never changed
+const MIGRATIONS: &[&str] = &["CREATE TABLE games (
+        id TEXT PRIMARY KEY NOT NULL,
+        room TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX games_updated_at ON games (updated_at);"];
+
+/// Storage in an SQLite database, keeping each room as a row of JSON
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open the database at `path`, creating it if need be, and bring its schema up to date
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::migrate(Connection::open(path)?)
+    }
+
+    /// A database which only lasts as long as the storage does
+    pub fn in_memory() -> Result<Self> {
+        Self::migrate(Connection::open_in_memory()?)
+    }
+
+    fn migrate(mut connection: Connection) -> Result<Self> {
+        let version = user_version(&connection)?;
+        if version > MIGRATIONS.len() {
+            return Err(anyhow!(
+                "The database is at version {}, which is newer than this server knows about",
+                version
+            ));
+        }
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", idx as i64 + 1)?;
+            transaction.commit()?;
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| anyhow!("The database connection was poisoned"))
+    }
+}
+
+/// How many migrations have been run against the database
+fn user_version(connection: &Connection) -> Result<usize> {
+    let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version.try_into()?)
+}
+
+impl Storage for SqliteStorage {
+    fn save(&self, id: &Uuid, room: &SavedRoom) -> Result<()> {
+        let json = serde_json::to_string(room)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.connection()?.execute(
+            "INSERT INTO games (id, room, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET room = excluded.room, updated_at = excluded.updated_at",
+            params![id.to_string(), json, now as i64],
+        )?;
+        Ok(())
+    }
+
+    fn load(&self, id: &Uuid) -> Result<Option<SavedRoom>> {
+        let json: Option<String> = self
+            .connection()?
+            .query_row(
+                "SELECT room FROM games WHERE id = ?1",
+                params![id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match json {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<Uuid>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare("SELECT id FROM games ORDER BY updated_at")?;
+        let ids = statement.query_map([], |row| row.get::<_, String>(0))?;
+        ids.map(|id| Ok(Uuid::parse_str(&id?)?)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use catan_game_logic::{GameOptions, Lobby, PlayerColour};
+
+    use super::*;
+    use crate::room::Room;
+    use crate::storage::Persistence;
+
+    #[test]
+    fn test_sqlite_storage() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let mut room = Room::new(Lobby::new(GameOptions::default()));
+        room.join(PlayerColour::Red).unwrap();
+        let id = Uuid::new_v4();
+        assert_eq!(storage.load(&id).unwrap(), None);
+
+        storage
+            .save(&id, &room.save(Persistence::Snapshot))
+            .unwrap();
+        room.join(PlayerColour::Blue).unwrap();
+        let saved = room.save(Persistence::Snapshot);
+        storage.save(&id, &saved).unwrap();
+        assert_eq!(storage.load(&id).unwrap(), Some(saved));
+        assert_eq!(storage.list().unwrap(), vec![id]);
+    }
+
+    #[test]
+    fn test_migrations() {
+        let path = std::env::temp_dir().join(format!("catan-{}.db", Uuid::new_v4()));
+        let id = Uuid::new_v4();
+        let saved = Room::new(Lobby::new(GameOptions::default())).save(Persistence::EventLog);
+        SqliteStorage::open(&path)
+            .unwrap()
+            .save(&id, &saved)
+            .unwrap();
+
+        // opening it again doesn't run anything twice, and keeps what was there
+        let storage = SqliteStorage::open(&path).unwrap();
+        let version = user_version(&storage.connection().unwrap()).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(storage.load(&id).unwrap(), Some(saved));
+
+        // but a database from a newer server is left alone
+        storage
+            .connection()
+            .unwrap()
+            .pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+            .unwrap();
+        drop(storage);
+        assert!(SqliteStorage::open(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
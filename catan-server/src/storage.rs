@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use catan_game_logic::{Action, Chat, Game, Lobby, PlayerColour};
+
+/// Somewhere to keep games, so they outlive the server process and being evicted from memory
+pub trait Storage: Debug + Send + Sync {
+    /// Write `room` under `id`, replacing whatever was there
+    fn save(&self, id: &Uuid, room: &SavedRoom) -> Result<()>;
+
+    /// The room saved under `id`, if there is one
+    fn load(&self, id: &Uuid) -> Result<Option<SavedRoom>>;
+
+    /// The id of every saved room
+    fn list(&self) -> Result<Vec<Uuid>>;
+}
+
+/// How a room's game is written to storage
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Persistence {
+    /// The game as it stands, which loads quickly, although the dice and everything else left
+    /// to chance are reseeded when it does
+    #[default]
+    Snapshot,
+    /// The seed the game started from and every action taken since, which are replayed to load
+    /// it, picking up exactly where it left off
+    ///
+    /// decisions the game made for players who ran out of time don't replay, so games where
+    /// anything timed out are saved as snapshots from then on
+    EventLog,
+}
+
+/// Everything needed to bring a room back, as written to storage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedRoom {
+    pub lobby: Lobby,
+    /// The tokens handed out for each seat
+    pub seats: Vec<(Uuid, PlayerColour)>,
+    #[serde(default)]
+    pub spectators: Vec<Uuid>,
+    #[serde(default)]
+    pub chat: Chat,
+    /// What the game was, or will be, started from
+    pub seed: u64,
+    pub game: SavedGame,
+}
+
+/// A room's game, in whichever form it was saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SavedGame {
+    /// The room is still a lobby
+    NotStarted,
+    Snapshot {
+        game: Box<Game>,
+    },
+    /// Every action taken since the game started from the room's seed, in order
+    EventLog {
+        actions: Vec<(PlayerColour, Action)>,
+    },
+}
+
+/// Storage which only lasts as long as the process, keeping rooms as JSON like a database
+/// would
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    rooms: Mutex<HashMap<Uuid, String>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn rooms(&self) -> Result<std::sync::MutexGuard<'_, HashMap<Uuid, String>>> {
+        self.rooms
+            .lock()
+            .map_err(|_| anyhow!("The storage was poisoned"))
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn save(&self, id: &Uuid, room: &SavedRoom) -> Result<()> {
+        let json = serde_json::to_string(room)?;
+        self.rooms()?.insert(*id, json);
+        Ok(())
+    }
+
+    fn load(&self, id: &Uuid) -> Result<Option<SavedRoom>> {
+        match self.rooms()?.get(id) {
+            Some(json) => Ok(Some(serde_json::from_str(json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<Uuid>> {
+        Ok(self.rooms()?.keys().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use catan_game_logic::{GameOptions, VertexId};
+
+    use super::*;
+    use crate::room::Room;
+
+    /// A room two players have started, with Red's first settlement down
+    fn started_room() -> Room {
+        let mut room = Room::new(Lobby::new(GameOptions::default()));
+        for colour in [PlayerColour::Red, PlayerColour::Blue] {
+            room.join(colour).unwrap();
+            room.set_ready(colour, true).unwrap();
+        }
+        room.start(PlayerColour::Red).unwrap();
+        let settle = Action::PlaceInitialSettlement {
+            vertex: VertexId::new(2),
+        };
+        room.apply(PlayerColour::Red, settle).unwrap();
+        room
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        let storage = MemoryStorage::new();
+        let room = started_room();
+        let id = Uuid::new_v4();
+        assert_eq!(storage.load(&id).unwrap(), None);
+
+        for persistence in [Persistence::Snapshot, Persistence::EventLog] {
+            let saved = room.save(persistence);
+            storage.save(&id, &saved).unwrap();
+            assert_eq!(storage.load(&id).unwrap(), Some(saved));
+        }
+        assert_eq!(storage.list().unwrap(), vec![id]);
+    }
+}
@@ -0,0 +1,315 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::response::Response;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+use uuid::Uuid;
+
+use catan_game_logic::{ChatMessage, GameEvent, Lobby, PlayerColour, PlayerView, WireFormat};
+
+use crate::error::ApiError;
+use crate::manager::GameManager;
+use crate::room::Room;
+
+/// Query string of an event stream, since browsers can't set headers on a WebSocket
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize)]
+pub struct StreamQuery {
+    /// Seat or spectator token
+    pub token: Option<Uuid>,
+    /// Sequence number of the first event to send, to pick up where an earlier stream left off
+    #[serde(default)]
+    pub since: usize,
+    /// Sequence number of the first chat message to send, likewise
+    #[serde(default)]
+    pub chat_since: usize,
+    /// What to send messages in, as text frames for JSON and binary frames for anything else
+    #[serde(default)]
+    pub format: WireFormat,
+}
+
+/// Something pushed down an event stream, in the stream's `format`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Event {
+        seq: usize,
+        event: GameEvent,
+    },
+    /// The seat's view of the game, sent to seated connections whenever the room changes
+    View {
+        view: Box<PlayerView>,
+    },
+    /// The lobby, sent whenever it changes until the game starts
+    Lobby {
+        lobby: Box<Lobby>,
+    },
+    /// A message in the room's chat, from a player or announcing something which happened
+    Chat {
+        message: ChatMessage,
+    },
+}
+
+/// Stream a game's events and chat to a WebSocket as they happen, or its lobby until it starts
+///
+/// events only ever say what the whole table could see, e.g. that a card was stolen but not
+/// which, so everyone is sent the same events. What each connection is allowed to see beyond
+/// that goes in its `View` messages, which are redacted for its seat, and spectators get none.
+/// The stream closes as soon as its token stops working, e.g. when it's rotated
+pub(crate) async fn stream_events(
+    State(games): State<GameManager>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<StreamQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let token = query.token.ok_or(ApiError::unauthorized())?;
+    let updates = games.with_room(&id, |room| {
+        room.viewer(&token)?;
+        Ok(room.subscribe())
+    })?;
+    Ok(ws.on_upgrade(move |socket| stream(socket, games, id, token, query, updates)))
+}
+
+async fn stream(
+    mut socket: WebSocket,
+    games: GameManager,
+    id: Uuid,
+    token: Uuid,
+    mut cursor: StreamQuery,
+    mut updates: Receiver<()>,
+) {
+    loop {
+        let messages = games.with_room(&id, |room| {
+            let viewer = room.viewer(&token)?;
+            Ok(catch_up(room, viewer, &mut cursor))
+        });
+        let Ok(messages) = messages else {
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        };
+        for message in messages {
+            let Some(message) = frame(cursor.format, &message) else {
+                return;
+            };
+            if socket.send(message).await.is_err() {
+                return;
+            }
+        }
+
+        tokio::select! {
+            update = updates.recv() => match update {
+                // missed updates don't matter, since everything is read from the room's logs
+                Ok(()) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        }
+    }
+}
+
+fn frame(format: WireFormat, message: &StreamMessage) -> Option<Message> {
+    let bytes = format.encode(message).ok()?;
+    if format.is_text() {
+        Some(Message::Text(String::from_utf8(bytes).ok()?.into()))
+    } else {
+        Some(Message::Binary(bytes.into()))
+    }
+}
+
+/// Every chat message and event `cursor` hasn't seen, moving it past them, followed by the
+/// viewer's view, or the lobby if the game hasn't started
+pub(crate) fn catch_up(
+    room: &Room,
+    viewer: Option<PlayerColour>,
+    cursor: &mut StreamQuery,
+) -> Vec<StreamMessage> {
+    let mut messages: Vec<_> = room
+        .chat()
+        .since(cursor.chat_since)
+        .map(|message| StreamMessage::Chat {
+            message: message.clone(),
+        })
+        .collect();
+    cursor.chat_since = room.chat().next_message().max(cursor.chat_since);
+
+    let Ok(game) = room.game() else {
+        let lobby = Box::new(room.lobby().clone());
+        messages.push(StreamMessage::Lobby { lobby });
+        return messages;
+    };
+    messages.extend(
+        game.subscribe(cursor.since)
+            .map(|(seq, event)| StreamMessage::Event {
+                seq,
+                event: event.clone(),
+            }),
+    );
+    cursor.since = game.next_event().max(cursor.since);
+    if let Some(view) = viewer.and_then(|viewer| game.view_for(&viewer).ok()) {
+        messages.push(StreamMessage::View {
+            view: Box::new(view),
+        });
+    }
+    messages
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use futures_util::{Stream, StreamExt};
+    use serde_json::Value;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::tungstenite;
+
+    use super::*;
+    use crate::routes::router;
+    use catan_game_logic::{Action, GameOptions, Lobby, VertexId};
+
+    async fn serve(games: GameManager) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(games)).await });
+        format!("ws://{}", addr)
+    }
+
+    async fn receive(
+        socket: &mut (impl Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin),
+    ) -> Value {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let games = GameManager::new();
+        let id = games.create(Lobby::new(GameOptions::default())).unwrap();
+        let (red, watcher) = games
+            .with_room(&id, |room| {
+                Ok((room.join(PlayerColour::Red)?, room.spectate()))
+            })
+            .unwrap();
+        let url = serve(games.clone()).await;
+
+        let stream_url = format!("{}/games/{}/events?token={}", url, id, red);
+        let (mut socket, _) = connect_async(stream_url).await.unwrap();
+        let stream_url = format!("{}/games/{}/events?token={}", url, id, watcher);
+        let (mut spectator, _) = connect_async(stream_url).await.unwrap();
+        assert!(connect_async(format!("{}/games/{}/events", url, id))
+            .await
+            .is_err());
+
+        // the lobby is sent until the game starts
+        assert_eq!(receive(&mut socket).await["lobby"]["host"], "red");
+
+        // chat is sent ahead of whatever else changed
+        games
+            .with_room(&id, |room| room.say(PlayerColour::Red, "hi"))
+            .unwrap();
+        assert_eq!(receive(&mut socket).await["message"]["text"], "hi");
+        games
+            .with_room(&id, |room| {
+                room.join(PlayerColour::Blue)?;
+                room.set_ready(PlayerColour::Red, true)?;
+                room.set_ready(PlayerColour::Blue, true)?;
+                room.start(PlayerColour::Red)
+            })
+            .unwrap();
+
+        // and then the seat's view, after the events so far
+        let mut message = receive(&mut socket).await;
+        while message["type"] != "view" {
+            message = receive(&mut socket).await;
+        }
+        assert_eq!(message["view"]["viewer"], "red");
+
+        let settle = Action::PlaceInitialSettlement {
+            vertex: VertexId::new(2),
+        };
+        games
+            .with_room(&id, |room| room.apply(PlayerColour::Red, settle))
+            .unwrap();
+        let mut events = Vec::new();
+        loop {
+            let message = receive(&mut socket).await;
+            match message["type"].as_str() {
+                Some("event") => events.push(message["event"]["type"].to_string()),
+                _ if message["view"]["victory_points"]["settlements"] == 1 => break,
+                _ => (),
+            }
+        }
+        assert!(events.contains(&"\"settlement_built\"".to_string()));
+
+        // spectators see the same events, and no view
+        loop {
+            let message = receive(&mut spectator).await;
+            assert_ne!(message["type"], "view");
+            if message["event"]["type"] == "settlement_built" {
+                break;
+            }
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_binary_stream() {
+        let games = GameManager::new();
+        let id = games.create(Lobby::new(GameOptions::default())).unwrap();
+        let red = games
+            .with_room(&id, |room| room.join(PlayerColour::Red))
+            .unwrap();
+        let url = serve(games.clone()).await;
+
+        // binary formats are sent as binary frames
+        let stream_url = format!("{}/games/{}/events?token={}&format=cbor", url, id, red);
+        let (mut socket, _) = connect_async(stream_url).await.unwrap();
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let tungstenite::Message::Binary(bytes) = message else {
+            panic!("expected a binary frame, got {:?}", message);
+        };
+        let message: Value = WireFormat::Cbor.decode(&bytes).unwrap();
+        assert_eq!(message["lobby"]["host"], "red");
+
+        let stream_url = format!("{}/games/{}/events?token={}&format=xml", url, id, red);
+        assert!(connect_async(stream_url).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rotated_token() {
+        let games = GameManager::new();
+        let id = games.create(Lobby::new(GameOptions::default())).unwrap();
+        let red = games
+            .with_room(&id, |room| room.join(PlayerColour::Red))
+            .unwrap();
+        let url = serve(games.clone()).await;
+        let stream_url = format!("{}/games/{}/events?token={}", url, id, red);
+        let (mut socket, _) = connect_async(stream_url).await.unwrap();
+        assert_eq!(receive(&mut socket).await["type"], "lobby");
+
+        // the stream closes once the token it was opened with is swapped out
+        games.with_room(&id, |room| room.rotate(&red)).unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap();
+        assert!(matches!(
+            closed,
+            Some(Ok(tungstenite::Message::Close(_))) | None
+        ));
+    }
+}
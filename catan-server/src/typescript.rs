@@ -0,0 +1,55 @@
+use catan_game_logic::TypeScript;
+
+use crate::admin::{EndRequest, GameDetails, GameListing, RollbackRequest};
+use crate::error::ErrorBody;
+use crate::routes::{
+    ChatRequest, CreateGame, CreatedGame, JoinRequest, ReadyRequest, Resume, Seat, Spectator,
+};
+use crate::stream::StreamMessage;
+
+/// Declarations for every body the server sends or accepts, along with the engine's own
+/// payloads from `TypeScript::api`
+pub fn typescript() -> TypeScript {
+    TypeScript::api()
+        .with::<CreateGame>()
+        .with::<CreatedGame>()
+        .with::<JoinRequest>()
+        .with::<ReadyRequest>()
+        .with::<Seat>()
+        .with::<Spectator>()
+        .with::<Resume>()
+        .with::<ChatRequest>()
+        .with::<StreamMessage>()
+        .with::<ErrorBody>()
+        .with::<GameListing>()
+        .with::<GameDetails>()
+        .with::<EndRequest>()
+        .with::<RollbackRequest>()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_typescript() {
+        let mut out = Vec::new();
+        let declared = typescript().write(&mut out).unwrap();
+        assert!(declared > TypeScript::api().len());
+        let out = String::from_utf8(out).unwrap();
+
+        for name in [
+            "StreamMessage",
+            "SequencedEvent",
+            "RoomState",
+            "ApiError",
+            "PlayerView",
+        ] {
+            let declaration = format!("export type {} =", name);
+            assert_eq!(out.matches(&declaration).count(), 1, "{}", name);
+        }
+        // spectators resume without a view, and the field is left out altogether
+        assert!(out.contains("view?: PlayerView"));
+        assert!(out.contains("game?: unknown"));
+    }
+}